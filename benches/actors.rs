@@ -0,0 +1,204 @@
+//! Benchmarks of the custom actor pipeline
+//!
+//! The data provider is replaced by a mock one, which generates closing prices, so that
+//! the benchmarks measure only the actor framework and the processing, and not the network.
+//!
+//! - `throughput` sends a number of chunks to a processor actor, and waits until the writer
+//!   and collection actors have handled all of them;
+//! - `latency` sends a single chunk to a processor actor, and waits until the batch
+//!   that it completes is published to a subscriber.
+//!
+//! Run them with `cargo bench`. The writer actor's output file is created in a temporary directory.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use time::OffsetDateTime;
+use tokio::runtime::Runtime;
+
+use stock_trading_cli_with_async_streams::constants::{
+    CHUNK_SIZE, COLLECTION_MAILBOX_CAPACITY, WINDOW_SIZE,
+};
+use stock_trading_cli_with_async_streams::my_async_actors::{
+    ActorHandle, BrokerActorHandle, CollectionActorHandle, DeadLetterActorHandle, Iteration,
+    PipelineHandles, ProcessorActorHandle, ProcessorActorMsg, StatsActorHandle, WebhookActorHandle,
+    WriterActorHandle,
+};
+use stock_trading_cli_with_async_streams::tail_buffer::TailBufferLimits;
+use stock_trading_cli_with_async_streams::types::Symbol;
+
+/// The number of closing prices per symbol, which is about a year of trading days
+const NUM_CLOSES: usize = 250;
+
+/// The numbers of chunks that are sent in a single measurement of the throughput
+const NUM_CHUNKS: [usize; 3] = [1, 16, 128];
+
+/// A mock data provider, which returns the same deterministic closing prices
+/// for `CHUNK_SIZE` symbols every time
+fn mock_symbols_closes() -> HashMap<Symbol, Vec<f64>> {
+    (0..CHUNK_SIZE)
+        .map(|i| {
+            let closes = (0..NUM_CLOSES)
+                .map(|day| 100.0 + i as f64 + (day as f64 / 10.0).sin())
+                .collect();
+            (format!("SYM{}", i).into(), closes)
+        })
+        .collect()
+}
+
+/// Returns a [`SymbolsClosesMsg`](ProcessorActorMsg::SymbolsClosesMsg) message
+/// that completes a whole iteration on its own
+fn symbols_closes_msg(handles: &PipelineHandles, id: u64) -> ProcessorActorMsg {
+    ProcessorActorMsg::SymbolsClosesMsg {
+        symbols_closes: mock_symbols_closes(),
+        quality: Vec::new(),
+        from: OffsetDateTime::now_utc(),
+        handles: handles.clone(),
+        iteration: Iteration {
+            id,
+            start: Instant::now(),
+            to: OffsetDateTime::now_utc(),
+            num_chunks: 1,
+        },
+        chunk_id: 0,
+        window_size: WINDOW_SIZE,
+    }
+}
+
+/// Create the singleton actors of the pipeline, whose collection actor publishes
+/// the batches through the `broker_handle`
+fn pipeline(broker_handle: BrokerActorHandle) -> PipelineHandles {
+    let stats_handle = StatsActorHandle::new(0);
+    let dead_letter_handle = DeadLetterActorHandle::with_stats(0, stats_handle.clone());
+
+    PipelineHandles {
+        writer_handle: WriterActorHandle::new(0),
+        collection_handle: CollectionActorHandle::with_listeners(
+            0,
+            COLLECTION_MAILBOX_CAPACITY,
+            TailBufferLimits::default(),
+            stats_handle.clone(),
+            WebhookActorHandle::new(0),
+            broker_handle,
+            dead_letter_handle.clone(),
+        ),
+        stats_handle,
+        dead_letter_handle,
+        settings: Default::default(),
+    }
+}
+
+/// Stops the actors that are left running after the writer and collection actors
+async fn shutdown(handles: PipelineHandles, broker_handle: BrokerActorHandle) {
+    broker_handle.shutdown().await.unwrap();
+    handles.dead_letter_handle.shutdown().await.unwrap();
+    handles.stats_handle.shutdown().await.unwrap();
+}
+
+fn throughput(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("throughput");
+
+    for num_chunks in NUM_CHUNKS {
+        group.throughput(Throughput::Elements(num_chunks as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_chunks),
+            &num_chunks,
+            |b, &num_chunks| {
+                b.to_async(&runtime).iter_custom(|iters| async move {
+                    let mut elapsed = Duration::ZERO;
+
+                    for _ in 0..iters {
+                        let broker_handle = BrokerActorHandle::new(0);
+                        let handles = pipeline(broker_handle.clone());
+                        let processor_handle = ProcessorActorHandle::new(0);
+
+                        let start = Instant::now();
+                        for id in 0..num_chunks as u64 {
+                            processor_handle
+                                .send(symbols_closes_msg(&handles, id))
+                                .await
+                                .unwrap();
+                        }
+                        // each shutdown is acknowledged after all messages before it are handled
+                        processor_handle.shutdown().await.unwrap();
+                        handles.writer_handle.shutdown().await.unwrap();
+                        handles.collection_handle.shutdown().await.unwrap();
+                        elapsed += start.elapsed();
+
+                        shutdown(handles, broker_handle).await;
+                    }
+
+                    elapsed
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn latency(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+
+    let (broker_handle, handles, processor_handle, mut subscription) = runtime.block_on(async {
+        let broker_handle = BrokerActorHandle::new(0);
+        let handles = pipeline(broker_handle.clone());
+        let subscription = broker_handle.subscribe().await.unwrap();
+        (
+            broker_handle,
+            handles,
+            ProcessorActorHandle::new(0),
+            subscription,
+        )
+    });
+
+    let mut id = 0;
+    c.bench_function("latency", |b| {
+        // the subscription is borrowed across the measurements, so the runtime is entered for each of them
+        b.iter_custom(|iters| {
+            runtime.block_on(async {
+                let mut elapsed = Duration::ZERO;
+
+                for _ in 0..iters {
+                    id += 1;
+                    let msg = symbols_closes_msg(&handles, id);
+
+                    let start = Instant::now();
+                    processor_handle.send(msg).await.unwrap();
+                    black_box(subscription.recv().await.unwrap());
+                    elapsed += start.elapsed();
+                }
+
+                elapsed
+            })
+        });
+    });
+
+    runtime.block_on(async {
+        processor_handle.shutdown().await.unwrap();
+        handles.writer_handle.shutdown().await.unwrap();
+        handles.collection_handle.shutdown().await.unwrap();
+        shutdown(handles, broker_handle).await;
+    });
+}
+
+/// Moves the writer actor's output file out of the way, into a temporary directory
+fn output_in_temp_dir() {
+    let dir = std::env::temp_dir().join("stock-trading-cli-bench");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(dir).unwrap();
+}
+
+fn main() {
+    // the reports' directory is resolved relative to the original working directory
+    let mut criterion = Criterion::default().configure_from_args();
+    output_in_temp_dir();
+
+    throughput(&mut criterion);
+    latency(&mut criterion);
+
+    criterion.final_summary();
+}
@@ -0,0 +1,146 @@
+//! Benchmarks of the signals, i.e., of the performance indicators' calculations
+//!
+//! Every signal is measured over closing prices of realistic lengths, from a month of daily bars
+//! to a few days of five-minute intraday bars, so that changes to the calculations,
+//! such as computing them incrementally, can be compared quantitatively.
+//!
+//! - `async_signals` measures the signals of [`async_signals`], which the pipeline uses;
+//! - `sync_signals` measures the signals of [`sync_signals`], which the strategies use;
+//! - `risk` measures the daily returns and the volatility of [`risk`];
+//! - `row` measures a whole row of performance indicators, as the processor actors calculate it,
+//!   and, with the `arrow` feature, as the columnar processing of `ColumnarCloses` does,
+//!   Arrow arrays included.
+//!
+//! Run them with `cargo bench --bench signals`, and add `--features arrow` for the columnar processing.
+
+#[cfg(feature = "arrow")]
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use criterion::measurement::WallTime;
+use criterion::{BenchmarkGroup, BenchmarkId, Criterion, Throughput};
+use tokio::runtime::{Builder, Runtime};
+
+use stock_trading_cli_with_async_streams::async_signals::{self, AsyncStockSignal};
+#[cfg(feature = "arrow")]
+use stock_trading_cli_with_async_streams::columnar::ColumnarCloses;
+use stock_trading_cli_with_async_streams::constants::WINDOW_SIZE;
+use stock_trading_cli_with_async_streams::prelude::PerformanceIndicatorsRow;
+#[cfg(feature = "arrow")]
+use stock_trading_cli_with_async_streams::prelude::Symbol;
+use stock_trading_cli_with_async_streams::risk;
+use stock_trading_cli_with_async_streams::sync_signals::{self, StockSignal};
+
+/// The lengths of the series of closing prices: about a month and a year of trading days,
+/// five trading days of five-minute bars, and five years of trading days
+const SERIES_LENGTHS: [usize; 4] = [21, 250, 390, 1_260];
+
+/// Returns `len` deterministic closing prices, which wander around 100
+fn closes(len: usize) -> Vec<f64> {
+    (0..len)
+        .map(|day| 100.0 + (day as f64 / 10.0).sin() * 5.0 + day as f64 / 100.0)
+        .collect()
+}
+
+/// Measures the `calculate` function over every series length, in the `group`, under the `name`
+fn bench_series<T>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    name: &str,
+    calculate: impl Fn(&[f64]) -> T,
+) {
+    for len in SERIES_LENGTHS {
+        let series = closes(len);
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::new(name, len), &series, |b, series| {
+            b.iter(|| black_box(calculate(black_box(series))));
+        });
+    }
+}
+
+/// The async signals are ready as soon as they are polled, so they are driven by
+/// a single-threaded runtime, whose overhead is part of what the pipeline pays for them
+fn async_signals(c: &mut Criterion, runtime: &Runtime) {
+    let mut group = c.benchmark_group("async_signals");
+
+    bench_series(&mut group, "min_price", |series| {
+        runtime.block_on(async_signals::MinPrice {}.calculate(series))
+    });
+    bench_series(&mut group, "max_price", |series| {
+        runtime.block_on(async_signals::MaxPrice {}.calculate(series))
+    });
+    bench_series(&mut group, "price_difference", |series| {
+        runtime.block_on(async_signals::PriceDifference {}.calculate(series))
+    });
+    bench_series(&mut group, "windowed_sma", |series| {
+        let sma = async_signals::WindowedSMA {
+            window_size: WINDOW_SIZE,
+        };
+        runtime.block_on(sma.calculate(series))
+    });
+
+    group.finish();
+}
+
+fn sync_signals(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sync_signals");
+
+    bench_series(&mut group, "min_price", |series| {
+        sync_signals::MinPrice {}.calculate(series)
+    });
+    bench_series(&mut group, "max_price", |series| {
+        sync_signals::MaxPrice {}.calculate(series)
+    });
+    bench_series(&mut group, "price_difference", |series| {
+        sync_signals::PriceDifference {}.calculate(series)
+    });
+    bench_series(&mut group, "windowed_sma", |series| {
+        let sma = sync_signals::WindowedSMA {
+            window_size: WINDOW_SIZE,
+        };
+        sma.calculate(series)
+    });
+
+    group.finish();
+}
+
+fn risk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("risk");
+
+    bench_series(&mut group, "daily_returns", risk::daily_returns);
+    bench_series(&mut group, "volatility", |series| {
+        risk::volatility(&risk::daily_returns(series))
+    });
+
+    group.finish();
+}
+
+fn row(c: &mut Criterion, runtime: &Runtime) {
+    let mut group = c.benchmark_group("row");
+
+    bench_series(&mut group, "performance_indicators", |series| {
+        runtime.block_on(PerformanceIndicatorsRow::calculate(
+            "SYM",
+            series,
+            WINDOW_SIZE,
+        ))
+    });
+    #[cfg(feature = "arrow")]
+    bench_series(&mut group, "columnar", |series| {
+        let symbols_closes = HashMap::from([(Symbol::from("SYM"), series.to_vec())]);
+        ColumnarCloses::new(&symbols_closes).rows(WINDOW_SIZE)
+    });
+
+    group.finish();
+}
+
+fn main() {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    let mut criterion = Criterion::default().configure_from_args();
+
+    async_signals(&mut criterion, &runtime);
+    sync_signals(&mut criterion);
+    risk(&mut criterion);
+    row(&mut criterion, &runtime);
+
+    criterion.final_summary();
+}
@@ -0,0 +1,90 @@
+//! Generates the gRPC service stubs, and collects build information
+//!
+//! The service is defined here, in Rust, instead of being compiled from `proto/stocks.proto`,
+//! so that building the app doesn't require `protoc`.
+//! The protobuf messages are defined in `src/grpc.rs`.
+//!
+//! The build information, the git commit hash and the build timestamp, is passed to the crate
+//! through environment variables, which are read at compile time in `src/constants.rs`.
+
+use std::path::Path;
+use std::process::Command;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tonic_build::manual::{Builder, Method, Service};
+
+fn main() {
+    compile_grpc_service();
+    collect_build_info();
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+/// Generates the gRPC service stubs
+fn compile_grpc_service() {
+    let method = |name: &str, route_name: &str, input_type: &str, output_type: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(input_type)
+            .output_type(output_type)
+            .codec_path("tonic::codec::ProstCodec")
+    };
+
+    let service = Service::builder()
+        .name("Stocks")
+        .package("stocks")
+        .method(method("tail", "Tail", "super::TailRequest", "super::TailReply").build())
+        .method(
+            method(
+                "symbol_latest",
+                "SymbolLatest",
+                "super::SymbolLatestRequest",
+                "super::SymbolLatestReply",
+            )
+            .build(),
+        )
+        .method(
+            method(
+                "subscribe",
+                "Subscribe",
+                "super::SubscribeRequest",
+                "super::Batch",
+            )
+            .server_streaming()
+            .build(),
+        )
+        .build();
+
+    Builder::new().compile(&[service]);
+}
+
+/// Passes the git commit hash and the build timestamp to the crate
+///
+/// The commit hash is `unknown` if the crate isn't built from a git repository.
+///
+/// The build script is rerun, and hence the timestamp is updated, when the checked-out commit changes.
+fn collect_build_info() {
+    let git_commit_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_COMMIT_HASH={}", git_commit_hash);
+
+    let build_timestamp = OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", build_timestamp);
+
+    // a nonexistent path would make the build script rerun on every build
+    for path in [".git/HEAD", ".git/refs/heads", ".git/packed-refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
@@ -1,6 +1,12 @@
 //! Uses [actix](https://crates.io/crates/actix) as an asynchronous actor framework.
 //!
 //! Requires `#[actix::main]`.
+//!
+//! The message handlers are synchronous, so the asynchronous work of the [`FetchActor`]
+//! and of the [`ProcessorActor`], fetching and processing the data, is wrapped into an actor future
+//! with `into_actor(self)`, and spawned in the actor's context with `spawn(ctx)`, which polls it.
+//! A future that is merely created, or pinned, in a handler would never be polled, and the handler
+//! would silently do nothing.
 
 use std::collections::HashMap;
 use std::fs::File;
@@ -11,6 +17,7 @@ use actix::{Actor, ActorContext, Addr, ContextFutureSpawner, Handler, Message, W
 use anyhow::{Context, Error, Result};
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
+use tokio::sync::mpsc::UnboundedSender;
 use yahoo_finance_api as yahoo;
 
 use crate::async_signals::{AsyncStockSignal, MaxPrice, MinPrice, PriceDifference, WindowedSMA};
@@ -129,7 +136,7 @@ impl Handler<QuoteRequestsMsg> for FetchActor {
                 symbols_closes.insert(symbol, closes);
             }
 
-            let symbols_closes_msg = SymbolsClosesMsg { symbols_closes, from, writer_address, start };
+            let symbols_closes_msg = SymbolsClosesMsg { symbols_closes, from, writer_address, start, echo: true };
 
             // Spawn another Actor and send it the message.
             let proc_address = ProcessorActor.start();
@@ -158,18 +165,21 @@ impl Handler<QuoteRequestsMsg> for FetchActor {
 ///
 /// It also contains a [`WriterActor`] address.
 ///
+/// The rows are also printed to `stdout`, if `echo` is set.
+///
 /// There is no expected response.
 #[derive(Message)]
 #[rtype(result = "MsgResponseType")]
-struct SymbolsClosesMsg {
+pub struct SymbolsClosesMsg {
     pub symbols_closes: HashMap<String, Vec<f64>>,
     pub from: OffsetDateTime,
     pub writer_address: Addr<WriterActor>,
-    start: Instant,
+    pub start: Instant,
+    pub echo: bool,
 }
 
 /// Actor for creating performance indicators from fetched stock data
-struct ProcessorActor;
+pub struct ProcessorActor;
 
 impl Actor for ProcessorActor {
     type Context = actix::Context<Self>;
@@ -188,6 +198,7 @@ impl Handler<SymbolsClosesMsg> for ProcessorActor {
         let from = msg.from;
         let writer_address = msg.writer_address;
         let start = msg.start;
+        let echo = msg.echo;
 
         let from = OffsetDateTime::format(from, &Rfc3339).expect("Couldn't format 'from'.");
 
@@ -226,10 +237,12 @@ impl Handler<SymbolsClosesMsg> for ProcessorActor {
                     rows.push(row);
 
                     // A simple way to output CSV data
-                    println!(
-                        "{},{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
-                        from, symbol, last_price, pct_change, period_min, period_max, sma,
-                    );
+                    if echo {
+                        println!(
+                            "{},{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
+                            from, symbol, last_price, pct_change, period_min, period_max, sma,
+                        );
+                    }
                 } else {
                     eprintln!("Got no data for symbol \"{}\".", symbol);
                 }
@@ -279,9 +292,12 @@ struct PerformanceIndicatorsRowsMsg {
 }
 
 /// Actor for writing calculated performance indicators for fetched stock data into a CSV file
+///
+/// With a `listener`, it writes no file, and tells the listener about every chunk of rows instead.
 pub struct WriterActor {
     pub file_name: String,
     pub writer: Option<BufWriter<File>>,
+    pub listener: Option<UnboundedSender<usize>>,
 }
 
 impl Default for WriterActor {
@@ -295,6 +311,16 @@ impl WriterActor {
         Self {
             file_name: CSV_FILE_PATH.to_string(),
             writer: None,
+            listener: None,
+        }
+    }
+
+    /// Create a [`WriterActor`] that writes no file, but sends the number of rows of every chunk
+    /// to the `listener`, which is how the `bench-variants` subcommand waits for the chunks
+    pub fn with_listener(listener: UnboundedSender<usize>) -> Self {
+        Self {
+            listener: Some(listener),
+            ..Self::new()
         }
     }
 }
@@ -304,6 +330,9 @@ impl Actor for WriterActor {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         ctx.set_mailbox_capacity(16); // Default capacity is 16 messages.
+        if self.listener.is_some() {
+            return;
+        }
         let mut file = File::create(&self.file_name)
             .unwrap_or_else(|_| panic!("Could not open target file \"{}\".", self.file_name));
         let _ = writeln!(&mut file, "{}", CSV_HEADER);
@@ -339,6 +368,11 @@ impl Handler<PerformanceIndicatorsRowsMsg> for WriterActor {
         let rows = msg.rows;
         let start = msg.start;
 
+        if let Some(listener) = &self.listener {
+            let _ = listener.send(rows.len());
+            return Ok(());
+        }
+
         if let Some(file) = &mut self.writer {
             for row in rows {
                 let _ = writeln!(
@@ -0,0 +1,181 @@
+//! Asset classes
+//!
+//! The symbol universe can mix equities, ETFs and crypto. Symbols are tagged with their asset class
+//! through the `asset-classes` option, e.g., `etf:SPY+QQQ,crypto:BTC+ETH`; the untagged ones are
+//! equities.
+//!
+//! The data provider is asked for every symbol under the name that its asset class is quoted
+//! under: the equities and the ETFs under their tickers, and the crypto under their US-dollar pairs,
+//! e.g., `BTC-USD` for `BTC`, unless a pair is given.
+//!
+//! Every row of performance indicators is annotated with its symbol's asset class, which the web app,
+//! the gRPC API and the JSON Lines output files include. The CSV output file keeps its columns,
+//! so the rows that are read back from it are tagged again.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::Batch;
+
+/// The asset class of a symbol
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetClass {
+    #[default]
+    Equity,
+    Etf,
+    Crypto,
+}
+
+impl Display for AssetClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AssetClass::Equity => "equity",
+            AssetClass::Etf => "etf",
+            AssetClass::Crypto => "crypto",
+        })
+    }
+}
+
+impl FromStr for AssetClass {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.trim().to_lowercase().as_str() {
+            "equity" => AssetClass::Equity,
+            "etf" => AssetClass::Etf,
+            "crypto" => AssetClass::Crypto,
+            _ => bail!(
+                "`{}` isn't an asset class; it's one of `equity`, `etf` and `crypto`.",
+                s
+            ),
+        })
+    }
+}
+
+/// An asset class, and the symbols that are tagged with it
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetClassTag {
+    pub class: AssetClass,
+    pub symbols: Vec<String>,
+}
+
+impl FromStr for AssetClassTag {
+    type Err = anyhow::Error;
+
+    /// Parses a tag in the `CLASS:SYMBOLS` format, whose symbols are separated by plus signs,
+    /// e.g., `crypto:BTC+ETH`
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((class, symbols)) = s.split_once(':') else {
+            bail!(
+                "`{}` isn't an asset class tag in the `CLASS:SYMBOLS` format, such as `etf:SPY+QQQ`.",
+                s
+            );
+        };
+
+        let symbols: Vec<String> = symbols
+            .split('+')
+            .map(|symbol| symbol.trim().to_uppercase())
+            .filter(|symbol| !symbol.is_empty())
+            .collect();
+        ensure!(
+            !symbols.is_empty(),
+            "The asset class tag `{}` has no symbols.",
+            s
+        );
+
+        Ok(Self {
+            class: class.parse()?,
+            symbols,
+        })
+    }
+}
+
+/// The asset classes of the tagged symbols
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AssetClasses {
+    classes: HashMap<String, AssetClass>,
+}
+
+impl AssetClasses {
+    /// Creates the asset classes out of the `tags`; a symbol that is tagged more than once
+    /// keeps its last tag
+    pub fn new(tags: &[AssetClassTag]) -> Self {
+        let classes = tags
+            .iter()
+            .flat_map(|tag| tag.symbols.iter().map(|symbol| (symbol.clone(), tag.class)))
+            .collect();
+
+        Self { classes }
+    }
+
+    /// Returns the asset class of a `symbol`, which is an equity unless it's tagged otherwise
+    pub fn class_of(&self, symbol: &str) -> AssetClass {
+        self.classes.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Returns the name that the data provider quotes a `symbol` under
+    pub fn provider_symbol(&self, symbol: &str) -> String {
+        match self.class_of(symbol) {
+            AssetClass::Crypto if !symbol.contains('-') => format!("{}-USD", symbol),
+            AssetClass::Equity | AssetClass::Etf | AssetClass::Crypto => symbol.to_string(),
+        }
+    }
+
+    /// Annotates the rows of a `batch` with their symbols' asset classes
+    pub fn tag(&self, batch: &mut Batch) {
+        for row in batch {
+            row.asset_class = self.class_of(&row.symbol);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::my_async_actors::PerformanceIndicatorsRow;
+
+    fn row(symbol: &str) -> PerformanceIndicatorsRow {
+        PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price: 1.0,
+            pct_change: 0.0,
+            period_min: 1.0,
+            period_max: 1.0,
+            sma: 1.0,
+            asset_class: AssetClass::Etf,
+        }
+    }
+
+    #[test]
+    fn symbols_are_routed_by_their_asset_classes() {
+        let tags: Vec<AssetClassTag> = ["etf:spy+QQQ", "crypto:BTC+ETH-EUR"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let classes = AssetClasses::new(&tags);
+
+        assert_eq!(AssetClass::Etf, classes.class_of("SPY"));
+        assert_eq!(AssetClass::Crypto, classes.class_of("ETH-EUR"));
+        assert_eq!(AssetClass::Equity, classes.class_of("AAPL"));
+
+        assert_eq!("BTC-USD", classes.provider_symbol("BTC"));
+        assert_eq!("ETH-EUR", classes.provider_symbol("ETH-EUR"));
+        assert_eq!("SPY", classes.provider_symbol("SPY"));
+        assert_eq!("BRK-B", classes.provider_symbol("BRK-B"));
+
+        let mut batch = vec![row("BTC"), row("AAPL")];
+        classes.tag(&mut batch);
+        assert_eq!(AssetClass::Crypto, batch[0].asset_class);
+        assert_eq!(AssetClass::Equity, batch[1].asset_class);
+
+        assert!("bond:TLT".parse::<AssetClassTag>().is_err());
+        assert!("etf".parse::<AssetClassTag>().is_err());
+        assert!("etf:+".parse::<AssetClassTag>().is_err());
+    }
+}
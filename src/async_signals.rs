@@ -209,4 +209,91 @@ mod tests {
         let signal = WindowedSMA { window_size: 10 };
         assert_eq!(signal.calculate(&series).await, Some(vec![]));
     }
+
+    /// Properties of the signals over random series, which both the async signals and their sync
+    /// counterparts, [`crate::sync_signals`], are checked against, so that they stay in lockstep
+    mod properties {
+        use proptest::prelude::*;
+
+        use crate::sync_signals::{self, StockSignal};
+
+        use super::*;
+
+        /// The signals of a series
+        #[derive(Debug, PartialEq)]
+        struct Signals {
+            min: Option<f64>,
+            max: Option<f64>,
+            diff: Option<(f64, f64)>,
+            sma: Option<Vec<f64>>,
+        }
+
+        impl Signals {
+            fn of_async(series: &[f64], window_size: usize) -> Self {
+                futures::executor::block_on(async {
+                    Self {
+                        min: MinPrice {}.calculate(series).await,
+                        max: MaxPrice {}.calculate(series).await,
+                        diff: PriceDifference {}.calculate(series).await,
+                        sma: WindowedSMA { window_size }.calculate(series).await,
+                    }
+                })
+            }
+
+            fn of_sync(series: &[f64], window_size: usize) -> Self {
+                Self {
+                    min: sync_signals::MinPrice {}.calculate(series),
+                    max: sync_signals::MaxPrice {}.calculate(series),
+                    diff: sync_signals::PriceDifference {}.calculate(series),
+                    sma: sync_signals::WindowedSMA { window_size }.calculate(series),
+                }
+            }
+
+            /// Checks the invariants of the signals of a non-empty `series`
+            fn check(&self, series: &[f64], window_size: usize) -> Result<(), TestCaseError> {
+                let (min, max) = (self.min.unwrap(), self.max.unwrap());
+                prop_assert!(series.contains(&min) && series.contains(&max));
+                prop_assert!(series.iter().all(|&close| min <= close && close <= max));
+
+                let (first, last) = (series[0], series[series.len() - 1]);
+                let (abs_diff, rel_diff) = self.diff.unwrap();
+                prop_assert_eq!(last - first, abs_diff);
+                prop_assert_eq!(abs_diff / first, rel_diff);
+                prop_assert_eq!(last >= first, rel_diff >= 0.0);
+
+                // an average can be off by the rounding of the sum
+                let sma = self.sma.as_ref().unwrap();
+                let tolerance = max * 1e-9;
+                prop_assert_eq!((series.len() + 1).saturating_sub(window_size), sma.len());
+                prop_assert!(sma
+                    .iter()
+                    .all(|&avg| min - tolerance <= avg && avg <= max + tolerance));
+
+                Ok(())
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn signals_keep_their_invariants(
+                series in prop::collection::vec(0.01..10_000.0f64, 1..300),
+                window_size in 2..60usize,
+            ) {
+                let signals = Signals::of_async(&series, window_size);
+                signals.check(&series, window_size)?;
+                Signals::of_sync(&series, window_size).check(&series, window_size)?;
+                prop_assert_eq!(Signals::of_sync(&series, window_size), signals);
+            }
+
+            #[test]
+            fn a_window_of_one_close_or_an_empty_series_has_no_sma(
+                series in prop::collection::vec(0.01..10_000.0f64, 0..10),
+            ) {
+                let signals = Signals::of_async(&series, 1);
+                prop_assert_eq!(None, signals.sma.clone());
+                prop_assert_eq!(series.is_empty(), signals.min.is_none());
+                prop_assert_eq!(Signals::of_sync(&series, 1), signals);
+            }
+        }
+    }
 }
@@ -0,0 +1,161 @@
+//! Audit log of web requests
+//!
+//! With the `audit-log` option, every request to the web app is recorded, after it has been answered,
+//! as a line of JSON in an append-only file, so that the requests to a server that is shared
+//! across a team can be traced back to their callers. The requests are recorded by the [`record`]
+//! middleware, and written by the [`crate::my_async_actors::AuditActorHandle`]'s actor, so that
+//! a slow disk doesn't hold up the responses.
+//!
+//! The handlers that return rows of performance indicators tell the middleware how many
+//! through the [`RowsReturned`] response extension.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Instant;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::constants::REQUEST_ID_HEADER;
+use crate::my_async_actors::{ActorHandle, AuditActorHandle, AuditActorMsg};
+
+/// The number of rows of performance indicators in a response, which the handlers that return
+/// rows add to their responses' extensions
+#[derive(Clone, Copy, Debug)]
+pub struct RowsReturned(pub usize);
+
+/// A recorded web request
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// When the request was answered, in the RFC 3339 format
+    pub time: String,
+    pub method: String,
+    pub path: String,
+    /// The query string, if there is one
+    pub params: Option<String>,
+    /// The client's address
+    pub caller: String,
+    /// The request's correlation ID, which is also in the traces
+    pub request_id: Option<String>,
+    /// The response's status code
+    pub status: u16,
+    /// The time that it took to answer the request, in milliseconds
+    pub latency_ms: f64,
+    /// The number of rows of performance indicators in the response, for the requests that return them
+    pub rows: Option<usize>,
+}
+
+/// An append-only file with a line of JSON per recorded request
+pub struct AuditLog {
+    writer: BufWriter<File>,
+}
+
+impl AuditLog {
+    /// Opens the file at `path` for appending, or creates it
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Writes a `record`, and flushes it to the file
+    pub fn write(&mut self, record: &AuditRecord) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        writeln!(self.writer)?;
+
+        self.writer.flush()
+    }
+}
+
+/// The middleware that records every request, after it has been answered,
+/// through the audit actor of the `handle`
+pub async fn record(
+    State(handle): State<AuditActorHandle>,
+    ConnectInfo(caller): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let params = request.uri().query().map(String::from);
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+
+    let response = next.run(request).await;
+
+    let record = AuditRecord {
+        time: OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_default(),
+        method,
+        path,
+        params,
+        caller: caller.to_string(),
+        request_id,
+        status: response.status().as_u16(),
+        latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+        rows: response
+            .extensions()
+            .get::<RowsReturned>()
+            .map(|rows| rows.0),
+    };
+    // a request that can't be recorded is still answered
+    if let Err(err) = handle.send(AuditActorMsg::Record(record)).await {
+        tracing::warn!("Couldn't record the request in the audit log: {}", err);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_appended_as_lines_of_json() {
+        let path = std::env::temp_dir().join(format!("audit-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let record = AuditRecord {
+            time: "2024-01-01T00:00:00Z".to_string(),
+            method: "GET".to_string(),
+            path: "/range".to_string(),
+            params: Some("start=2024-01-01T00:00:00Z".to_string()),
+            caller: "127.0.0.1:50000".to_string(),
+            request_id: Some("1".to_string()),
+            status: 200,
+            latency_ms: 1.5,
+            rows: Some(10),
+        };
+        AuditLog::open(&path).unwrap().write(&record).unwrap();
+        // a restart appends to the log instead of truncating it
+        let other = AuditRecord {
+            path: "/stats".to_string(),
+            params: None,
+            rows: None,
+            ..record.clone()
+        };
+        AuditLog::open(&path).unwrap().write(&other).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<AuditRecord> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(vec![record, other], records);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
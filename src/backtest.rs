@@ -0,0 +1,360 @@
+//! Backtesting of trading strategies
+//!
+//! A backtest replays the daily closes of every symbol over the period, from its start.
+//! At every close, the performance indicators are calculated over the closes so far,
+//! the same as in the main loop, and fed to a [`StrategyEngine`], whose strategy decides
+//! on a [`Signal`], the same as in the paper trading.
+//!
+//! The trades are long-only: a buy signal opens a position with the whole capital, unless one
+//! is already open, and a sell signal closes it. A position that is still open at the end of
+//! the period is closed at the last close.
+//!
+//! The [`Trade`]s, and a [`Summary`] of them for every symbol, are written in every selected
+//! output format, through the [`sinks`].
+
+use std::fmt::{Display, Formatter};
+
+use anyhow::{ensure, Context, Result};
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+
+use crate::cli::{Args, BarInterval};
+use crate::constants::{BACKTEST_SUMMARY_FILE_STEM, BACKTEST_TRADES_FILE_STEM};
+use crate::my_async_actors::PerformanceIndicatorsRow;
+use crate::quotes;
+use crate::sinks::{self, Record};
+use crate::strategies::{Signal, StrategyEngine};
+use crate::types::MsgResponseType;
+
+/// A closed position
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Trade {
+    pub symbol: String,
+    pub entry_time: String,
+    pub exit_time: String,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub return_pct: f64,
+}
+
+impl Display for Trade {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{},${:.2},${:.2},{:.2}%",
+            self.symbol,
+            self.entry_time,
+            self.exit_time,
+            self.entry_price,
+            self.exit_price,
+            self.return_pct
+        )
+    }
+}
+
+impl Record for Trade {
+    const CSV_HEADER: &'static str = "symbol,entry time,exit time,entry price,exit price,return %";
+    const PARQUET_SCHEMA: &'static str = "
+        message trades {
+            REQUIRED BYTE_ARRAY symbol (UTF8);
+            REQUIRED BYTE_ARRAY entry_time (UTF8);
+            REQUIRED BYTE_ARRAY exit_time (UTF8);
+            REQUIRED DOUBLE entry_price;
+            REQUIRED DOUBLE exit_price;
+            REQUIRED DOUBLE return_pct;
+        }
+    ";
+
+    fn texts(&self) -> Vec<&str> {
+        vec![&self.symbol, &self.entry_time, &self.exit_time]
+    }
+
+    fn decimals(&self) -> Vec<f64> {
+        vec![self.entry_price, self.exit_price, self.return_pct]
+    }
+}
+
+/// The statistics of a symbol's trades
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub symbol: String,
+    pub strategy: String,
+    pub trades: usize,
+    /// The return of the capital over the whole period, with every trade compounded
+    pub total_return_pct: f64,
+    /// The largest fall of the capital from a previous peak, marked to the closes
+    pub max_drawdown_pct: f64,
+    /// The share of the trades with a positive return
+    pub win_rate_pct: f64,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{},{:.2}%,{:.2}%,{:.2}%",
+            self.symbol,
+            self.strategy,
+            self.trades,
+            self.total_return_pct,
+            self.max_drawdown_pct,
+            self.win_rate_pct
+        )
+    }
+}
+
+impl Record for Summary {
+    const CSV_HEADER: &'static str =
+        "symbol,strategy,trades,total return %,max drawdown %,win rate %";
+    const PARQUET_SCHEMA: &'static str = "
+        message summaries {
+            REQUIRED BYTE_ARRAY symbol (UTF8);
+            REQUIRED BYTE_ARRAY strategy (UTF8);
+            REQUIRED INT64 trades;
+            REQUIRED DOUBLE total_return_pct;
+            REQUIRED DOUBLE max_drawdown_pct;
+            REQUIRED DOUBLE win_rate_pct;
+        }
+    ";
+
+    fn texts(&self) -> Vec<&str> {
+        vec![&self.symbol, &self.strategy]
+    }
+
+    fn integers(&self) -> Vec<i64> {
+        vec![self.trades as i64]
+    }
+
+    fn decimals(&self) -> Vec<f64> {
+        vec![
+            self.total_return_pct,
+            self.max_drawdown_pct,
+            self.win_rate_pct,
+        ]
+    }
+}
+
+/// Replays the `symbol`'s `closes`, with their times, through the performance indicators,
+/// with an SMA of `window_size` closes, and through the strategy of the `engine`
+///
+/// # Returns
+/// The trades, and their summary
+pub async fn backtest(
+    symbol: &str,
+    closes: &[(OffsetDateTime, f64)],
+    engine: &mut StrategyEngine,
+    window_size: usize,
+) -> Result<(Vec<Trade>, Summary)> {
+    let prices: Vec<f64> = closes.iter().map(|&(_, price)| price).collect();
+
+    let mut trades = Vec::new();
+    let mut position: Option<(OffsetDateTime, f64)> = None;
+    let mut capital = 1.0;
+    let mut peak = 1.0;
+    let mut max_drawdown = 0.0_f64;
+
+    for (i, &(time, price)) in closes.iter().enumerate() {
+        // an open position is marked to every close
+        if position.is_some() && i > 0 && prices[i - 1] > 0.0 {
+            capital *= price / prices[i - 1];
+        }
+        peak = f64::max(peak, capital);
+        max_drawdown = max_drawdown.max((peak - capital) / peak);
+
+        let row = PerformanceIndicatorsRow::calculate(symbol, &prices[..=i], window_size)
+            .await
+            .context("There are no closes to calculate the indicators over.")?;
+        match (engine.signal(&row), position) {
+            (Signal::Buy, None) => position = Some((time, price)),
+            (Signal::Sell, Some(entry)) => {
+                trades.push(trade(symbol, entry, (time, price))?);
+                position = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let (Some(entry), Some(&exit)) = (position, closes.last()) {
+        trades.push(trade(symbol, entry, exit)?);
+    }
+
+    let wins = trades.iter().filter(|trade| trade.return_pct > 0.0).count();
+    let win_rate = if trades.is_empty() {
+        0.0
+    } else {
+        wins as f64 / trades.len() as f64
+    };
+    let summary = Summary {
+        symbol: symbol.to_string(),
+        strategy: engine.name().to_string(),
+        trades: trades.len(),
+        total_return_pct: (capital - 1.0) * 100.0,
+        max_drawdown_pct: max_drawdown * 100.0,
+        win_rate_pct: win_rate * 100.0,
+    };
+
+    Ok((trades, summary))
+}
+
+/// Creates the trade of the `symbol` that was opened at the `entry` close and closed at the `exit` one
+fn trade(
+    symbol: &str,
+    (entry_time, entry_price): (OffsetDateTime, f64),
+    (exit_time, exit_price): (OffsetDateTime, f64),
+) -> Result<Trade> {
+    let return_pct = if entry_price > 0.0 {
+        (exit_price / entry_price - 1.0) * 100.0
+    } else {
+        0.0
+    };
+
+    Ok(Trade {
+        symbol: symbol.to_string(),
+        entry_time: entry_time.format(&Rfc3339)?,
+        exit_time: exit_time.format(&Rfc3339)?,
+        entry_price,
+        exit_price,
+        return_pct,
+    })
+}
+
+/// **The backtest**
+///
+/// Replays the history of every symbol of the `args`, over their period, through their strategy,
+/// and writes the trades and their summaries in every selected output format.
+///
+/// The symbols that haven't been replayed yet are skipped when the `shutdown_token` is cancelled,
+/// but the results so far are still written.
+///
+/// # Errors
+/// - If the strategy's parameters aren't valid
+/// - [time::error::Parse](https://docs.rs/time/0.3.36/time/error/enum.Parse.html)
+/// - [std::io::Error] if the symbols file can't be read, or if an output file can't be written
+/// - If the backtest is incomplete, because some symbols couldn't be fetched
+pub async fn run(args: Args, shutdown_token: CancellationToken) -> Result<MsgResponseType> {
+    let now = OffsetDateTime::now_utc();
+    let (from, to) = args.period(now)?;
+    let to = to.unwrap_or(now);
+    let provider = quotes::provider(args.quotes_fixtures.as_deref())?;
+    let mut engine = StrategyEngine::new(args.strategy.clone())?;
+
+    let mut trades = Vec::new();
+    let mut summaries = Vec::new();
+    let mut fetch_errors = 0;
+
+    for symbol in args.symbols()? {
+        if shutdown_token.is_cancelled() {
+            break;
+        }
+
+        let closes = match provider.bars(&symbol, from, to, BarInterval::OneDay).await {
+            Ok(closes) if !closes.is_empty() => closes,
+            Ok(_) => {
+                tracing::warn!("Got no data for symbol \"{}\".", symbol);
+                continue;
+            }
+            Err(err) => {
+                fetch_errors += 1;
+                tracing::warn!(
+                    "There was an API error \"{}\" while fetching data for the symbol \"{}\"; \
+                     skipping the symbol.",
+                    err,
+                    symbol
+                );
+                continue;
+            }
+        };
+
+        let (symbol_trades, summary) =
+            backtest(&symbol, &closes, &mut engine, args.window_size).await?;
+        if !args.quiet {
+            println!(
+                "{}: {} trades, a total return of {:.2}%, a max drawdown of {:.2}%, \
+                 and a win rate of {:.2}%",
+                summary.symbol,
+                summary.trades,
+                summary.total_return_pct,
+                summary.max_drawdown_pct,
+                summary.win_rate_pct
+            );
+        }
+
+        trades.extend(symbol_trades);
+        summaries.push(summary);
+    }
+
+    for format in &args.format {
+        for path in [
+            sinks::write_records(format, BACKTEST_TRADES_FILE_STEM, &trades, args.dry_run)?,
+            sinks::write_records(format, BACKTEST_SUMMARY_FILE_STEM, &summaries, args.dry_run)?,
+        ] {
+            if !args.dry_run {
+                tracing::info!("The backtest's results were written to {}.", path.display());
+            }
+        }
+    }
+
+    ensure!(
+        fetch_errors == 0,
+        "The backtest is incomplete: {} symbols couldn't be fetched.",
+        fetch_errors
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+    use crate::cli::StrategyArgs;
+
+    /// The closes of consecutive days, from the start of 2024
+    fn daily(prices: &[f64]) -> Vec<(OffsetDateTime, f64)> {
+        let start = OffsetDateTime::parse("2024-01-01T00:00:00Z", &Rfc3339).unwrap();
+        prices
+            .iter()
+            .enumerate()
+            .map(|(day, &price)| (start + Duration::days(day as i64), price))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn sma_crossover_trades_are_summarized() {
+        // a rise, a fall, and a rise that is still open at the end of the period
+        let prices = [
+            10.0, 10.0, 10.0, 12.0, 14.0, 16.0, 15.0, 12.0, 9.0, 8.0, 9.0, 11.0, 13.0,
+        ];
+        let mut engine = StrategyEngine::new(StrategyArgs {
+            strategy: "sma-crossover".to_string(),
+            fast_window: 2,
+            slow_window: 3,
+        })
+        .unwrap();
+
+        let (trades, summary) = backtest("AAPL", &daily(&prices), &mut engine, 3)
+            .await
+            .unwrap();
+
+        assert_eq!(2, trades.len());
+        assert_eq!("2024-01-04T00:00:00Z", trades[0].entry_time);
+        assert_eq!(12.0, trades[0].entry_price);
+        assert_eq!(12.0, trades[0].exit_price);
+        assert_eq!(11.0, trades[1].entry_price);
+        assert_eq!(13.0, trades[1].exit_price);
+        assert_eq!("2024-01-13T00:00:00Z", trades[1].exit_time);
+
+        assert_eq!("sma-crossover", summary.strategy);
+        assert_eq!(2, summary.trades);
+        assert_eq!(50.0, summary.win_rate_pct);
+        assert!((summary.total_return_pct - (13.0 / 11.0 - 1.0) * 100.0).abs() < 1e-9);
+        assert!((summary.max_drawdown_pct - (1.0 - 12.0 / 16.0) * 100.0).abs() < 1e-9);
+        assert_eq!(
+            "AAPL,2024-01-04T00:00:00Z,2024-01-08T00:00:00Z,$12.00,$12.00,0.00%",
+            trades[0].to_string()
+        );
+    }
+}
@@ -0,0 +1,558 @@
+//! Benchmarks of the implementation variants
+//!
+//! Comparing the implementations is what the application is for, so the `bench-variants`
+//! subcommand runs every [`ImplementationVariant`] for a number of iterations, and prints
+//! a table of their latencies and memory usage.
+//!
+//! The data provider is replaced by a mock one, which generates the same daily closes
+//! of the symbols over the period every time, so that the benchmarks measure only
+//! the implementations, and not the network. An iteration processes all symbols in chunks,
+//! the same as an iteration of the main loop, and its latency is the time until the rows
+//! of all chunks have been calculated, which, with the actors, is when the collection actor
+//! publishes the iteration's batch.
+//!
+//! The memory is the resident set size of the process, which is sampled after every iteration,
+//! and which is only available on Linux.
+//!
+//! The Actix actors need an Actix system of their own, so their variants are run in one,
+//! on a blocking thread, against the same mock closes. Their writer actor writes no file,
+//! and their processor actors calculate the indicators with their own window size.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix::Actor;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use futures::StreamExt;
+use rayon::prelude::*;
+use time::OffsetDateTime;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::actix_async_actors::{ProcessorActor, SymbolsClosesMsg, WriterActor};
+use crate::cli::{Args, ImplementationVariant};
+use crate::constants::{COLLECTION_MAILBOX_CAPACITY, WRITER_MAILBOX_CAPACITY};
+use crate::memory::resident_set_size;
+use crate::my_async_actors::{
+    ActorHandle, BrokerActorHandle, CollectionActorHandle, DeadLetterActorHandle, Iteration,
+    PerformanceIndicatorsRow, PipelineHandles, PipelineSettings, ProcessorActorHandle,
+    ProcessorActorMsg, StatsActorHandle, WebhookActorHandle, WriterActorHandle,
+};
+use crate::runtime;
+use crate::tail_buffer::TailBufferLimits;
+use crate::types::{Batch, MsgResponseType, Symbol};
+
+/// The measurements of a variant
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Measurement {
+    /// The latency of every iteration
+    pub latencies: Vec<Duration>,
+    /// The resident set size before the first iteration, in bytes
+    pub rss_before: Option<u64>,
+    /// The largest resident set size after an iteration, in bytes
+    pub rss_peak: Option<u64>,
+}
+
+impl Measurement {
+    /// The mean latency of the iterations
+    pub fn mean(&self) -> Duration {
+        match self.latencies.len() {
+            0 => Duration::ZERO,
+            n => self.latencies.iter().sum::<Duration>() / n as u32,
+        }
+    }
+
+    /// The latency that the `percentile` of the iterations don't exceed,
+    /// by the nearest-rank method
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let mut latencies = self.latencies.clone();
+        latencies.sort();
+        let rank = (percentile / 100.0 * latencies.len() as f64).ceil() as usize;
+
+        latencies
+            .get(rank.saturating_sub(1))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Records the resident set size after an iteration
+    fn sample_rss(&mut self) {
+        if let Some(rss) = resident_set_size() {
+            self.rss_peak = Some(self.rss_peak.unwrap_or_default().max(rss));
+        }
+    }
+}
+
+/// A mock data provider, which returns the same deterministic daily closes
+/// of the `symbols` every time, `num_closes` of them per symbol
+pub fn mock_symbols_closes(symbols: &[Symbol], num_closes: usize) -> HashMap<Symbol, Vec<f64>> {
+    symbols
+        .iter()
+        .enumerate()
+        .map(|(i, symbol)| {
+            let closes = (0..num_closes)
+                .map(|day| 100.0 + i as f64 + (day as f64 / 10.0).sin())
+                .collect();
+            (Arc::clone(symbol), closes)
+        })
+        .collect()
+}
+
+/// Returns the chunks of the `symbols` the way that the `variant` makes them in the main loop
+fn chunks_of_symbols<'a>(
+    symbols: &'a [Symbol],
+    chunk_size: usize,
+    variant: &ImplementationVariant,
+) -> Vec<&'a [Symbol]> {
+    match variant {
+        ImplementationVariant::MyActorsNoRayon
+        | ImplementationVariant::ActixActorsNoRayon
+        | ImplementationVariant::NoActorsNoRayon => symbols.chunks(chunk_size).collect(),
+
+        ImplementationVariant::MyActorsStream => symbols.chunks(1).collect(),
+
+        #[cfg(feature = "arrow")]
+        ImplementationVariant::MyActorsArrow => symbols.chunks(chunk_size).collect(),
+
+        ImplementationVariant::MyActorsRayon
+        | ImplementationVariant::ActixActorsRayon
+        | ImplementationVariant::NoActorsRayon => symbols.par_chunks(chunk_size).collect(),
+    }
+}
+
+/// The settings of a benchmark, which are the same for every variant
+struct Bench<'a> {
+    symbols: &'a [Symbol],
+    num_closes: usize,
+    from: OffsetDateTime,
+    chunk_size: usize,
+    window_size: usize,
+    /// The number of symbols that the stream variant processes at the same time
+    stream_concurrency: usize,
+    iterations: usize,
+    shutdown_token: &'a CancellationToken,
+}
+
+impl Bench<'_> {
+    /// Runs the `variant` for the benchmark's iterations, or until the shutdown token is cancelled
+    ///
+    /// # Errors
+    /// - If some messages couldn't be delivered to the actors
+    async fn measure(&self, variant: &ImplementationVariant) -> Result<Measurement> {
+        let chunks = chunks_of_symbols(self.symbols, self.chunk_size, variant);
+        let mut measurement = Measurement {
+            rss_before: resident_set_size(),
+            ..Measurement::default()
+        };
+
+        match variant {
+            ImplementationVariant::MyActorsNoRayon
+            | ImplementationVariant::MyActorsRayon
+            | ImplementationVariant::MyActorsStream => {
+                self.my_actors(&chunks, variant, &mut measurement).await?
+            }
+            #[cfg(feature = "arrow")]
+            ImplementationVariant::MyActorsArrow => {
+                self.my_actors(&chunks, variant, &mut measurement).await?
+            }
+            ImplementationVariant::ActixActorsNoRayon | ImplementationVariant::ActixActorsRayon => {
+                measurement = self.actix_actors(&chunks, variant, measurement).await?
+            }
+            ImplementationVariant::NoActorsNoRayon | ImplementationVariant::NoActorsRayon => {
+                self.no_actors(&chunks, variant, &mut measurement).await?
+            }
+        }
+
+        Ok(measurement)
+    }
+
+    /// The custom actors: a processor actor per chunk, and the writer and collection actors
+    ///
+    /// The stream variant has a chunk per symbol, and runs at most `stream_concurrency`
+    /// processor actors at a time.
+    ///
+    /// The writer actor writes nothing, so that the file system isn't measured.
+    async fn my_actors(
+        &self,
+        chunks: &[&[Symbol]],
+        variant: &ImplementationVariant,
+        measurement: &mut Measurement,
+    ) -> Result<()> {
+        let stats_handle = StatsActorHandle::new(0);
+        let dead_letter_handle = DeadLetterActorHandle::with_stats(0, stats_handle.clone());
+        let broker_handle = BrokerActorHandle::new(0);
+        let handles = PipelineHandles {
+            writer_handle: WriterActorHandle::with_dead_letters(
+                0,
+                WRITER_MAILBOX_CAPACITY,
+                vec![],
+                false,
+                dead_letter_handle.clone(),
+            ),
+            collection_handle: CollectionActorHandle::with_listeners(
+                0,
+                COLLECTION_MAILBOX_CAPACITY,
+                TailBufferLimits::default(),
+                stats_handle.clone(),
+                WebhookActorHandle::new(0),
+                broker_handle.clone(),
+                dead_letter_handle.clone(),
+            ),
+            stats_handle,
+            dead_letter_handle,
+            settings: Arc::new(PipelineSettings {
+                #[cfg(feature = "arrow")]
+                columnar: matches!(variant, ImplementationVariant::MyActorsArrow),
+                ..Default::default()
+            }),
+        };
+        let mut subscription = broker_handle.subscribe().await?;
+
+        for id in 1..=self.iterations as u64 {
+            if self.shutdown_token.is_cancelled() {
+                break;
+            }
+
+            let start = Instant::now();
+            let iteration = Iteration {
+                id,
+                start,
+                to: OffsetDateTime::now_utc(),
+                num_chunks: chunks.len(),
+            };
+            let msg =
+                |(chunk_id, chunk): (usize, &&[Symbol])| ProcessorActorMsg::SymbolsClosesMsg {
+                    symbols_closes: mock_symbols_closes(chunk, self.num_closes),
+                    quality: Vec::new(),
+                    from: self.from,
+                    handles: handles.clone(),
+                    iteration,
+                    chunk_id,
+                    window_size: self.window_size,
+                };
+            let msgs: Vec<_> = match variant {
+                ImplementationVariant::MyActorsRayon => {
+                    chunks.par_iter().enumerate().map(msg).collect()
+                }
+                _ => chunks.iter().enumerate().map(msg).collect(),
+            };
+
+            let process = |msg| async move {
+                let actor_handle = ProcessorActorHandle::new(0);
+                actor_handle
+                    .send(msg)
+                    .await
+                    .context("Couldn't send a chunk to a ProcessorActor.")?;
+                Ok::<_, anyhow::Error>(actor_handle)
+            };
+            if let ImplementationVariant::MyActorsStream = variant {
+                let results: Vec<Result<()>> = futures::stream::iter(msgs)
+                    .map(|msg| async move { process(msg).await?.shutdown().await })
+                    .buffer_unordered(self.stream_concurrency)
+                    .collect()
+                    .await;
+                results.into_iter().collect::<Result<()>>()?;
+            } else {
+                let mut in_flight = JoinSet::new();
+                for msg in msgs {
+                    let actor_handle = process(msg).await?;
+                    in_flight.spawn(async move { actor_handle.shutdown().await });
+                }
+                while in_flight.join_next().await.is_some() {}
+            }
+            let batch: Batch = subscription
+                .recv()
+                .await
+                .context("The BrokerActor stopped before the batch was published.")?;
+            measurement.latencies.push(start.elapsed());
+            measurement.sample_rss();
+            std::hint::black_box(batch);
+        }
+
+        handles.writer_handle.shutdown().await?;
+        handles.collection_handle.shutdown().await?;
+        broker_handle.shutdown().await?;
+        handles.dead_letter_handle.shutdown().await?;
+        handles.stats_handle.shutdown().await?;
+
+        Ok(())
+    }
+
+    /// The Actix actors: a processor actor per chunk, and the writer actor, in an Actix system
+    /// of their own, which runs on a blocking thread, and which returns the `measurement`
+    ///
+    /// The writer actor writes nothing, and tells the benchmark about every chunk instead,
+    /// so that an iteration is complete once the writer has been told about all of its chunks.
+    ///
+    /// # Errors
+    /// - If some messages couldn't be delivered to the actors
+    /// - If the thread of the Actix system panicked
+    async fn actix_actors(
+        &self,
+        chunks: &[&[Symbol]],
+        variant: &ImplementationVariant,
+        mut measurement: Measurement,
+    ) -> Result<Measurement> {
+        let chunks: Vec<Vec<Symbol>> = chunks.iter().map(|chunk| chunk.to_vec()).collect();
+        let variant = variant.clone();
+        let num_closes = self.num_closes;
+        let from = self.from;
+        let iterations = self.iterations;
+        let shutdown_token = self.shutdown_token.clone();
+
+        let system = move || {
+            actix_rt::System::new().block_on(async move {
+                let (listener, mut written) = tokio::sync::mpsc::unbounded_channel();
+                let writer_address = WriterActor::with_listener(listener).start();
+
+                for _ in 0..iterations {
+                    if shutdown_token.is_cancelled() {
+                        break;
+                    }
+
+                    let start = Instant::now();
+                    let symbols_closes = |chunk: &Vec<Symbol>| -> HashMap<String, Vec<f64>> {
+                        mock_symbols_closes(chunk, num_closes)
+                            .into_iter()
+                            .map(|(symbol, closes)| (symbol.to_string(), closes))
+                            .collect()
+                    };
+                    let chunks_closes: Vec<_> = match variant {
+                        ImplementationVariant::ActixActorsRayon => {
+                            chunks.par_iter().map(symbols_closes).collect()
+                        }
+                        _ => chunks.iter().map(symbols_closes).collect(),
+                    };
+
+                    for symbols_closes in chunks_closes {
+                        ProcessorActor
+                            .start()
+                            .send(SymbolsClosesMsg {
+                                symbols_closes,
+                                from,
+                                writer_address: writer_address.clone(),
+                                start,
+                                echo: false,
+                            })
+                            .await
+                            .context("Couldn't send a chunk to a ProcessorActor.")?;
+                    }
+                    let mut rows = 0;
+                    for _ in 0..chunks.len() {
+                        rows += written
+                            .recv()
+                            .await
+                            .context("The WriterActor stopped before all chunks were written.")?;
+                    }
+                    measurement.latencies.push(start.elapsed());
+                    measurement.sample_rss();
+                    std::hint::black_box(rows);
+                }
+
+                Ok(measurement)
+            })
+        };
+
+        runtime::spawn_blocking(system)
+            .await
+            .context("The Actix system's thread panicked.")?
+    }
+
+    /// No actors: a task per chunk, which calculates the rows of its symbols
+    async fn no_actors(
+        &self,
+        chunks: &[&[Symbol]],
+        variant: &ImplementationVariant,
+        measurement: &mut Measurement,
+    ) -> Result<()> {
+        for _ in 0..self.iterations {
+            if self.shutdown_token.is_cancelled() {
+                break;
+            }
+
+            let start = Instant::now();
+            let symbols_closes = |chunk: &&[Symbol]| mock_symbols_closes(chunk, self.num_closes);
+            let chunks_closes: Vec<_> = match variant {
+                ImplementationVariant::NoActorsRayon => {
+                    chunks.par_iter().map(symbols_closes).collect()
+                }
+                _ => chunks.iter().map(symbols_closes).collect(),
+            };
+
+            let mut in_flight = JoinSet::new();
+            for symbols_closes in chunks_closes {
+                let window_size = self.window_size;
+                in_flight.spawn(async move {
+                    let mut rows = Vec::with_capacity(symbols_closes.len());
+                    for (symbol, closes) in &symbols_closes {
+                        rows.extend(
+                            PerformanceIndicatorsRow::calculate(
+                                Arc::clone(symbol),
+                                closes,
+                                window_size,
+                            )
+                            .await,
+                        );
+                    }
+                    rows
+                });
+            }
+            let mut batch: Batch = Vec::with_capacity(self.symbols.len());
+            while let Some(rows) = in_flight.join_next().await {
+                batch.extend(rows.context("A chunk's task failed.")?);
+            }
+            measurement.latencies.push(start.elapsed());
+            measurement.sample_rss();
+            std::hint::black_box(batch);
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats a size in bytes in mebibytes
+fn mib(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(bytes) => format!("{:.1}", bytes as f64 / (1024.0 * 1024.0)),
+        None => "n/a".to_string(),
+    }
+}
+
+/// Formats a duration in milliseconds
+fn ms(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+/// Returns the comparison table of the variants' `measurements`
+pub fn table(measurements: &[(String, Measurement)]) -> String {
+    let mut table = format!(
+        "{:<24}{:>12}{:>12}{:>12}{:>12}{:>12}{:>16}{:>16}\n",
+        "variant",
+        "iterations",
+        "mean (ms)",
+        "p50 (ms)",
+        "p95 (ms)",
+        "max (ms)",
+        "peak RSS (MiB)",
+        "RSS delta (MiB)"
+    );
+
+    for (variant, measurement) in measurements {
+        let delta = measurement
+            .rss_peak
+            .zip(measurement.rss_before)
+            .map(|(peak, before)| peak.saturating_sub(before));
+        let line = format!(
+            "{:<24}{:>12}{:>12}{:>12}{:>12}{:>12}{:>16}{:>16}",
+            variant,
+            measurement.latencies.len(),
+            ms(measurement.mean()),
+            ms(measurement.percentile(50.0)),
+            ms(measurement.percentile(95.0)),
+            ms(measurement.percentile(100.0)),
+            mib(measurement.rss_peak),
+            mib(delta)
+        );
+        table.push_str(&line);
+        table.push('\n');
+    }
+
+    table
+}
+
+/// **The benchmark of the implementation variants**
+///
+/// Runs every [`ImplementationVariant`] for `iterations` iterations over the symbols of the `args`,
+/// whose closes come from a mock data provider, and prints the comparison table.
+///
+/// The variants that haven't been run yet are skipped when the `shutdown_token` is cancelled,
+/// but the measurements so far are still printed.
+///
+/// # Errors
+/// - [time::error::Parse](https://docs.rs/time/0.3.36/time/error/enum.Parse.html)
+/// - [std::io::Error] if the symbols file can't be read
+/// - If some messages couldn't be delivered to the actors
+pub async fn run(
+    args: Args,
+    iterations: usize,
+    shutdown_token: CancellationToken,
+) -> Result<MsgResponseType> {
+    let now = OffsetDateTime::now_utc();
+    let (from, to) = args.period(now)?;
+    let to = to.unwrap_or(now);
+    let symbols: Vec<Symbol> = args.symbols()?.into_iter().map(Symbol::from).collect();
+    let bench = Bench {
+        symbols: &symbols,
+        num_closes: (to - from).whole_days().max(1) as usize,
+        from,
+        chunk_size: args.chunk_size,
+        window_size: args.window_size,
+        stream_concurrency: args.stream_concurrency,
+        iterations,
+        shutdown_token: &shutdown_token,
+    };
+
+    let mut measurements = Vec::new();
+    for variant in ImplementationVariant::value_variants() {
+        if shutdown_token.is_cancelled() {
+            break;
+        }
+
+        let name = variant
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_else(|| format!("{:?}", variant));
+        tracing::info!("Benchmarking the {} variant...", name);
+        measurements.push((name, bench.measure(variant).await?));
+    }
+
+    print!("{}", table(&measurements));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_are_nearest_ranks() {
+        let measurement = Measurement {
+            latencies: (1..=20).rev().map(Duration::from_millis).collect(),
+            ..Measurement::default()
+        };
+
+        assert_eq!(Duration::from_micros(10_500), measurement.mean());
+        assert_eq!(Duration::from_millis(10), measurement.percentile(50.0));
+        assert_eq!(Duration::from_millis(19), measurement.percentile(95.0));
+        assert_eq!(Duration::from_millis(20), measurement.percentile(100.0));
+        assert_eq!(Duration::ZERO, Measurement::default().percentile(95.0));
+    }
+
+    #[tokio::test]
+    async fn every_variant_completes_its_iterations() {
+        let symbols: Vec<Symbol> = (0..7).map(|i| format!("SYM{}", i).into()).collect();
+        let shutdown_token = CancellationToken::new();
+        let bench = Bench {
+            symbols: &symbols,
+            num_closes: 50,
+            from: OffsetDateTime::now_utc(),
+            chunk_size: 3,
+            window_size: 5,
+            stream_concurrency: 2,
+            iterations: 2,
+            shutdown_token: &shutdown_token,
+        };
+
+        for variant in ImplementationVariant::value_variants() {
+            let measurement = bench.measure(variant).await.unwrap();
+            assert_eq!(2, measurement.latencies.len(), "{variant:?}");
+        }
+
+        let table = table(&[("no-actors-rayon".to_string(), Measurement::default())]);
+        assert!(table.starts_with("variant"));
+        assert!(table.contains("no-actors-rayon"));
+    }
+}
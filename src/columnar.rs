@@ -0,0 +1,133 @@
+//! The columnar processing of a chunk
+//!
+//! The `my-actors-arrow` variant is a performance experiment: instead of calculating the performance
+//! indicators symbol by symbol, with the signals of [`crate::async_signals`], over a vector of closes
+//! each, the processing puts the closes of the whole chunk in a single Arrow list array, and calculates
+//! every symbol's indicators with the `arrow-arith` compute kernels, over zero-copy slices of it.
+//!
+//! The rows are the same as the ones of the other variants, save for the rounding of the sums
+//! of the SMA, which the kernels add up in a different order.
+
+use std::collections::HashMap;
+
+use arrow_arith::aggregate;
+use arrow_array::cast::AsArray;
+use arrow_array::types::Float64Type;
+use arrow_array::{Float64Array, ListArray};
+
+use crate::asset_classes::AssetClass;
+use crate::my_async_actors::PerformanceIndicatorsRow;
+use crate::types::Symbol;
+
+/// The closes of a chunk's symbols, in a list array of a list of closes per symbol
+#[derive(Debug)]
+pub struct ColumnarCloses {
+    symbols: Vec<Symbol>,
+    closes: ListArray,
+}
+
+impl ColumnarCloses {
+    /// Copies the `symbols_closes` into a list array, in the order of the symbols
+    pub fn new(symbols_closes: &HashMap<Symbol, Vec<f64>>) -> Self {
+        let symbols = symbols_closes.keys().cloned().collect();
+        let closes = ListArray::from_iter_primitive::<Float64Type, _, _>(
+            symbols_closes
+                .values()
+                .map(|closes| Some(closes.iter().copied().map(Some))),
+        );
+
+        Self { symbols, closes }
+    }
+
+    /// Calculates the performance indicators of every symbol, with an SMA of `window_size` closes
+    ///
+    /// # Returns
+    /// The rows by their symbols; a symbol without closes has no row
+    pub fn rows(&self, window_size: usize) -> HashMap<Symbol, PerformanceIndicatorsRow> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter_map(|(index, symbol)| {
+                let closes = self.closes.value(index);
+                let row = Self::row(symbol, closes.as_primitive::<Float64Type>(), window_size)?;
+                Some((Symbol::clone(symbol), row))
+            })
+            .collect()
+    }
+
+    /// Calculates the performance indicators of the `symbol` over its `closes`, the way that
+    /// [`PerformanceIndicatorsRow::calculate`] does
+    fn row(
+        symbol: &Symbol,
+        closes: &Float64Array,
+        window_size: usize,
+    ) -> Option<PerformanceIndicatorsRow> {
+        let len = closes.len();
+        let last_price = closes.values().last().copied()?;
+
+        let first = closes.value(0);
+        let first = if first == 0.0 { 1.0 } else { first };
+        let pct_change = (last_price - closes.value(0)) / first * 100.0;
+
+        // only the newest window's average is in the row
+        let sma = if window_size > 1 && len >= window_size {
+            aggregate::sum(&closes.slice(len - window_size, window_size)).unwrap_or_default()
+                / window_size as f64
+        } else {
+            0.0
+        };
+
+        Some(PerformanceIndicatorsRow {
+            symbol: Symbol::clone(symbol),
+            last_price,
+            pct_change,
+            period_min: aggregate::min(closes).unwrap_or_default(),
+            period_max: aggregate::max(closes).unwrap_or_default(),
+            sma,
+            asset_class: AssetClass::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn columnar_rows_are_the_rows_of_the_signals() {
+        let series: [(&str, Vec<f64>); 5] = [
+            (
+                "AAPL",
+                (0..50).map(|day| 100.0 + (day as f64).sin()).collect(),
+            ),
+            ("MSFT", vec![0.0, 2.0, 1.0, 4.0]),
+            ("NVDA", vec![3.0, 2.0]),
+            ("UBER", vec![7.0]),
+            ("META", vec![]),
+        ];
+        let symbols_closes: HashMap<Symbol, Vec<f64>> = series
+            .into_iter()
+            .map(|(symbol, closes)| (Symbol::from(symbol), closes))
+            .collect();
+
+        let rows = ColumnarCloses::new(&symbols_closes).rows(3);
+        assert_eq!(4, rows.len());
+        for (symbol, closes) in &symbols_closes {
+            let expected =
+                PerformanceIndicatorsRow::calculate(Symbol::clone(symbol), closes, 3).await;
+            let Some(expected) = expected else {
+                assert!(!rows.contains_key(symbol));
+                continue;
+            };
+            let row = &rows[symbol];
+            assert_eq!(expected.last_price, row.last_price, "{symbol}");
+            assert_eq!(expected.pct_change, row.pct_change, "{symbol}");
+            assert_eq!(expected.period_min, row.period_min, "{symbol}");
+            assert_eq!(expected.period_max, row.period_max, "{symbol}");
+            assert!(
+                (expected.sma - row.sma).abs() <= 1e-9 * expected.sma,
+                "{symbol}"
+            );
+        }
+    }
+}
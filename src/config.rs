@@ -0,0 +1,230 @@
+//! Runtime configuration
+//!
+//! Some settings can be changed while the application is running, through the web app.
+//!
+//! The configuration is owned by the [`crate::my_async_actors::ConfigActorHandle`]'s actor,
+//! and the main loop fetches it at every tick, so changes take effect in the next iteration.
+//!
+//! The settings that the long-lived actors work with are also pushed to them as a [`Reconfiguration`],
+//! so that they take effect without a restart.
+
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::constants::{
+    CHUNK_SIZE, FLUSH_INTERVAL_SECS, INDICATORS, MAX_CHUNK_SIZE, MAX_FLUSH_INTERVAL_SECS,
+    MAX_TICK_INTERVAL_SECS, MAX_WINDOW_SIZE, MIN_TICK_INTERVAL_SECS, TICK_INTERVAL_SECS,
+    WINDOW_SIZE,
+};
+
+/// The current runtime settings
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct RuntimeConfig {
+    /// The main loop's tick interval, in seconds
+    pub tick_interval_secs: u64,
+    /// The number of symbols that a single fetch actor works with
+    pub chunk_size: usize,
+    /// The simple moving average's window size
+    pub window_size: usize,
+    /// The performance indicators that are served by the web app and by the gRPC API;
+    /// the other ones are reported as zero
+    pub indicators: Vec<String>,
+    /// How often the output file is flushed, in seconds; zero means after every chunk
+    pub flush_interval_secs: u64,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            tick_interval_secs: TICK_INTERVAL_SECS,
+            chunk_size: CHUNK_SIZE,
+            window_size: WINDOW_SIZE,
+            indicators: INDICATORS.iter().map(|i| i.to_string()).collect(),
+            flush_interval_secs: FLUSH_INTERVAL_SECS,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Applies a validated `update` to the configuration
+    ///
+    /// Only the provided fields are changed.
+    pub fn apply(&mut self, update: ConfigUpdate) {
+        if let Some(tick_interval_secs) = update.tick_interval_secs {
+            self.tick_interval_secs = tick_interval_secs;
+        }
+        if let Some(chunk_size) = update.chunk_size {
+            self.chunk_size = chunk_size;
+        }
+        if let Some(window_size) = update.window_size {
+            self.window_size = window_size;
+        }
+        if let Some(indicators) = update.indicators {
+            self.indicators = indicators;
+        }
+        if let Some(flush_interval_secs) = update.flush_interval_secs {
+            self.flush_interval_secs = flush_interval_secs;
+        }
+    }
+
+    /// Returns the settings that the long-lived actors work with
+    pub fn reconfiguration(&self) -> Reconfiguration {
+        Reconfiguration {
+            window_size: self.window_size,
+            indicators: self.indicators.clone(),
+            flush_interval: Duration::from_secs(self.flush_interval_secs),
+        }
+    }
+}
+
+/// The settings that are pushed to the long-lived actors when the runtime configuration changes
+///
+/// Every actor picks the settings that it works with:
+/// - the processor actors, the window size,
+/// - the writer actor, the flush interval,
+/// - the collection actor, the indicator set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reconfiguration {
+    /// The simple moving average's window size
+    pub window_size: usize,
+    /// The performance indicators that are served; the other ones are reported as zero
+    pub indicators: Vec<String>,
+    /// How often the output file is flushed; zero means after every chunk
+    pub flush_interval: Duration,
+}
+
+impl Default for Reconfiguration {
+    fn default() -> Self {
+        RuntimeConfig::default().reconfiguration()
+    }
+}
+
+/// A subset of runtime settings that can be updated live
+///
+/// All fields are optional; only the provided ones are updated.
+#[derive(Clone, Debug, Default, Deserialize, ToSchema)]
+pub struct ConfigUpdate {
+    /// The main loop's tick interval, in seconds
+    pub tick_interval_secs: Option<u64>,
+    /// The number of symbols that a single fetch actor works with
+    pub chunk_size: Option<usize>,
+    /// The simple moving average's window size
+    pub window_size: Option<usize>,
+    /// The performance indicators that are served, out of the calculated ones
+    pub indicators: Option<Vec<String>>,
+    /// How often the output file is flushed, in seconds; zero means after every chunk
+    pub flush_interval_secs: Option<u64>,
+}
+
+impl ConfigUpdate {
+    /// Checks that all provided values are within their sanity bounds
+    ///
+    /// # Errors
+    /// - If a value is out of its bounds; the error message describes the bounds
+    pub fn validate(&self) -> Result<()> {
+        if let Some(tick) = self.tick_interval_secs {
+            if !(MIN_TICK_INTERVAL_SECS..=MAX_TICK_INTERVAL_SECS).contains(&tick) {
+                bail!(
+                    "The tick interval must be between {} and {} seconds.",
+                    MIN_TICK_INTERVAL_SECS,
+                    MAX_TICK_INTERVAL_SECS
+                );
+            }
+        }
+        if let Some(chunk_size) = self.chunk_size {
+            if !(1..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+                bail!("The chunk size must be between 1 and {}.", MAX_CHUNK_SIZE);
+            }
+        }
+        if let Some(window_size) = self.window_size {
+            if !(2..=MAX_WINDOW_SIZE).contains(&window_size) {
+                bail!("The window size must be between 2 and {}.", MAX_WINDOW_SIZE);
+            }
+        }
+        if let Some(indicators) = &self.indicators {
+            if let Some(unknown) = indicators
+                .iter()
+                .find(|indicator| !INDICATORS.contains(&indicator.as_str()))
+            {
+                bail!(
+                    "Unknown indicator \"{}\"; the indicators are: {}.",
+                    unknown,
+                    INDICATORS.join(", ")
+                );
+            }
+        }
+        if let Some(flush_interval_secs) = self.flush_interval_secs {
+            if flush_interval_secs > MAX_FLUSH_INTERVAL_SECS {
+                bail!(
+                    "The flush interval must be at most {} seconds.",
+                    MAX_FLUSH_INTERVAL_SECS
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_applies_only_provided_fields() {
+        let mut config = RuntimeConfig::default();
+        config.apply(ConfigUpdate {
+            chunk_size: Some(10),
+            ..Default::default()
+        });
+
+        assert_eq!(10, config.chunk_size);
+        assert_eq!(TICK_INTERVAL_SECS, config.tick_interval_secs);
+        assert_eq!(WINDOW_SIZE, config.window_size);
+    }
+
+    #[test]
+    fn update_out_of_bounds_is_rejected() {
+        let update = ConfigUpdate {
+            tick_interval_secs: Some(0),
+            ..Default::default()
+        };
+        assert!(update.validate().is_err());
+
+        let update = ConfigUpdate {
+            chunk_size: Some(0),
+            ..Default::default()
+        };
+        assert!(update.validate().is_err());
+
+        let update = ConfigUpdate {
+            window_size: Some(1),
+            ..Default::default()
+        };
+        assert!(update.validate().is_err());
+
+        let update = ConfigUpdate {
+            indicators: Some(vec!["sma".to_string(), "rsi".to_string()]),
+            ..Default::default()
+        };
+        assert!(update.validate().is_err());
+
+        let update = ConfigUpdate {
+            flush_interval_secs: Some(MAX_FLUSH_INTERVAL_SECS + 1),
+            ..Default::default()
+        };
+        assert!(update.validate().is_err());
+
+        let update = ConfigUpdate {
+            tick_interval_secs: Some(60),
+            chunk_size: Some(1),
+            window_size: Some(2),
+            indicators: Some(vec!["price".to_string(), "sma".to_string()]),
+            flush_interval_secs: Some(MAX_FLUSH_INTERVAL_SECS),
+        };
+        assert!(update.validate().is_ok());
+    }
+}
@@ -0,0 +1,145 @@
+//! The configuration file
+//!
+//! It's a TOML file whose keys are the long names of the command-line options,
+//! e.g., `tick-interval = 60`, so that a set of options doesn't have to be repeated on every run.
+//! The options that are provided on the command line take precedence over the file.
+//!
+//! Named profiles, such as `[profile.dev]` and `[profile.prod]`, override the top-level settings,
+//! and one of them is selected with the `--profile` option:
+//!
+//! ```toml
+//! symbols = ["AAPL", "MSFT"]
+//! tick-interval = 60
+//!
+//! [profile.dev]
+//! tick-interval = 5
+//! dry-run = true
+//!
+//! [profile.prod]
+//! tick-interval = 3600
+//! format = ["csv", "parquet"]
+//! ```
+//!
+//! A `true` boolean stands for a flag, such as `dry-run`, and an array stands for a comma-separated list.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use toml::{Table, Value};
+
+/// The key of the table of the named profiles
+const PROFILES: &str = "profile";
+
+/// Loads the settings of the config file at `path`: the top-level ones,
+/// overridden by those of the `profile`, if it's provided
+///
+/// # Errors
+/// - [std::io::Error] if the file can't be read
+/// - If the file isn't valid TOML, or if the profile isn't in it
+pub fn load(path: &Path, profile: Option<&str>) -> Result<Table> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read the config file {}.", path.display()))?;
+
+    parse(&contents, profile)
+        .with_context(|| format!("The config file {} isn't valid.", path.display()))
+}
+
+/// Parses the settings of a config file, and applies the `profile` to them, if it's provided
+fn parse(contents: &str, profile: Option<&str>) -> Result<Table> {
+    let mut settings: Table = contents.parse()?;
+    let profiles = match settings.remove(PROFILES) {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => bail!("`{}` must be a table of profiles.", PROFILES),
+        None => Table::new(),
+    };
+
+    if let Some(profile) = profile {
+        match profiles.get(profile) {
+            Some(Value::Table(overrides)) => settings.extend(overrides.clone()),
+            Some(_) => bail!("The profile `{}` must be a table.", profile),
+            None => {
+                let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                bail!(
+                    "There is no profile `{}`; the profiles are: [{}].",
+                    profile,
+                    names.join(", ")
+                );
+            }
+        }
+    }
+
+    Ok(settings)
+}
+
+/// Converts the setting `key` to the command-line arguments of its option
+///
+/// # Errors
+/// - If the `value` can't be an option's value, e.g., if it's a table
+pub fn to_args(key: &str, value: &Value) -> Result<Vec<String>> {
+    let value = match value {
+        Value::Boolean(true) => return Ok(vec![format!("--{}", key)]),
+        Value::Boolean(false) => return Ok(vec![]),
+        Value::String(value) => value.clone(),
+        Value::Integer(_) | Value::Float(_) => value.to_string(),
+        Value::Datetime(datetime) => datetime.to_string(),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::String(value) => Ok(value.clone()),
+                Value::Integer(_) | Value::Float(_) => Ok(value.to_string()),
+                _ => bail!("The setting `{}` can only list strings and numbers.", key),
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join(","),
+        Value::Table(_) => bail!("The setting `{}` can't be a table.", key),
+    };
+
+    // the value is attached, so that a value which starts with a hyphen isn't taken for an option
+    Ok(vec![format!("--{}={}", key, value)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        symbols = ["AAPL", "MSFT"]
+        tick-interval = 60
+        from = 2024-01-01T00:00:00Z
+
+        [profile.dev]
+        tick-interval = 5
+        dry-run = true
+
+        [profile.prod]
+        format = ["csv", "parquet"]
+    "#;
+
+    #[test]
+    fn profile_overrides_top_level_settings() {
+        let settings = parse(CONFIG, Some("dev")).unwrap();
+        let args: Vec<String> = settings
+            .iter()
+            .flat_map(|(key, value)| to_args(key, value).unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                "--dry-run",
+                "--from=2024-01-01T00:00:00Z",
+                "--symbols=AAPL,MSFT",
+                "--tick-interval=5",
+            ],
+            args
+        );
+
+        let settings = parse(CONFIG, None).unwrap();
+        assert_eq!(Some(&Value::Integer(60)), settings.get("tick-interval"));
+        assert!(!settings.contains_key(PROFILES));
+
+        let err = parse(CONFIG, Some("staging")).unwrap_err().to_string();
+        assert_eq!(
+            "There is no profile `staging`; the profiles are: [dev, prod].",
+            err
+        );
+    }
+}
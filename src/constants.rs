@@ -1,19 +1,208 @@
 pub const TICK_INTERVAL_SECS: u64 = 5;
-pub const SHUTDOWN_INTERVAL_SECS: u64 = 2;
+pub const MIN_TICK_INTERVAL_SECS: u64 = 1;
+pub const MAX_TICK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// The default number of ticks that an iteration of the main loop can miss before it is cancelled
+pub const MAX_MISSED_TICKS: u32 = 3;
+
+/// How often the main-loop watchdog checks the running iteration, in milliseconds
+pub const LOOP_WATCHDOG_CHECK_INTERVAL_MS: u64 = 500;
 
 pub const CHUNK_SIZE: usize = 5;
+pub const MAX_CHUNK_SIZE: usize = 1000;
 
 pub const NUM_THREADS: usize = 4;
 
+/// The default number of fetch workers in the pool
+pub const FETCH_POOL_SIZE: usize = 16;
+
+/// The default number of symbols that the stream variant fetches and processes at the same time
+pub const STREAM_CONCURRENCY: usize = 16;
+
+/// The default time limit for a fetch actor to handle a single message, in seconds
+pub const HANDLER_TIMEOUT_SECS: u64 = 30;
+
+/// The default time limit for an embedded pipeline's triggered batch to be assembled, in seconds;
+/// a chunk that is given up on never makes it into a batch
+pub const TRIGGER_TIMEOUT_SECS: u64 = 2 * HANDLER_TIMEOUT_SECS;
+
 pub const WINDOW_SIZE: usize = 30;
+pub const MAX_WINDOW_SIZE: usize = 1000;
+
+/// The default window size of the intraday pipeline's simple moving average, in bars
+pub const INTRADAY_WINDOW_SIZE: usize = 12;
+
+/// How far before the end of its period the intraday pipeline's period starts, in days
+pub const INTRADAY_LOOKBACK_DAYS: i64 = 5;
+
+/// The maximum length of a symbol, which is well above the length of any ticker
+pub const MAX_SYMBOL_LEN: usize = 16;
+
+/// The config file that is used if it exists and no other one is provided
+pub const CONFIG_FILE_PATH: &str = "./config.toml";
+
+/// The `from` date and time that the interactive prompt suggests, a year before the start
+pub const DEFAULT_FROM: &str = "-1y";
+
+/// How far the end of the period moves at every iteration of a backfill, in days
+pub const BACKFILL_STEP_DAYS: u64 = 1;
+
+/// The data-quality checks: the longest gap of missing trading days that is taken for holidays,
+/// and the age of the newest bar, relative to the end of the period, after which a series is stale
+pub const QUALITY_MAX_GAP_DAYS: usize = 1;
+pub const QUALITY_STALE_DAYS: i64 = 5;
+/// The time by which a bar can be ahead of the local clock, i.e., after the end of the period,
+/// before it's taken for the provider's clock, or the local one, being off, and dropped
+pub const QUALITY_CLOCK_SKEW_SECS: i64 = 300;
+
+/// The default number of iterations of every implementation variant in a benchmark
+pub const BENCH_VARIANTS_ITERATIONS: usize = 10;
+
+/// The default speed multiplier of a replay, by which the tick interval is divided
+pub const REPLAY_SPEED: f64 = 1.0;
+
+/// The default confidence level of the historical Value-at-Risk and Conditional Value-at-Risk
+pub const RISK_CONFIDENCE: f64 = 0.95;
+
+/// The maximum number of symbols in every ranking of the top movers
+pub const MOVERS_SIZE: usize = 5;
+
+/// The default windows of the fast and the slow simple moving averages of the SMA crossover strategy
+pub const BACKTEST_FAST_WINDOW: usize = 10;
+pub const BACKTEST_SLOW_WINDOW: usize = 30;
+
+/// The paths of a backtest's output files, without the extensions of their formats
+pub const BACKTEST_TRADES_FILE_STEM: &str = "./backtest-trades";
+pub const BACKTEST_SUMMARY_FILE_STEM: &str = "./backtest-summary";
+
+/// The default initial cash of the paper-trading account, and the value of the strategy's buy orders
+pub const PAPER_CASH: f64 = 100_000.0;
+pub const PAPER_ORDER_VALUE: f64 = 10_000.0;
+/// The number of the newest fills that the paper-trading account keeps
+pub const PAPER_FILLS_SIZE: usize = 100;
+
+/// The path of a watchlist's output files, to which the watchlist's name and the extensions
+/// of the formats are appended
+pub const WATCHLIST_FILE_STEM: &str = "./output";
+
+/// The output files of the intraday pipeline, with the extension of their format, e.g., `intraday.csv`
+pub const INTRADAY_FILE_STEM: &str = "./intraday";
+
+/// The file of the portfolio's valuations, a row per holding and a total row per iteration
+pub const PORTFOLIO_FILE_PATH: &str = "./portfolio.csv";
+pub const PORTFOLIO_CSV_HEADER: &str = "time,symbol,quantity,cost basis,price,market value,\
+    unrealized p&l,unrealized p&l %,daily change,daily change %";
+
+/// The file of the top movers, a row per ranked symbol per iteration
+pub const MOVERS_FILE_PATH: &str = "./movers.csv";
+pub const MOVERS_CSV_HEADER: &str = "time,iteration,ranking,rank,symbol,change %,volatility %";
+
+/// The file of the option chains' summaries, a row per underlying per iteration
+pub const OPTIONS_FILE_PATH: &str = "./options.csv";
+pub const OPTIONS_CSV_HEADER: &str =
+    "time,underlying,nearest expiry,contracts,call volume,put volume,put/call ratio,max pain";
+
+/// The performance indicators that we calculate for each symbol
+pub const INDICATORS: [&str; 5] = ["price", "change %", "min", "max", "sma"];
+
+/// How often the writer actor flushes the output file, in seconds; zero means after every chunk
+pub const FLUSH_INTERVAL_SECS: u64 = 0;
+pub const MAX_FLUSH_INTERVAL_SECS: u64 = 60;
 
 pub const CSV_FILE_PATH: &str = "./output.csv";
+pub const JSONL_FILE_PATH: &str = "./output.jsonl";
+pub const PARQUET_FILE_PATH: &str = "./output.parquet";
 pub const CSV_HEADER: &str = "period start,symbol,price,change %,min,max,30d avg";
 
+/// The tracing target of the calculated rows, which the `quiet` option turns off;
+/// it's under the crate's target, so the crate's tracing directives also apply to it
+pub const ROWS_TRACING_TARGET: &str = "stock_trading_cli_with_async_streams::rows";
+
+/// The mailbox capacity of the actors whose mailbox capacity isn't configurable
 pub const ACTOR_CHANNEL_CAPACITY: usize = 1;
+/// The default mailbox capacity of the writer actor, which gets a message for every chunk of symbols
+pub const WRITER_MAILBOX_CAPACITY: usize = 16;
+/// The default mailbox capacity of the collection actor, which gets a message for every chunk of symbols
+pub const COLLECTION_MAILBOX_CAPACITY: usize = 16;
+/// The capacity of the collection actor's priority lane, which gets the tail requests
+/// from the web app and from the gRPC API
+pub const COLLECTION_PRIORITY_MAILBOX_CAPACITY: usize = 16;
+/// The default capacity of the fetch workers' shared work queue, in chunks of symbols
+pub const FETCH_QUEUE_CAPACITY: usize = 16;
 pub const SHUTDOWN_CHANNEL_CAPACITY: usize = 1;
 
+/// How many times a crashed supervised actor is restarted before the supervisor gives up on it
+pub const MAX_ACTOR_RESTARTS: usize = 5;
+
+/// How often the watchdog pings the writer and collection actors, in seconds
+pub const HEARTBEAT_INTERVAL_SECS: u64 = 5;
+/// How long the watchdog waits for an actor to answer a ping before it considers the actor unresponsive,
+/// in seconds
+pub const HEARTBEAT_TIMEOUT_SECS: u64 = 2;
+
+/// How long the writer and collection actors may take to handle a single message before it is logged
+/// as slow, in milliseconds
+pub const SLOW_HANDLER_THRESHOLD_MS: u64 = 500;
+
+/// The crate version
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The git commit that the app was built from, collected by the build script
+pub const GIT_COMMIT_HASH: &str = env!("GIT_COMMIT_HASH");
+/// The time at which the app was built, collected by the build script
+pub const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
+
 pub const WEB_SERVER_ADDRESS: &str = "127.0.0.1:3000";
+pub const GRPC_SERVER_ADDRESS: &str = "127.0.0.1:50051";
 
-/// The tail buffer's capacity in terms of the number of batches it can hold
+/// The header that carries a request's correlation ID, both in the web app and in the gRPC API
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The interval after which a client is allowed one more tail request, in milliseconds
+pub const TAIL_RATE_LIMIT_REPLENISH_MS: u64 = 500;
+/// The maximum number of tail requests that a client can send in a burst
+pub const TAIL_RATE_LIMIT_BURST_SIZE: u32 = 10;
+/// How often the per-client rate-limiter state is purged of stale clients
+pub const RATE_LIMITER_CLEANUP_INTERVAL_SECS: u64 = 60;
+
+/// The default tail buffer's capacity in terms of the number of batches it can hold
 pub const TAIL_BUFFER_SIZE: usize = 10;
+
+/// How much memory the tail buffer's batches may take up before the oldest ones are spilled to disk, in MiB
+pub const TAIL_MEMORY_BUDGET_MB: usize = 64;
+
+/// The number of batches that can wait for a slow gRPC subscriber before new ones are dropped for it
+pub const SUBSCRIBER_CHANNEL_CAPACITY: usize = 16;
+
+/// The number of the most recent dead letters that are kept
+pub const DEAD_LETTER_BUFFER_SIZE: usize = 100;
+
+/// The timeout of a single webhook request, in seconds
+pub const WEBHOOK_TIMEOUT_SECS: u64 = 5;
+
+/// The maximum number of headlines that are served per symbol
+pub const NEWS_SIZE: usize = 10;
+
+/// The timeout of a single news provider request, in seconds
+pub const NEWS_TIMEOUT_SECS: u64 = 10;
+
+/// The timeout of fetching a single option chain, in seconds
+pub const OPTIONS_TIMEOUT_SECS: u64 = 10;
+
+/// The default maximum number of alerts that are posted to a Slack or Discord channel per minute
+pub const ALERT_CHAT_RATE_LIMIT: u32 = 10;
+
+/// The default subject template of the email alerts
+pub const ALERT_EMAIL_SUBJECT: &str = "Stock alert: {symbol} satisfies `{rule}`";
+
+/// The default body template of the email alerts
+pub const ALERT_EMAIL_BODY: &str = "At {time}, {symbol} satisfies the alert rule `{rule}`.
+
+price: ${price}
+change: {pct_change}%
+min: ${period_min}
+max: ${period_max}
+sma: ${sma}
+";
+
+/// The number of symbols that are named in the summary of a kind of fetch failure
+pub const FAILURE_EXAMPLES: usize = 3;
@@ -0,0 +1,126 @@
+//! Aggregation of fetch failures
+//!
+//! When the data provider is down, every symbol fails at every tick. Instead of logging a warning
+//! per symbol, the fetch actors report the failures of their chunks to the statistics actor,
+//! [`crate::my_async_actors::StatsActorHandle`], which collapses the identical ones, and logs
+//! a single warning per kind of failure whenever an iteration is completed or cancelled, e.g.,
+//! `412 fetches failed with "error sending request" in the last iteration, e.g., AAPL, MSFT, NVDA.`
+//!
+//! Failures are identical if their error messages are, once the symbol has been left out of them.
+//! Every failure is still logged on its own, at the debug level.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::constants::FAILURE_EXAMPLES;
+use crate::types::Symbol;
+
+/// The failures of a kind
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Failures {
+    count: u64,
+    /// The first few symbols that failed
+    examples: Vec<Symbol>,
+}
+
+/// The fetch failures, by their error messages
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ErrorSummary {
+    failures: BTreeMap<String, Failures>,
+}
+
+impl ErrorSummary {
+    /// Records a failure of a `symbol`, which the data provider knows as `provider_symbol`,
+    /// with the error `message`
+    pub fn record(&mut self, symbol: &Symbol, provider_symbol: &str, message: &str) {
+        let message = message.replace(provider_symbol, "<symbol>");
+        let failures = self.failures.entry(message).or_default();
+        failures.count += 1;
+        if failures.examples.len() < FAILURE_EXAMPLES {
+            failures.examples.push(Symbol::clone(symbol));
+        }
+    }
+
+    /// Adds the `other` failures to these ones
+    pub fn merge(&mut self, other: ErrorSummary) {
+        for (message, other) in other.failures {
+            let failures = self.failures.entry(message).or_default();
+            failures.count += other.count;
+            let room = FAILURE_EXAMPLES.saturating_sub(failures.examples.len());
+            failures
+                .examples
+                .extend(other.examples.into_iter().take(room));
+        }
+    }
+
+    /// Returns the number of failures of all kinds
+    pub fn count(&self) -> u64 {
+        self.failures.values().map(|failures| failures.count).sum()
+    }
+
+    /// Returns whether there are no failures
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Empties the summary, and returns a line per kind of failure, the most frequent first
+    pub fn take_lines(&mut self) -> Vec<String> {
+        let mut failures: Vec<_> = std::mem::take(&mut self.failures).into_iter().collect();
+        failures.sort_by_key(|(_, failures)| Reverse(failures.count));
+
+        failures
+            .into_iter()
+            .map(|(message, failures)| {
+                format!(
+                    "{} {} failed with \"{}\" in the last iteration, e.g., {}.",
+                    failures.count,
+                    if failures.count == 1 {
+                        "fetch"
+                    } else {
+                        "fetches"
+                    },
+                    message,
+                    failures.examples.join(", ")
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_failures_are_collapsed() {
+        let symbols: Vec<Symbol> = ["AAPL", "MSFT", "NVDA", "UBER"]
+            .into_iter()
+            .map(Symbol::from)
+            .collect();
+
+        let mut chunk = ErrorSummary::default();
+        for symbol in &symbols[..2] {
+            chunk.record(symbol, symbol, "operation timed out");
+        }
+        let mut summary = ErrorSummary::default();
+        summary.merge(chunk);
+        for symbol in &symbols[2..] {
+            summary.record(symbol, symbol, "operation timed out");
+        }
+        // the symbol is left out of the message
+        summary.record(&symbols[0], "AAPL", "No data for AAPL");
+        summary.record(&symbols[1], "MSFT", "No data for MSFT");
+        assert_eq!(6, summary.count());
+
+        assert_eq!(
+            vec![
+                "4 fetches failed with \"operation timed out\" in the last iteration, \
+                 e.g., AAPL, MSFT, NVDA.",
+                "2 fetches failed with \"No data for <symbol>\" in the last iteration, \
+                 e.g., AAPL, MSFT.",
+            ],
+            summary.take_lines()
+        );
+        assert!(summary.is_empty());
+    }
+}
@@ -0,0 +1,49 @@
+//! Error types
+//!
+//! Errors that happen while an actor handles a message don't kill the actor.
+//! They are logged, and counted in the statistics, and the actor goes on with the next message.
+
+use std::time::Duration;
+
+use thiserror::Error;
+use yahoo_finance_api as yahoo;
+
+/// An error that occurs while an actor handles a message
+#[derive(Debug, Error)]
+pub enum ActorError {
+    /// A message couldn't be sent to another actor, because it isn't running
+    #[error("Couldn't send a message to the {0}.")]
+    Send(&'static str),
+
+    /// Another actor didn't respond to a request
+    #[error("The {0} didn't respond.")]
+    NoResponse(&'static str),
+
+    /// A response couldn't be sent back, because the requester has stopped waiting for it
+    #[error("Failed to send {0}.")]
+    Reply(&'static str),
+
+    /// The data provider couldn't be created
+    #[error("Couldn't create the data provider.")]
+    Provider(#[source] yahoo::YahooError),
+
+    /// A date or time couldn't be formatted
+    #[error("Couldn't format a date or time.")]
+    Format(#[from] time::error::Format),
+
+    /// The output file couldn't be opened or written to
+    #[error("Couldn't open or write to the output file.")]
+    Io(#[from] std::io::Error),
+
+    /// The tail buffer's batches couldn't be spilled to disk or read back
+    #[error("Couldn't read or write the spilled batches.")]
+    Spill(#[source] std::io::Error),
+
+    /// The blocking work of opening, writing or closing the output files didn't run to completion
+    #[error("The blocking work on the output files didn't complete.")]
+    Blocking(#[from] crate::runtime::JoinError),
+
+    /// Handling a message took too long, so it was cancelled
+    #[error("Handling the message timed out after {0:?}.")]
+    Timeout(Duration),
+}
@@ -0,0 +1,339 @@
+//! Event log of the completed batches
+//!
+//! With the `event-log` option, every batch that the collection actor completes is appended,
+//! as a line of JSON, to an append-only file, together with its sequence number, the time at which
+//! it was appended, and the start of its period. The sequence numbers keep increasing across
+//! restarts.
+//!
+//! The log is the source of truth of the batches: the collection actor's tail buffer is restored
+//! from it at startup, see [`newest`], and the `replay` subcommand reprocesses its batches,
+//! see [`read`].
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::types::Batch;
+
+/// The number of bytes that are read at a time from the end of the event log,
+/// when looking for its last event
+const TAIL_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// A completed batch in the event log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchEvent {
+    /// The sequence number, which starts at 1
+    pub seq: u64,
+    /// When the batch was appended, in the RFC 3339 format
+    pub time: String,
+    /// The start of the period of the batch's rows, in the RFC 3339 format
+    pub from: String,
+    pub rows: Batch,
+}
+
+/// Only the sequence number of a [`BatchEvent`], whose rows don't need to be parsed
+#[derive(Deserialize)]
+struct EventSeq {
+    seq: u64,
+}
+
+/// An append-only file with a line of JSON per completed batch
+pub struct EventLog {
+    writer: BufWriter<File>,
+    /// The sequence number of the next batch
+    next_seq: u64,
+}
+
+impl EventLog {
+    /// Opens the event log at `path` for appending, or creates it,
+    /// and continues the sequence numbers of its events
+    ///
+    /// Only the last complete event is read, from the end of the file. A torn last line,
+    /// which an interrupted [`Self::append`] leaves behind, is truncated with a warning.
+    ///
+    /// # Errors
+    /// - [std::io::Error] if the file can't be opened, read or truncated
+    /// - If the last complete event isn't in the format that [`Self::append`] writes
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .with_context(|| format!("Couldn't open the event log {}.", path.display()))?;
+
+        let len = file.metadata()?.len();
+        let (complete_len, last) = last_complete_line(&mut file)
+            .with_context(|| format!("Couldn't read the event log {}.", path.display()))?;
+        if complete_len < len {
+            tracing::warn!(
+                "The last line of the event log {} is incomplete; truncating it.",
+                path.display()
+            );
+            file.set_len(complete_len)?;
+        }
+        let last_seq = match last {
+            Some(line) => {
+                serde_json::from_slice::<EventSeq>(&line)
+                    .with_context(|| format!("Invalid last event of {}.", path.display()))?
+                    .seq
+            }
+            None => 0,
+        };
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            next_seq: last_seq + 1,
+        })
+    }
+
+    /// Appends the `rows` of a batch, whose period starts at `from`, as appended at the `time`,
+    /// and flushes them to the file
+    ///
+    /// # Returns
+    /// The batch's sequence number
+    pub fn append(&mut self, from: &str, rows: Batch, time: OffsetDateTime) -> io::Result<u64> {
+        let event = BatchEvent {
+            seq: self.next_seq,
+            time: time.format(&Rfc3339).unwrap_or_default(),
+            from: from.to_string(),
+            rows,
+        };
+        serde_json::to_writer(&mut self.writer, &event)?;
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+
+        self.next_seq += 1;
+
+        Ok(event.seq)
+    }
+}
+
+/// Finds the last complete, non-empty line of the `file`, by reading it backwards from its end
+///
+/// # Returns
+/// The length of the file without its torn last line, if any, i.e., the bytes after
+/// its last newline, and the last complete, non-empty line, if any
+fn last_complete_line(file: &mut File) -> io::Result<(u64, Option<Vec<u8>>)> {
+    let mut pos = file.seek(SeekFrom::End(0))?;
+    let mut tail = Vec::new();
+
+    loop {
+        let chunk_size = TAIL_CHUNK_SIZE.min(pos);
+        pos -= chunk_size;
+        let mut chunk = vec![0; chunk_size as usize];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+
+        let complete = tail
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |i| i + 1);
+        let content = &tail[..complete];
+        let end = content
+            .iter()
+            .rposition(|byte| !byte.is_ascii_whitespace())
+            .map(|i| i + 1);
+        // the line must start after a newline, or at the start of the file
+        let line =
+            end.and_then(
+                |end| match content[..end].iter().rposition(|&byte| byte == b'\n') {
+                    Some(newline) => Some(content[newline + 1..end].to_vec()),
+                    None if pos == 0 => Some(content[..end].to_vec()),
+                    None => None,
+                },
+            );
+
+        if line.is_some() || pos == 0 {
+            return Ok((pos + complete as u64, line));
+        }
+    }
+}
+
+/// Calls `f` with the number and the contents of every complete, non-empty line
+/// of the event log at `path`, oldest first
+///
+/// A torn last line, which an interrupted [`EventLog::append`] leaves behind,
+/// is skipped with a warning.
+fn for_each_line(path: &Path, mut f: impl FnMut(usize, Vec<u8>) -> Result<()>) -> Result<()> {
+    let file = File::open(path)
+        .with_context(|| format!("Couldn't read the event log {}.", path.display()))?;
+    let mut reader = BufReader::new(file);
+
+    for number in 1.. {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if line.last() != Some(&b'\n') {
+            tracing::warn!(
+                "Skipping the incomplete last line {} of the event log {}.",
+                number,
+                path.display()
+            );
+            break;
+        }
+        if !line.trim_ascii().is_empty() {
+            f(number, line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses an event from the `line` with the `number` of the event log at `path`
+fn parse(path: &Path, number: usize, line: &[u8]) -> Result<BatchEvent> {
+    serde_json::from_slice(line)
+        .with_context(|| format!("Invalid event on line {} of {}.", number, path.display()))
+}
+
+/// Reads all events of the event log at `path`, oldest first
+///
+/// A torn last line is skipped with a warning.
+///
+/// # Errors
+/// - [std::io::Error] if the file can't be read
+/// - If a complete event isn't in the format that [`EventLog::append`] writes
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<BatchEvent>> {
+    let path = path.as_ref();
+    let mut events = Vec::new();
+    for_each_line(path, |number, line| {
+        events.push(parse(path, number, &line)?);
+        Ok(())
+    })?;
+
+    Ok(events)
+}
+
+/// Reads the newest `n` batches of the event log at `path`, oldest first,
+/// with the times at which they were appended, for restoring the tail buffer
+///
+/// The file is streamed, and only the newest `n` lines are held and parsed.
+/// A torn last line is skipped with a warning.
+///
+/// # Errors
+/// - [std::io::Error] if the file can't be read
+/// - If one of the newest events isn't in the format that [`EventLog::append`] writes
+pub fn newest(path: impl AsRef<Path>, n: usize) -> Result<Vec<(OffsetDateTime, Batch)>> {
+    let path = path.as_ref();
+    let mut lines = VecDeque::with_capacity(n);
+    if n > 0 {
+        for_each_line(path, |number, line| {
+            if lines.len() == n {
+                lines.pop_front();
+            }
+            lines.push_back((number, line));
+            Ok(())
+        })?;
+    }
+
+    lines
+        .into_iter()
+        .map(|(number, line)| {
+            let event = parse(path, number, &line)?;
+            let time = OffsetDateTime::parse(&event.time, &Rfc3339)
+                .with_context(|| format!("Invalid time of the event {}.", event.seq))?;
+            Ok((time, event.rows))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::my_async_actors::PerformanceIndicatorsRow;
+
+    fn batch(last_price: f64) -> Batch {
+        vec![PerformanceIndicatorsRow {
+            symbol: "AAPL".into(),
+            last_price,
+            pct_change: 1.0,
+            period_min: 2.0,
+            period_max: 3.0,
+            sma: 4.0,
+            asset_class: Default::default(),
+        }]
+    }
+
+    #[test]
+    fn sequence_numbers_continue_across_restarts() {
+        let path = std::env::temp_dir().join(format!("events-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let from = "2024-01-01T00:00:00Z";
+        let time = OffsetDateTime::parse("2024-02-01T00:00:00Z", &Rfc3339).unwrap();
+
+        let mut log = EventLog::open(&path).unwrap();
+        assert_eq!(1, log.append(from, batch(1.0), time).unwrap());
+        assert_eq!(2, log.append(from, batch(2.0), time).unwrap());
+        drop(log);
+        let mut log = EventLog::open(&path).unwrap();
+        assert_eq!(3, log.append(from, batch(3.0), time).unwrap());
+
+        let events = read(&path).unwrap();
+        assert_eq!(
+            vec![1, 2, 3],
+            events.iter().map(|event| event.seq).collect::<Vec<_>>()
+        );
+        assert_eq!(from, events[0].from);
+
+        // the newest batches, oldest first
+        let newest = newest(&path, 2).unwrap();
+        assert_eq!(
+            vec![2.0, 3.0],
+            newest
+                .iter()
+                .map(|(_, batch)| batch[0].last_price)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(time, newest[0].0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn torn_last_line_is_truncated_and_the_sequence_continues() {
+        let path = std::env::temp_dir().join(format!("events-torn-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let from = "2024-01-01T00:00:00Z";
+        let time = OffsetDateTime::parse("2024-02-01T00:00:00Z", &Rfc3339).unwrap();
+
+        let mut log = EventLog::open(&path).unwrap();
+        assert_eq!(1, log.append(from, batch(1.0), time).unwrap());
+        assert_eq!(2, log.append(from, batch(2.0), time).unwrap());
+        drop(log);
+        // an append that was interrupted partway through
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(
+            file,
+            r#"{{"seq":3,"time":"2024-02-01T00:00:00Z","from":"2024-01-"#
+        )
+        .unwrap();
+        drop(file);
+
+        // the torn line is skipped by the readers
+        assert_eq!(2, read(&path).unwrap().len());
+        assert_eq!(1, newest(&path, 1).unwrap().len());
+
+        let mut log = EventLog::open(&path).unwrap();
+        assert_eq!(3, log.append(from, batch(3.0), time).unwrap());
+        drop(log);
+
+        let events = read(&path).unwrap();
+        assert_eq!(
+            vec![1, 2, 3],
+            events.iter().map(|event| event.seq).collect::<Vec<_>>()
+        );
+        assert_eq!(3.0, events[2].rows[0].last_price);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
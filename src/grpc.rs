@@ -0,0 +1,283 @@
+//! The gRPC API
+//!
+//! A [`tonic`]-based alternative to the REST API of the web app, for clients that prefer
+//! protobuf over JSON.
+//!
+//! It is backed by the same [`CollectionActorHandle`] as the web app, and its subscriptions
+//! by the [`BrokerActorHandle`].
+//!
+//! The protocol is described in `proto/stocks.proto`.
+
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tonic::{Request, Response, Status};
+
+use crate::constants::REQUEST_ID_HEADER;
+use crate::grpc::proto::stocks_server::Stocks;
+use crate::grpc::proto::{
+    Batch, SubscribeRequest, SymbolLatestReply, SymbolLatestRequest, TailReply, TailRequest,
+};
+use crate::my_async_actors::{
+    ActorHandle, BrokerActorHandle, CollectionActorHandle, CollectionActorMsg,
+    PerformanceIndicatorsRow, VersionedTail,
+};
+
+/// The protobuf messages, which mirror `proto/stocks.proto`, and the generated service stubs
+pub mod proto {
+    /// Calculated performance indicators of a single symbol
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PerformanceIndicatorsRow {
+        #[prost(string, tag = "1")]
+        pub symbol: String,
+        #[prost(double, tag = "2")]
+        pub last_price: f64,
+        #[prost(double, tag = "3")]
+        pub pct_change: f64,
+        #[prost(double, tag = "4")]
+        pub period_min: f64,
+        #[prost(double, tag = "5")]
+        pub period_max: f64,
+        #[prost(double, tag = "6")]
+        pub sma: f64,
+        /// One of `equity`, `etf` and `crypto`
+        #[prost(string, tag = "7")]
+        pub asset_class: String,
+    }
+
+    /// A single iteration of the main loop
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Batch {
+        #[prost(message, repeated, tag = "1")]
+        pub rows: Vec<PerformanceIndicatorsRow>,
+    }
+
+    /// A request for the last `n` batches
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TailRequest {
+        #[prost(uint32, tag = "1")]
+        pub n: u32,
+    }
+
+    /// The last `n` batches, newest first
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TailReply {
+        #[prost(string, tag = "1")]
+        pub from: String,
+        #[prost(message, repeated, tag = "2")]
+        pub batches: Vec<Batch>,
+    }
+
+    /// A request for the newest row of a single symbol
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SymbolLatestRequest {
+        #[prost(string, tag = "1")]
+        pub symbol: String,
+    }
+
+    /// The newest row of a single symbol
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SymbolLatestReply {
+        #[prost(string, tag = "1")]
+        pub from: String,
+        #[prost(message, optional, tag = "2")]
+        pub row: Option<PerformanceIndicatorsRow>,
+    }
+
+    /// A request for streaming newly-assembled batches, optionally of a single symbol
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubscribeRequest {
+        #[prost(string, optional, tag = "1")]
+        pub symbol: Option<String>,
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/stocks.Stocks.rs"));
+}
+
+impl From<PerformanceIndicatorsRow> for proto::PerformanceIndicatorsRow {
+    fn from(row: PerformanceIndicatorsRow) -> Self {
+        Self {
+            symbol: row.symbol.to_string(),
+            last_price: row.last_price,
+            pct_change: row.pct_change,
+            period_min: row.period_min,
+            period_max: row.period_max,
+            sma: row.sma,
+            asset_class: row.asset_class.to_string(),
+        }
+    }
+}
+
+impl From<crate::types::Batch> for Batch {
+    fn from(batch: crate::types::Batch) -> Self {
+        Self {
+            rows: batch.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Our gRPC service
+pub struct StocksService {
+    /// The CLI argument `from`, the same as in the web app's state
+    from: String,
+    /// The CLI argument `tail-buffer-size`, which limits the number of batches in tail requests
+    tail_buffer_size: usize,
+    /// The single collection actor instance
+    collection_handle: CollectionActorHandle,
+    /// The single broker actor instance, for subscriptions
+    broker_handle: BrokerActorHandle,
+    /// Ends the subscription streams on shutdown, so that the server can stop
+    shutdown_token: CancellationToken,
+}
+
+impl StocksService {
+    /// Create a new [`StocksService`]
+    pub fn new(
+        from: String,
+        tail_buffer_size: usize,
+        collection_handle: CollectionActorHandle,
+        broker_handle: BrokerActorHandle,
+        shutdown_token: CancellationToken,
+    ) -> Self {
+        Self {
+            from,
+            tail_buffer_size,
+            collection_handle,
+            broker_handle,
+            shutdown_token,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Stocks for StocksService {
+    /// Returns the last `n` batches, where `n` is limited to the buffer capacity
+    async fn tail(&self, request: Request<TailRequest>) -> Result<Response<TailReply>, Status> {
+        let request_id = request_id(&request);
+        let n = (request.into_inner().n as usize).clamp(0, self.tail_buffer_size);
+
+        let response = self
+            .collection_handle
+            .call(|sender| CollectionActorMsg::TailRequest {
+                sender,
+                n,
+                if_none_match: None,
+                request_id,
+            })
+            .await;
+
+        match response {
+            Ok(VersionedTail {
+                tail: Some(tail), ..
+            }) => Ok(Response::new(TailReply {
+                from: self.from.clone(),
+                batches: tail.into_iter().map(Into::into).collect(),
+            })),
+            _ => Err(Status::internal("The collection actor didn't respond.")),
+        }
+    }
+
+    /// Returns the newest row of a single symbol
+    async fn symbol_latest(
+        &self,
+        request: Request<SymbolLatestRequest>,
+    ) -> Result<Response<SymbolLatestReply>, Status> {
+        let request_id = request_id(&request);
+        let symbol = request.into_inner().symbol.trim().to_uppercase();
+
+        let row = self
+            .collection_handle
+            .call(|sender| CollectionActorMsg::SymbolLatestRequest {
+                sender,
+                symbol: symbol.clone(),
+                request_id,
+            })
+            .await
+            .map_err(|_| Status::internal("The collection actor didn't respond."))?;
+
+        match row {
+            Some(row) => Ok(Response::new(SymbolLatestReply {
+                from: self.from.clone(),
+                row: Some(row.into()),
+            })),
+            None => Err(Status::not_found(format!("No data for {}.", symbol))),
+        }
+    }
+
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<Batch, Status>> + Send>>;
+
+    /// Streams every newly-assembled batch, optionally filtered by a symbol
+    ///
+    /// Empty batches, and batches that don't contain the symbol, are skipped.
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let symbol = request
+            .into_inner()
+            .symbol
+            .map(|symbol| symbol.trim().to_uppercase());
+
+        let receiver = self
+            .broker_handle
+            .subscribe()
+            .await
+            .map_err(|_| Status::unavailable("The broker actor isn't running."))?;
+
+        let stream = ReceiverStream::new(receiver)
+            .filter_map(move |mut batch| {
+                if let Some(symbol) = &symbol {
+                    batch.retain(|row| *row.symbol == **symbol);
+                }
+                let batch = (!batch.is_empty()).then(|| batch.into());
+                async move { batch }
+            })
+            .map(Ok)
+            .take_until(self.shutdown_token.clone().cancelled_owned());
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// Extracts the client's correlation ID from the `x-request-id` metadata, if provided
+fn request_id<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TAIL_BUFFER_SIZE;
+
+    #[tokio::test]
+    async fn empty_buffer_is_reported() {
+        let service = StocksService::new(
+            "2024-01-01T00:00:00Z".to_string(),
+            TAIL_BUFFER_SIZE,
+            CollectionActorHandle::new(0),
+            BrokerActorHandle::new(0),
+            CancellationToken::new(),
+        );
+
+        let reply = service
+            .tail(Request::new(TailRequest { n: 3 }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(reply.batches.is_empty());
+
+        let status = service
+            .symbol_latest(Request::new(SymbolLatestRequest {
+                symbol: "aapl".into(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(tonic::Code::NotFound, status.code());
+    }
+}
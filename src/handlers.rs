@@ -1,42 +1,261 @@
 //! Web-request handlers
 
-use axum::{debug_handler, Json};
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
-use axum::response::Html;
-use serde::Serialize;
-use tokio::sync::mpsc;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::{debug_handler, Extension, Json};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use utoipa::{OpenApi, ToSchema};
 
-use crate::constants::{ACTOR_CHANNEL_CAPACITY, TAIL_BUFFER_SIZE};
-use crate::my_async_actors::{ActorHandle, CollectionActorHandle, CollectionActorMsg};
-use crate::types::{TailResponse, TailResponseString};
+use crate::asset_classes::AssetClass;
+use crate::audit::RowsReturned;
+use crate::cli::ImplementationVariant;
+use crate::config::{ConfigUpdate, RuntimeConfig};
+use crate::constants::{
+    BUILD_TIMESTAMP, GIT_COMMIT_HASH, REQUEST_ID_HEADER, RISK_CONFIDENCE, VERSION,
+};
+use crate::memory::{self, AllocatorStats, BufferMemory, MemoryStats};
+use crate::movers::{Mover, Movers};
+use crate::my_async_actors::{
+    ActorHandle, AuditActorHandle, CollectionActorMsg, ConfigActorHandle, DeadLetter, DeadLetters,
+    Health, NewsActorHandle, OptionsActorHandle, PaperTradingActorHandle, PerformanceIndicatorsRow,
+    PipelineHandles, PortfolioActorHandle, Stats, SymbolsActorHandle, VersionedTail,
+    WatchdogActorHandle, WatchlistActorHandle, WebhookActorHandle,
+};
+use crate::news::{Headline, SymbolNews};
+use crate::option_chains::OptionsSummary;
+use crate::paper::{Fill, Order, OrderSource, Portfolio, Position, Side};
+use crate::portfolio::{Holding, HoldingValue, Valuation};
+use crate::quality::{QualityIssue, QualityReport};
+use crate::risk::{self, RiskMetrics, RiskReport, SymbolRisk};
+use crate::sectors::SectorAggregate;
+use crate::types::{Batch, Symbol, SymbolsResponse, TailResponse, TailResponseString};
+use crate::watchlists::Watchlist;
+use crate::webhooks::{Webhook, WebhookRegistration};
+
+/// The OpenAPI specification of our web app
+///
+/// It is served at `/openapi.json`, and it can be explored through the Swagger UI at `/swagger-ui`.
+///
+/// API consumers can use it to generate clients.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Stock-Tracking CLI with Async Streams"),
+    paths(
+        root,
+        get_desc,
+        get_version,
+        get_tail,
+        get_tail_str,
+        get_symbol_tail,
+        get_range,
+        post_symbols,
+        delete_symbol,
+        get_config,
+        put_config,
+        get_stats,
+        get_memory_stats,
+        get_health,
+        get_dead_letters,
+        post_webhook,
+        get_webhooks,
+        delete_webhook,
+        post_paper_order,
+        get_paper_portfolio,
+        get_portfolio,
+        post_holding,
+        delete_holding,
+        get_quality,
+        get_risk,
+        get_movers,
+        get_sectors,
+        get_symbol_news,
+        get_options,
+        get_watchlists,
+        get_watchlist_tail
+    ),
+    components(schemas(
+        Version,
+        Tail,
+        SymbolTail,
+        Range,
+        TimedBatch,
+        PerformanceIndicatorsRow,
+        AssetClass,
+        AddSymbols,
+        AddedSymbols,
+        RuntimeConfig,
+        ConfigUpdate,
+        Stats,
+        MemoryStats,
+        AllocatorStats,
+        BufferMemory,
+        Health,
+        DeadLetters,
+        DeadLetter,
+        WebhookRegistration,
+        Webhook,
+        Order,
+        Side,
+        Fill,
+        OrderSource,
+        Position,
+        Portfolio,
+        Holding,
+        HoldingValue,
+        Valuation,
+        QualityReport,
+        QualityIssue,
+        RiskReport,
+        SymbolRisk,
+        RiskMetrics,
+        Movers,
+        Mover,
+        SectorAggregate,
+        SymbolNews,
+        Headline,
+        OptionsSummary,
+        Watchlist,
+        WatchlistTail
+    ))
+)]
+pub struct ApiDoc;
 
 /// Our web app's state for keeping some variables
 ///
 /// It must be [`Clone`], which means we can't store a `!Clone` channel receiver in it.
 #[derive(Clone)]
 pub struct WebAppState {
-    /// The CLI argument `from`, so we don't have to pass it in tail response messages to the web app
+    /// The CLI argument `from`, in the display time zone, so we don't have to pass it in tail
+    /// response messages to the web app
     pub from: String,
-    /// The single collection actor instance
-    pub collection_handle: CollectionActorHandle,
+    /// The CLI argument `tail-buffer-size`, which limits the number of batches in tail requests
+    pub tail_buffer_size: usize,
+    /// The CLI argument `variant`
+    pub variant: ImplementationVariant,
+    /// The singleton actor instances of the pipeline: writer, collection, statistics and dead letters
+    pub handles: PipelineHandles,
+    /// The single symbol registry actor instance
+    pub symbols_handle: SymbolsActorHandle,
+    /// The single runtime configuration actor instance
+    pub config_handle: ConfigActorHandle,
+    /// The single webhook registry and dispatcher actor instance
+    pub webhook_handle: WebhookActorHandle,
+    /// The single watchdog actor instance, which monitors the liveness of the singletons
+    pub watchdog_handle: WatchdogActorHandle,
+    /// The single paper-trading actor instance, if paper trading is enabled
+    pub paper_handle: Option<PaperTradingActorHandle>,
+    /// The single portfolio actor instance, which values the holdings
+    pub portfolio_handle: PortfolioActorHandle,
+    /// The single watchlist actor instance, which keeps the newest batches of every watchlist
+    pub watchlist_handle: WatchlistActorHandle,
+    /// The single audit actor instance, if there is an audit log
+    pub audit_handle: Option<AuditActorHandle>,
+    /// The single news actor instance, if there is a news provider
+    pub news_handle: Option<NewsActorHandle>,
+    /// The single options actor instance, if there are underlyings
+    pub options_handle: Option<OptionsActorHandle>,
+}
+
+/// Build information of the running app
+#[derive(Serialize, ToSchema)]
+pub struct Version {
+    /// The crate version
+    version: &'static str,
+    /// The git commit that the app was built from
+    git_commit: &'static str,
+    /// The time at which the app was built
+    build_timestamp: &'static str,
+    /// The active implementation variant
+    variant: String,
 }
 
 /// An array of the last `n` fully-assembled batches,
 /// where each batch contains processed data for all S&P 500 symbols.
 ///
 /// The batches are created at regular time intervals.
-#[derive(Default, Serialize)]
+#[derive(Default, Serialize, ToSchema)]
 pub struct Tail {
     from: String,
+    #[schema(value_type = Vec<Vec<PerformanceIndicatorsRow>>)]
     tail: TailResponse,
 }
 
+/// An array of the last `n` rows of processed data of a single symbol, newest first
+#[derive(Default, Serialize, ToSchema)]
+pub struct SymbolTail {
+    from: String,
+    symbol: String,
+    tail: Batch,
+}
+
+/// An array of the last `n` batches of a watchlist, newest first,
+/// with the rows of the watchlist's symbols and the watchlist's indicators
+#[derive(Serialize, ToSchema)]
+pub struct WatchlistTail {
+    from: String,
+    name: String,
+    #[schema(value_type = Vec<Vec<PerformanceIndicatorsRow>>)]
+    tail: TailResponse,
+}
+
+/// The query of a time-range request, whose bounds are RFC 3339 date-times
+///
+/// A missing `start` means the beginning of the buffer, and a missing `end` means now.
+#[derive(Deserialize)]
+pub struct RangeQuery {
+    start: Option<String>,
+    end: Option<String>,
+}
+
+/// The query of a risk request, whose confidence level is [`RISK_CONFIDENCE`] if it's missing
+#[derive(Deserialize)]
+pub struct RiskQuery {
+    confidence: Option<f64>,
+}
+
+/// An array of the fully-assembled batches that were completed within a time range, newest first
+#[derive(Default, Serialize, ToSchema)]
+pub struct Range {
+    from: String,
+    batches: Vec<TimedBatch>,
+}
+
+/// A fully-assembled batch with the moment of its completion, as an RFC 3339 date-time
+#[derive(Serialize, ToSchema)]
+pub struct TimedBatch {
+    completed_at: String,
+    batch: Batch,
+}
+
+/// A request for adding new symbols (tickers) at runtime
+#[derive(Deserialize, ToSchema)]
+pub struct AddSymbols {
+    symbols: Vec<String>,
+}
+
+/// A response to a request for adding new symbols (tickers) at runtime
+///
+/// Contains the symbols that were actually added, i.e., that weren't already present,
+/// and all symbols that are tracked after the addition.
+#[derive(Default, Serialize, ToSchema)]
+pub struct AddedSymbols {
+    #[schema(value_type = Vec<String>)]
+    added: Vec<Symbol>,
+    #[schema(value_type = Vec<String>)]
+    symbols: Vec<Symbol>,
+}
+
 /// Describes the app
 ///
 /// content-type: text/html; charset=utf-8
 ///
 /// GET /
+#[utoipa::path(get, path = "/", responses((status = 200, description = "App description", body = String, content_type = "text/html")))]
 #[debug_handler]
 pub async fn root() -> (StatusCode, Html<&'static str>) {
     (StatusCode::OK, description().await)
@@ -47,10 +266,36 @@ pub async fn root() -> (StatusCode, Html<&'static str>) {
 /// content-type: text/html; charset=utf-8
 ///
 /// GET /desc
+#[utoipa::path(get, path = "/desc", responses((status = 200, description = "App description", body = String, content_type = "text/html")))]
 pub async fn get_desc() -> (StatusCode, Html<&'static str>) {
     (StatusCode::OK, description().await)
 }
 
+/// Shows build information: the crate version, the git commit, the build timestamp,
+/// and the active implementation variant
+///
+/// content-type: application/json
+///
+/// GET /version
+#[utoipa::path(get, path = "/version", responses((status = 200, description = "Build information", body = Version)))]
+pub async fn get_version(State(state): State<WebAppState>) -> (StatusCode, Json<Version>) {
+    let variant = state
+        .variant
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_else(|| format!("{:?}", state.variant));
+
+    (
+        StatusCode::OK,
+        Json(Version {
+            version: VERSION,
+            git_commit: GIT_COMMIT_HASH,
+            build_timestamp: BUILD_TIMESTAMP,
+            variant,
+        }),
+    )
+}
+
 /// Fetches the last `n` iterations of the main loop, which occur at a fixed time interval,
 /// and which include calculated performance indicators for all symbols.
 ///
@@ -59,44 +304,76 @@ pub async fn get_desc() -> (StatusCode, Html<&'static str>) {
 ///
 /// Works with [`crate::my_async_actors::PerformanceIndicatorsRow`]s.
 ///
+/// The response contains an `ETag` header. If a client sends it back in the `If-None-Match`
+/// header, and no new batch has been produced in the meantime, we respond with
+/// `304 Not Modified` and an empty body, which saves serialization for frequent pollers.
+///
 /// content-type: application/json
 ///
 /// GET /tail/n
+#[utoipa::path(
+    get,
+    path = "/tail/{n}",
+    params(
+        ("n" = usize, Path, description = "Number of the most recent batches to return"),
+        ("If-None-Match" = Option<String>, Header, description = "The `ETag` of a previous response"),
+    ),
+    responses(
+        (status = 200, description = "The last `n` batches", body = Tail),
+        (status = 304, description = "No new batches since the provided `ETag`"),
+        (status = 500, description = "The collection actor didn't respond", body = Tail),
+    )
+)]
 pub async fn get_tail(
     State(state): State<WebAppState>,
     Path(n): Path<usize>,
-) -> (StatusCode, Json<Tail>) {
+    headers: HeaderMap,
+) -> Response {
     // limit n to buffer capacity
-    let n = n.clamp(0, TAIL_BUFFER_SIZE);
+    let n = n.clamp(0, state.tail_buffer_size);
 
-    // create channel for sending the collection actor a tail request message
-    let (sender, mut receiver) = mpsc::channel(ACTOR_CHANNEL_CAPACITY);
+    let if_none_match = parse_if_none_match(&headers);
+    let request_id = request_id(&headers);
 
-    // our web application acts like an actor here, sending the collection actor a message
+    // our web application acts like an actor here, calling the collection actor
     //
-    // we use the actor's send method for sending it the message, which is the only way
-    // to send an actor a message anyway
+    // we use the actor's call method, which packages the sending half of a oneshot reply channel
+    // into a tail request message, together with the requested number of batches, n
     //
-    // in the message, we give it the sending half of the channel, and the requested number of batches, n
-    let _ = state
+    // then we wait (block) for response from the collection actor, which is sent back
+    // through the reply channel
+    let response = state
+        .handles
         .collection_handle
-        .send(CollectionActorMsg::TailRequest { sender, n })
+        .call(|sender| CollectionActorMsg::TailRequest {
+            sender,
+            n,
+            if_none_match,
+            request_id,
+        })
         .await;
 
-    // then we wait (block) for response from the collection actor, which we receive
-    // at the receiving half of the channel
-    if let Some(tail) = receiver.recv().await {
+    match response {
         // we add the *from* field only at the beginning of the batch, and to at the
         // beginning of each row, but this should be enough
-        (
+        Ok(VersionedTail {
+            version,
+            tail: Some(tail),
+        }) => (
             StatusCode::OK,
+            etag(version),
+            rows_returned(&tail),
             Json(Tail {
                 from: state.from,
                 tail,
             }),
         )
-    } else {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(Tail::default()))
+            .into_response(),
+        Ok(VersionedTail {
+            version,
+            tail: None,
+        }) => (StatusCode::NOT_MODIFIED, etag(version)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, Json(Tail::default())).into_response(),
     }
 }
 
@@ -111,55 +388,1205 @@ pub async fn get_tail(
 /// This output looks like the CLI output (`stdout` or tracing output), which is also the same
 /// as the CSV file format that we write.
 ///
+/// Supports `ETag`s in the same way as [`get_tail`].
+///
 /// content-type: application/json
 ///
 /// GET /tailstr/n
+#[utoipa::path(
+    get,
+    path = "/tailstr/{n}",
+    params(
+        ("n" = usize, Path, description = "Number of the most recent batches to return"),
+        ("If-None-Match" = Option<String>, Header, description = "The `ETag` of a previous response"),
+    ),
+    responses(
+        (status = 200, description = "The last `n` batches as CSV-formatted rows", body = Vec<Vec<String>>),
+        (status = 304, description = "No new batches since the provided `ETag`"),
+        (status = 500, description = "The collection actor didn't respond", body = Vec<Vec<String>>),
+    )
+)]
 pub async fn get_tail_str(
     State(state): State<WebAppState>,
     Path(n): Path<usize>,
-) -> (StatusCode, Json<TailResponseString>) {
+    headers: HeaderMap,
+) -> Response {
     // limit n to buffer capacity
-    let n = n.clamp(0, TAIL_BUFFER_SIZE);
+    let n = n.clamp(0, state.tail_buffer_size);
 
-    // create channel for sending the collection actor a tail request message
-    let (sender, mut receiver) = mpsc::channel(ACTOR_CHANNEL_CAPACITY);
+    let if_none_match = parse_if_none_match(&headers);
+    let request_id = request_id(&headers);
 
-    // our web application acts like an actor here, sending the collection actor a message
+    // our web application acts like an actor here, calling the collection actor
     //
-    // we use the actor's send method for sending it the message, which is the only way
-    // to send an actor a message anyway
+    // we use the actor's call method, which packages the sending half of a oneshot reply channel
+    // into a tail request message, together with the requested number of batches, n
     //
-    // in the message, we give it the sending half of the channel, and the requested number of batches, n
-    let _ = state
+    // then we wait (block) for response from the collection actor, which is sent back
+    // through the reply channel
+    let response = state
+        .handles
         .collection_handle
-        .send(CollectionActorMsg::TailRequest { sender, n })
+        .call(|sender| CollectionActorMsg::TailRequest {
+            sender,
+            n,
+            if_none_match,
+            request_id,
+        })
         .await;
 
-    // then we wait (block) for response from the collection actor, which we receive
-    // at the receiving half of the channel
-    if let Some(tail) = receiver.recv().await {
-        // we now add the *from* field at the beginning of each row that goes to output
-        //
-        // since we use the same message type as in [`get_tail`], the same message handler is used inside
-        // the collection actor, and it returns [`TailResponse`], which is the above `tail` variable
-        //
-        // we (currently) don't have an iterator over [`TailResponse`], so we need to use the nested loops
-        let mut batches = Vec::new();
-        for batch in tail {
-            let mut new_batch = Vec::new();
-            for row in batch {
-                let new_row = format!("{},{}", state.from, row);
-                new_batch.push(new_row);
+    let (version, tail) = match response {
+        Ok(VersionedTail {
+            version,
+            tail: Some(tail),
+        }) => (version, tail),
+        Ok(VersionedTail {
+            version,
+            tail: None,
+        }) => return (StatusCode::NOT_MODIFIED, etag(version)).into_response(),
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(TailResponseString::default()),
+            )
+                .into_response()
+        }
+    };
+
+    // we now add the *from* field at the beginning of each row that goes to output
+    //
+    // since we use the same message type as in [`get_tail`], the same message handler is used inside
+    // the collection actor, and it returns [`TailResponse`], which is the above `tail` variable
+    //
+    // we (currently) don't have an iterator over [`TailResponse`], so we need to use the nested loops
+    let rows = rows_returned(&tail);
+    let mut batches = Vec::new();
+    for batch in tail {
+        let mut new_batch = Vec::new();
+        for row in batch {
+            let new_row = format!("{},{}", state.from, row);
+            new_batch.push(new_row);
+        }
+        batches.push(new_batch);
+    }
+    (StatusCode::OK, etag(version), rows, Json(batches)).into_response()
+}
+
+/// Returns the last `n` rows of processed data of a single symbol `sym`
+///
+/// The rows are picked out of the buffered batches by the collection actor,
+/// so a client doesn't have to download whole batches to follow a single symbol.
+///
+/// Since every batch contains at most one row per symbol, `n` is limited to the buffer capacity.
+///
+/// Works with [`crate::my_async_actors::PerformanceIndicatorsRow`]s.
+///
+/// content-type: application/json
+///
+/// GET /tail/n/symbol/sym
+#[utoipa::path(
+    get,
+    path = "/tail/{n}/symbol/{sym}",
+    params(
+        ("n" = usize, Path, description = "Number of the most recent rows to return"),
+        ("sym" = String, Path, description = "The symbol (ticker)"),
+    ),
+    responses(
+        (status = 200, description = "The last `n` rows of the symbol", body = SymbolTail),
+        (status = 500, description = "The collection actor didn't respond", body = SymbolTail),
+    )
+)]
+pub async fn get_symbol_tail(
+    State(state): State<WebAppState>,
+    Path((n, sym)): Path<(usize, String)>,
+    headers: HeaderMap,
+) -> (
+    StatusCode,
+    Option<Extension<RowsReturned>>,
+    Json<SymbolTail>,
+) {
+    // limit n to buffer capacity
+    let n = n.clamp(0, state.tail_buffer_size);
+    let symbol = sym.trim().to_uppercase();
+    let request_id = request_id(&headers);
+
+    let response = state
+        .handles
+        .collection_handle
+        .call(|sender| CollectionActorMsg::SymbolTailRequest {
+            sender,
+            n,
+            symbol: symbol.clone(),
+            request_id,
+        })
+        .await;
+
+    if let Ok(tail) = response {
+        (
+            StatusCode::OK,
+            Some(Extension(RowsReturned(tail.len()))),
+            Json(SymbolTail {
+                from: state.from,
+                symbol,
+                tail,
+            }),
+        )
+    } else {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            None,
+            Json(SymbolTail::default()),
+        )
+    }
+}
+
+/// Returns the buffered batches that were completed between `start` and `end`, both inclusive,
+/// newest first
+///
+/// Only the batches that are still in the buffer can be returned, so the range is effectively
+/// limited to the last `tail-buffer-size` iterations.
+///
+/// Works with [`crate::my_async_actors::PerformanceIndicatorsRow`]s.
+///
+/// content-type: application/json
+///
+/// GET /range?start=..&end=..
+#[utoipa::path(
+    get,
+    path = "/range",
+    params(
+        ("start" = Option<String>, Query, description = "RFC 3339 date-time; the oldest batch if missing"),
+        ("end" = Option<String>, Query, description = "RFC 3339 date-time; now if missing"),
+    ),
+    responses(
+        (status = 200, description = "The batches that were completed within the range", body = Range),
+        (status = 400, description = "A bound isn't an RFC 3339 date-time, or `start` is after `end`"),
+        (status = 500, description = "The collection actor didn't respond"),
+    )
+)]
+pub async fn get_range(
+    State(state): State<WebAppState>,
+    Query(query): Query<RangeQuery>,
+    headers: HeaderMap,
+) -> Result<(Extension<RowsReturned>, Json<Range>), (StatusCode, String)> {
+    let parse = |bound: Option<String>, default: OffsetDateTime| match bound {
+        Some(bound) => OffsetDateTime::parse(&bound, &Rfc3339).map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid date-time \"{}\": {}", bound, err),
+            )
+        }),
+        None => Ok(default),
+    };
+    let start = parse(query.start, OffsetDateTime::UNIX_EPOCH)?;
+    let end = parse(query.end, OffsetDateTime::now_utc())?;
+    if start > end {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The start is after the end.".to_string(),
+        ));
+    }
+    let request_id = request_id(&headers);
+
+    let range = state
+        .handles
+        .collection_handle
+        .call(|sender| CollectionActorMsg::RangeRequest {
+            sender,
+            start,
+            end,
+            request_id,
+        })
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))?;
+
+    let rows = range.iter().map(|(_, batch)| batch.len()).sum();
+    let batches = range
+        .into_iter()
+        .map(|(completed_at, batch)| TimedBatch {
+            completed_at: completed_at.format(&Rfc3339).unwrap_or_default(),
+            batch,
+        })
+        .collect();
+
+    Ok((
+        Extension(RowsReturned(rows)),
+        Json(Range {
+            from: state.from,
+            batches,
+        }),
+    ))
+}
+
+/// Adds new symbols (tickers) to the live set of symbols that the main loop works with
+///
+/// The new symbols are fetched and processed starting with the next iteration of the main loop.
+///
+/// Symbols are trimmed and uppercased, and those that are already tracked are skipped.
+///
+/// content-type: application/json
+///
+/// POST /symbols
+#[utoipa::path(
+    post,
+    path = "/symbols",
+    request_body = AddSymbols,
+    responses(
+        (status = 200, description = "The added symbols and all tracked symbols", body = AddedSymbols),
+        (status = 500, description = "The symbol registry didn't respond", body = AddedSymbols),
+    )
+)]
+pub async fn post_symbols(
+    State(state): State<WebAppState>,
+    Json(request): Json<AddSymbols>,
+) -> (StatusCode, Json<AddedSymbols>) {
+    let added = match state.symbols_handle.add(request.symbols).await {
+        Ok(added) => added,
+        Err(err) => {
+            tracing::error!("{:#}", err);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AddedSymbols::default()),
+            );
+        }
+    };
+
+    match state.symbols_handle.get().await {
+        Ok(symbols) => (StatusCode::OK, Json(AddedSymbols { added, symbols })),
+        Err(err) => {
+            tracing::error!("{:#}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AddedSymbols::default()),
+            )
+        }
+    }
+}
+
+/// Removes a symbol (ticker) from the live set of symbols that the main loop works with
+///
+/// The symbol is not fetched anymore starting with the next iteration of the main loop,
+/// and its rows are purged from the buffered batches.
+///
+/// Responds with the remaining symbols.
+///
+/// content-type: application/json
+///
+/// DELETE /symbols/sym
+#[utoipa::path(
+    delete,
+    path = "/symbols/{sym}",
+    params(("sym" = String, Path, description = "The symbol to remove")),
+    responses(
+        (status = 200, description = "The remaining symbols", body = Vec<String>),
+        (status = 404, description = "The symbol isn't tracked", body = Vec<String>),
+        (status = 500, description = "The symbol registry didn't respond", body = Vec<String>),
+    )
+)]
+pub async fn delete_symbol(
+    State(state): State<WebAppState>,
+    Path(symbol): Path<String>,
+) -> (StatusCode, Json<SymbolsResponse>) {
+    let symbol = symbol.trim().to_uppercase();
+
+    let status = match state.symbols_handle.remove(symbol.clone()).await {
+        Ok(true) => {
+            if let Err(err) = state
+                .handles
+                .collection_handle
+                .send(CollectionActorMsg::PurgeSymbol(symbol.clone()))
+                .await
+            {
+                state
+                    .handles
+                    .dead_letter_handle
+                    .undelivered("CollectionActor", format!("PurgeSymbol({})", symbol), err)
+                    .await;
             }
-            batches.push(new_batch);
+            StatusCode::OK
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            tracing::error!("{:#}", err);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(Vec::default()));
+        }
+    };
+
+    match state.symbols_handle.get().await {
+        Ok(symbols) => (status, Json(symbols)),
+        Err(err) => {
+            tracing::error!("{:#}", err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(Vec::default()))
         }
-        (StatusCode::OK, Json(batches))
+    }
+}
+
+/// Fetches the current runtime configuration
+///
+/// content-type: application/json
+///
+/// GET /config
+#[utoipa::path(
+    get,
+    path = "/config",
+    responses(
+        (status = 200, description = "The current runtime configuration", body = RuntimeConfig),
+        (status = 500, description = "The configuration actor didn't respond"),
+    )
+)]
+pub async fn get_config(
+    State(state): State<WebAppState>,
+) -> Result<Json<RuntimeConfig>, (StatusCode, String)> {
+    state
+        .config_handle
+        .get()
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Updates a subset of the runtime configuration
+///
+/// Only the provided fields are updated, and the changes take effect
+/// in the next iteration of the main loop.
+///
+/// The writer and collection actors are reconfigured right away, with the updated
+/// flush interval and indicator set, respectively.
+///
+/// Responds with the updated configuration.
+///
+/// content-type: application/json
+///
+/// PUT /config
+#[utoipa::path(
+    put,
+    path = "/config",
+    request_body = ConfigUpdate,
+    responses(
+        (status = 200, description = "The updated runtime configuration", body = RuntimeConfig),
+        (status = 400, description = "A provided value is out of its bounds"),
+        (status = 500, description = "The configuration actor didn't respond"),
+    )
+)]
+pub async fn put_config(
+    State(state): State<WebAppState>,
+    Json(update): Json<ConfigUpdate>,
+) -> Result<Json<RuntimeConfig>, (StatusCode, String)> {
+    update
+        .validate()
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+
+    let config = state
+        .config_handle
+        .update(update)
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))?;
+
+    state.handles.reconfigure(config.reconfiguration()).await;
+
+    Ok(Json(config))
+}
+
+/// Fetches internal statistics of the actor system and of the pipeline
+///
+/// They include the number of spawned actors, the number of completed iterations,
+/// the duration of the last iteration, the number of fetch errors, and mailbox queue depths
+/// and backpressure counters of the singleton actors, so that operators can diagnose slowdowns
+/// and tune the mailbox capacities.
+///
+/// content-type: application/json
+///
+/// GET /stats
+#[utoipa::path(
+    get,
+    path = "/stats",
+    responses(
+        (status = 200, description = "The current statistics", body = Stats),
+        (status = 500, description = "The statistics actor didn't respond"),
+    )
+)]
+pub async fn get_stats(
+    State(state): State<WebAppState>,
+) -> Result<Json<Stats>, (StatusCode, String)> {
+    let mut stats = state
+        .handles
+        .stats_handle
+        .get()
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))?;
+
+    stats.writer_queue_depth = state.handles.writer_handle.queue_depth();
+    stats.collection_queue_depth = state.handles.collection_handle.queue_depth();
+    stats.writer_queue_full = state.handles.writer_handle.queue_full_count();
+    stats.writer_send_timeouts = state.handles.writer_handle.send_timeout_count();
+    stats.collection_queue_full = state.handles.collection_handle.queue_full_count();
+    stats.collection_send_timeouts = state.handles.collection_handle.send_timeout_count();
+
+    Ok(Json(stats))
+}
+
+/// Fetches the memory statistics of the process: its resident set size, the statistics
+/// of the allocator, with the `jemalloc` feature, the size of the buffer of the newest batches,
+/// and the mailbox queue depths of the singleton actors, so that operators of long-running
+/// deployments can confirm that nothing is leaking
+///
+/// content-type: application/json
+///
+/// GET /stats/memory
+#[utoipa::path(
+    get,
+    path = "/stats/memory",
+    responses(
+        (status = 200, description = "The current memory statistics", body = MemoryStats),
+        (status = 500, description = "The collection actor didn't respond"),
+    )
+)]
+pub async fn get_memory_stats(
+    State(state): State<WebAppState>,
+    headers: HeaderMap,
+) -> Result<Json<MemoryStats>, (StatusCode, String)> {
+    let request_id = request_id(&headers);
+
+    let tail_buffer = state
+        .handles
+        .collection_handle
+        .call(|sender| CollectionActorMsg::MemoryRequest { sender, request_id })
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))?;
+
+    Ok(Json(MemoryStats {
+        resident_bytes: memory::resident_set_size(),
+        allocator: memory::allocator_stats(),
+        tail_buffer,
+        writer_queue_depth: state.handles.writer_handle.queue_depth(),
+        collection_queue_depth: state.handles.collection_handle.queue_depth(),
+    }))
+}
+
+/// Reports the liveness of the singleton actors that the pipeline can't do without
+///
+/// The watchdog actor pings them periodically; an actor that hasn't answered the latest
+/// heartbeat is listed as unresponsive, and the app is reported as unhealthy.
+///
+/// content-type: application/json
+///
+/// GET /health
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "All monitored actors are responsive", body = Health),
+        (status = 503, description = "Some monitored actors are unresponsive", body = Health),
+    )
+)]
+pub async fn get_health(State(state): State<WebAppState>) -> (StatusCode, Json<Health>) {
+    let health = state
+        .watchdog_handle
+        .get()
+        .await
+        .unwrap_or_else(|_| Health {
+            healthy: false,
+            unresponsive: vec!["WatchdogActor".to_string()],
+        });
+
+    let status = if health.healthy {
+        StatusCode::OK
     } else {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(Vec::default()))
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(health))
+}
+
+/// Fetches the messages that couldn't be delivered to an actor, or that an actor failed to handle
+///
+/// Only the most recent dead letters are kept, newest first, but all of them are counted.
+///
+/// content-type: application/json
+///
+/// GET /dead-letters
+#[utoipa::path(
+    get,
+    path = "/dead-letters",
+    responses(
+        (status = 200, description = "The recorded dead letters", body = DeadLetters),
+        (status = 500, description = "The dead letter actor didn't respond"),
+    )
+)]
+pub async fn get_dead_letters(
+    State(state): State<WebAppState>,
+) -> Result<Json<DeadLetters>, (StatusCode, String)> {
+    state
+        .handles
+        .dead_letter_handle
+        .get()
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Fetches the data-quality reports of the symbols whose newest data has issues:
+/// missing trading days, zero or negative prices, or a stale series
+///
+/// The symbols without issues aren't listed.
+///
+/// content-type: application/json
+///
+/// GET /quality
+#[utoipa::path(
+    get,
+    path = "/quality",
+    responses(
+        (status = 200, description = "The reports of the symbols with data-quality issues", body = Vec<QualityReport>),
+        (status = 500, description = "The collection actor didn't respond"),
+    )
+)]
+pub async fn get_quality(
+    State(state): State<WebAppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<QualityReport>>, (StatusCode, String)> {
+    let request_id = request_id(&headers);
+
+    state
+        .handles
+        .collection_handle
+        .call(|sender| CollectionActorMsg::QualityRequest { sender, request_id })
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Fetches the historical Value-at-Risk and Conditional Value-at-Risk of every symbol,
+/// over the daily returns of its newest fetch, and of the portfolio of the holdings,
+/// weighted by their market values
+///
+/// Both are percentages of daily loss, at the confidence level.
+///
+/// content-type: application/json
+///
+/// GET /risk
+#[utoipa::path(
+    get,
+    path = "/risk",
+    params(
+        ("confidence" = Option<f64>, Query, description = "The confidence level, between 0 and 1; 0.95 if missing"),
+    ),
+    responses(
+        (status = 200, description = "The risk metrics of the symbols and of the portfolio", body = RiskReport),
+        (status = 400, description = "The confidence level isn't between 0 and 1"),
+        (status = 500, description = "The portfolio or the collection actor didn't respond"),
+    )
+)]
+pub async fn get_risk(
+    State(state): State<WebAppState>,
+    headers: HeaderMap,
+    Query(query): Query<RiskQuery>,
+) -> Result<Json<RiskReport>, (StatusCode, String)> {
+    let request_id = request_id(&headers);
+    let confidence = query.confidence.unwrap_or(RISK_CONFIDENCE);
+    if !risk::is_valid_confidence(confidence) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("The confidence level {} isn't between 0 and 1.", confidence),
+        ));
+    }
+    let internal_error =
+        |err: anyhow::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err));
+
+    let weights = state
+        .portfolio_handle
+        .valuation()
+        .await
+        .map_err(internal_error)?
+        .holdings
+        .into_iter()
+        .map(|holding| (holding.symbol, holding.market_value))
+        .collect();
+
+    state
+        .handles
+        .collection_handle
+        .call(|sender| CollectionActorMsg::RiskRequest {
+            sender,
+            confidence,
+            weights,
+            request_id,
+        })
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Fetches the top movers of the newest completed batch: the gainers and the losers,
+/// by their percentage change, and the most volatile symbols, by the standard deviation
+/// of their daily returns
+///
+/// content-type: application/json
+///
+/// GET /movers
+#[utoipa::path(
+    get,
+    path = "/movers",
+    responses(
+        (status = 200, description = "The top movers of the newest completed batch", body = Movers),
+        (status = 404, description = "No batch has been completed yet"),
+        (status = 500, description = "The collection actor didn't respond"),
+    )
+)]
+pub async fn get_movers(
+    State(state): State<WebAppState>,
+    headers: HeaderMap,
+) -> Result<Json<Movers>, (StatusCode, String)> {
+    let request_id = request_id(&headers);
+
+    state
+        .handles
+        .collection_handle
+        .call(|sender| CollectionActorMsg::MoversRequest { sender, request_id })
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))?
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                "No batch has been completed yet.".to_string(),
+            )
+        })
+}
+
+/// Fetches the aggregates of the sectors' rows in the newest completed batch:
+/// the average percentage change, and the breadth, i.e., the numbers of advancing
+/// and declining symbols
+///
+/// The sectors are declared with the `sectors` option; the ones without rows aren't listed.
+///
+/// content-type: application/json
+///
+/// GET /sectors
+#[utoipa::path(
+    get,
+    path = "/sectors",
+    responses(
+        (status = 200, description = "The aggregates of the sectors' rows in the newest batch", body = Vec<SectorAggregate>),
+        (status = 500, description = "The collection actor didn't respond"),
+    )
+)]
+pub async fn get_sectors(
+    State(state): State<WebAppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SectorAggregate>>, (StatusCode, String)> {
+    let request_id = request_id(&headers);
+
+    state
+        .handles
+        .collection_handle
+        .call(|sender| CollectionActorMsg::SectorsRequest { sender, request_id })
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Fetches the recent headlines of a tracked symbol, newest first, alongside its row
+/// of the newest batch
+///
+/// The headlines are retrieved from the news provider that the `news` option selects,
+/// and they are cached until the next batch is assembled.
+///
+/// content-type: application/json
+///
+/// GET /symbol/sym/news
+#[utoipa::path(
+    get,
+    path = "/symbol/{sym}/news",
+    params(
+        ("sym" = String, Path, description = "The symbol, case-insensitive"),
+    ),
+    responses(
+        (status = 200, description = "The symbol's headlines and indicators", body = SymbolNews),
+        (status = 404, description = "News isn't enabled, or the symbol isn't tracked"),
+        (status = 500, description = "An actor didn't respond"),
+        (status = 502, description = "The news provider failed"),
+    )
+)]
+pub async fn get_symbol_news(
+    State(state): State<WebAppState>,
+    Path(sym): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<SymbolNews>, (StatusCode, String)> {
+    let news_handle = state.news_handle.as_ref().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            "News isn't enabled; the `news` option enables it.".to_string(),
+        )
+    })?;
+    let symbol = sym.trim().to_uppercase();
+    let request_id = request_id(&headers);
+    let internal = |err: anyhow::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err));
+
+    // only the tracked symbols' headlines are retrieved, so that the provider isn't asked
+    // for arbitrary symbols
+    let symbols = state.symbols_handle.get().await.map_err(internal)?;
+    if !symbols.iter().any(|tracked| **tracked == *symbol) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("The symbol {} isn't tracked.", symbol),
+        ));
+    }
+
+    let indicators = state
+        .handles
+        .collection_handle
+        .call(|sender| CollectionActorMsg::SymbolLatestRequest {
+            sender,
+            symbol: symbol.clone(),
+            request_id,
+        })
+        .await
+        .map_err(internal)?;
+
+    match news_handle
+        .headlines(symbol.clone())
+        .await
+        .map_err(internal)?
+    {
+        Ok((iteration, headlines)) => Ok(Json(SymbolNews {
+            symbol,
+            indicators,
+            iteration,
+            headlines,
+        })),
+        Err(err) => Err((StatusCode::BAD_GATEWAY, format!("{:#}", err))),
+    }
+}
+
+/// Fetches the newest summaries of the underlyings' option chains: their put/call ratios
+/// and the max pains of their nearest expiries
+///
+/// The underlyings are given with the `options` option; the ones whose chains haven't been
+/// fetched yet aren't listed.
+///
+/// content-type: application/json
+///
+/// GET /options
+#[utoipa::path(
+    get,
+    path = "/options",
+    responses(
+        (status = 200, description = "The newest summaries of the option chains", body = Vec<OptionsSummary>),
+        (status = 404, description = "No underlyings were given"),
+        (status = 500, description = "The options actor didn't respond"),
+    )
+)]
+pub async fn get_options(
+    State(state): State<WebAppState>,
+) -> Result<Json<Vec<OptionsSummary>>, (StatusCode, String)> {
+    let options_handle = state.options_handle.as_ref().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            "No underlyings were given; the `options` option gives them.".to_string(),
+        )
+    })?;
+
+    options_handle
+        .summaries()
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Registers a new webhook with optional filter rules: a symbol and a threshold
+///
+/// Whenever a new batch has been assembled, its rows that match the filter rules are POSTed
+/// to the webhook's URL in the JSON format. A row matches if it is of the given symbol, and if
+/// its absolute percentage change is at least the given threshold.
+///
+/// Responds with the registered webhook, whose `id` is used for unregistering it.
+///
+/// content-type: application/json
+///
+/// POST /webhooks
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    request_body = WebhookRegistration,
+    responses(
+        (status = 201, description = "The registered webhook", body = Webhook),
+        (status = 400, description = "The URL or a filter rule is invalid"),
+        (status = 500, description = "The webhook actor didn't respond"),
+    )
+)]
+pub async fn post_webhook(
+    State(state): State<WebAppState>,
+    Json(registration): Json<WebhookRegistration>,
+) -> Result<(StatusCode, Json<Webhook>), (StatusCode, String)> {
+    registration
+        .validate()
+        .map_err(|err| (StatusCode::BAD_REQUEST, format!("{:#}", err)))?;
+
+    state
+        .webhook_handle
+        .register(registration)
+        .await
+        .map(|webhook| (StatusCode::CREATED, Json(webhook)))
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Fetches all registered webhooks
+///
+/// content-type: application/json
+///
+/// GET /webhooks
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    responses(
+        (status = 200, description = "The registered webhooks", body = Vec<Webhook>),
+        (status = 500, description = "The webhook actor didn't respond"),
+    )
+)]
+pub async fn get_webhooks(
+    State(state): State<WebAppState>,
+) -> Result<Json<Vec<Webhook>>, (StatusCode, String)> {
+    state
+        .webhook_handle
+        .list()
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Unregisters the webhook with the given `id`
+///
+/// DELETE /webhooks/id
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    params(("id" = u64, Path, description = "The webhook's identifier")),
+    responses(
+        (status = 204, description = "The webhook was unregistered"),
+        (status = 404, description = "The webhook isn't registered"),
+        (status = 500, description = "The webhook actor didn't respond"),
+    )
+)]
+pub async fn delete_webhook(
+    State(state): State<WebAppState>,
+    Path(id): Path<u64>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state.webhook_handle.unregister(id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            format!("The webhook {} isn't registered.", id),
+        )),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err))),
+    }
+}
+
+/// Places an order in the paper-trading account
+///
+/// The order is filled at the latest fetched price of its symbol, if the cash covers a buy,
+/// or if the held shares cover a sell.
+///
+/// Responds with the fill.
+///
+/// content-type: application/json
+///
+/// POST /paper/orders
+#[utoipa::path(
+    post,
+    path = "/paper/orders",
+    request_body = Order,
+    responses(
+        (status = 200, description = "The filled order", body = Fill),
+        (status = 404, description = "Paper trading isn't enabled"),
+        (status = 422, description = "The order was rejected"),
+        (status = 500, description = "The paper-trading actor didn't respond"),
+    )
+)]
+pub async fn post_paper_order(
+    State(state): State<WebAppState>,
+    Json(order): Json<Order>,
+) -> Result<Json<Fill>, (StatusCode, String)> {
+    let paper_handle = paper_trading(&state)?;
+
+    match paper_handle.place(order).await {
+        Ok(Ok(fill)) => Ok(Json(fill)),
+        Ok(Err(err)) => Err((StatusCode::UNPROCESSABLE_ENTITY, format!("{:#}", err))),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err))),
+    }
+}
+
+/// Fetches the cash, the positions and the newest fills of the paper-trading account
+///
+/// content-type: application/json
+///
+/// GET /paper/portfolio
+#[utoipa::path(
+    get,
+    path = "/paper/portfolio",
+    responses(
+        (status = 200, description = "The paper-trading account", body = Portfolio),
+        (status = 404, description = "Paper trading isn't enabled"),
+        (status = 500, description = "The paper-trading actor didn't respond"),
+    )
+)]
+pub async fn get_paper_portfolio(
+    State(state): State<WebAppState>,
+) -> Result<Json<Portfolio>, (StatusCode, String)> {
+    paper_trading(&state)?
+        .portfolio()
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Returns the paper-trading actor's handle, or the response that paper trading isn't enabled
+fn paper_trading(state: &WebAppState) -> Result<&PaperTradingActorHandle, (StatusCode, String)> {
+    state.paper_handle.as_ref().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            "Paper trading isn't enabled; the `paper-trading` option enables it.".to_string(),
+        )
+    })
+}
+
+/// Fetches the valuation of the holdings at the latest prices: their market values,
+/// unrealized profits and losses, and daily changes, and the totals
+///
+/// content-type: application/json
+///
+/// GET /portfolio
+#[utoipa::path(
+    get,
+    path = "/portfolio",
+    responses(
+        (status = 200, description = "The valuation of the holdings", body = Valuation),
+        (status = 500, description = "The portfolio actor didn't respond"),
+    )
+)]
+pub async fn get_portfolio(
+    State(state): State<WebAppState>,
+) -> Result<Json<Valuation>, (StatusCode, String)> {
+    state
+        .portfolio_handle
+        .valuation()
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Adds a holding to the portfolio, or replaces the holding of its symbol
+///
+/// The symbol is added to the live set of symbols, too, so that it's fetched
+/// starting with the next iteration of the main loop.
+///
+/// Responds with the valuation of the holdings.
+///
+/// content-type: application/json
+///
+/// POST /portfolio/holdings
+#[utoipa::path(
+    post,
+    path = "/portfolio/holdings",
+    request_body = Holding,
+    responses(
+        (status = 200, description = "The valuation of the holdings", body = Valuation),
+        (status = 400, description = "The quantity or the cost basis isn't positive"),
+        (status = 500, description = "The portfolio or the symbol registry didn't respond"),
+    )
+)]
+pub async fn post_holding(
+    State(state): State<WebAppState>,
+    Json(holding): Json<Holding>,
+) -> Result<Json<Valuation>, (StatusCode, String)> {
+    holding
+        .validate()
+        .map_err(|err| (StatusCode::BAD_REQUEST, format!("{:#}", err)))?;
+    let internal_error =
+        |err: anyhow::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err));
+
+    state
+        .symbols_handle
+        .add(vec![holding.symbol.clone()])
+        .await
+        .map_err(internal_error)?;
+    state
+        .portfolio_handle
+        .set(holding)
+        .await
+        .map_err(internal_error)?;
+
+    state
+        .portfolio_handle
+        .valuation()
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Removes the holding of a symbol from the portfolio
+///
+/// The symbol is still fetched; it can be removed through `DELETE /symbols/sym`.
+///
+/// DELETE /portfolio/holdings/sym
+#[utoipa::path(
+    delete,
+    path = "/portfolio/holdings/{sym}",
+    params(("sym" = String, Path, description = "The symbol whose holding to remove")),
+    responses(
+        (status = 204, description = "The holding was removed"),
+        (status = 404, description = "There is no holding of the symbol"),
+        (status = 500, description = "The portfolio actor didn't respond"),
+    )
+)]
+pub async fn delete_holding(
+    State(state): State<WebAppState>,
+    Path(sym): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state.portfolio_handle.remove(sym.clone()).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            format!("There is no holding of {}.", sym),
+        )),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err))),
     }
 }
 
+/// Fetches the watchlists, with their symbols and indicators
+///
+/// content-type: application/json
+///
+/// GET /watchlists
+#[utoipa::path(
+    get,
+    path = "/watchlists",
+    responses(
+        (status = 200, description = "The watchlists", body = Vec<Watchlist>),
+        (status = 500, description = "The watchlist actor didn't respond"),
+    )
+)]
+pub async fn get_watchlists(
+    State(state): State<WebAppState>,
+) -> Result<Json<Vec<Watchlist>>, (StatusCode, String)> {
+    state
+        .watchlist_handle
+        .watchlists()
+        .await
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)))
+}
+
+/// Fetches the last `n` batches of the watchlist `name`, newest first, which contain
+/// the rows of the watchlist's symbols, with only the watchlist's indicators
+///
+/// If `n` is greater than the buffer size, we return the entire contents of the buffer,
+/// whether it is full or not.
+///
+/// content-type: application/json
+///
+/// GET /watchlists/name/tail/n
+#[utoipa::path(
+    get,
+    path = "/watchlists/{name}/tail/{n}",
+    params(
+        ("name" = String, Path, description = "The name of the watchlist"),
+        ("n" = usize, Path, description = "Number of the most recent batches to return"),
+    ),
+    responses(
+        (status = 200, description = "The last `n` batches of the watchlist", body = WatchlistTail),
+        (status = 404, description = "There is no such watchlist"),
+        (status = 500, description = "The watchlist actor didn't respond"),
+    )
+)]
+pub async fn get_watchlist_tail(
+    State(state): State<WebAppState>,
+    Path((name, n)): Path<(String, usize)>,
+) -> Result<(Extension<RowsReturned>, Json<WatchlistTail>), (StatusCode, String)> {
+    // limit n to buffer capacity
+    let n = n.clamp(0, state.tail_buffer_size);
+
+    match state.watchlist_handle.tail(name.clone(), n).await {
+        Ok(Some(tail)) => Ok((
+            rows_returned(&tail),
+            Json(WatchlistTail {
+                from: state.from,
+                name,
+                tail,
+            }),
+        )),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("There is no watchlist `{}`.", name),
+        )),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err))),
+    }
+}
+
+/// Counts the rows of a `tail`, for the audit log
+fn rows_returned(tail: &TailResponse) -> Extension<RowsReturned> {
+    Extension(RowsReturned(tail.iter().map(Vec::len).sum()))
+}
+
+/// Creates an `ETag` header out of a tail buffer version
+fn etag(version: u64) -> [(axum::http::HeaderName, String); 1] {
+    [(ETAG, format!("\"{}\"", version))]
+}
+
+/// Extracts a tail buffer version from the `If-None-Match` header
+///
+/// Both strong (`"7"`) and weak (`W/"7"`) `ETag`s are accepted.
+/// If the header contains a list of `ETag`s, the first valid one is used.
+fn parse_if_none_match(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(IF_NONE_MATCH)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .filter_map(|tag| {
+            let tag = tag.trim();
+            let tag = tag.strip_prefix("W/").unwrap_or(tag);
+            tag.trim_matches('"').parse().ok()
+        })
+        .next()
+}
+
+/// Extracts a request's correlation ID from the `x-request-id` header
+///
+/// The header is set by the request-ID middleware, unless the client has provided it.
+fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
 /// Describes the app
 async fn description() -> Html<&'static str> {
     Html("<p>Stock Trading CLI with Async Streams</p>")
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn if_none_match_is_parsed() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, parse_if_none_match(&headers));
+
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("\"7\""));
+        assert_eq!(Some(7), parse_if_none_match(&headers));
+
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("W/\"8\""));
+        assert_eq!(Some(8), parse_if_none_match(&headers));
+
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("\"abc\", \"9\""));
+        assert_eq!(Some(9), parse_if_none_match(&headers));
+
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert_eq!(None, parse_if_none_match(&headers));
+    }
+}
@@ -0,0 +1,233 @@
+//! The intraday pipeline
+//!
+//! With the `intraday-tick-interval` option, a fast intraday pipeline runs alongside the main,
+//! daily-bar, pipeline, in the same process. It fetches the same symbols, which can change at runtime,
+//! but in intraday bars, e.g., of five minutes, over the few days before the end of its period,
+//! at its own tick interval, and with its own window size of the simple moving average.
+//!
+//! The pipelines share the actor infrastructure: the fetch workers, through the pool's
+//! [`FetchQueue`], the statistics, and the dead letters. The intraday pipeline has its own writer
+//! actor, which writes its rows to its own output files, e.g., `intraday.csv`, and its own collection
+//! and broker actors, so that its batches aren't mixed with the daily ones in the web app, nor in
+//! the consumers of the daily batches.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use time::OffsetDateTime;
+use time_tz::{OffsetDateTimeExt, Tz};
+use tokio_util::sync::CancellationToken;
+
+use crate::asset_classes::AssetClasses;
+use crate::cli::Args;
+use crate::constants::{INTRADAY_FILE_STEM, INTRADAY_LOOKBACK_DAYS};
+use crate::my_async_actors::{
+    calc_num_chunks, ActorHandle, BrokerActorHandle, CollectionActorHandle, FetchActorMsg,
+    FetchQueue, Iteration, PipelineHandles, PipelineSettings, SymbolsActorHandle,
+    WebhookActorHandle, WriterActorHandle,
+};
+use crate::price_windows::PriceWindows;
+use crate::runtime::{self, Interval, JoinHandle};
+use crate::tail_buffer::TailBufferLimits;
+
+/// The intraday pipeline's actors, and the task of its loop
+pub struct IntradayPipeline {
+    handles: PipelineHandles,
+    broker_handle: BrokerActorHandle,
+    webhook_handle: WebhookActorHandle,
+    stop_token: CancellationToken,
+    task: Option<JoinHandle<()>>,
+}
+
+impl IntradayPipeline {
+    /// Spawns the intraday pipeline's actors, and its loop, which ticks every `tick_interval`
+    ///
+    /// The loop fetches the symbols of the `symbols_handle`, through the `fetch_queue`,
+    /// reports to the statistics and the dead letters of the `daily` pipeline's handles,
+    /// and shares its limit of simultaneous data provider requests.
+    pub fn spawn(
+        args: &Args,
+        nticks: usize,
+        tick_interval: Duration,
+        symbols_handle: SymbolsActorHandle,
+        daily: &PipelineHandles,
+        fetch_queue: FetchQueue,
+    ) -> Self {
+        let stats_handle = daily.stats_handle.clone();
+        let dead_letter_handle = daily.dead_letter_handle.clone();
+        // the batches are published only to the pipeline's own consumers, of which there are none yet,
+        // and they aren't sent to the webhooks that are registered with the daily pipeline
+        let broker_handle = BrokerActorHandle::new(nticks);
+        let webhook_handle = WebhookActorHandle::new(nticks);
+        let handles = PipelineHandles {
+            writer_handle: WriterActorHandle::at_stem(
+                nticks,
+                args.writer_mailbox_capacity,
+                args.format.clone(),
+                INTRADAY_FILE_STEM,
+                args.dry_run,
+                dead_letter_handle.clone(),
+            ),
+            collection_handle: CollectionActorHandle::with_listeners(
+                nticks,
+                args.collection_mailbox_capacity,
+                TailBufferLimits {
+                    size: args.tail_buffer_size,
+                    memory_budget_mb: args.tail_memory_budget_mb,
+                },
+                stats_handle.clone(),
+                webhook_handle.clone(),
+                broker_handle.clone(),
+                dead_letter_handle.clone(),
+            ),
+            stats_handle,
+            dead_letter_handle,
+            settings: Arc::new(PipelineSettings {
+                risk_confidence: None,
+                asset_classes: AssetClasses::new(&args.asset_classes),
+                bars: args.intraday_bars,
+                windows: args.rolling_windows.then(PriceWindows::default),
+                concurrency: daily.settings.concurrency.clone(),
+                quotes: daily.settings.quotes.clone(),
+                #[cfg(feature = "arrow")]
+                columnar: daily.settings.columnar,
+            }),
+        };
+
+        let stop_token = CancellationToken::new();
+        let task = runtime::spawn(Self::run(
+            Looping {
+                handles: handles.clone(),
+                symbols_handle,
+                fetch_queue,
+                chunk_size: args.chunk_size,
+                window_size: args.intraday_window_size,
+                timezone: args.timezone,
+            },
+            tick_interval,
+            stop_token.clone(),
+        ));
+
+        Self {
+            handles,
+            broker_handle,
+            webhook_handle,
+            stop_token,
+            task: Some(task),
+        }
+    }
+
+    /// Stops the pipeline's loop, and waits for it; the chunks that it has handed over
+    /// are still processed, so its actors are shut down only after the fetch pool
+    pub async fn stop(&mut self) {
+        self.stop_token.cancel();
+        if let Some(task) = self.task.take() {
+            if let Err(err) = task.await {
+                tracing::error!("The intraday pipeline's loop failed: {}", err);
+            }
+        }
+    }
+
+    /// Shuts the pipeline's actors down, after [`IntradayPipeline::stop`]
+    ///
+    /// # Returns
+    /// The results of shutting down each actor
+    pub async fn shutdown(mut self) -> Vec<Result<()>> {
+        self.stop().await;
+
+        vec![
+            self.handles.collection_handle.shutdown().await,
+            self.broker_handle.shutdown().await,
+            self.handles.writer_handle.shutdown().await,
+            self.webhook_handle.shutdown().await,
+        ]
+    }
+
+    /// The pipeline's loop, which hands the chunks of the current symbols over to the fetch workers
+    /// at every tick, until the `stop_token` is cancelled
+    async fn run(looping: Looping, tick_interval: Duration, stop_token: CancellationToken) {
+        let Looping {
+            handles,
+            symbols_handle,
+            fetch_queue,
+            chunk_size,
+            window_size,
+            timezone,
+        } = looping;
+        let mut interval = Interval::new(tick_interval);
+        let mut iteration_id = 0;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stop_token.cancelled() => break,
+            }
+
+            let start = Instant::now();
+            let to = OffsetDateTime::now_utc();
+            // the start of the period is displayed in the display time zone, like the daily one's
+            let from = to - time::Duration::days(INTRADAY_LOOKBACK_DAYS);
+            let from = match timezone {
+                Some(timezone) => from.to_timezone(timezone),
+                None => from,
+            };
+            let symbols = match symbols_handle.get().await {
+                Ok(symbols) => symbols,
+                Err(err) => {
+                    tracing::warn!("The intraday pipeline couldn't get the symbols: {:#}", err);
+                    continue;
+                }
+            };
+            if let Some(windows) = &handles.settings.windows {
+                windows.retain(&symbols);
+            }
+
+            iteration_id += 1;
+            let iteration = Iteration {
+                id: iteration_id,
+                start,
+                to,
+                num_chunks: calc_num_chunks(symbols.len(), chunk_size),
+            };
+            let dispatch = async {
+                for (chunk_id, chunk) in symbols.chunks(chunk_size).enumerate() {
+                    if let Err(err) = fetch_queue
+                        .send(FetchActorMsg::QuoteRequestsMsg {
+                            symbols: chunk.into(),
+                            from,
+                            handles: handles.clone(),
+                            iteration,
+                            chunk_id,
+                            window_size,
+                        })
+                        .await
+                    {
+                        handles
+                            .dead_letter_handle
+                            .undelivered("FetchPool", "QuoteRequestsMsg", err)
+                            .await;
+                    }
+                }
+            };
+            // a stop doesn't wait for a full work queue
+            tokio::select! {
+                _ = dispatch => {}
+                _ = stop_token.cancelled() => break,
+            }
+        }
+
+        tracing::debug!("The intraday pipeline's loop is stopped.");
+    }
+}
+
+/// What the intraday pipeline's loop works with
+struct Looping {
+    handles: PipelineHandles,
+    symbols_handle: SymbolsActorHandle,
+    fetch_queue: FetchQueue,
+    chunk_size: usize,
+    window_size: usize,
+    /// The display time zone of the start of the period; UTC if `None`
+    timezone: Option<&'static Tz>,
+}
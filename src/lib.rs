@@ -1,10 +1,47 @@
 pub mod actix_async_actors;
+pub mod alerts;
+pub mod asset_classes;
 pub mod async_signals;
+pub mod audit;
+pub mod backtest;
+pub mod bench_variants;
 pub mod cli;
+#[cfg(feature = "arrow")]
+pub mod columnar;
+pub mod config;
+pub mod config_file;
 pub mod constants;
+pub mod error_summary;
+pub mod errors;
+pub mod event_log;
+pub mod grpc;
 pub mod handlers;
+pub mod intraday;
 pub mod logic;
+pub mod loop_watchdog;
+pub mod memory;
+pub mod movers;
 pub mod my_async_actors;
+pub mod news;
+pub mod option_chains;
+pub mod paper;
+pub mod pipeline;
+pub mod portfolio;
+pub mod prelude;
+pub mod price_windows;
 pub mod process;
+pub mod quality;
+pub mod quotes;
+pub mod reload;
+pub mod replay;
+pub mod risk;
+pub mod runtime;
+pub mod sectors;
+pub mod sinks;
+pub mod state;
+pub mod strategies;
 pub mod sync_signals;
+pub mod tail_buffer;
 pub mod types;
+pub mod watchlists;
+pub mod webhooks;
@@ -17,27 +17,106 @@
 
 #![allow(unused_imports)]
 
-use std::sync::OnceLock;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use actix::Actor;
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
+use axum::body::Body;
+use axum::http::{self, HeaderName};
+use axum::middleware;
+use axum::routing::{delete, get, post};
 use axum::Router;
-use axum::routing::get;
 use clap::Parser;
 use rayon::prelude::*;
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use tower_governor::governor::GovernorConfigBuilder;
+use tower_governor::GovernorLayer;
+use tower_http::compression::CompressionLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 // use crate::actix_async_actors::{handle_symbol_data, WriterActor};
-use crate::cli::{Args, ImplementationVariant};
+use crate::alerts::AlertTriggers;
+use crate::asset_classes::AssetClasses;
+use crate::audit;
+use crate::backtest;
+use crate::bench_variants;
+use crate::cli::{check_period, Args, BarInterval, ImplementationVariant};
+use crate::config::RuntimeConfig;
 use crate::constants::{
-    ACTOR_CHANNEL_CAPACITY, CHUNK_SIZE, CSV_HEADER, TICK_INTERVAL_SECS, WEB_SERVER_ADDRESS,
+    ACTOR_CHANNEL_CAPACITY, CHUNK_SIZE, CSV_FILE_PATH, CSV_HEADER, GRPC_SERVER_ADDRESS,
+    HEARTBEAT_INTERVAL_SECS, LOOP_WATCHDOG_CHECK_INTERVAL_MS, MOVERS_FILE_PATH, OPTIONS_FILE_PATH,
+    PORTFOLIO_FILE_PATH, RATE_LIMITER_CLEANUP_INTERVAL_SECS, REQUEST_ID_HEADER,
+    ROWS_TRACING_TARGET, SLOW_HANDLER_THRESHOLD_MS, TAIL_RATE_LIMIT_BURST_SIZE,
+    TAIL_RATE_LIMIT_REPLENISH_MS, TICK_INTERVAL_SECS, WEB_SERVER_ADDRESS,
 };
-use crate::handlers::{get_desc, get_tail, get_tail_str, root, WebAppState};
+use crate::event_log;
+use crate::grpc::proto::stocks_server::StocksServer;
+use crate::grpc::StocksService;
+use crate::handlers::{
+    delete_holding, delete_symbol, delete_webhook, get_config, get_dead_letters, get_desc,
+    get_health, get_memory_stats, get_movers, get_options, get_paper_portfolio, get_portfolio,
+    get_quality, get_range, get_risk, get_sectors, get_stats, get_symbol_news, get_symbol_tail,
+    get_tail, get_tail_str, get_version, get_watchlist_tail, get_watchlists, get_webhooks,
+    post_holding, post_paper_order, post_symbols, post_webhook, put_config, root, ApiDoc,
+    WebAppState,
+};
+use crate::intraday::IntradayPipeline;
+use crate::loop_watchdog::LoopWatchdog;
 use crate::my_async_actors::{
-    ActorHandle, ActorMessage, CollectionActorHandle, UniversalActorHandle, WriterActorHandle,
+    fetch_stream, join_actor_tasks, ActorHandle, AlertActorHandle, AuditActorHandle,
+    BrokerActorHandle, CollectionActorHandle, ConfigActorHandle, DeadLetterActorHandle,
+    EventLogActorHandle, FetchActorMsg, FetchPool, Iteration, Monitored, NewsActorHandle,
+    OptionsActorHandle, PaperTradingActorHandle, PerformanceIndicatorsRow, PipelineHandles,
+    PipelineSettings, PortfolioActorHandle, SlowHandlerLogger, StatsActorHandle, StatsActorMsg,
+    SymbolsActorHandle, WatchdogActorHandle, WatchlistActorHandle, WebhookActorHandle,
+    WriterActorHandle,
 };
-use crate::types::MsgResponseType;
+use crate::news;
+use crate::option_chains::YahooOptions;
+use crate::price_windows::PriceWindows;
+use crate::quotes;
+#[cfg(unix)]
+use crate::reload::reload_on_hangup;
+use crate::replay;
+use crate::runtime::Interval;
+use crate::state::StateFile;
+use crate::strategies::StrategyEngine;
+use crate::tail_buffer::TailBufferLimits;
+use crate::types::{Batch, MsgResponseType, Symbol};
+
+/// How the main loop runs, which is chosen by the CLI subcommand
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// An iteration at every tick, until the shutdown token is cancelled
+    Run,
+    /// A single iteration
+    Once,
+    /// No iterations; the newest rows from the output file are served
+    /// until the shutdown token is cancelled
+    Serve,
+    /// An iteration for every `step` of the period, whose end moves by the `step`
+    /// at every iteration, until it reaches the end of the period
+    Backfill { step: time::Duration },
+    /// No iterations; the symbols' history is replayed through a trading strategy instead,
+    /// see [`crate::backtest::run`]
+    Backtest,
+    /// No iterations; the implementation variants are benchmarked against a mock data provider
+    /// instead, see [`crate::bench_variants::run`]
+    BenchVariants { iterations: usize },
+    /// No fetching; the batches that were recorded in the output file are replayed, one at every tick
+    /// of the tick interval divided by the `speed` multiplier, and then served
+    /// until the shutdown token is cancelled, see [`crate::replay`]
+    Replay { speed: f64 },
+}
 
 /// **The main loop**
 ///
@@ -55,35 +134,281 @@ use crate::types::MsgResponseType;
 /// Most implementations use the Actor model, and the main implementation
 /// is based on it.
 ///
+/// The `mode` decides how many iterations there are, and whether the web app is spawned;
+/// see [`Mode`]. A backtest is delegated to [`backtest::run`], and a benchmark of
+/// the implementation variants to [`bench_variants::run`].
+///
+/// The loop runs until the `shutdown_token` is cancelled, or until the `mode`'s iterations
+/// are done. Then the loop stops scheduling
+/// new iterations, the web app completes in-flight requests and stops, and the function
+/// returns after all in-flight chunks have been processed and all actors have been shut down.
+///
 /// # Errors
 /// - [time::error::Parse](https://docs.rs/time/0.3.36/time/error/enum.Parse.html)
-pub async fn main_loop(args: Args) -> Result<MsgResponseType> {
-    let from = OffsetDateTime::parse(&args.from, &Rfc3339)
-        .context("The provided date or time format isn't correct.")?;
-    let variant = args.variant;
-
-    let symbols: Vec<String> = args.symbols.split(',').map(|s| s.to_string()).collect();
-    static SYMBOLS: OnceLock<Vec<String>> = OnceLock::new();
-    // let symbols = SYMBOLS.get_or_init(|| args.symbols.split(",").map(|s| s.to_string()).collect());
-    let symbols = SYMBOLS.get_or_init(|| symbols);
-
-    let chunks_of_symbols: Vec<&[String]> = match variant {
-        ImplementationVariant::MyActorsNoRayon
-        | ImplementationVariant::ActixActorsNoRayon
-        | ImplementationVariant::NoActorsNoRayon => symbols.chunks(CHUNK_SIZE).collect(), // stdlib chunks
-
-        ImplementationVariant::MyActorsRayon
-        | ImplementationVariant::ActixActorsRayon
-        | ImplementationVariant::NoActorsRayon => symbols.par_chunks(CHUNK_SIZE).collect(), // rayon parallel chunks
-    };
+/// - if the provided end of the period doesn't come after its start
+/// - [std::io::Error] if the symbols file can't be read
+/// - [std::io::Error] if the output file can't be read when serving
+/// - If a single run, i.e., one that isn't stopped by the `shutdown_token`, is incomplete,
+///   because some symbols couldn't be fetched, or some messages couldn't be handled or delivered
+/// - [std::io::Error] if the web app can't be started or if it fails
+pub async fn main_loop(
+    args: Args,
+    mode: Mode,
+    shutdown_token: CancellationToken,
+) -> Result<MsgResponseType> {
+    // a backtest replays the history on its own, without the actors and the web app
+    if mode == Mode::Backtest {
+        return backtest::run(args, shutdown_token).await;
+    }
+    if let Mode::BenchVariants { iterations } = mode {
+        return bench_variants::run(args, iterations, shutdown_token).await;
+    }
+
+    // a fixed end of the period is for analyzing a historical window
+    let (from, fixed_to) = args.period(OffsetDateTime::now_utc())?;
+    // The start of the period is output in the display time zone. It's the same instant,
+    // only at a different offset, so the data provider is asked for the same period.
+    let display_from = args.in_display_timezone(from);
+    let display_from_arg = display_from.format(&Rfc3339)?;
+    let mut symbols = args.symbols()?;
+    // the holdings are valued at the prices of their symbols, so those are fetched, too
+    symbols.extend(args.holdings.iter().map(|h| h.symbol.clone()));
+    // and so are the symbols of the watchlists
+    symbols.extend(args.watchlists.iter().flat_map(|w| w.symbols.clone()));
+    let variant = args.variant.clone();
 
-    // used only in CollectionActor
+    // used only for the initial capacities of actors' buffers
     let nticks = symbols.len();
 
+    // The initial tick interval, chunk size and window size are taken from the command line.
+    let initial_config = RuntimeConfig {
+        tick_interval_secs: args.tick_interval_secs,
+        chunk_size: args.chunk_size,
+        window_size: args.window_size,
+        indicators: args
+            .indicators
+            .clone()
+            .unwrap_or_else(|| RuntimeConfig::default().indicators),
+        ..Default::default()
+    };
+    // The state that was saved before a restart is restored, relative to the arguments.
+    // A dry run restores it, but doesn't save it.
+    let mut state_file = StateFile::load(
+        args.state_file.clone(),
+        args.dry_run,
+        symbols,
+        initial_config.clone(),
+        from,
+    )?;
+
+    // The symbols can be added at runtime, through the web app, so they are owned by an actor.
+    // The main loop asks the actor for the current symbols at every tick.
+    let symbols_handle = SymbolsActorHandle::new(nticks);
+    symbols_handle.add(state_file.symbols()).await?;
+
+    // Some settings can be changed at runtime, through the web app, so they are owned by an actor.
+    // The main loop asks the actor for the current configuration at every tick.
+    let config = state_file.config();
+    let config_handle = ConfigActorHandle::with_config(nticks, config.clone());
+
     // Use with my Actor implementation
     // Tested and it works with the integrated web application.
-    let writer_handle = WriterActorHandle::new(nticks);
-    let collection_handle = CollectionActorHandle::new(nticks);
+    let stats_handle = StatsActorHandle::new(nticks);
+    // a dry run writes nothing, neither to the output files nor to the webhooks
+    let webhook_handle = if args.dry_run {
+        WebhookActorHandle::dry_run(nticks)
+    } else {
+        WebhookActorHandle::new(nticks)
+    };
+    // consumers of the newly-assembled batches subscribe to the broker actor
+    let broker_handle = BrokerActorHandle::new(nticks);
+    // the alert rules are checked against every batch that the broker actor publishes,
+    // which is also fed to the trading strategy, if its signals raise alerts, too
+    let triggers = AlertTriggers {
+        rules: args.alerts.clone(),
+        signals: if args.alert_signals {
+            Some(StrategyEngine::new(args.strategy.clone())?)
+        } else {
+            None
+        },
+    };
+    let alert_handle = if triggers.is_empty() {
+        None
+    } else {
+        Some(AlertActorHandle::with_rules(
+            nticks,
+            triggers,
+            args.alert_channels.clone(),
+            args.mailer()?,
+            args.alert_chat_rate_limit,
+            args.dry_run,
+            broker_handle.subscribe().await?,
+        ))
+    };
+    // the paper-trading account trades on every batch that the broker actor publishes
+    let paper_handle = if args.paper_trading {
+        Some(PaperTradingActorHandle::with_account(
+            nticks,
+            args.paper_cash,
+            StrategyEngine::new(args.strategy.clone())?,
+            args.paper_order_value,
+            broker_handle.subscribe().await?,
+        ))
+    } else {
+        None
+    };
+    // every request to the web app is recorded, if there is an audit log
+    let audit_handle = args
+        .audit_log
+        .as_deref()
+        .map(|path| AuditActorHandle::with_path(nticks, path))
+        .transpose()?;
+    // the headlines are retrieved from the news provider, if there is one, and they are cached
+    // until the broker actor publishes the next batch
+    let news_handle = match &args.news {
+        Some(source) => Some(NewsActorHandle::with_provider(
+            nticks,
+            news::provider(source)?,
+            broker_handle.subscribe().await?,
+        )),
+        None => None,
+    };
+    // the option chains of the underlyings, if there are any, are fetched whenever the broker actor
+    // publishes a batch, and their summaries are written to their own file, unless it's a dry run;
+    // the chains are current, so they aren't fetched for historical or recorded batches
+    let options_handle = match mode {
+        Mode::Run | Mode::Once if !args.options.is_empty() => {
+            Some(OptionsActorHandle::with_underlyings(
+                nticks,
+                Box::new(YahooOptions::new()?),
+                args.options.clone(),
+                (!args.dry_run).then_some(Path::new(OPTIONS_FILE_PATH)),
+                broker_handle.subscribe().await?,
+            )?)
+        }
+        _ => None,
+    };
+    // every batch that the broker actor publishes is appended to the event log, if there is one,
+    // unless the batches are served or replayed instead of fetched, or it's a dry run
+    let event_log_handle = match (&args.event_log, mode) {
+        (Some(path), Mode::Run | Mode::Once | Mode::Backfill { .. }) if !args.dry_run => {
+            Some(EventLogActorHandle::with_path(
+                nticks,
+                path,
+                display_from_arg.clone(),
+                broker_handle.subscribe().await?,
+            )?)
+        }
+        _ => None,
+    };
+    // the holdings are valued at every batch that the broker actor publishes,
+    // and the valuations are written to their own file, unless it's a dry run
+    let portfolio_handle = PortfolioActorHandle::with_holdings(
+        nticks,
+        args.holdings.clone(),
+        (!args.dry_run).then(|| PathBuf::from(PORTFOLIO_FILE_PATH)),
+        broker_handle.subscribe().await?,
+    );
+    // messages that can't be delivered or handled end up in the dead letter actor
+    let dead_letter_handle = DeadLetterActorHandle::with_stats(nticks, stats_handle.clone());
+    // the writer and collection actors get a message for every chunk of symbols,
+    // so their mailbox capacities are configurable
+    // when serving or replaying, the output file is read back, and nothing is written
+    let formats = match mode {
+        Mode::Serve | Mode::Replay { .. } => Vec::new(),
+        Mode::Run
+        | Mode::Once
+        | Mode::Backfill { .. }
+        | Mode::Backtest
+        | Mode::BenchVariants { .. } => args.format.clone(),
+    };
+    // the rows of every watchlist are picked out of every batch that the broker actor publishes,
+    // and written to the watchlist's own files, unless it's a dry run
+    let watchlist_handle = WatchlistActorHandle::with_watchlists(
+        nticks,
+        args.watchlists.clone(),
+        args.tail_buffer_size,
+        display_from_arg.clone(),
+        if args.dry_run {
+            Vec::new()
+        } else {
+            formats.clone()
+        },
+        broker_handle.subscribe().await?,
+    );
+    let writer_handle = WriterActorHandle::with_dead_letters(
+        nticks,
+        args.writer_mailbox_capacity,
+        formats,
+        args.dry_run,
+        dead_letter_handle.clone(),
+    );
+    let collection_handle = CollectionActorHandle::with_listeners(
+        nticks,
+        args.collection_mailbox_capacity,
+        TailBufferLimits {
+            size: args.tail_buffer_size,
+            memory_budget_mb: args.tail_memory_budget_mb,
+        },
+        stats_handle.clone(),
+        webhook_handle.clone(),
+        broker_handle.clone(),
+        dead_letter_handle.clone(),
+    );
+    // the slow messages of the busiest actors are logged by an interceptor,
+    // instead of timing them in every message handler
+    let threshold = Duration::from_millis(SLOW_HANDLER_THRESHOLD_MS);
+    writer_handle.add_interceptor(SlowHandlerLogger::new("WriterActor", threshold));
+    collection_handle.add_interceptor(SlowHandlerLogger::new("CollectionActor", threshold));
+    let handles = PipelineHandles {
+        writer_handle,
+        collection_handle,
+        stats_handle,
+        dead_letter_handle,
+        settings: Arc::new(PipelineSettings {
+            risk_confidence: args.risk_confidence,
+            asset_classes: AssetClasses::new(&args.asset_classes),
+            bars: BarInterval::OneDay,
+            windows: args.rolling_windows.then(PriceWindows::default),
+            concurrency: args
+                .max_concurrency
+                .map(|permits| Arc::new(Semaphore::new(permits))),
+            quotes: Some(quotes::provider(args.quotes_fixtures.as_deref())?),
+            #[cfg(feature = "arrow")]
+            columnar: matches!(variant, ImplementationVariant::MyActorsArrow),
+        }),
+    };
+    // the rows of the sectors are aggregated in every completed batch
+    if !args.sectors.is_empty() {
+        handles
+            .collection_handle
+            .tag_sectors(args.sectors.clone())
+            .await?;
+    }
+    // the top movers of every completed batch are written to their own file,
+    // unless it's a dry run, or nothing is written
+    if !args.dry_run && matches!(mode, Mode::Run | Mode::Once | Mode::Backfill { .. }) {
+        handles
+            .collection_handle
+            .write_movers(PathBuf::from(MOVERS_FILE_PATH))
+            .await?;
+    }
+    // the long-lived actors start with all indicators, until they are told otherwise
+    if args.indicators.is_some() || config != initial_config {
+        handles
+            .reconfigure(config_handle.get().await?.reconfiguration())
+            .await;
+    }
+    // the watchdog pings the singletons that the pipeline can't do without,
+    // so that one that has stopped responding is reported, both in the logs and at `/health`
+    let watchdog_handle = WatchdogActorHandle::with_monitored(
+        nticks,
+        vec![
+            Monitored::Writer(handles.writer_handle.clone()),
+            Monitored::Collection(handles.collection_handle.clone()),
+        ],
+        Duration::from_secs(HEARTBEAT_INTERVAL_SECS),
+    );
 
     // // Use with Actix Actor implementation
     // // We need to ensure that we have one and only one `WriterActor` - a Singleton.
@@ -97,102 +422,469 @@ pub async fn main_loop(args: Args) -> Result<MsgResponseType> {
     // let writer_address = WriterActor::new().start();
 
     // // Use with async without Actors
-    // let mut writer = start_writer()?;
+    // let mut writer = start_writer().await?;
 
-    tracing::debug!("starting the web application");
+    // the newest batches of the event log, if there is one, are served until new ones are completed
+    let restored = match (&args.event_log, mode) {
+        (Some(path), Mode::Run | Mode::Serve) if path.exists() => {
+            let batches = event_log::newest(path, args.tail_buffer_size)?;
+            tracing::info!(
+                "Restored {} batches from {}.",
+                batches.len(),
+                path.display()
+            );
+            handles.collection_handle.restore(batches).await?;
+            true
+        }
+        _ => false,
+    };
+    // when serving without an event log, the newest rows from the output file are served
+    // instead of fetched ones; the output file doesn't have the asset classes, so they're tagged again
+    if mode == Mode::Serve && !restored {
+        let mut rows = read_newest_rows(CSV_FILE_PATH)?;
+        handles.settings.asset_classes.tag(&mut rows);
+        tracing::info!("Serving {} rows from {}.", rows.len(), CSV_FILE_PATH);
+        handles
+            .collection_handle
+            .preload(display_from_arg.clone(), rows)
+            .await?;
+    }
+    // when replaying, the recorded batches take the place of the fetched ones
+    let mut recorded = match mode {
+        Mode::Replay { speed } => {
+            let (batches, path) = match &args.event_log {
+                Some(path) => (
+                    event_log::read(path)?
+                        .into_iter()
+                        .map(|event| (event.from, event.rows))
+                        .collect(),
+                    path.display().to_string(),
+                ),
+                None => {
+                    let mut batches = replay::read(CSV_FILE_PATH)?;
+                    for (_, rows) in &mut batches {
+                        handles.settings.asset_classes.tag(rows);
+                    }
+                    (batches, CSV_FILE_PATH.to_string())
+                }
+            };
+            tracing::info!(
+                "Replaying {} batches from {} at {}x speed.",
+                batches.len(),
+                path,
+                speed
+            );
+            Some(batches.into_iter())
+        }
+        _ => None,
+    };
 
-    // build our web application with a state and with a route
+    // the web app and the gRPC API share the actors with the main loop
     let state = WebAppState {
-        from: args.from,
-        collection_handle: collection_handle.clone(),
+        from: display_from_arg,
+        tail_buffer_size: args.tail_buffer_size,
+        variant: variant.clone(),
+        handles: handles.clone(),
+        symbols_handle: symbols_handle.clone(),
+        config_handle: config_handle.clone(),
+        webhook_handle: webhook_handle.clone(),
+        watchdog_handle: watchdog_handle.clone(),
+        paper_handle: paper_handle.clone(),
+        audit_handle: audit_handle.clone(),
+        news_handle: news_handle.clone(),
+        options_handle: options_handle.clone(),
+        portfolio_handle: portfolio_handle.clone(),
+        watchlist_handle: watchlist_handle.clone(),
     };
-    let app = Router::new()
-        .route("/", get(root))
-        .route("/desc", get(get_desc))
-        .route("/tail/:n", get(get_tail))
-        .route("/tailstr/:n", get(get_tail_str))
-        .with_state(state);
 
-    // run our web app with hyper
-    // we need to spawn it as a separate tokio task so that we don't get blocked here
-    let listener = tokio::net::TcpListener::bind(WEB_SERVER_ADDRESS).await?;
-    tracing::info!("listening on {}", listener.local_addr()?);
-    tokio::spawn(async move { axum::serve(listener, app).await });
-    tracing::debug!("started the web application");
+    // the config file can be reloaded, with SIGHUP, only in the modes that run until CTRL+C
+    #[cfg(unix)]
+    let reloader = match mode {
+        Mode::Run | Mode::Serve | Mode::Replay { .. } => Some(tokio::spawn(reload_on_hangup(
+            args.clone(),
+            state.clone(),
+            shutdown_token.clone(),
+        ))),
+        Mode::Once | Mode::Backfill { .. } | Mode::Backtest | Mode::BenchVariants { .. } => None,
+    };
+
+    // the web app and the gRPC server are needed only by the modes that run until CTRL+C
+    let servers = match mode {
+        Mode::Run | Mode::Serve | Mode::Replay { .. } => {
+            Some(start_servers(state, broker_handle.clone(), &shutdown_token).await?)
+        }
+        Mode::Once | Mode::Backfill { .. } | Mode::Backtest | Mode::BenchVariants { .. } => None,
+    };
 
     tracing::debug!("starting the main loop");
 
-    let mut interval = tokio::time::interval(Duration::from_secs(TICK_INTERVAL_SECS));
+    // a replay divides the tick interval by its speed multiplier
+    let tick_period = |tick_interval_secs| match mode {
+        Mode::Replay { speed } => replay::pace(tick_interval_secs, speed),
+        _ => Duration::from_secs(tick_interval_secs),
+    };
+    let mut tick_interval_secs = config_handle.get().await?.tick_interval_secs;
+    let mut interval = Interval::new(tick_period(tick_interval_secs));
+
+    // A fixed number of fetch workers, which work on chunks of symbols of all iterations.
+    // The chunks that they haven't finished yet are waited for on shutdown.
+    let fetch_pool = FetchPool::new(
+        args.fetch_workers,
+        args.fetch_queue_capacity,
+        Duration::from_secs(args.handler_timeout_secs),
+    );
+    if let Err(err) = handles
+        .stats_handle
+        .send(StatsActorMsg::ActorsSpawned(fetch_pool.size() as u64))
+        .await
+    {
+        handles
+            .dead_letter_handle
+            .undelivered("StatsActor", "ActorsSpawned", err)
+            .await;
+    }
+    tracing::debug!("started {} fetch workers", fetch_pool.size());
+
+    // the iterations of the ticking modes are cancelled if they miss too many ticks
+    let loop_watchdog = match mode {
+        Mode::Run | Mode::Replay { .. } if args.max_missed_ticks > 0 => Some(LoopWatchdog::spawn(
+            Duration::from_millis(LOOP_WATCHDOG_CHECK_INTERVAL_MS),
+        )),
+        _ => None,
+    };
+
+    // the intraday pipeline runs alongside the daily one, and shares its fetch workers,
+    // only while running live
+    let mut intraday = match (mode, args.intraday_tick_interval_secs) {
+        (Mode::Run, Some(intraday_tick_interval_secs)) => {
+            tracing::info!(
+                "Running the intraday pipeline, in {} bars, every {} s.",
+                args.intraday_bars.as_str(),
+                intraday_tick_interval_secs
+            );
+            Some(IntradayPipeline::spawn(
+                &args,
+                nticks,
+                Duration::from_secs(intraday_tick_interval_secs),
+                symbols_handle.clone(),
+                &handles,
+                fetch_pool.queue(),
+            ))
+        }
+        _ => None,
+    };
+
+    // in quiet mode, the batches are summarized instead of having their rows printed
+    let summaries = if args.quiet {
+        Some(tokio::spawn(print_summaries(
+            broker_handle.subscribe().await?,
+        )))
+    } else {
+        None
+    };
+
+    // correlates the messages of an iteration across the actors in the traces,
+    // and keeps increasing across restarts, if the state is saved
+    let mut iteration_id = state_file.iteration();
+
+    // a backfill's period ends a step after the start in the first iteration,
+    // and at the provided end, or at the current time, in the last one;
+    // an interrupted backfill resumes after the newest period that all of its symbols were fetched for
+    let backfill_end = fixed_to.unwrap_or_else(OffsetDateTime::now_utc);
+    let mut backfill_to = from;
+    if let Mode::Backfill { .. } = mode {
+        let symbols = symbols_handle.get().await?;
+        if let Some(resumed) = state_file.resume_backfill(&symbols, backfill_end) {
+            tracing::info!("Resuming the backfill after {}.", resumed);
+            backfill_to = resumed;
+        }
+    }
+
+    // an error that stops the loop, which is returned only after the orderly shutdown
+    let mut loop_error = None;
 
     loop {
-        interval.tick().await;
+        match mode {
+            Mode::Run | Mode::Replay { .. } => tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown_token.cancelled() => break,
+            },
+            // the iterations follow one another without waiting, but a shutdown still stops them
+            Mode::Once | Mode::Backfill { .. } | Mode::Backtest | Mode::BenchVariants { .. } => {
+                if shutdown_token.is_cancelled() {
+                    break;
+                }
+            }
+            Mode::Serve => {
+                shutdown_token.cancelled().await;
+                break;
+            }
+        }
 
-        // We always want a fresh period end time, which is "now" in the UTC time zone.
-        let to = OffsetDateTime::now_utc();
+        let to = match mode {
+            Mode::Backfill { step } => {
+                backfill_to = (backfill_to + step).min(backfill_end);
+                backfill_to
+            }
+            // Unless a fixed end was provided, we want a fresh period end time,
+            // which is "now" in the UTC time zone.
+            _ => fixed_to.unwrap_or_else(OffsetDateTime::now_utc),
+        };
+        // the local clock can be set back before the start of the period while running,
+        // in which case a periodic run skips the tick, and a single run fails after shutting down
+        if let Err(err) = check_period(from, to, OffsetDateTime::now_utc()) {
+            match mode {
+                Mode::Run | Mode::Replay { .. } => {
+                    tracing::error!("Skipping the iteration: {:#}", err);
+                    continue;
+                }
+                _ => {
+                    loop_error = Some(err);
+                    break;
+                }
+            }
+        }
 
-        // For standard output only, i.e., not for CSV
-        println!("\n\n*** {} ***\n", to);
+        if !args.quiet {
+            // For standard output only, i.e., not for CSV
+            println!("\n\n*** {} ***\n", args.in_display_timezone(to));
 
-        // A simple way to output a CSV header
-        println!("{}", CSV_HEADER);
+            // A simple way to output a CSV header
+            match args.risk_confidence {
+                Some(confidence) => {
+                    let confidence = (confidence * 1000.0).round() / 10.0;
+                    println!("{},VaR {}%,CVaR {}%", CSV_HEADER, confidence, confidence);
+                }
+                None => println!("{}", CSV_HEADER),
+            }
+        }
 
         let start = Instant::now();
 
+        // the symbols can change between iterations, and so can the number of chunks
+        let symbols = symbols_handle.get().await?;
+        if let Some(windows) = &handles.settings.windows {
+            windows.retain(&symbols);
+        }
+
+        // the runtime configuration can also change between iterations
+        let config = config_handle.get().await?;
+        let chunk_size = config.chunk_size;
+        let window_size = config.window_size;
+        if config.tick_interval_secs != tick_interval_secs {
+            tick_interval_secs = config.tick_interval_secs;
+            let period = tick_period(tick_interval_secs);
+            interval = Interval::starting_at(Instant::now() + period, period);
+            tracing::info!("The tick interval is now {} s.", tick_interval_secs);
+        }
+
+        let chunks_of_symbols: Vec<&[Symbol]> = match variant {
+            ImplementationVariant::MyActorsNoRayon
+            | ImplementationVariant::ActixActorsNoRayon
+            | ImplementationVariant::NoActorsNoRayon => symbols.chunks(chunk_size).collect(), // stdlib chunks
+
+            // a chunk per symbol, which the stream fetches as soon as it has room
+            ImplementationVariant::MyActorsStream => symbols.chunks(1).collect(),
+
+            #[cfg(feature = "arrow")]
+            ImplementationVariant::MyActorsArrow => symbols.chunks(chunk_size).collect(),
+
+            ImplementationVariant::MyActorsRayon
+            | ImplementationVariant::ActixActorsRayon
+            | ImplementationVariant::NoActorsRayon => symbols.par_chunks(chunk_size).collect(), // rayon parallel chunks
+        };
+
+        iteration_id += 1;
+        let iteration = Iteration {
+            id: iteration_id,
+            start,
+            to,
+            // used only in CollectionActor
+            num_chunks: chunks_of_symbols.len(),
+        };
+
         //
         // WITH MY OWN IMPLEMENTATION OF ACTORS
         //
 
-        // Without rayon. Not sequential. Multiple "`FetchActor`s" and "`ProcessorActor`s".
-        // This is fast!
+        // Without rayon. Not sequential. A fixed-size pool of fetch workers.
         //
         // This is considered the main, DEFAULT, implementation of the application.
         //
-        // We start multiple instances of `Actor` - one per chunk of symbols,
-        // and they will start the next `Actor` in the process - one each.
-        // A single `ActorHandle` creates a single `Actor` instance and runs it on a new Tokio (asynchronous) task.
-        //
-        // Explicit concurrency with async/await paradigm: Run multiple instances of the same Future concurrently.
-        // That's why it's fast - we spawn multiple tasks, i.e., multiple actors, concurrently, at the same time.
-        // They'll also spawn multiple "`ProcessorActor`s" concurrently (at the same time).
-        //
-        // It's around 0.8 s on new computer with chunk size = 5; it wasn't measured on the old one.
-        // It's less than 0.6 s on new computer with chunk size = 1!
-        // It's around 1.4 s with CS = 10, and over 5 s with CS = 50.
-        // Prints execution time after each chunk, which doesn't look super-nice, and that also
-        // slows down execution a little, but at least we can measure the execution time,
-        // which is important to us.
+        // We put chunks of symbols in the pool's work queue, and the first available worker
+        // fetches and processes a chunk, without spawning any new tasks.
+        // Sending blocks while the queue is full, i.e., while all workers are busy.
+        // Unlike the approach below, this keeps the number of tasks and the memory usage stable,
+        // even with a large number of symbols.
         //
         // Tested and it works with the integrated web application.
-        for chunk in chunks_of_symbols.clone() {
-            let actor_handle = UniversalActorHandle::new(nticks);
-            let _ = actor_handle
-                .send(ActorMessage::QuoteRequestsMsg {
-                    symbols: chunk.into(),
-                    from,
-                    to,
-                    writer_handle: writer_handle.clone(),
-                    collection_handle: collection_handle.clone(),
-                    start,
-                })
+        //
+        // When replaying, a recorded batch is delivered as a single chunk instead,
+        // and, after the last one, the replayed batches are served until CTRL+C.
+        let replayed = match recorded.as_mut() {
+            Some(recorded) => {
+                let Some(batch) = recorded.next() else {
+                    tracing::info!("The recording has been replayed; serving it until CTRL+C.");
+                    shutdown_token.cancelled().await;
+                    break;
+                };
+                Some(batch)
+            }
+            None => None,
+        };
+
+        // The watchdog cancels an iteration that has missed too many ticks, e.g., because
+        // handing over its chunks blocks on a hung data provider, so that the main loop
+        // resumes at the next tick instead of stalling.
+        let iteration_token = match &loop_watchdog {
+            Some(watchdog) => watchdog.begin(
+                iteration_id,
+                tick_period(tick_interval_secs) * args.max_missed_ticks,
+            ),
+            None => CancellationToken::new(),
+        };
+        let mut handed_over = 0;
+        let dispatch = async {
+            if let Some((from, rows)) = replayed {
+                for row in &rows {
+                    tracing::info!(target: ROWS_TRACING_TARGET, "{},{}", from, row);
+                }
+                let iteration = Iteration {
+                    num_chunks: 1,
+                    ..iteration
+                };
+                if let Err(err) = handles.deliver(from, rows, iteration, 0).await {
+                    handles
+                        .dead_letter_handle
+                        .undelivered("PipelineHandles", "PerformanceIndicatorsChunk", err)
+                        .await;
+                }
+                handed_over += 1;
+            } else if let ImplementationVariant::MyActorsStream = variant {
+                // Without rayon. Not sequential. A stream of the symbols, of which at most
+                // `stream-concurrency` are fetched and processed at a time, and the next one
+                // starts as soon as any one has finished, instead of chunks of them.
+                fetch_stream(
+                    symbols.clone(),
+                    display_from,
+                    &handles,
+                    iteration,
+                    window_size,
+                    args.stream_concurrency,
+                    Duration::from_secs(args.handler_timeout_secs),
+                )
                 .await;
+                handed_over = iteration.num_chunks;
+            } else {
+                for (chunk_id, chunk) in chunks_of_symbols.clone().into_iter().enumerate() {
+                    if let Err(err) = fetch_pool
+                        .send(FetchActorMsg::QuoteRequestsMsg {
+                            symbols: chunk.into(),
+                            from: display_from,
+                            handles: handles.clone(),
+                            iteration,
+                            chunk_id,
+                            window_size,
+                        })
+                        .await
+                    {
+                        handles
+                            .dead_letter_handle
+                            .undelivered("FetchPool", "QuoteRequestsMsg", err)
+                            .await;
+                    }
+                    handed_over += 1;
+                }
+            }
+        };
+        let cancelled = tokio::select! {
+            _ = dispatch => false,
+            _ = iteration_token.cancelled() => true,
+        };
+        if let Some(watchdog) = &loop_watchdog {
+            watchdog.end();
+        }
+        if cancelled {
+            tracing::error!(
+                "Cancelled the iteration {} after {:.3?}: {} of its {} chunks had been handed over; \
+                 the fetch pool's queue holds {} chunks, and the writer's and the collection's \
+                 mailboxes hold {} and {} messages. Resuming at the next tick.",
+                iteration_id,
+                start.elapsed(),
+                handed_over,
+                iteration.num_chunks,
+                fetch_pool.queue_depth(),
+                handles.writer_handle.queue_depth(),
+                handles.collection_handle.queue_depth()
+            );
+            if let Err(err) = handles
+                .stats_handle
+                .send(StatsActorMsg::IterationCancelled)
+                .await
+            {
+                handles
+                    .dead_letter_handle
+                    .undelivered("StatsActor", "IterationCancelled", err)
+                    .await;
+            }
         }
 
+        // // Without rayon. Not sequential. Multiple "`FetchActor`s" and "`ProcessorActor`s".
+        // // This is fast!
+        //
+        // // We start multiple instances of `Actor` - one per chunk of symbols,
+        // // and they will start the next `Actor` in the process - one each.
+        // // A single `ActorHandle` creates a single `Actor` instance and runs it on a new Tokio (asynchronous) task.
+        //
+        // // Explicit concurrency with async/await paradigm: Run multiple instances of the same Future concurrently.
+        // // That's why it's fast - we spawn multiple tasks, i.e., multiple actors, concurrently, at the same time.
+        // // They'll also spawn multiple "`ProcessorActor`s" concurrently (at the same time).
+        //
+        // // It's around 0.8 s on new computer with chunk size = 5; it wasn't measured on the old one.
+        // // It's less than 0.6 s on new computer with chunk size = 1!
+        // // It's around 1.4 s with CS = 10, and over 5 s with CS = 50.
+        // // Prints execution time after each chunk, which doesn't look super-nice, and that also
+        // // slows down execution a little, but at least we can measure the execution time,
+        // // which is important to us.
+        //
+        // // Tested and it works with the integrated web application.
+        // for (chunk_id, chunk) in chunks_of_symbols.clone().into_iter().enumerate() {
+        //     let actor_handle = FetchActorHandle::new(nticks);
+        //     let _ = handles
+        //         .stats_handle
+        //         .send(StatsActorMsg::ActorsSpawned(1))
+        //         .await;
+        //     let _ = actor_handle
+        //         .send(FetchActorMsg::QuoteRequestsMsg {
+        //             symbols: chunk.into(),
+        //             from,
+        //             handles: handles.clone(),
+        //             iteration,
+        //             chunk_id,
+        //             window_size,
+        //         })
+        //         .await;
+        //     // the actor finishes the chunk before it acknowledges the shutdown
+        //     in_flight.spawn(async move { actor_handle.shutdown().await });
+        // }
+
         // // With rayon. Same speed as without rayon; fast (chunks or par_chunks doesn't make a difference).
         // // It's around 0.7 s on new computer with chunk size = 5; it wasn't measured on the old one.
         // // It's around 1.3 s with CS = 1, and around 1.3 s with CS = 10.
         // // Tested and it works with the integrated web application.
         // let queries: Vec<_> = chunks_of_symbols
         //     .par_iter()
-        //     .map(|chunk| async {
-        //         let actor_handle: UniversalActorHandle = ActorHandle::new(nticks);
+        //     .enumerate()
+        //     .map(|(chunk_id, chunk)| async move {
+        //         let actor_handle: FetchActorHandle = ActorHandle::new(nticks);
         //         actor_handle
-        //             .send(ActorMessage::QuoteRequestsMsg {
+        //             .send(FetchActorMsg::QuoteRequestsMsg {
         //                 symbols: (*chunk).into(),
         //                 from,
-        //                 to,
-        //                 writer_handle: writer_handle.clone(),
-        //                 collection_handle: collection_handle.clone(),
-        //                 start,
+        //                 handles: handles.clone(),
+        //                 iteration,
+        //                 chunk_id,
+        //                 window_size,
         //             })
         //             .await
         //     })
@@ -272,8 +964,8 @@ pub async fn main_loop(args: Args) -> Result<MsgResponseType> {
         //     handles.push(handle);
         // }
         // let rows = futures::future::join_all(handles).await;
-        // let rows = rows.iter().map(|r| r.as_ref().unwrap()).collect::<Vec<_>>();
-        // write_to_csv(&mut writer, rows, start)?;
+        // let rows = rows.into_iter().map(|r| r.unwrap()).collect::<Vec<_>>();
+        // write_to_csv(&mut writer, rows, start).await?;
 
         // // rayon: 0.8-0.9 s (new computer); was 1.0 s on old computer - with chunk size = 5
         // // With CS = 1 it's ~0.9 s, and with CS = 10 it's ~1.3 s.
@@ -282,9 +974,406 @@ pub async fn main_loop(args: Args) -> Result<MsgResponseType> {
         //     .map(|chunk| handle_symbol_data(chunk, from, to))
         //     .collect();
         // let rows = futures::future::join_all(queries).await;
-        // let rows = rows.iter().map(|r| r).collect::<Vec<_>>();
-        // write_to_csv(&mut writer, rows, start)?;
+        // write_to_csv(&mut writer, rows, start).await?;
+
+        if !args.quiet {
+            println!();
+        }
+
+        // the state is saved after every iteration, so that a crash loses at most an iteration
+        if let Err(err) = save_state(
+            &mut state_file,
+            iteration_id,
+            &symbols,
+            &config,
+            &handles.collection_handle,
+        )
+        .await
+        {
+            tracing::warn!("{:#}", err);
+        }
+
+        let done = match mode {
+            Mode::Once => true,
+            Mode::Backfill { .. } => to >= backfill_end,
+            Mode::Run
+            | Mode::Serve
+            | Mode::Backtest
+            | Mode::BenchVariants { .. }
+            | Mode::Replay { .. } => false,
+        };
+        if done {
+            break;
+        }
+    }
+
+    tracing::debug!("stopped the main loop");
+
+    if let Some(loop_watchdog) = loop_watchdog {
+        loop_watchdog.stop().await;
+    }
+
+    if let Some(servers) = servers {
+        servers.stop().await?;
+    }
+    #[cfg(unix)]
+    if let Some(reloader) = reloader {
+        reloader
+            .await
+            .context("The config file reloading task failed to complete.")??;
+    }
+
+    // The intraday pipeline stops handing chunks over before the fetch workers are stopped.
+    if let Some(intraday) = intraday.as_mut() {
+        intraday.stop().await;
+    }
+
+    // Let the fetch workers deliver the chunks that are in flight or still queued.
+    fetch_pool.shutdown().await;
+    tracing::debug!("the in-flight chunks have been delivered");
 
-        println!();
+    // the final state includes the in-flight chunks
+    let symbols = symbols_handle.get().await?;
+    let config = config_handle.get().await?;
+    if let Err(err) = save_state(
+        &mut state_file,
+        iteration_id,
+        &symbols,
+        &config,
+        &handles.collection_handle,
+    )
+    .await
+    {
+        tracing::warn!("{:#}", err);
+    }
+
+    // The singletons are stopped only after all chunks have been delivered to them.
+    // The writer actor flushes and closes the output file before it acknowledges the shutdown,
+    // and it confirms the final flush, so a failed one is reported here.
+    // The watchdog is stopped first, so that it doesn't report the stopped actors as unresponsive.
+    let mut results = match intraday {
+        Some(intraday) => intraday.shutdown().await,
+        None => Vec::new(),
+    };
+    results.extend([
+        watchdog_handle.shutdown().await,
+        handles.collection_handle.shutdown().await,
+        broker_handle.shutdown().await,
+        handles.writer_handle.shutdown().await,
+        webhook_handle.shutdown().await,
+        handles.dead_letter_handle.shutdown().await,
+    ]);
+    // the statistics are final only after the pipeline has stopped
+    let stats = handles.stats_handle.get().await;
+    results.extend([
+        handles.stats_handle.shutdown().await,
+        symbols_handle.shutdown().await,
+        config_handle.shutdown().await,
+    ]);
+    if let Some(alert_handle) = alert_handle {
+        results.push(alert_handle.shutdown().await);
+    }
+    if let Some(paper_handle) = paper_handle {
+        results.push(paper_handle.shutdown().await);
+    }
+    // the web app has stopped, so all of its requests have been recorded
+    if let Some(audit_handle) = audit_handle {
+        results.push(audit_handle.shutdown().await);
+    }
+    if let Some(event_log_handle) = event_log_handle {
+        results.push(event_log_handle.shutdown().await);
+    }
+    if let Some(news_handle) = news_handle {
+        results.push(news_handle.shutdown().await);
+    }
+    if let Some(options_handle) = options_handle {
+        results.push(options_handle.shutdown().await);
+    }
+    results.push(portfolio_handle.shutdown().await);
+    results.push(watchlist_handle.shutdown().await);
+    for err in results.into_iter().filter_map(Result::err) {
+        tracing::warn!("{:#}", err);
+    }
+    tracing::debug!("stopped the actors");
+
+    // the summaries end with the broker's subscriptions
+    if let Some(summaries) = summaries {
+        summaries.await?;
+    }
+
+    // Every actor's task, and every supervisor's task, is awaited, so none of them is cut off.
+    join_actor_tasks().await;
+    tracing::debug!("all actor tasks have finished");
+
+    if let Some(err) = loop_error {
+        return Err(err);
+    }
+
+    // The modes that exit on their own, e.g., in cron jobs, report an incomplete run
+    // through the exit code.
+    if matches!(mode, Mode::Once | Mode::Backfill { .. }) {
+        let stats = stats?;
+        ensure!(
+            stats.fetch_errors == 0 && stats.actor_errors == 0 && stats.dead_letters == 0,
+            "The run is incomplete: {} symbols couldn't be fetched, {} messages couldn't be handled, \
+             and there are {} dead letters.",
+            stats.fetch_errors,
+            stats.actor_errors,
+            stats.dead_letters
+        );
+    }
+
+    Ok(())
+}
+
+/// Saves the state that a restart would otherwise lose, if it's saved: the sequence number
+/// of the newest iteration, the current `symbols` and `config`, and the symbols' last-fetched
+/// times, which the collection actor keeps
+///
+/// # Errors
+/// - If the [`CollectionActor`](crate::my_async_actors::CollectionActorHandle) isn't running
+/// - If the state file can't be written
+async fn save_state(
+    state_file: &mut StateFile,
+    iteration_id: u64,
+    symbols: &[Symbol],
+    config: &RuntimeConfig,
+    collection_handle: &CollectionActorHandle,
+) -> Result<()> {
+    if !state_file.is_saved() {
+        return Ok(());
     }
+
+    let last_fetched = collection_handle.last_fetched().await?;
+    state_file
+        .save(iteration_id, symbols, config, last_fetched)
+        .await
+}
+
+/// Prints a summary of every batch that is published through the `subscription`,
+/// until the subscription ends
+async fn print_summaries(mut subscription: tokio::sync::mpsc::Receiver<Batch>) {
+    while let Some(batch) = subscription.recv().await {
+        let gainers = batch.iter().filter(|row| row.pct_change > 0.0).count();
+        let losers = batch.iter().filter(|row| row.pct_change < 0.0).count();
+        println!(
+            "{}: a batch of {} rows was completed; {} symbols gained, and {} lost.",
+            OffsetDateTime::now_utc(),
+            batch.len(),
+            gainers,
+            losers
+        );
+    }
+}
+
+/// The tasks of the web app and of the gRPC server
+struct Servers {
+    web_app: tokio::task::JoinHandle<std::io::Result<()>>,
+    grpc_server: tokio::task::JoinHandle<Result<(), tonic::transport::Error>>,
+}
+
+impl Servers {
+    /// Waits for the web app and the gRPC server to complete the in-flight requests and stop,
+    /// which they do after the shutdown token has been cancelled
+    ///
+    /// # Errors
+    /// - If either of them has failed
+    async fn stop(self) -> Result<()> {
+        self.web_app
+            .await
+            .context("The web application task failed to complete.")??;
+        tracing::debug!("stopped the web application");
+
+        self.grpc_server
+            .await
+            .context("The gRPC server task failed to complete.")??;
+        tracing::debug!("stopped the gRPC server");
+
+        Ok(())
+    }
+}
+
+/// Starts the web app and the gRPC server, which run until the `shutdown_token` is cancelled
+///
+/// # Errors
+/// - [std::io::Error] if the web app can't be started
+/// - If the gRPC server's address is invalid
+async fn start_servers(
+    state: WebAppState,
+    broker_handle: BrokerActorHandle,
+    shutdown_token: &CancellationToken,
+) -> Result<Servers> {
+    let from = state.from.clone();
+    let tail_buffer_size = state.tail_buffer_size;
+    let collection_handle = state.handles.collection_handle.clone();
+    let audit_handle = state.audit_handle.clone();
+
+    tracing::debug!("starting the web application");
+
+    // the tail and range routes are rate-limited per client (IP address), so that a misbehaving
+    // poller can't flood the collection actor with requests and starve batch ingestion
+    let governor_config = Arc::new(
+        GovernorConfigBuilder::default()
+            .per_millisecond(TAIL_RATE_LIMIT_REPLENISH_MS)
+            .burst_size(TAIL_RATE_LIMIT_BURST_SIZE)
+            .finish()
+            .context("Invalid rate-limiter configuration.")?,
+    );
+    let governor_limiter = governor_config.limiter().clone();
+    let cleanup_token = shutdown_token.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(RATE_LIMITER_CLEANUP_INTERVAL_SECS));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => governor_limiter.retain_recent(),
+                _ = cleanup_token.cancelled() => break,
+            }
+        }
+    });
+    let tail_routes = Router::new()
+        .route("/tail/:n", get(get_tail))
+        .route("/tailstr/:n", get(get_tail_str))
+        .route("/tail/:n/symbol/:sym", get(get_symbol_tail))
+        .route("/range", get(get_range))
+        .route("/watchlists/:name/tail/:n", get(get_watchlist_tail))
+        .layer(GovernorLayer {
+            config: governor_config,
+        });
+
+    // build our web application with a state and with a route
+    //
+    // tail responses can be large (up to `TAIL_BUFFER_SIZE` batches of ~500 rows each),
+    // but they are highly compressible, so we compress them (gzip or brotli) if the client
+    // supports it, i.e., if it sends an appropriate `Accept-Encoding` header
+    let request_id_header = HeaderName::from_static(REQUEST_ID_HEADER);
+    let mut app = Router::new()
+        .route("/", get(root))
+        .route("/desc", get(get_desc))
+        .route("/version", get(get_version))
+        .route("/symbols", post(post_symbols))
+        .route("/symbols/:sym", delete(delete_symbol))
+        .route("/config", get(get_config).put(put_config))
+        .route("/stats", get(get_stats))
+        .route("/stats/memory", get(get_memory_stats))
+        .route("/health", get(get_health))
+        .route("/dead-letters", get(get_dead_letters))
+        .route("/quality", get(get_quality))
+        .route("/risk", get(get_risk))
+        .route("/movers", get(get_movers))
+        .route("/sectors", get(get_sectors))
+        .route("/symbol/:sym/news", get(get_symbol_news))
+        .route("/options", get(get_options))
+        .route("/watchlists", get(get_watchlists))
+        .route("/webhooks", get(get_webhooks).post(post_webhook))
+        .route("/webhooks/:id", delete(delete_webhook))
+        .route("/paper/orders", post(post_paper_order))
+        .route("/paper/portfolio", get(get_paper_portfolio))
+        .route("/portfolio", get(get_portfolio))
+        .route("/portfolio/holdings", post(post_holding))
+        .route("/portfolio/holdings/:sym", delete(delete_holding))
+        .merge(tail_routes)
+        .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi()))
+        .layer(CompressionLayer::new());
+    // the audit log records the requests with their correlation IDs, after they have been answered,
+    // including those that the rate limiter has turned down
+    if let Some(audit_handle) = audit_handle {
+        app = app.layer(middleware::from_fn_with_state(audit_handle, audit::record));
+    }
+    let app = app
+        // every request gets a correlation ID (unless the client has provided one), which is
+        // recorded in the request's tracing span, passed on to the collection actor in tail
+        // requests, and returned to the client in the response
+        .layer(PropagateRequestIdLayer::new(request_id_header.clone()))
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|request: &http::Request<Body>| {
+                let request_id = request
+                    .headers()
+                    .get(REQUEST_ID_HEADER)
+                    .and_then(|value| value.to_str().ok());
+                tracing::info_span!(
+                    "request",
+                    method = %request.method(),
+                    uri = %request.uri(),
+                    request_id,
+                )
+            }),
+        )
+        .layer(SetRequestIdLayer::new(request_id_header, MakeRequestUuid))
+        .with_state(state);
+
+    // run our web app with hyper
+    // we need to spawn it as a separate tokio task so that we don't get blocked here
+    let listener = tokio::net::TcpListener::bind(WEB_SERVER_ADDRESS).await?;
+    tracing::info!("listening on {}", listener.local_addr()?);
+    // the web app completes in-flight requests before it stops, when the token gets cancelled
+    let web_app_shutdown = shutdown_token.clone().cancelled_owned();
+    let web_app = tokio::spawn(async move {
+        // the rate limiter needs to know clients' addresses
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(web_app_shutdown)
+        .await
+    });
+    tracing::debug!("started the web application");
+
+    tracing::debug!("starting the gRPC server");
+    let grpc_service = StocksService::new(
+        from,
+        tail_buffer_size,
+        collection_handle,
+        broker_handle.clone(),
+        shutdown_token.clone(),
+    );
+    let grpc_address = GRPC_SERVER_ADDRESS.parse()?;
+    tracing::info!("gRPC listening on {}", grpc_address);
+    // the gRPC server also completes in-flight requests before it stops, when the token gets cancelled
+    let grpc_shutdown = shutdown_token.clone().cancelled_owned();
+    let grpc_server = tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(StocksServer::new(grpc_service))
+            .serve_with_shutdown(grpc_address, grpc_shutdown),
+    );
+    tracing::debug!("started the gRPC server");
+
+    Ok(Servers {
+        web_app,
+        grpc_server,
+    })
+}
+
+/// Reads the output file at `path`, and returns the newest row of every symbol in it,
+/// in the order in which the symbols first appear
+///
+/// # Errors
+/// - [std::io::Error] if the file can't be read
+/// - If a row isn't in the format that the writer actor writes
+fn read_newest_rows(path: &str) -> Result<Vec<PerformanceIndicatorsRow>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read the output file {}.", path))?;
+
+    let mut rows: Vec<PerformanceIndicatorsRow> = Vec::new();
+    let mut positions = HashMap::new();
+    for line in contents
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+    {
+        // the period start comes before the row
+        let (_from, row) = line
+            .split_once(',')
+            .with_context(|| format!("Invalid row \"{}\" in {}.", line, path))?;
+        let row: PerformanceIndicatorsRow = row.parse()?;
+        match positions.get(&row.symbol) {
+            Some(&position) => rows[position] = row,
+            None => {
+                positions.insert(row.symbol.clone(), rows.len());
+                rows.push(row);
+            }
+        }
+    }
+
+    Ok(rows)
 }
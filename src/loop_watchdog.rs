@@ -0,0 +1,126 @@
+//! Main-loop watchdog
+//!
+//! An iteration of the main loop can get stuck, e.g., when all fetch workers are blocked on a hung
+//! data provider and the work queue is full, so that handing the iteration's chunks over to the
+//! fetch pool blocks. The main loop would then silently stop ticking.
+//!
+//! The [`LoopWatchdog`]'s task checks the running iteration at a fixed interval, and cancels it,
+//! through its cancellation token, once it has run for longer than its budget, i.e., once it has
+//! missed the `max-missed-ticks` number of ticks. The main loop then abandons the iteration, logs
+//! the diagnostics, and resumes at the next tick. The chunks that were already handed over are still
+//! processed; the collection actor abandons the incomplete batch when a later one is completed.
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::runtime::{self, JoinHandle};
+
+/// The iteration that is being watched
+struct Running {
+    id: u64,
+    started: Instant,
+    budget: Duration,
+    token: CancellationToken,
+}
+
+/// A task that cancels the iterations of the main loop that run for too long
+pub struct LoopWatchdog {
+    running: Arc<Mutex<Option<Running>>>,
+    stop_token: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl LoopWatchdog {
+    /// Spawns the watchdog's task, which checks the running iteration every `check_interval`
+    pub fn spawn(check_interval: Duration) -> Self {
+        let running: Arc<Mutex<Option<Running>>> = Arc::default();
+        let stop_token = CancellationToken::new();
+
+        let task = runtime::spawn({
+            let running = Arc::clone(&running);
+            let stop_token = stop_token.clone();
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = runtime::sleep(check_interval) => {}
+                        _ = stop_token.cancelled() => break,
+                    }
+
+                    let running = running.lock().unwrap_or_else(PoisonError::into_inner);
+                    if let Some(iteration) = running.as_ref() {
+                        let elapsed = iteration.started.elapsed();
+                        if elapsed > iteration.budget && !iteration.token.is_cancelled() {
+                            tracing::warn!(
+                                "The iteration {} has been running for {:.3?}, longer than its budget \
+                                 of {:.3?}; cancelling it.",
+                                iteration.id,
+                                elapsed,
+                                iteration.budget
+                            );
+                            iteration.token.cancel();
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            running,
+            stop_token,
+            task,
+        }
+    }
+
+    /// Starts watching the iteration `id`, which is cancelled after it has run for longer than `budget`
+    ///
+    /// # Returns
+    /// The iteration's cancellation token
+    pub fn begin(&self, id: u64, budget: Duration) -> CancellationToken {
+        let token = CancellationToken::new();
+        *self.running.lock().unwrap_or_else(PoisonError::into_inner) = Some(Running {
+            id,
+            started: Instant::now(),
+            budget,
+            token: token.clone(),
+        });
+
+        token
+    }
+
+    /// Stops watching the running iteration, which has finished
+    pub fn end(&self) {
+        *self.running.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Stops the watchdog's task, and waits for it
+    pub async fn stop(self) {
+        self.stop_token.cancel();
+        if let Err(err) = self.task.await {
+            tracing::error!("The main-loop watchdog failed: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn only_iterations_over_their_budget_are_cancelled() {
+        let watchdog = LoopWatchdog::spawn(Duration::from_millis(5));
+
+        let token = watchdog.begin(1, Duration::from_secs(60));
+        runtime::sleep(Duration::from_millis(30)).await;
+        assert!(!token.is_cancelled());
+        watchdog.end();
+
+        let token = watchdog.begin(2, Duration::from_millis(10));
+        tokio::time::timeout(Duration::from_secs(5), token.cancelled())
+            .await
+            .unwrap();
+
+        watchdog.stop().await;
+    }
+}
@@ -1,55 +1,137 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use time::format_description::well_known::Rfc3339;
+use std::io::IsTerminal;
+
+use clap::error::ErrorKind;
+use clap::CommandFactory;
+use tokio_util::sync::CancellationToken;
 use tracing_subscriber::EnvFilter;
 
-use stock::cli::Args;
-use stock::constants::SHUTDOWN_INTERVAL_SECS;
-use stock::logic::main_loop;
+use stock::cli::{prompt_from, Cli, Command, LogFormat};
+use stock::constants::ROWS_TRACING_TARGET;
+use stock::logic::{main_loop, Mode};
 use stock::types::MsgResponseType;
 use stock_trading_cli_with_async_streams as stock;
 
+/// The jemalloc allocator, whose statistics the `/stats/memory` route includes
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
 /// The [`main`] function
 ///
 /// We want to make it as small and as clean as possible,
 /// so we delegate most of the tasks to the [`main_loop`] function.
 ///
-/// This solution waits for tasks to fully finish by sleeping for some time.
+/// This solution waits for tasks to fully finish by cancelling a shutdown token
+/// and then waiting for the main loop to return.
 /// It uses tokio.
 /// This supports a fully-graceful shutdown, meaning all symbols will be fetched and processed
-/// when a CTRL+C signal arrives.
+/// when a CTRL+C signal arrives, and in-flight web requests will be completed.
 // #[actix::main]
 #[tokio::main]
 async fn main() -> Result<MsgResponseType> {
-    let args = Args::parse();
+    let cli = Cli::parse_with_config()?;
+    let (mut args, mode) = match cli.command {
+        Command::Run(args) => (args, Mode::Run),
+        Command::Once(args) => (args, Mode::Once),
+        Command::Serve(args) => (args, Mode::Serve),
+        Command::Backfill(backfill) => (
+            backfill.args,
+            Mode::Backfill {
+                step: time::Duration::days(backfill.step_days as i64),
+            },
+        ),
+        Command::Backtest(args) => (args, Mode::Backtest),
+        Command::BenchVariants(bench) => (
+            bench.args,
+            Mode::BenchVariants {
+                iterations: bench.iterations,
+            },
+        ),
+        Command::Replay(replay) => (
+            replay.args,
+            Mode::Replay {
+                speed: replay.speed,
+            },
+        ),
+    };
+
+    // a missing `from` is prompted for, but only if there is someone to answer the prompt
+    if args.from.is_none() {
+        if std::io::stdin().is_terminal() {
+            args.from = Some(prompt_from(std::io::stdin().lock(), std::io::stderr())?);
+        } else {
+            Cli::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "the following required argument was not provided: --from <FROM>",
+                )
+                .exit();
+        }
+    }
+
+    // resolve relative dates once, at startup, and parse early so that neither main loop
+    // nor web app start if date and time are not in the correct format
+    args.resolve_dates(time::OffsetDateTime::now_utc())?;
+    args.period(time::OffsetDateTime::now_utc())?;
 
-    // parse early so that neither main loop nor web app start
-    // if date and time are not in the correct format
-    time::OffsetDateTime::parse(&args.from, &Rfc3339)
-        .context("The provided date or time format isn't correct.")?;
+    // read the symbols file early, too, so that nothing starts if it can't be read
+    args.symbols()?;
+    // and check the settings of the email alerts
+    args.mailer()?;
+    // and the parameters of the trading strategy
+    stock::strategies::create(&args.strategy)?;
 
-    // initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
+    // initialize tracing; the verbosity from the command line takes precedence over `RUST_LOG`
+    let mut filter = match &cli.log_level {
+        Some(level) => EnvFilter::try_new(level).context("The provided log level isn't valid.")?,
+        None => EnvFilter::from_default_env(),
+    };
+    // in quiet mode, and in a benchmark, the rows aren't traced either
+    if args.quiet || matches!(mode, Mode::BenchVariants { .. }) {
+        filter = filter.add_directive(format!("{}=off", ROWS_TRACING_TARGET).parse()?);
+    }
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match cli.log_format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+
+    // the token is shared by the main loop and the web app, and it tells them when to stop
+    let shutdown_token = CancellationToken::new();
 
     // spawn the main processing loop as a separate task
-    tokio::spawn(async move { main_loop(args).await });
-
-    // await the shutdown signal
-    match tokio::signal::ctrl_c().await {
-        Ok(()) => {
-            tracing::info!(
-                "\nCTRL+C received. Giving tasks some time ({} s) to finish...",
-                SHUTDOWN_INTERVAL_SECS
-            );
-            tokio::time::sleep(tokio::time::Duration::from_secs(SHUTDOWN_INTERVAL_SECS)).await;
+    let mut main_loop_handle = tokio::spawn(main_loop(args, mode, shutdown_token.clone()));
+
+    // await the shutdown signal, unless the main loop returns first,
+    // which it does after its iterations with the `once`, `backfill` and `backtest` subcommands
+    let finished = tokio::select! {
+        signal = tokio::signal::ctrl_c() => {
+            match signal {
+                Ok(()) => {
+                    tracing::info!("\nCTRL+C received. Waiting for tasks to finish...");
+                }
+                Err(err) => {
+                    // also shut down in case of an error
+                    tracing::error!("Unable to listen for the shutdown signal: {}", err);
+                }
+            }
+            None
         }
-        Err(err) => {
-            // also shut down in case of an error
-            tracing::error!("Unable to listen for the shutdown signal: {}", err);
+        result = &mut main_loop_handle => Some(result),
+    };
+
+    // the main loop stops scheduling new iterations, the web app completes in-flight requests,
+    // and the main loop returns only after the in-flight chunks have been processed and
+    // all actors have been shut down, which includes flushing the output file
+    let result = match finished {
+        Some(result) => result,
+        None => {
+            shutdown_token.cancel();
+            main_loop_handle.await
         }
-    }
+    };
+    result.context("The main loop task failed to complete.")??;
 
     tracing::info!("Exiting now.");
 
@@ -0,0 +1,107 @@
+//! Memory statistics
+//!
+//! The `/stats/memory` route reports the memory usage of the process, so that long-running
+//! deployments can confirm that it levels off, along with the sizes of the structures that grow
+//! with the data: the collection actor's tail buffer, and the mailboxes of the writer and
+//! collection actors.
+//!
+//! With the `jemalloc` feature, the app runs on the jemalloc allocator instead of the system one,
+//! and the route also includes the allocator's statistics, which tell the memory that the app
+//! has allocated apart from the memory that the allocator holds on to.
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// The memory statistics of the process
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct MemoryStats {
+    /// The resident set size of the process, in bytes, or `None` if the OS doesn't report it
+    pub resident_bytes: Option<u64>,
+    /// The statistics of the allocator, or `None` without the `jemalloc` feature
+    pub allocator: Option<AllocatorStats>,
+    pub tail_buffer: BufferMemory,
+    /// The number of messages in the writer actor's mailbox
+    pub writer_queue_depth: usize,
+    /// The number of messages in the collection actor's mailbox
+    pub collection_queue_depth: usize,
+}
+
+/// The statistics of the jemalloc allocator, in bytes
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct AllocatorStats {
+    /// The memory that the app has allocated
+    pub allocated: u64,
+    /// The memory in the allocator's active pages, which is a little more than the allocated memory
+    pub active: u64,
+    /// The memory in the allocator's physically resident pages
+    pub resident: u64,
+    /// The memory in the allocator's mapped chunks
+    pub mapped: u64,
+    /// The memory that the allocator has retained, instead of returning it to the OS
+    pub retained: u64,
+}
+
+/// The size of the collection actor's buffer of the newest batches
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct BufferMemory {
+    /// The number of batches, both in memory and spilled
+    pub batches: usize,
+    /// The number of batches that are spilled to disk
+    pub spilled: usize,
+    /// The estimated size of the batches in memory, in bytes
+    pub estimated_bytes: usize,
+    /// The number of batches that are still being assembled
+    pub pending_batches: usize,
+    /// The number of symbols whose newest rows are kept
+    pub latest_rows: usize,
+}
+
+/// Returns the resident set size of the process, in bytes, if it's known
+pub fn resident_set_size() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+
+    Some(kib * 1024)
+}
+
+/// Returns the current statistics of the jemalloc allocator
+///
+/// They are cached by the allocator, so they are refreshed first.
+///
+/// # Returns
+/// `None` if they can't be read
+#[cfg(feature = "jemalloc")]
+pub fn allocator_stats() -> Option<AllocatorStats> {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    epoch::advance().ok()?;
+
+    Some(AllocatorStats {
+        allocated: stats::allocated::read().ok()? as u64,
+        active: stats::active::read().ok()? as u64,
+        resident: stats::resident::read().ok()? as u64,
+        mapped: stats::mapped::read().ok()? as u64,
+        retained: stats::retained::read().ok()? as u64,
+    })
+}
+
+/// Returns `None`, as the statistics of the system allocator aren't available;
+/// see the `jemalloc` feature
+#[cfg(not(feature = "jemalloc"))]
+pub fn allocator_stats() -> Option<AllocatorStats> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_resident_set_size_is_reported_on_linux() {
+        if cfg!(target_os = "linux") {
+            assert!(resident_set_size().is_some_and(|rss| rss > 0));
+        }
+        assert_eq!(cfg!(feature = "jemalloc"), allocator_stats().is_some());
+    }
+}
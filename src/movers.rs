@@ -0,0 +1,227 @@
+//! Top movers
+//!
+//! Whenever the collection actor completes a batch, it ranks the batch's symbols:
+//! - the gainers, by their percentage change, highest first,
+//! - the losers, by their percentage change, lowest first,
+//! - the most volatile, by the standard deviation of their daily returns, highest first.
+//!
+//! Every ranking holds at most [`MOVERS_SIZE`] symbols. The rankings of the newest batch are
+//! traced along with the rows, written to their own CSV file, and served by the web app.
+//!
+//! [`MOVERS_SIZE`]: crate::constants::MOVERS_SIZE
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::constants::MOVERS_CSV_HEADER;
+use crate::risk;
+use crate::types::{Batch, Symbol};
+
+/// A symbol in a ranking
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Mover {
+    #[schema(value_type = String)]
+    pub symbol: Symbol,
+    pub pct_change: f64,
+    /// The standard deviation of the daily returns, as a percentage,
+    /// or `None` if the symbol has fewer than two returns
+    pub volatility_pct: Option<f64>,
+}
+
+/// The rankings of a completed batch
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Movers {
+    /// The sequence number of the batch's iteration
+    pub iteration: u64,
+    /// When the batch was completed, in the RFC 3339 format
+    pub time: String,
+    pub gainers: Vec<Mover>,
+    pub losers: Vec<Mover>,
+    pub most_volatile: Vec<Mover>,
+}
+
+impl Movers {
+    /// Ranks the symbols of a `batch`, with the daily `returns` of the symbols,
+    /// keeping at most `n` of them in every ranking
+    pub fn rank(
+        batch: &Batch,
+        returns: &BTreeMap<Symbol, Vec<f64>>,
+        n: usize,
+        iteration: u64,
+        time: String,
+    ) -> Self {
+        let movers: Vec<Mover> = batch
+            .iter()
+            .map(|row| Mover {
+                symbol: row.symbol.clone(),
+                pct_change: row.pct_change,
+                volatility_pct: returns
+                    .get(&row.symbol)
+                    .and_then(|returns| risk::volatility(returns))
+                    .map(|volatility| volatility * 100.0),
+            })
+            .collect();
+
+        let ranking = |keep: fn(&Mover) -> Option<f64>| {
+            let mut ranked: Vec<(f64, &Mover)> = movers
+                .iter()
+                .filter_map(|mover| keep(mover).map(|key| (key, mover)))
+                .collect();
+            // the highest keys first
+            ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+            ranked
+                .into_iter()
+                .take(n)
+                .map(|(_, mover)| mover.clone())
+                .collect()
+        };
+
+        Self {
+            iteration,
+            time,
+            gainers: ranking(|mover| (mover.pct_change > 0.0).then_some(mover.pct_change)),
+            losers: ranking(|mover| (mover.pct_change < 0.0).then_some(-mover.pct_change)),
+            most_volatile: ranking(|mover| mover.volatility_pct),
+        }
+    }
+
+    /// Removes a `symbol` from the rankings, after it has been removed at runtime
+    pub fn remove(&mut self, symbol: &str) {
+        for ranking in [&mut self.gainers, &mut self.losers, &mut self.most_volatile] {
+            ranking.retain(|mover| *mover.symbol != *symbol);
+        }
+    }
+
+    /// Returns the rankings by their names
+    fn rankings(&self) -> [(&'static str, &[Mover]); 3] {
+        [
+            ("gainers", &self.gainers),
+            ("losers", &self.losers),
+            ("most volatile", &self.most_volatile),
+        ]
+    }
+}
+
+impl Display for Movers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rankings: Vec<String> = self
+            .rankings()
+            .iter()
+            .map(|(name, ranking)| {
+                let movers: Vec<String> = ranking
+                    .iter()
+                    .map(|mover| match (*name, mover.volatility_pct) {
+                        ("most volatile", Some(volatility)) => {
+                            format!("{} {:.2}%", mover.symbol, volatility)
+                        }
+                        _ => format!("{} {:+.2}%", mover.symbol, mover.pct_change),
+                    })
+                    .collect();
+                format!("{}: {}", name, movers.join(", "))
+            })
+            .collect();
+
+        write!(f, "{}", rankings.join("; "))
+    }
+}
+
+/// Writes the rankings as CSV, a row per ranked symbol, after a header
+pub struct MoversCsv {
+    writer: BufWriter<File>,
+}
+
+impl MoversCsv {
+    /// Creates a CSV file at `path`, truncating an existing one
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", MOVERS_CSV_HEADER)?;
+
+        Ok(Self { writer })
+    }
+
+    /// Writes the rankings of a batch, and flushes them to the file
+    pub fn write(&mut self, movers: &Movers) -> io::Result<()> {
+        for (name, ranking) in movers.rankings() {
+            for (rank, mover) in ranking.iter().enumerate() {
+                let volatility = mover
+                    .volatility_pct
+                    .map(|volatility| format!("{:.2}%", volatility))
+                    .unwrap_or_default();
+                writeln!(
+                    self.writer,
+                    "{},{},{},{},{},{:.2}%,{}",
+                    movers.time,
+                    movers.iteration,
+                    name,
+                    rank + 1,
+                    mover.symbol,
+                    mover.pct_change,
+                    volatility
+                )?;
+            }
+        }
+
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::my_async_actors::PerformanceIndicatorsRow;
+
+    fn row(symbol: &str, pct_change: f64) -> PerformanceIndicatorsRow {
+        PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price: 1.0,
+            pct_change,
+            period_min: 1.0,
+            period_max: 1.0,
+            sma: 1.0,
+            asset_class: Default::default(),
+        }
+    }
+
+    #[test]
+    fn symbols_are_ranked_by_change_and_volatility() {
+        let batch = vec![
+            row("AAPL", 3.0),
+            row("MSFT", -1.0),
+            row("NVDA", 5.0),
+            row("TSLA", -4.0),
+            row("IBM", 0.0),
+        ];
+        let returns = BTreeMap::from([
+            ("AAPL".into(), vec![0.01, 0.03]),
+            ("TSLA".into(), vec![-0.1, 0.1]),
+            ("IBM".into(), vec![0.0]),
+        ]);
+        let symbols = |ranking: &[Mover]| {
+            ranking
+                .iter()
+                .map(|mover| mover.symbol.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut movers = Movers::rank(&batch, &returns, 2, 7, "2024-01-01T00:00:00Z".to_string());
+        assert_eq!(vec!["NVDA", "AAPL"], symbols(&movers.gainers));
+        assert_eq!(vec!["TSLA", "MSFT"], symbols(&movers.losers));
+        // IBM has a single return, and MSFT and NVDA have none
+        assert_eq!(vec!["TSLA", "AAPL"], symbols(&movers.most_volatile));
+        assert_eq!(
+            "gainers: NVDA +5.00%, AAPL +3.00%; losers: TSLA -4.00%, MSFT -1.00%; \
+             most volatile: TSLA 14.14%, AAPL 1.41%",
+            movers.to_string()
+        );
+
+        movers.remove("TSLA");
+        assert_eq!(vec!["MSFT"], symbols(&movers.losers));
+        assert_eq!(vec!["AAPL"], symbols(&movers.most_volatile));
+    }
+}
@@ -1,30 +1,75 @@
 //! My own asynchronous actor framework implementation
 //!
 //! Requires `#[tokio::main]`.
+//!
+//! The actors' tasks and timers go through [`crate::runtime`], so they run on smol's executor
+//! instead of Tokio's with the `smol` feature.
 
 #![allow(dead_code)]
 
-use std::collections::{HashMap, VecDeque};
-use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::time::Instant;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter, Write};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex as StdMutex, PoisonError, RwLock, Weak};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use serde::Serialize;
+use futures::{FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
-use tokio::sync::mpsc;
-use yahoo_finance_api as yahoo;
+use tokio::sync::mpsc::error::{SendError, SendTimeoutError, TrySendError};
+use tokio::sync::{mpsc, oneshot, Mutex, Semaphore};
+use tracing::Instrument;
+use utoipa::ToSchema;
 
+use crate::alerts::{AlertChannel, AlertEvent, AlertTriggers, Mailer, RateLimiter};
+use crate::asset_classes::{AssetClass, AssetClasses};
 use crate::async_signals::{AsyncStockSignal, MaxPrice, MinPrice, PriceDifference, WindowedSMA};
+use crate::audit::{AuditLog, AuditRecord};
+use crate::cli::{BarInterval, OutputFormat};
+#[cfg(feature = "arrow")]
+use crate::columnar::ColumnarCloses;
+use crate::config::{ConfigUpdate, Reconfiguration, RuntimeConfig};
 use crate::constants::{
-    ACTOR_CHANNEL_CAPACITY, CHUNK_SIZE, CSV_FILE_PATH, CSV_HEADER, TAIL_BUFFER_SIZE, WINDOW_SIZE,
+    ACTOR_CHANNEL_CAPACITY, COLLECTION_MAILBOX_CAPACITY, COLLECTION_PRIORITY_MAILBOX_CAPACITY,
+    DEAD_LETTER_BUFFER_SIZE, FLUSH_INTERVAL_SECS, HANDLER_TIMEOUT_SECS, HEARTBEAT_INTERVAL_SECS,
+    HEARTBEAT_TIMEOUT_SECS, INDICATORS, MAX_ACTOR_RESTARTS, MOVERS_SIZE, NEWS_SIZE,
+    NEWS_TIMEOUT_SECS, OPTIONS_TIMEOUT_SECS, ROWS_TRACING_TARGET, SUBSCRIBER_CHANNEL_CAPACITY,
+    WEBHOOK_TIMEOUT_SECS, WRITER_MAILBOX_CAPACITY,
 };
+use crate::error_summary::ErrorSummary;
+use crate::errors::ActorError;
+use crate::event_log::EventLog;
+use crate::memory::BufferMemory;
+use crate::movers::{Movers, MoversCsv};
+use crate::news::{Headline, NewsCache, NewsProvider};
+use crate::option_chains::{OptionsCsv, OptionsProvider, OptionsSummary};
+use crate::paper::{Fill, Order, OrderSource, PaperAccount, Portfolio, Side};
+use crate::portfolio::{Holding, PortfolioTracker, Valuation, ValuationCsv};
+use crate::price_windows::PriceWindows;
+use crate::quality::{self, QualityReport};
+use crate::quotes::{QuoteProvider, YahooQuotes};
+use crate::risk::{self, RiskMetrics, RiskReport};
+use crate::runtime::{self, Interval, JoinError, JoinHandle, TaskSet};
+use crate::sectors::{self, Sector, SectorAggregate};
+use crate::sinks::{self, Sink};
+use crate::strategies::{Signal, StrategyEngine};
+use crate::tail_buffer::{TailBuffer, TailBufferLimits};
 use crate::types::{
-    Batch, CollectionMsgErrorType, MsgResponseType, TailResponse, UniversalMsgErrorType,
-    WriterMsgErrorType,
+    AlertMsgErrorType, AuditMsgErrorType, Batch, BrokerMsgErrorType, CollectionMsgErrorType,
+    ConfigMsgErrorType, DeadLetterMsgErrorType, EventLogMsgErrorType, FetchMsgErrorType,
+    MsgResponseType, NewsMsgErrorType, OptionsMsgErrorType, PaperTradingMsgErrorType,
+    PortfolioMsgErrorType, ProcessorMsgErrorType, RangeResponse, StatsMsgErrorType, Symbol,
+    SymbolsMsgErrorType, SymbolsResponse, TailResponse, WatchdogMsgErrorType,
+    WatchlistMsgErrorType, WebhookMsgErrorType, WriterMsgErrorType,
 };
+use crate::watchlists::{Watchlist, WatchlistBuffer};
+use crate::webhooks::{Webhook, WebhookPayload, WebhookRegistration};
 
 // ============================================================================
 //
@@ -51,6 +96,10 @@ use crate::types::{
 ///
 /// Most errors are handled inside methods - they are not propagated.
 ///
+/// Errors that occur while handling a message are returned from [`Actor::handle`]
+/// as an [`ActorError`]. They don't stop the actor; [`Actor::run`] reports them
+/// through [`report_error`] and goes on with the next message.
+///
 /// The [`tokio::sync::mpsc::error::SendError`] error type isn't
 /// informative; it doesn't return any useful piece of information.
 /// It returns something like `SendError{..}`, which is not very
@@ -80,6 +129,13 @@ use crate::types::{
 /// Likewise, the trait has an associated type for messages, `Msg`,
 /// to make it more general. It could have also been a generic type,
 /// `M`, but it's a little better to have an associated type instead.
+///
+/// Every actor's message type has a `Shutdown` variant, which is the
+/// graceful drain/shutdown protocol of the actor system.
+/// An actor handles all messages that were sent to it before the `Shutdown`
+/// message, then it closes its mailbox, does its stop logic, such as flushing
+/// a file, and only then acknowledges the shutdown. The actor's task ends
+/// after that, even if some of its handles are still alive.
 trait Actor<R> {
     /// The associated type [`Self::Msg`] represents an incoming message type.
     type Msg;
@@ -88,7 +144,7 @@ trait Actor<R> {
     fn new(receiver: mpsc::Receiver<Self::Msg>, nticks: usize) -> Self;
 
     /// Start the [`Actor`]
-    async fn start(&mut self) -> Result<MsgResponseType> {
+    async fn start(&mut self) -> Result<MsgResponseType, ActorError> {
         #[cfg(debug_assertions)]
         tracing::debug!("Actor {:p} is started.", self);
 
@@ -96,7 +152,7 @@ trait Actor<R> {
     }
 
     /// Run the [`Actor`]
-    async fn run(&mut self) -> Result<R>;
+    async fn run(&mut self) -> Result<R, ActorError>;
 
     /// Stop the [`Actor`]
     fn stop(&mut self) {
@@ -105,7 +161,28 @@ trait Actor<R> {
     }
 
     /// Handle the message
-    async fn handle(&mut self, msg: Self::Msg) -> Result<R>;
+    async fn handle(&mut self, msg: Self::Msg) -> Result<R, ActorError>;
+
+    /// Returns the [`Interceptors`] that run around the messages that the [`Actor`] handles, if any
+    fn interceptors(&self) -> Option<&Interceptors<Self::Msg>> {
+        None
+    }
+
+    /// Handle the message, with the [`Actor`]'s interceptors running around it
+    ///
+    /// The actors' run loops call this function, rather than [`Actor::handle`].
+    async fn handle_intercepted(&mut self, msg: Self::Msg) -> Result<R, ActorError> {
+        let Some(interceptors) = self.interceptors().cloned() else {
+            return self.handle(msg).await;
+        };
+
+        let msg = interceptors.on_handle(msg);
+        let start = Instant::now();
+        let result = self.handle(msg).await;
+        interceptors.on_handled(start.elapsed(), result.as_ref().err());
+
+        result
+    }
 }
 
 /// The [`ActorHandle`] controls creation and execution of actors.
@@ -124,7 +201,11 @@ trait Actor<R> {
 /// The type [`R`] represents a response message type.
 ///
 /// The type [`E`] represents an error type.
-pub(crate) trait ActorHandle<R, E> {
+///
+/// It is public, so that the handles can also be driven from outside the crate,
+/// e.g., by the benchmarks in `benches/`.
+#[allow(async_fn_in_trait)]
+pub trait ActorHandle<R, E> {
     /// The type [`Self::Msg`] represents an incoming message type.
     type Msg;
 
@@ -140,6 +221,85 @@ pub(crate) trait ActorHandle<R, E> {
 
     /// Send a message to an [`Actor`] instance through the [`ActorHandle`]
     async fn send(&self, msg: Self::Msg) -> Result<R, E>;
+
+    /// Send a request to an [`Actor`] instance through the [`ActorHandle`],
+    /// and wait for the actor's response
+    ///
+    /// This is the ask pattern. The `request` function packages the sending half
+    /// of a oneshot reply channel into a message, and the actor responds through it.
+    ///
+    /// # Errors
+    /// - If the actor isn't running
+    /// - If the actor drops the reply channel without responding
+    async fn call<T>(&self, request: impl FnOnce(oneshot::Sender<T>) -> Self::Msg) -> Result<T>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        self.send(request(sender))
+            .await
+            .context("Couldn't send the request.")?;
+        receiver.await.context("No response was received.")
+    }
+}
+
+/// Reports an error that an `actor` has run into while handling a message
+///
+/// The error is logged, and it is also forwarded to the [`DeadLetterActor`],
+/// which counts it in the statistics, if the actor knows the [`DeadLetterActor`].
+async fn report_error(
+    actor: &str,
+    err: &ActorError,
+    dead_letter_handle: Option<&DeadLetterActorHandle>,
+) {
+    tracing::error!("{} failed to handle a message: {}", actor, err);
+
+    if let Some(dead_letter_handle) = dead_letter_handle {
+        dead_letter_handle.unhandled(actor, err).await;
+    }
+}
+
+/// Creates an actor of the type `$actor`, with a mailbox that can hold [`ACTOR_CHANNEL_CAPACITY`]
+/// messages, and runs it
+///
+/// The optional closure-like block gets the new actor before it runs, for setting it up, e.g.,
+/// for giving it the handles of the actors that it sends messages to.
+///
+/// Evaluates to the sending side of the actor's mailbox, which the actor's handle holds.
+///
+/// The actor's task is tracked by the runtime supervisor; see [`track`].
+///
+/// This is the boilerplate that every unsupervised actor's handle needs.
+/// It is a macro rather than a generic function, because the futures of the [`Actor`]
+/// trait's `async` functions can't be required to be [`Send`] in a generic function,
+/// and they have to be for spawning them.
+macro_rules! spawn_actor {
+    ($actor:ty, $nticks:expr $(, |$var:ident| $configure:block)?) => {{
+        let (sender, receiver) = mpsc::channel(ACTOR_CHANNEL_CAPACITY);
+        #[allow(unused_mut)]
+        let mut actor = <$actor>::new(receiver, $nticks);
+        $(
+            let $var = &mut actor;
+            $configure
+        )?
+        track(stringify!($actor), async move { actor.run().await });
+
+        sender
+    }};
+}
+
+/// Runs a message `handler`, but gives up on it after `timeout`
+///
+/// The handler is cancelled, i.e., dropped at its current `.await` point, if it runs out of time,
+/// so that a hung call, such as a data provider request that never completes, can't stall the actor.
+/// The actor then reports the [`ActorError::Timeout`] and goes on with the next message.
+async fn with_timeout(
+    timeout: Duration,
+    handler: impl Future<Output = Result<MsgResponseType, ActorError>>,
+) -> Result<MsgResponseType, ActorError> {
+    runtime::timeout(timeout, handler)
+        .await
+        .map_err(|_| ActorError::Timeout(timeout))?
 }
 
 // ============================================================================
@@ -147,338 +307,439 @@ pub(crate) trait ActorHandle<R, E> {
 //
 //
 //
-//        [`ActorMessage`], [`UniversalActor`], [`UniversalActorHandle`]
+//                   Middleware [`Interceptor`] & [`Interceptors`]
 //
 //
 //
 //
 // ============================================================================
 
-/// The [`ActorMessage`] enumeration
-///
-/// Supports two message types:
-/// - [`QuoteRequestsMsg`],
-/// - [`SymbolsClosesMsg`],
-///
-/// There is no expected response for any of the message types.
+/// A hook that runs around the messages of an actor, for cross-cutting concerns,
+/// such as logging, metrics and tracing, which then don't have to be pasted into
+/// every message handler
 ///
-/// We could have an oneshot channel for sending the response back in general case.
-/// It could be used for every message type.
-/// We simply don't need it in our specific (custom) case.
-pub enum ActorMessage {
-    QuoteRequestsMsg {
-        symbols: Vec<String>,
-        from: OffsetDateTime,
-        to: OffsetDateTime,
-        writer_handle: WriterActorHandle,
-        collection_handle: CollectionActorHandle,
-        start: Instant,
-    },
-    SymbolsClosesMsg {
-        symbols_closes: HashMap<String, Vec<f64>>,
-        from: OffsetDateTime,
-        writer_handle: WriterActorHandle,
-        collection_handle: CollectionActorHandle,
-        start: Instant,
-    },
+/// All hooks are optional. The `on_send` and `on_handle` hooks take the message by value
+/// and return it, so they can also modify it.
+pub trait Interceptor<M>: Send + Sync {
+    /// Called in the actor's handle, before a message is put in the actor's mailbox
+    fn on_send(&self, msg: M) -> M {
+        msg
+    }
+
+    /// Called in the actor, before it handles a message
+    fn on_handle(&self, msg: M) -> M {
+        msg
+    }
+
+    /// Called in the actor, after it has handled a message, with the time that it took,
+    /// and with the error that the actor ran into, if any
+    fn on_handled(&self, _elapsed: Duration, _err: Option<&ActorError>) {}
 }
 
-/// A universal (general) type of actor
-///
-/// It can receive and handle two message types.
+/// The chain of [`Interceptor`]s of an actor, which is shared by the actor's handles
+/// and the actor itself, including its restarted incarnations
 ///
-/// It was not made public on purpose.
-///
-/// It can only be created through [`UniversalActorHandle`], which is public.
-struct UniversalActor {
-    receiver: mpsc::Receiver<ActorMessage>,
+/// Interceptors can be added at any time, and they run in the order in which they were added.
+pub struct Interceptors<M> {
+    chain: Arc<RwLock<Vec<Arc<dyn Interceptor<M>>>>>,
 }
 
-impl Actor<MsgResponseType> for UniversalActor {
-    type Msg = ActorMessage;
+impl<M> Interceptors<M> {
+    /// Appends an `interceptor` to the chain
+    fn add(&self, interceptor: impl Interceptor<M> + 'static) {
+        self.chain
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::new(interceptor));
+    }
+
+    /// Returns the current chain, so that the lock isn't held while the hooks run
+    fn chain(&self) -> Vec<Arc<dyn Interceptor<M>>> {
+        self.chain
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
 
-    /// Create a new [`UniversalActor`]
-    fn new(receiver: mpsc::Receiver<ActorMessage>, _: usize) -> Self {
-        Self { receiver }
+    /// Runs the `on_send` hooks of the chain
+    fn on_send(&self, msg: M) -> M {
+        self.chain()
+            .iter()
+            .fold(msg, |msg, interceptor| interceptor.on_send(msg))
     }
 
-    /// Run the [`UniversalActor`]
-    async fn run(&mut self) -> Result<MsgResponseType> {
-        tracing::debug!("UniversalActor {:p} is running.", self);
+    /// Runs the `on_handle` hooks of the chain
+    fn on_handle(&self, msg: M) -> M {
+        self.chain()
+            .iter()
+            .fold(msg, |msg, interceptor| interceptor.on_handle(msg))
+    }
 
-        while let Some(msg) = self.receiver.recv().await {
-            self.handle(msg).await?;
+    /// Runs the `on_handled` hooks of the chain
+    fn on_handled(&self, elapsed: Duration, err: Option<&ActorError>) {
+        for interceptor in self.chain() {
+            interceptor.on_handled(elapsed, err);
         }
+    }
+}
 
-        Ok(())
+impl<M> Clone for Interceptors<M> {
+    fn clone(&self) -> Self {
+        Self {
+            chain: Arc::clone(&self.chain),
+        }
     }
+}
 
-    /// Handle the [`ActorMessage`]
-    async fn handle(&mut self, msg: ActorMessage) -> Result<MsgResponseType> {
-        match msg {
-            ActorMessage::QuoteRequestsMsg {
-                symbols,
-                from,
-                to,
-                writer_handle,
-                collection_handle,
-                start,
-            } => {
-                Self::handle_quote_requests_msg(
-                    symbols,
-                    from,
-                    to,
-                    writer_handle,
-                    collection_handle,
-                    start,
-                )
-                .await
-                .context("Expected some result from `handle_quote_requests_msg()`")?;
-            }
-            ActorMessage::SymbolsClosesMsg {
-                symbols_closes,
-                from,
-                writer_handle,
-                collection_handle,
-                start,
-            } => {
-                Self::handle_symbols_closes_msg(
-                    symbols_closes,
-                    from,
-                    writer_handle,
-                    collection_handle,
-                    start,
-                )
-                .await
-                .context("Expected some result from `handle_symbols_closes_msg()`")?;
-            }
+impl<M> Default for Interceptors<M> {
+    fn default() -> Self {
+        Self {
+            chain: Arc::default(),
         }
+    }
+}
 
-        Ok(())
+/// An [`Interceptor`] that logs the messages that took an `actor` longer than `threshold`
+/// to handle, at the warning level
+pub struct SlowHandlerLogger {
+    actor: &'static str,
+    threshold: Duration,
+}
+
+impl SlowHandlerLogger {
+    /// Create a new [`SlowHandlerLogger`] for the `actor`
+    pub fn new(actor: &'static str, threshold: Duration) -> Self {
+        Self { actor, threshold }
     }
 }
 
-impl UniversalActor {
-    /// The [`QuoteRequestsMsg`] message handler for the fetch [`UniversalActor`] actor
-    ///
-    /// Spawns a new processor [`UniversalActor`] and sends it a [`SymbolsClosesMsg`] message.
-    ///
-    /// The message contains a hash map of `symbols` and associated `Vec<f64>` with closing prices for that symbol
-    /// in case there was no error when fetching the data, or an empty vector in case of an error,
-    /// in which case it logs the error message at the warning level.
-    ///
-    /// So, in case of an API error for a symbol, when trying to fetch its data,
-    /// we don't break the program but rather continue, skipping the symbol.
-    ///
-    /// # Errors
-    /// - [yahoo_finance_api::YahooError](https://docs.rs/yahoo_finance_api/2.2.1/yahoo_finance_api/enum.YahooError.html)
-    async fn handle_quote_requests_msg(
-        symbols: Vec<String>,
-        from: OffsetDateTime,
-        to: OffsetDateTime,
-        writer_handle: WriterActorHandle,
-        collection_handle: CollectionActorHandle,
-        start: Instant,
-    ) -> Result<MsgResponseType> {
-        let provider = yahoo::YahooConnector::new().context(format!("Skipping: {:?}", symbols))?;
+impl<M> Interceptor<M> for SlowHandlerLogger {
+    fn on_handled(&self, elapsed: Duration, _err: Option<&ActorError>) {
+        if elapsed > self.threshold {
+            tracing::warn!("{} took {:?} to handle a message.", self.actor, elapsed);
+        }
+    }
+}
 
-        let mut symbols_closes: HashMap<String, Vec<f64>> = HashMap::with_capacity(symbols.len());
+// ============================================================================
+//
+//
+//
+//
+//                     Supervision [`Mailbox`] & [`supervise`]
+//
+//
+//
+//
+// ============================================================================
 
-        for symbol in symbols {
-            let closes = match Self::fetch_closing_data(&symbol, from, to, &provider).await {
-                Ok(closes) => closes,
-                Err(err) => {
-                    tracing::warn!(
-                        "There was an API error \"{}\" while fetching data for the symbol \"{}\"; \
-                         skipping the symbol.",
-                        err,
-                        symbol
-                    );
-                    vec![]
-                }
-            };
+/// The sending side of a supervised actor's mailbox
+///
+/// It is shared by all clones of the actor's handle.
+///
+/// When the supervisor restarts a crashed actor, it replaces the sender with
+/// the sender of the new actor's mailbox, so the existing handles keep working.
+///
+/// It also counts how often senders run into backpressure, i.e., into a full mailbox,
+/// so that operators can tune the mailbox capacities.
+struct Mailbox<M> {
+    sender: Arc<RwLock<mpsc::Sender<M>>>,
+    backpressure: Arc<BackpressureCounters>,
+}
 
-            symbols_closes.insert(symbol, closes);
-        }
+/// The backpressure counters of a [`Mailbox`]
+#[derive(Default)]
+struct BackpressureCounters {
+    /// The number of sends that found the mailbox full
+    queue_full: AtomicU64,
+    /// The number of bounded-wait sends that gave up because the mailbox stayed full
+    send_timeouts: AtomicU64,
+}
 
-        let symbols_closes_msg = ActorMessage::SymbolsClosesMsg {
-            symbols_closes,
-            from,
-            writer_handle,
-            collection_handle,
-            start,
+impl<M> Mailbox<M> {
+    /// Create a new [`Mailbox`] that can hold `capacity` messages,
+    /// and return it together with the receiving side
+    fn new(capacity: usize) -> (Self, mpsc::Receiver<M>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+
+        let mailbox = Self {
+            sender: Arc::new(RwLock::new(sender)),
+            backpressure: Arc::default(),
         };
 
-        // Spawn another Actor and send it the message.
-        let actor_handle = UniversalActorHandle::new(0);
-        actor_handle
-            .send(symbols_closes_msg)
-            .await
-            .context("Couldn't send a message to the ProcessorActor.")?;
+        (mailbox, receiver)
+    }
 
-        Ok(())
+    /// Returns the sender of the current actor's mailbox
+    fn sender(&self) -> mpsc::Sender<M> {
+        self.sender
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the number of messages that are waiting in the mailbox
+    fn queue_depth(&self) -> usize {
+        let sender = self.sender();
+        sender.max_capacity() - sender.capacity()
+    }
+
+    /// Returns the number of sends that have found the mailbox full
+    fn queue_full_count(&self) -> u64 {
+        self.backpressure.queue_full.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bounded-wait sends that have timed out
+    fn send_timeout_count(&self) -> u64 {
+        self.backpressure.send_timeouts.load(Ordering::Relaxed)
     }
 
-    /// The [`SymbolsClosesMsg`] message handler for the processor [`UniversalActor`] actor
+    /// Sends a message, waiting for a free slot if the mailbox is full
     ///
-    /// Sends a [`PerformanceIndicatorsRowsMsg`] message to the [`WriterActor`],
-    /// whose address it gets from the [`SymbolsClosesMsg`] message.
-    async fn handle_symbols_closes_msg(
-        symbols_closes: HashMap<String, Vec<f64>>,
-        from: OffsetDateTime,
-        writer_handle: WriterActorHandle,
-        collection_handle: CollectionActorHandle,
-        start: Instant,
-    ) -> Result<MsgResponseType> {
-        let from = OffsetDateTime::format(from, &Rfc3339).expect("Couldn't format 'from'.");
+    /// A full mailbox is counted before waiting.
+    async fn send(&self, msg: M) -> Result<(), SendError<M>> {
+        let sender = self.sender();
+        match sender.try_send(msg) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(msg)) => {
+                self.backpressure.queue_full.fetch_add(1, Ordering::Relaxed);
+                sender.send(msg).await
+            }
+            Err(TrySendError::Closed(msg)) => Err(SendError(msg)),
+        }
+    }
 
-        let mut rows: Vec<PerformanceIndicatorsRow> = Vec::with_capacity(symbols_closes.len());
+    /// Sends a message without waiting, or gives it back if the mailbox is full or closed
+    fn try_send(&self, msg: M) -> Result<(), TrySendError<M>> {
+        let result = self.sender().try_send(msg);
+        if let Err(TrySendError::Full(_)) = result {
+            self.backpressure.queue_full.fetch_add(1, Ordering::Relaxed);
+        }
 
-        for symbol_closes in symbols_closes {
-            let symbol = symbol_closes.0;
-            let closes = symbol_closes.1;
+        result
+    }
 
-            if !closes.is_empty() {
-                let min = MinPrice {};
-                let max = MaxPrice {};
-                let price_diff = PriceDifference {};
-                let n_window_sma = WindowedSMA {
-                    window_size: WINDOW_SIZE,
-                };
+    /// Sends a message, waiting at most `timeout` for a free slot if the mailbox is full
+    async fn send_timeout(&self, msg: M, timeout: Duration) -> Result<(), SendTimeoutError<M>> {
+        let sender = self.sender();
+        let msg = match sender.try_send(msg) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Full(msg)) => msg,
+            Err(TrySendError::Closed(msg)) => return Err(SendTimeoutError::Closed(msg)),
+        };
+        self.backpressure.queue_full.fetch_add(1, Ordering::Relaxed);
 
-                let last_price = *closes.last().expect("Expected non-empty closes.");
-                let (_, pct_change) = price_diff.calculate(&closes).await.unwrap_or((0., 0.));
-                let pct_change = pct_change * 100.0;
-                let period_min: f64 = min.calculate(&closes).await.unwrap_or_default();
-                let period_max: f64 = max.calculate(&closes).await.unwrap_or_default();
-                let sma = n_window_sma.calculate(&closes).await.unwrap_or(vec![]);
-                let sma = *sma.last().unwrap_or(&0.0);
-
-                let row = PerformanceIndicatorsRow {
-                    symbol: symbol.clone(),
-                    last_price,
-                    pct_change,
-                    period_min,
-                    period_max,
-                    sma,
-                };
+        let result = sender.send_timeout(msg, timeout).await;
+        if let Err(SendTimeoutError::Timeout(_)) = result {
+            self.backpressure
+                .send_timeouts
+                .fetch_add(1, Ordering::Relaxed);
+        }
 
-                rows.push(row);
+        result
+    }
+}
 
-                // A simple way to output CSV data
-                tracing::info!(
-                    "{},{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
-                    from,
-                    symbol,
-                    last_price,
-                    pct_change,
-                    period_min,
-                    period_max,
-                    sma,
-                );
-            } else {
-                tracing::warn!("Got no data for symbol \"{}\".", symbol);
-            }
+impl<M> Clone for Mailbox<M> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: Arc::clone(&self.sender),
+            backpressure: Arc::clone(&self.backpressure),
         }
+    }
+}
 
-        // Assemble a message for the single writer actor.
-        let perf_ind_msg = PerformanceIndicatorsRowsMsg { from, rows, start };
+/// A [`Mailbox`] with two lanes, a regular one and a priority one
+///
+/// The actor always takes a message from the priority lane first, if there is one,
+/// so the messages in it don't have to wait behind a flood of messages in the regular lane.
+struct PriorityMailbox<M> {
+    regular: Mailbox<M>,
+    priority: Mailbox<M>,
+}
 
-        // Send the message to the single writer actor.
-        writer_handle
-            .send(perf_ind_msg.clone())
-            .await
-            .context("Couldn't send a message to the WriterActor.")?;
+impl<M> PriorityMailbox<M> {
+    /// Create a new [`PriorityMailbox`] whose regular lane can hold `capacity` messages,
+    /// and whose priority lane can hold `priority_capacity` messages,
+    /// and return it together with the receiving sides of the regular and the priority lane
+    fn new(
+        capacity: usize,
+        priority_capacity: usize,
+    ) -> (Self, (mpsc::Receiver<M>, mpsc::Receiver<M>)) {
+        let (regular, receiver) = Mailbox::new(capacity);
+        let (priority, priority_receiver) = Mailbox::new(priority_capacity);
 
-        // Assemble a message for the single collection actor.
-        let coll_msg = CollectionActorMsg::PerformanceIndicatorsChunk(perf_ind_msg);
+        (Self { regular, priority }, (receiver, priority_receiver))
+    }
 
-        // Send the message to the single collection actor.
-        collection_handle
-            .send(coll_msg)
-            .await
-            .context("Couldn't send a message to the CollectionActor.")?;
+    /// Returns the regular lane, or the priority lane if `priority` is `true`
+    fn lane(&self, priority: bool) -> &Mailbox<M> {
+        if priority {
+            &self.priority
+        } else {
+            &self.regular
+        }
+    }
 
-        Ok(())
+    /// Returns the number of messages that are waiting in both lanes
+    fn queue_depth(&self) -> usize {
+        self.regular.queue_depth() + self.priority.queue_depth()
     }
 
-    /// Retrieve data for a single `symbol` from a data source (`provider`) and extract the closing prices
-    ///
-    /// # Returns
-    /// - Vector of closing prices in case of no error, or,
-    ///
-    /// # Errors
-    /// - [`yahoo::YahooError`](https://docs.rs/yahoo_finance_api/2.2.1/yahoo_finance_api/enum.YahooError.html)
-    ///   in case of an error.
-    async fn fetch_closing_data(
-        symbol: &str,
-        from: OffsetDateTime,
-        to: OffsetDateTime,
-        provider: &yahoo::YahooConnector,
-    ) -> Result<Vec<f64>, yahoo::YahooError> {
-        // This function takes a single symbol.
-        // The crate that we're using doesn't contain a function that works with a chunk of symbols.
-        let yresponse = provider.get_quote_history(symbol, from, to).await?;
+    /// Returns the number of sends that have found either lane full
+    fn queue_full_count(&self) -> u64 {
+        self.regular.queue_full_count() + self.priority.queue_full_count()
+    }
 
-        let mut quotes = yresponse.quotes()?;
+    /// Returns the number of bounded-wait sends to either lane that have timed out
+    fn send_timeout_count(&self) -> u64 {
+        self.regular.send_timeout_count() + self.priority.send_timeout_count()
+    }
+}
 
-        let mut result = vec![];
-        if !quotes.is_empty() {
-            quotes.sort_by_cached_key(|k| k.timestamp);
-            result = quotes.iter().map(|q| q.adjclose).collect();
+impl<M> Clone for PriorityMailbox<M> {
+    fn clone(&self) -> Self {
+        Self {
+            regular: self.regular.clone(),
+            priority: self.priority.clone(),
         }
+    }
+}
+
+/// The sending side of a supervised actor's mailbox, which [`supervise`] re-wires
+/// to a restarted actor's new mailbox
+trait Lanes {
+    /// The receiving side of the mailbox, which is handed over to the actor
+    type Receiver: Send + 'static;
+    /// A weak reference to the mailbox, which doesn't keep the actor alive
+    type Weak: Send + 'static;
+
+    /// Returns a weak reference to the mailbox
+    fn downgrade(&self) -> Self::Weak;
+
+    /// Replaces the channels of the mailbox behind `weak` with new ones of the same capacities,
+    /// and returns their receiving side, or `None` if all handles have been dropped
+    fn rewire(weak: &Self::Weak) -> Option<Self::Receiver>;
+}
 
-        Ok(result)
+impl<M: Send + 'static> Lanes for Mailbox<M> {
+    type Receiver = mpsc::Receiver<M>;
+    type Weak = Weak<RwLock<mpsc::Sender<M>>>;
+
+    fn downgrade(&self) -> Self::Weak {
+        Arc::downgrade(&self.sender)
+    }
+
+    fn rewire(weak: &Self::Weak) -> Option<Self::Receiver> {
+        let mailbox = weak.upgrade()?;
+        let mut current = mailbox.write().unwrap_or_else(PoisonError::into_inner);
+        // the new mailbox has the same capacity as the old one
+        let (sender, receiver) = mpsc::channel(current.max_capacity());
+        *current = sender;
+
+        Some(receiver)
     }
 }
 
-impl Drop for UniversalActor {
-    fn drop(&mut self) {
-        self.stop();
+impl<M: Send + 'static> Lanes for PriorityMailbox<M> {
+    type Receiver = (mpsc::Receiver<M>, mpsc::Receiver<M>);
+    type Weak = (<Mailbox<M> as Lanes>::Weak, <Mailbox<M> as Lanes>::Weak);
+
+    fn downgrade(&self) -> Self::Weak {
+        (self.regular.downgrade(), self.priority.downgrade())
+    }
+
+    fn rewire((regular, priority): &Self::Weak) -> Option<Self::Receiver> {
+        Some((Mailbox::rewire(regular)?, Mailbox::rewire(priority)?))
     }
 }
 
-/// A handle for the [`UniversalActor`]
-///
-/// Only the handle is public; the [`UniversalActor`] isn't.
+/// Runs an actor under supervision
 ///
-/// We can only create [`UniversalActor`]s through the [`UniversalActorHandle`].
+/// The actor is spawned right away by calling `spawn_actor` with the `receiver`.
 ///
-/// It contains the `sender` field, which represents
-/// a sender of the [`ActorMessage`] in an MPSC channel.
+/// A supervisor task then monitors the actor's task. If the actor panics, or if it
+/// stops because of an error, the supervisor creates a new mailbox, re-wires the `mailbox`,
+/// and with it all handles, to it, and spawns a new actor instance, by calling `spawn_actor`
+/// again, with `restarted` set to `true`. The new instance starts with a fresh state.
 ///
-/// The handle is the sender, and the actor is the receiver
-/// of a message in the channel.
+/// The actor isn't restarted if it has stopped regularly, i.e., after a `Shutdown` message
+/// or after all its handles have been dropped, nor if all its handles have been dropped
+/// in the meantime. The supervisor also gives up after [`MAX_ACTOR_RESTARTS`] restarts.
 ///
-/// We only create a single [`UniversalActor`] instance in an [`UniversalActorHandle`].
-#[derive(Clone)]
-pub struct UniversalActorHandle {
-    sender: mpsc::Sender<ActorMessage>,
+/// The supervisor only holds a weak reference to the `mailbox`, so it doesn't keep the actor alive.
+fn supervise<L, F>(name: &'static str, mailbox: &L, receiver: L::Receiver, mut spawn_actor: F)
+where
+    L: Lanes,
+    F: FnMut(L::Receiver, bool) -> JoinHandle<Result<MsgResponseType, ActorError>> + Send + 'static,
+{
+    let mailbox = mailbox.downgrade();
+    let mut actor_task = spawn_actor(receiver, false);
+
+    // the supervisor's task is tracked by the runtime supervisor, and the actor's task by the supervisor
+    track(name, async move {
+        let mut restarts = 0;
+
+        loop {
+            match actor_task.await {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(err)) => tracing::error!("{} failed: {:#}", name, err),
+                Err(JoinError::Panicked) => tracing::error!("{} panicked.", name),
+                // the runtime is shutting down
+                Err(JoinError::Cancelled) => return Ok(()),
+            }
+
+            if restarts == MAX_ACTOR_RESTARTS {
+                tracing::error!("{} crashed too many times; it won't be restarted.", name);
+                return Ok(());
+            }
+            let Some(receiver) = L::rewire(&mailbox) else {
+                return Ok(());
+            };
+
+            restarts += 1;
+            tracing::warn!("Restarting {} ({}/{}).", name, restarts, MAX_ACTOR_RESTARTS);
+            actor_task = spawn_actor(receiver, true);
+        }
+    });
 }
 
-impl ActorHandle<MsgResponseType, UniversalMsgErrorType> for UniversalActorHandle {
-    type Msg = ActorMessage;
+/// The runtime supervisor, which owns the tasks of all actors
+///
+/// The actors' tasks are spawned in it instead of being detached, so that a panic of an actor
+/// doesn't go unnoticed, and so that the shutdown can wait for all of them through [`join_actor_tasks`].
+///
+/// The finished tasks are reaped whenever a new task is spawned, so the set doesn't grow
+/// with the actors that are spawned for every chunk of symbols.
+static ACTOR_TASKS: LazyLock<StdMutex<TaskSet>> = LazyLock::new(StdMutex::default);
 
-    /// Create a new [`UniversalActorHandle`]
-    ///
-    /// This function creates a single [`UniversalActor`] instance,
-    /// and a MPSC channel for communicating to the actor.
-    ///
-    /// # Panics
-    ///
-    /// Panics if it can't run the actor.
-    fn new(nticks: usize) -> Self {
-        let (sender, receiver) = mpsc::channel(ACTOR_CHANNEL_CAPACITY);
-        let mut actor = UniversalActor::new(receiver, nticks);
-        tokio::spawn(async move { actor.run().await });
+/// Spawns the `task` of the actor `name` in the runtime supervisor, see [`ACTOR_TASKS`]
+///
+/// The error that the actor stops with, or its panic, is logged with the actor's name.
+fn track<F>(name: &'static str, task: F)
+where
+    F: Future<Output = Result<MsgResponseType, ActorError>> + Send + 'static,
+{
+    let mut tasks = ACTOR_TASKS.lock().unwrap_or_else(PoisonError::into_inner);
+    tasks.reap();
 
-        Self { sender }
-    }
+    tasks.spawn(async move {
+        match AssertUnwindSafe(task).catch_unwind().await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => tracing::error!("{} failed: {:#}", name, err),
+            Err(_) => tracing::error!("{} panicked.", name),
+        }
+    });
+}
 
-    /// Send a message to an [`UniversalActor`] instance through the [`UniversalActorHandle`]
-    async fn send(&self, msg: ActorMessage) -> Result<MsgResponseType, UniversalMsgErrorType> {
-        self.sender.send(msg).await
-    }
+/// Waits for the tasks of all actors to finish
+///
+/// It is meant to be called at the very end of the shutdown, after all actors have been stopped.
+pub async fn join_actor_tasks() {
+    let mut tasks =
+        std::mem::take(&mut *ACTOR_TASKS.lock().unwrap_or_else(PoisonError::into_inner));
+
+    tasks.join_all().await;
 }
 
 // ============================================================================
@@ -486,482 +747,8156 @@ impl ActorHandle<MsgResponseType, UniversalMsgErrorType> for UniversalActorHandl
 //
 //
 //
-//  [`PerformanceIndicatorsRowsMsg`], [`WriterActor`], [`WriterActorHandle`]
+//                    [`Iteration`], [`PipelineHandles`]
 //
 //
 //
 //
 // ============================================================================
 
-/// A single row of calculated performance indicators for a symbol
-#[derive(Clone, Debug, Serialize)]
-pub struct PerformanceIndicatorsRow {
-    pub symbol: String,
-    pub last_price: f64,
-    pub pct_change: f64,
-    pub period_min: f64,
-    pub period_max: f64,
-    pub sma: f64,
+/// Information about an iteration of the main loop
+///
+/// It is passed along with the data through all actors in the pipeline.
+#[derive(Clone, Copy, Debug)]
+pub struct Iteration {
+    /// The iteration's sequence number, which increases monotonically starting at 1,
+    /// and which correlates the iteration's messages across the actors in the traces
+    ///
+    /// The [`CollectionActor`] uses it to detect missing, duplicate and out-of-order chunks.
+    pub id: u64,
+    /// The moment at which the iteration started, used for measuring its execution time
+    pub start: Instant,
+    /// The end of the period that the iteration fetches
+    pub to: OffsetDateTime,
+    /// The total number of chunks of symbols in the iteration
+    ///
+    /// The set of symbols, and with it the number of chunks, can change at runtime.
+    pub num_chunks: usize,
 }
 
-impl Display for PerformanceIndicatorsRow {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
-            self.symbol,
-            self.last_price,
-            self.pct_change,
-            self.period_min,
-            self.period_max,
-            self.sma
-        )
+/// Handles of the singleton actors that the pipeline's messages are eventually delivered to,
+/// and the settings that are fixed for the whole run
+///
+/// They are passed along with the data through the fetch and processor actors.
+#[derive(Clone)]
+pub struct PipelineHandles {
+    /// The single writer actor instance
+    pub writer_handle: WriterActorHandle,
+    /// The single collection actor instance
+    pub collection_handle: CollectionActorHandle,
+    /// The single statistics actor instance
+    pub stats_handle: StatsActorHandle,
+    /// The single dead letter actor instance, which records undelivered and unhandled messages
+    pub dead_letter_handle: DeadLetterActorHandle,
+    /// The settings that are fixed for the whole run, which are shared, to keep the messages small
+    pub settings: Arc<PipelineSettings>,
+}
+
+/// The settings of the pipeline that are fixed for the whole run
+#[derive(Debug, Default)]
+pub struct PipelineSettings {
+    /// The confidence level of the historical VaR and CVaR that the processor actors append
+    /// to the symbols' rows in the standard output, if any; see [`crate::risk`]
+    pub risk_confidence: Option<f64>,
+    /// The asset classes of the symbols, which the fetch actors route the symbols by,
+    /// and which the processor actors annotate the rows with
+    pub asset_classes: AssetClasses,
+    /// The interval of the bars that the fetch actors ask the data provider for
+    pub bars: BarInterval,
+    /// The rolling price windows of the symbols, which the fetch actors fetch only the newest bars
+    /// into, if any; see [`crate::price_windows`]
+    pub windows: Option<PriceWindows>,
+    /// Limits the simultaneous data provider requests of all fetch actors, if provided;
+    /// a permit is held for each request, and the pipelines that share the fetch workers
+    /// share the semaphore, too
+    pub concurrency: Option<Arc<Semaphore>>,
+    /// The provider that the fetch actors ask for the bars; if `None`, Yahoo! Finance is connected to
+    /// for every chunk of symbols; see [`crate::quotes`]
+    pub quotes: Option<Arc<dyn QuoteProvider>>,
+    /// Whether the processing calculates the indicators of a whole chunk over its closes
+    /// in Arrow arrays; only with the `arrow` feature, see [`crate::columnar`]
+    #[cfg(feature = "arrow")]
+    pub columnar: bool,
+}
+
+impl PipelineHandles {
+    /// Pushes the new runtime settings to the long-lived writer and collection actors
+    ///
+    /// A [`Reconfiguration`] that can't be delivered ends up in the dead letter actor.
+    pub async fn reconfigure(&self, reconfiguration: Reconfiguration) {
+        if let Err(err) = self
+            .writer_handle
+            .send(WriterActorMsg::Reconfigure(reconfiguration.clone()))
+            .await
+        {
+            self.dead_letter_handle
+                .undelivered("WriterActor", "Reconfigure", err)
+                .await;
+        }
+        if let Err(err) = self
+            .collection_handle
+            .send(CollectionActorMsg::Reconfigure(reconfiguration))
+            .await
+        {
+            self.dead_letter_handle
+                .undelivered("CollectionActor", "Reconfigure", err)
+                .await;
+        }
+    }
+
+    /// Delivers the `rows` of a chunk, which were calculated for the period that starts at `from`,
+    /// to the writer and collection actors, as the chunk with the `chunk_id` of the `iteration`
+    ///
+    /// # Errors
+    /// - [`ActorError::Send`] if the writer or the collection actor isn't running
+    pub async fn deliver(
+        &self,
+        from: String,
+        rows: Vec<PerformanceIndicatorsRow>,
+        iteration: Iteration,
+        chunk_id: usize,
+    ) -> Result<MsgResponseType, ActorError> {
+        // Assemble a message for the single writer actor.
+        let perf_ind_msg = PerformanceIndicatorsRowsMsg {
+            from,
+            rows,
+            iteration,
+            chunk_id,
+        };
+
+        // Send the message to the single writer actor.
+        self.writer_handle
+            .send(WriterActorMsg::PerformanceIndicatorsChunk(
+                perf_ind_msg.clone(),
+            ))
+            .await
+            .map_err(|_| ActorError::Send("WriterActor"))?;
+
+        // Assemble a message for the single collection actor.
+        let coll_msg = CollectionActorMsg::PerformanceIndicatorsChunk(perf_ind_msg);
+
+        // Send the message to the single collection actor.
+        self.collection_handle
+            .send(coll_msg)
+            .await
+            .map_err(|_| ActorError::Send("CollectionActor"))?;
+
+        Ok(())
     }
 }
 
-/// The [`PerformanceIndicatorsRowsMsg`] message
+// ============================================================================
+//
+//
+//
+//
+//        [`FetchActorMsg`], [`FetchActor`], [`FetchActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`FetchActorMsg`] enumeration
 ///
-/// It contains a `from` date and time field,
-/// and calculated performance indicators for a **chunk** of symbols.
+/// Supports two message types:
+/// - [`QuoteRequestsMsg`],
+/// - [`Shutdown`],
 ///
-/// There is no expected response.
+/// Only [`Shutdown`] expects a response, which is sent back through the provided `sender`.
 ///
 /// We could have an oneshot channel for sending the response back in general case.
+/// It could be used for every message type.
 /// We simply don't need it in our specific (custom) case.
-#[derive(Clone)]
-pub struct PerformanceIndicatorsRowsMsg {
-    from: String,
-    rows: Vec<PerformanceIndicatorsRow>,
-    start: Instant,
+pub enum FetchActorMsg {
+    QuoteRequestsMsg {
+        symbols: Vec<Symbol>,
+        from: OffsetDateTime,
+        handles: PipelineHandles,
+        /// The iteration, whose `to` is the end of the fetched period
+        iteration: Iteration,
+        /// The chunk's index in the iteration, which, together with the iteration's ID,
+        /// correlates the chunk's messages in the traces
+        chunk_id: usize,
+        window_size: usize,
+    },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// A fetch actor acknowledges it only after the processor actor that it has spawned
+    /// has also stopped, which means that the whole chunk has been delivered to
+    /// the writer and collection actors.
+    Shutdown { sender: oneshot::Sender<()> },
 }
 
-/// Actor for writing calculated performance indicators for fetched stock data into a CSV file
+/// Actor for fetching the closing prices of a chunk of symbols from the data provider
+///
+/// It hands the fetched data over to a [`ProcessorActor`].
 ///
 /// It is not made public on purpose.
 ///
-/// It can only be created through [`WriterActorHandle`], which is public.
-struct WriterActor {
-    receiver: mpsc::Receiver<PerformanceIndicatorsRowsMsg>,
-    pub file_name: String,
-    pub writer: Option<BufWriter<File>>,
+/// It can only be created through [`FetchActorHandle`], which is public.
+struct FetchActor {
+    receiver: mpsc::Receiver<FetchActorMsg>,
+    /// Taken from the messages, for reporting errors
+    dead_letter_handle: Option<DeadLetterActorHandle>,
+    /// The time limit for handling a single message
+    handler_timeout: Duration,
 }
 
-impl Actor<MsgResponseType> for WriterActor {
-    type Msg = PerformanceIndicatorsRowsMsg;
+impl Actor<MsgResponseType> for FetchActor {
+    type Msg = FetchActorMsg;
 
-    /// Create a new [`WriterActor`]
-    fn new(receiver: mpsc::Receiver<PerformanceIndicatorsRowsMsg>, _: usize) -> Self {
+    /// Create a new [`FetchActor`]
+    fn new(receiver: mpsc::Receiver<FetchActorMsg>, _: usize) -> Self {
         Self {
             receiver,
-            file_name: CSV_FILE_PATH.to_string(),
-            // file_name: OffsetDateTime::now_utc()
-            //     .format(&Rfc3339) // or Rfc2822 (has blanks), Iso8601
-            //     .expect("The provided date or time format isn't correct."),
-            writer: None,
+            dead_letter_handle: None,
+            handler_timeout: Duration::from_secs(HANDLER_TIMEOUT_SECS),
         }
     }
 
-    /// Start the [`WriterActor`]
-    ///
-    /// This function is meant to be used directly in the [`WriterActorHandle`].
-    async fn start(&mut self) -> Result<MsgResponseType> {
-        let mut file = File::create(&self.file_name)
-            .unwrap_or_else(|_| panic!("Could not open target file \"{}\".", self.file_name));
-        #[cfg(debug_assertions)]
-        tracing::debug!("The output file path is \"{}\".", self.file_name);
-        let _ = writeln!(&mut file, "{}", CSV_HEADER);
-        self.writer = Some(BufWriter::new(file));
-        tracing::debug!("WriterActor is started.");
+    /// Run the [`FetchActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("FetchActor {:p} is running.", self);
 
-        self.run().await?;
+        while let Some(msg) = self.receiver.recv().await {
+            let handler_timeout = self.handler_timeout;
+            if let Err(err) = with_timeout(handler_timeout, self.handle_intercepted(msg)).await {
+                report_error("FetchActor", &err, self.dead_letter_handle.as_ref()).await;
+            }
+        }
 
         Ok(())
     }
 
-    /// Run the [`WriterActor`]
-    ///
-    /// This function is meant to be used indirectly - only through the [`WriterActor::start`] function
-    async fn run(&mut self) -> Result<MsgResponseType> {
-        tracing::debug!("WriterActor is running.");
-
-        while let Some(msg) = self.receiver.recv().await {
-            self.handle(msg).await?;
+    /// Handle the [`FetchActorMsg`]
+    async fn handle(&mut self, msg: FetchActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            FetchActorMsg::QuoteRequestsMsg {
+                symbols,
+                from,
+                handles,
+                iteration,
+                chunk_id,
+                window_size,
+            } => {
+                self.dead_letter_handle = Some(handles.dead_letter_handle.clone());
+                let span =
+                    tracing::debug_span!("fetch", iteration = iteration.id, chunk = chunk_id);
+                Self::handle_quote_requests_msg(
+                    symbols,
+                    from,
+                    iteration.to,
+                    handles,
+                    iteration,
+                    chunk_id,
+                    window_size,
+                )
+                .instrument(span)
+                .await?;
+            }
+            FetchActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("FetchActor {:p} is shutting down.", self);
+                let _ = sender.send(());
+            }
         }
 
         Ok(())
     }
+}
 
-    /// Stop the [`WriterActor`]
+impl FetchActor {
+    /// The [`QuoteRequestsMsg`] message handler for the [`FetchActor`]
     ///
-    /// This function is meant to be called in the [`WriterActor`]'s destructor.
-    fn stop(&mut self) {
-        if let Some(writer) = &mut self.writer {
-            writer
-                .flush()
-                .expect("Failed to flush writer. Data loss :(")
+    /// Fetches the closing prices of the chunk's `symbols` with [`FetchActor::fetch_symbols_closes`],
+    /// from the pipeline's [`QuoteProvider`], or from Yahoo! Finance if it has none,
+    /// holding a permit of the pipeline's request semaphore, if any, for each request,
+    /// and merging the fetched bars into the pipeline's [`PriceWindows`], if any.
+    ///
+    /// It then spawns a new [`ProcessorActor`], sends it a [`SymbolsClosesMsg`] message
+    /// with the closing prices and the data-quality reports, and waits for it to stop,
+    /// so that the chunk has been fully processed when this handler returns.
+    ///
+    /// The workers of a [`FetchPool`] handle the same message without spawning a processor actor.
+    ///
+    /// A symbol whose data couldn't be fetched maps to an empty vector; the failure is summarized
+    /// in the statistics, and the symbol is skipped rather than breaking the program.
+    ///
+    /// # Errors
+    /// - [`ActorError::Provider`], if the default data provider can't be created
+    /// - [`ActorError::Send`], if the processor actor isn't running
+    /// - [`ActorError::NoResponse`], if the processor actor doesn't acknowledge its shutdown
+    async fn handle_quote_requests_msg(
+        symbols: Vec<Symbol>,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        handles: PipelineHandles,
+        iteration: Iteration,
+        chunk_id: usize,
+        window_size: usize,
+    ) -> Result<MsgResponseType, ActorError> {
+        let mut symbols_closes = HashMap::with_capacity(symbols.len());
+        let quality =
+            Self::fetch_symbols_closes(symbols, from, to, &handles, &mut symbols_closes).await?;
+
+        let symbols_closes_msg = ProcessorActorMsg::SymbolsClosesMsg {
+            symbols_closes,
+            quality,
+            from,
+            handles: handles.clone(),
+            iteration,
+            chunk_id,
+            window_size,
         };
 
-        tracing::debug!("WriterActor is flushed and properly stopped.");
+        // Spawn a processor actor and send it the message.
+        let actor_handle = ProcessorActorHandle::new(0);
+        if let Err(err) = handles
+            .stats_handle
+            .send(StatsActorMsg::ActorsSpawned(1))
+            .await
+        {
+            handles
+                .dead_letter_handle
+                .undelivered("StatsActor", "ActorsSpawned", err)
+                .await;
+        }
+        actor_handle
+            .send(symbols_closes_msg)
+            .await
+            .map_err(|_| ActorError::Send("ProcessorActor"))?;
+
+        // Wait for the processor actor to finish, so that our own shutdown means that
+        // the chunk has been fully processed.
+        actor_handle
+            .shutdown()
+            .await
+            .map_err(|_| ActorError::NoResponse("ProcessorActor"))?;
+
+        Ok(())
     }
 
-    /// The [`PerformanceIndicatorsRowsMsg`] message handler for the [`WriterActor`] actor
+    /// Fetches closing prices of `symbols` into `symbols_closes`, which is cleared first,
+    /// so that its allocation can be reused for every chunk
     ///
-    /// Writes results to file and measures & prints the iteration's execution time.
-    async fn handle(&mut self, msg: PerformanceIndicatorsRowsMsg) -> Result<MsgResponseType> {
-        let from = msg.from;
-        let rows = msg.rows;
-        let start = msg.start;
-
-        if let Some(file) = &mut self.writer {
-            for row in rows {
-                let _ = writeln!(
-                    file,
-                    "{},{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
-                    from,
-                    row.symbol,
-                    row.last_price,
-                    row.pct_change,
-                    row.period_min,
-                    row.period_max,
-                    row.sma,
-                );
+    /// The bars are fetched from the pipeline's quote provider, if any, or from Yahoo! Finance.
+    ///
+    /// If the pipeline's settings have a `concurrency` semaphore, a permit is held for each
+    /// data provider request, so that the requests of all fetchers are limited together.
+    ///
+    /// The `symbols_closes` map each symbol to its closing prices in case there was no error
+    /// when fetching the data, or to an empty vector in case of an error, in which case
+    /// the error message is logged at the warning level, and counted in the statistics.
+    ///
+    /// # Returns
+    /// The data-quality reports of the fetched symbols
+    ///
+    /// # Errors
+    /// - If the data provider can't be created
+    async fn fetch_symbols_closes(
+        symbols: Vec<Symbol>,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        handles: &PipelineHandles,
+        symbols_closes: &mut HashMap<Symbol, Vec<f64>>,
+    ) -> Result<Vec<QualityReport>, ActorError> {
+        let fallback;
+        let provider: &dyn QuoteProvider = match &handles.settings.quotes {
+            Some(quotes) => quotes.as_ref(),
+            None => {
+                fallback = YahooQuotes::new().map_err(ActorError::Provider)?;
+                &fallback
             }
+        };
+
+        symbols_closes.clear();
+        symbols_closes.reserve(symbols.len());
+        let mut reports = Vec::with_capacity(symbols.len());
+        let mut failures = ErrorSummary::default();
+
+        for symbol in symbols {
+            // the semaphore is never closed, so a permit is always acquired eventually
+            let permit = match &handles.settings.concurrency {
+                Some(semaphore) => semaphore.acquire().await.ok(),
+                None => None,
+            };
+            let provider_symbol = handles.settings.asset_classes.provider_symbol(&symbol);
+            let windows = handles.settings.windows.as_ref();
+            let fetch_from = windows.map_or(from, |windows| windows.fetch_from(&symbol, from, to));
+            let result = provider
+                .bars(&provider_symbol, fetch_from, to, handles.settings.bars)
+                .await;
+            drop(permit);
+
+            let closes = match result {
+                // the indicators are calculated only over the valid closes
+                Ok(bars) => {
+                    let bars = match windows {
+                        Some(windows) => windows.update(&symbol, from, to, bars),
+                        None => bars,
+                    };
+                    let (report, closes) = quality::check(&symbol, &bars, to);
+                    reports.push(report);
+                    closes
+                }
+                // the identical failures are summarized by the statistics actor
+                Err(err) => {
+                    tracing::debug!(
+                        "There was an API error \"{}\" while fetching data for the symbol \"{}\"; \
+                         skipping the symbol.",
+                        err,
+                        symbol
+                    );
+                    failures.record(&symbol, &provider_symbol, &err.to_string());
+                    vec![]
+                }
+            };
 
-            file.flush()
-                .context("Failed to flush to file. Data loss :/")?;
+            symbols_closes.insert(symbol, closes);
         }
 
-        tracing::info!("Took {:.3?} to complete.", start.elapsed());
-        #[cfg(debug_assertions)]
-        println!("Took {:.3?} to complete.\n", start.elapsed());
+        // Statistics are best-effort, so they must never break the pipeline.
+        if !failures.is_empty() {
+            if let Err(err) = handles
+                .stats_handle
+                .send(StatsActorMsg::FetchErrors(failures))
+                .await
+            {
+                handles
+                    .dead_letter_handle
+                    .undelivered("StatsActor", "FetchErrors", err)
+                    .await;
+            }
+        }
 
-        Ok(())
+        Ok(reports)
     }
 }
 
-impl Drop for WriterActor {
+impl Drop for FetchActor {
     fn drop(&mut self) {
         self.stop();
     }
 }
 
-/// A handle for the [`WriterActor`]
+/// A handle for the [`FetchActor`]
 ///
-/// Only the handle is public; the [`WriterActor`] isn't.
+/// Only the handle is public; the [`FetchActor`] isn't.
 ///
-/// We can only create [`WriterActor`]s through the [`WriterActorHandle`].
+/// We can only create [`FetchActor`]s through the [`FetchActorHandle`].
 ///
 /// It contains the `sender` field, which represents
-/// a sender of the [`PerformanceIndicatorsRowsMsg`] in an MPSC channel.
+/// a sender of the [`FetchActorMsg`] in an MPSC channel.
 ///
 /// The handle is the sender, and the actor is the receiver
 /// of a message in the channel.
 ///
-/// We only create a single [`WriterActor`] instance in a [`WriterActorHandle`].
+/// We only create a single [`FetchActor`] instance in a [`FetchActorHandle`].
 #[derive(Clone)]
-pub struct WriterActorHandle {
-    sender: mpsc::Sender<PerformanceIndicatorsRowsMsg>,
+pub struct FetchActorHandle {
+    sender: mpsc::Sender<FetchActorMsg>,
 }
 
-impl ActorHandle<MsgResponseType, WriterMsgErrorType> for WriterActorHandle {
-    type Msg = PerformanceIndicatorsRowsMsg;
+impl ActorHandle<MsgResponseType, FetchMsgErrorType> for FetchActorHandle {
+    type Msg = FetchActorMsg;
 
-    /// Create a new [`WriterActorHandle`]
-    ///
-    /// This function creates a single [`WriterActor`] instance,
-    /// and a MPSC channel for communicating with the actor.
+    /// Create a new [`FetchActorHandle`]
     ///
-    /// It also starts (runs) the actor.
+    /// This function creates a single [`FetchActor`] instance,
+    /// and a MPSC channel for communicating to the actor.
     ///
     /// # Panics
     ///
     /// Panics if it can't run the actor.
     fn new(nticks: usize) -> Self {
-        let (sender, receiver) = mpsc::channel(ACTOR_CHANNEL_CAPACITY);
-        let mut actor = WriterActor::new(receiver, nticks);
-        tokio::spawn(async move { actor.start().await });
+        let sender = spawn_actor!(FetchActor, nticks);
 
         Self { sender }
     }
 
-    /// Send a message to an [`WriterActor`] instance through the [`WriterActorHandle`]
-    async fn send(
-        &self,
-        msg: PerformanceIndicatorsRowsMsg,
-    ) -> Result<MsgResponseType, WriterMsgErrorType> {
+    /// Send a message to a [`FetchActor`] instance through the [`FetchActorHandle`]
+    async fn send(&self, msg: FetchActorMsg) -> Result<MsgResponseType, FetchMsgErrorType> {
         self.sender.send(msg).await
     }
 }
 
+impl FetchActorHandle {
+    /// Stops the [`FetchActor`]
+    ///
+    /// The actor stops only after the processor actor that it has spawned has stopped.
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`FetchActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| FetchActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the FetchActor.")
+    }
+}
+
 // ============================================================================
 //
 //
 //
 //
-//    [`CollectionActorMsg`], [`CollectionActor`], [`CollectionActorHandle`],
-//                        [`Batch`], [`TailResponse`]
+//   [`ProcessorActorMsg`], [`ProcessorActor`], [`ProcessorActorHandle`]
 //
 //
 //
 //
 // ============================================================================
 
-/// The [`CollectionActorMsg`] enumeration
-///
-/// Supports two message types:
-/// - [`TailRequest`],
-/// - [`PerformanceIndicatorsChunk`],
+/// The [`ProcessorActorMsg`] enumeration
 ///
-/// There is no expected response for any of the message types.
+/// Supports three message types:
+/// - [`SymbolsClosesMsg`],
+/// - [`Reconfigure`],
+/// - [`Shutdown`],
 ///
-/// We could have an oneshot channel for sending the response back in general case.
-/// It could be used for every message type.
-/// We simply don't need it in our specific (custom) case.
-pub enum CollectionActorMsg {
-    /// Wraps a [`PerformanceIndicatorsRowsMsg`] message
-    PerformanceIndicatorsChunk(PerformanceIndicatorsRowsMsg),
-    /// A request from web server for the last `n` batches of processed data
-    TailRequest {
-        sender: mpsc::Sender<TailResponse>,
-        n: usize,
+/// Only [`Shutdown`] expects a response, which is sent back through the provided `sender`.
+pub enum ProcessorActorMsg {
+    SymbolsClosesMsg {
+        symbols_closes: HashMap<Symbol, Vec<f64>>,
+        /// The data-quality reports of the symbols, whose rows they flag
+        quality: Vec<QualityReport>,
+        from: OffsetDateTime,
+        handles: PipelineHandles,
+        iteration: Iteration,
+        chunk_id: usize,
+        window_size: usize,
     },
+    /// New runtime settings, whose window size overrides the one in the [`SymbolsClosesMsg`]
+    /// messages that follow it
+    ///
+    /// The main loop's processor actors live for a single chunk, and they get the current window size
+    /// in their messages, so this is meant for long-lived processor actors.
+    Reconfigure(Reconfiguration),
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
 }
 
-/// Actor for collecting calculated performance indicators for fetched stock data into a buffer
-///
-/// It is used for storing the performance data in a buffer of capacity `N`,
-/// where `N` is the number of main loop iterations that occur at a fixed time interval.
+/// Actor for calculating the performance indicators of a chunk of symbols out of their closing prices
 ///
-/// This data can then be fetched by the web server.
+/// It delivers the calculated performance indicators to the writer and collection actors.
 ///
 /// It is not made public on purpose.
 ///
-/// It can only be created through [`CollectionActorHandle`], which is public.
-struct CollectionActor {
-    receiver: mpsc::Receiver<CollectionActorMsg>,
-    buffer: TailResponse,
-    batch: Batch,
-    chunk_cnt: usize,
-    num_chunks: usize,
+/// It can only be created through [`ProcessorActorHandle`], which is public.
+struct ProcessorActor {
+    receiver: mpsc::Receiver<ProcessorActorMsg>,
+    /// Taken from the messages, for reporting errors
+    dead_letter_handle: Option<DeadLetterActorHandle>,
+    /// The time limit for handling a single message
+    handler_timeout: Duration,
+    /// Set by a [`ProcessorActorMsg::Reconfigure`] message
+    window_size: Option<usize>,
+    /// Reused for every message that the actor handles
+    scratch: ChunkScratch,
 }
 
-impl Actor<MsgResponseType> for CollectionActor {
-    type Msg = CollectionActorMsg;
+impl Actor<MsgResponseType> for ProcessorActor {
+    type Msg = ProcessorActorMsg;
 
-    /// Create a new [`CollectionActor`]
-    fn new(receiver: mpsc::Receiver<CollectionActorMsg>, nticks: usize) -> Self {
+    /// Create a new [`ProcessorActor`]
+    fn new(receiver: mpsc::Receiver<ProcessorActorMsg>, _: usize) -> Self {
         Self {
             receiver,
-            buffer: VecDeque::with_capacity(TAIL_BUFFER_SIZE),
-            batch: Vec::with_capacity(nticks),
-            chunk_cnt: 0,
-            num_chunks: calc_num_chunks(nticks, CHUNK_SIZE),
+            dead_letter_handle: None,
+            handler_timeout: Duration::from_secs(HANDLER_TIMEOUT_SECS),
+            window_size: None,
+            scratch: ChunkScratch::default(),
         }
     }
 
-    /// Start the [`CollectionActor`]
-    ///
-    /// This function is meant to be used directly in the [`CollectionActorHandle`].
-    async fn start(&mut self) -> Result<MsgResponseType> {
-        #[cfg(debug_assertions)]
-        tracing::debug!("CollectionActor is starting...");
-
-        tracing::debug!("CollectionActor is started.");
+    /// Run the [`ProcessorActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("ProcessorActor {:p} is running.", self);
 
-        self.run().await?;
+        while let Some(msg) = self.receiver.recv().await {
+            let handler_timeout = self.handler_timeout;
+            if let Err(err) = with_timeout(handler_timeout, self.handle_intercepted(msg)).await {
+                report_error("ProcessorActor", &err, self.dead_letter_handle.as_ref()).await;
+            }
+        }
 
         Ok(())
     }
 
-    /// Run the [`CollectionActor`]
-    ///
-    /// This function is meant to be used indirectly - only through the [`CollectionActor::start`] function
-    async fn run(&mut self) -> Result<MsgResponseType> {
-        tracing::debug!("CollectionActor is running.");
-
-        while let Some(msg) = self.receiver.recv().await {
-            self.handle(msg).await?;
+    /// Handle the [`ProcessorActorMsg`]
+    async fn handle(&mut self, msg: ProcessorActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            ProcessorActorMsg::SymbolsClosesMsg {
+                symbols_closes,
+                quality,
+                from,
+                handles,
+                iteration,
+                chunk_id,
+                window_size,
+            } => {
+                self.dead_letter_handle = Some(handles.dead_letter_handle.clone());
+                let span =
+                    tracing::debug_span!("process", iteration = iteration.id, chunk = chunk_id);
+                self.scratch.symbols_closes = symbols_closes;
+                Self::handle_symbols_closes_msg(
+                    &mut self.scratch,
+                    quality,
+                    from,
+                    handles,
+                    iteration,
+                    chunk_id,
+                    self.window_size.unwrap_or(window_size),
+                )
+                .instrument(span)
+                .await?;
+            }
+            ProcessorActorMsg::Reconfigure(reconfiguration) => {
+                tracing::debug!(
+                    "ProcessorActor {:p} now works with the window size {}.",
+                    self,
+                    reconfiguration.window_size
+                );
+                self.window_size = Some(reconfiguration.window_size);
+            }
+            ProcessorActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("ProcessorActor {:p} is shutting down.", self);
+                let _ = sender.send(());
+            }
         }
 
         Ok(())
     }
+}
 
-    /// Stop the [`CollectionActor`]
+impl ProcessorActor {
+    /// The [`SymbolsClosesMsg`] message handler for the [`ProcessorActor`]
     ///
-    /// This function is meant to be called in the [`CollectionActor`]'s destructor.
-    fn stop(&mut self) {
-        tracing::debug!("CollectionActor is stopped.");
-    }
-
-    /// The [`CollectionActorMsg`] message handler for the [`CollectionActor`] actor
+    /// Sends a [`PerformanceIndicatorsRowsMsg`] message to the [`WriterActor`],
+    /// whose address it gets from the [`SymbolsClosesMsg`] message.
     ///
-    /// It collects chunks from Processing [`UniversalActor`]s, assembles them in batches,
-    /// and then stores the batches in the buffer.
+    /// The rows of the symbols with data-quality issues are flagged in the output, and the symbols'
+    /// `quality` reports are sent to the [`CollectionActor`] before the rows.
     ///
-    /// It also receives requests from web server for the last n batches of
-    /// processed data.
-    async fn handle(&mut self, msg: CollectionActorMsg) -> Result<MsgResponseType> {
-        match msg {
-            CollectionActorMsg::PerformanceIndicatorsChunk(msg) => {
-                Self::handle_perf_ind_chunk(self, msg).await;
-            }
-            CollectionActorMsg::TailRequest { sender, n } => {
-                Self::handle_tail_request(self, sender, n).await?;
-            }
-        }
+    /// The closing prices are drained from the `scratch`, whose buffers are kept for the next chunk.
+    async fn handle_symbols_closes_msg(
+        scratch: &mut ChunkScratch,
+        quality: Vec<QualityReport>,
+        from: OffsetDateTime,
+        handles: PipelineHandles,
+        iteration: Iteration,
+        chunk_id: usize,
+        window_size: usize,
+    ) -> Result<MsgResponseType, ActorError> {
+        let from = OffsetDateTime::format(from, &Rfc3339)?;
 
-        Ok(())
-    }
-}
+        let ChunkScratch {
+            symbols_closes,
+            columns,
+        } = scratch;
+        let mut rows: Vec<PerformanceIndicatorsRow> = Vec::with_capacity(symbols_closes.len());
+        let mut returns: Vec<(Symbol, Vec<f64>)> = Vec::with_capacity(symbols_closes.len());
+        // the columnar processing calculates the rows of the whole chunk at once
+        #[cfg(feature = "arrow")]
+        let mut columnar_rows = handles
+            .settings
+            .columnar
+            .then(|| ColumnarCloses::new(symbols_closes).rows(window_size));
+        #[cfg(not(feature = "arrow"))]
+        let mut columnar_rows: Option<HashMap<Symbol, PerformanceIndicatorsRow>> = None;
 
-impl CollectionActor {
-    /// Handle a [`CollectionActorMsg::PerformanceIndicatorsChunk`] message,
-    /// which wraps a [`PerformanceIndicatorsRowsMsg`] message
-    ///
-    /// Assembles chunks into complete batches and stores them in buffer.
-    ///
-    /// This ensures that a batch cannot be partially read by the web server.
-    /// It can only be fully-read, when it's ready.
-    /// Synchronization in the Actor model is accomplished via message passing.
-    ///
-    /// Takes care of keeping only the fresh data in the buffer, so that its
-    /// size doesn't ever grow, which prevents memory leaks.
-    /// Old data are removed from the buffer to make room for new data.
-    ///
-    /// The *from* field is discarded.
-    ///
-    /// This message comes from a processing actor.
-    async fn handle_perf_ind_chunk(
-        &mut self,
-        msg: PerformanceIndicatorsRowsMsg,
-    ) -> MsgResponseType {
-        let rows = msg.rows;
+        for (symbol, closes) in symbols_closes.drain() {
+            let row = match &mut columnar_rows {
+                Some(columnar_rows) => columnar_rows.remove(&symbol),
+                None => {
+                    PerformanceIndicatorsRow::calculate(Arc::clone(&symbol), &closes, window_size)
+                        .await
+                }
+            };
+            if let Some(mut row) = row {
+                row.asset_class = handles.settings.asset_classes.class_of(&symbol);
+                let symbol_returns = risk::daily_returns(&closes);
 
-        // when all chunks have been received, assemble a new batch from them and store the batch in the buffer
-        self.chunk_cnt += 1;
-        self.batch.extend(rows);
+                // the optional columns are written to the reused buffer, and not to new strings
+                columns.clear();
+
+                // the VaR and CVaR columns are empty if there are no returns
+                if let Some(confidence) = handles.settings.risk_confidence {
+                    let _ = match RiskMetrics::calculate(&symbol_returns, confidence) {
+                        Some(metrics) => {
+                            write!(columns, ",{:.2}%,{:.2}%", metrics.var_pct, metrics.cvar_pct)
+                        }
+                        None => write!(columns, ",,"),
+                    };
+                }
+
+                // the issues are appended to the row of a flagged symbol
+                if let Some(report) = quality
+                    .iter()
+                    .find(|report| *report.symbol == *symbol && !report.is_ok())
+                {
+                    let flags = report.flags();
+                    tracing::warn!(
+                        "The data of the symbol \"{}\" has quality issues: {}.",
+                        symbol,
+                        flags
+                    );
+                    let _ = write!(columns, ",{}", flags);
+                }
+
+                // A simple way to output CSV data
+                tracing::info!(
+                    target: ROWS_TRACING_TARGET,
+                    "{},{},${:.2},{:.2}%,${:.2},${:.2},${:.2}{}",
+                    from,
+                    symbol,
+                    row.last_price,
+                    row.pct_change,
+                    row.period_min,
+                    row.period_max,
+                    row.sma,
+                    columns,
+                );
 
-        if self.chunk_cnt == self.num_chunks {
-            self.buffer.push_front(self.batch.clone());
-            self.buffer.truncate(TAIL_BUFFER_SIZE);
-            self.batch.clear();
-            self.chunk_cnt = 0;
+                rows.push(row);
+                returns.push((symbol, symbol_returns));
+            } else {
+                tracing::warn!("Got no data for symbol \"{}\".", symbol);
+            }
         }
-    }
 
-    /// Handle a [`CollectionActorMsg::TailRequest`]
-    ///
-    /// Gets the last fully-assembled `n` batches of performance indicators
-    /// and sends them to the web server.
-    ///
-    /// This message is basically a command - a request for the newest `n`
-    /// batches.
-    ///
-    /// This message comes from the web server.
-    async fn handle_tail_request(
-        &mut self,
-        sender: mpsc::Sender<TailResponse>,
-        n: usize,
-    ) -> Result<MsgResponseType> {
-        let response = self.buffer.iter().take(n).cloned().collect();
-        sender
-            .send(response)
-            .await
-            .context("Failed to send a response to the web application.")?;
+        // The reports and the returns go first, so that they are in place when the chunk's rows are served.
+        if !quality.is_empty() {
+            handles
+                .collection_handle
+                .send(CollectionActorMsg::QualityReports(quality))
+                .await
+                .map_err(|_| ActorError::Send("CollectionActor"))?;
+        }
+        if !returns.is_empty() {
+            handles
+                .collection_handle
+                .send(CollectionActorMsg::Returns(returns))
+                .await
+                .map_err(|_| ActorError::Send("CollectionActor"))?;
+        }
 
-        Ok(())
+        handles.deliver(from, rows, iteration, chunk_id).await
     }
 }
 
-impl Drop for CollectionActor {
+impl Drop for ProcessorActor {
     fn drop(&mut self) {
         self.stop();
     }
 }
 
-/// A handle for the [`CollectionActor`]
-///
-/// Only the handle is public; the [`CollectionActor`] isn't.
-///
-/// We can only create [`CollectionActor`]s through the [`CollectionActorHandle`].
+/// A handle for the [`ProcessorActor`]
 ///
-/// It contains the `sender` field, which represents
-/// a sender of the [`CollectionActorMsg`] in an MPSC channel.
+/// Only the handle is public; the [`ProcessorActor`] isn't.
 ///
-/// The handle is the sender, and the actor is the receiver
-/// of a message in the channel.
+/// We can only create [`ProcessorActor`]s through the [`ProcessorActorHandle`].
 ///
-/// We only create a single [`CollectionActor`] instance in a [`CollectionActorHandle`].
+/// We only create a single [`ProcessorActor`] instance in a [`ProcessorActorHandle`].
 #[derive(Clone)]
-pub struct CollectionActorHandle {
-    sender: mpsc::Sender<CollectionActorMsg>,
+pub struct ProcessorActorHandle {
+    sender: mpsc::Sender<ProcessorActorMsg>,
 }
 
-impl ActorHandle<MsgResponseType, CollectionMsgErrorType> for CollectionActorHandle {
-    type Msg = CollectionActorMsg;
+impl ActorHandle<MsgResponseType, ProcessorMsgErrorType> for ProcessorActorHandle {
+    type Msg = ProcessorActorMsg;
 
-    /// Create a new [`CollectionActorHandle`]
+    /// Create a new [`ProcessorActorHandle`]
     ///
-    /// This function creates a single [`CollectionActor`] instance,
-    /// and a MPSC channel for communicating with the actor.
-    ///
-    /// It also starts (runs) the actor.
+    /// This function creates a single [`ProcessorActor`] instance,
+    /// and a MPSC channel for communicating to the actor.
     ///
     /// # Panics
     ///
     /// Panics if it can't run the actor.
     fn new(nticks: usize) -> Self {
-        let (sender, receiver) = mpsc::channel(ACTOR_CHANNEL_CAPACITY);
-        let mut actor = CollectionActor::new(receiver, nticks);
-        tokio::spawn(async move { actor.start().await });
+        let sender = spawn_actor!(ProcessorActor, nticks);
 
         Self { sender }
     }
 
-    /// Send a message to an [`CollectionActor`] instance through the [`CollectionActorHandle`]
-    async fn send(
-        &self,
-        msg: CollectionActorMsg,
-    ) -> Result<MsgResponseType, CollectionMsgErrorType> {
+    /// Send a message to a [`ProcessorActor`] instance through the [`ProcessorActorHandle`]
+    async fn send(&self, msg: ProcessorActorMsg) -> Result<MsgResponseType, ProcessorMsgErrorType> {
         self.sender.send(msg).await
     }
 }
 
-/// Helper function for calculating number of chunks in the current run of the program
+impl ProcessorActorHandle {
+    /// Stops the [`ProcessorActor`]
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`ProcessorActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| ProcessorActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the ProcessorActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//                              [`FetchPool`]
+//
+//
+//
+//
+// ============================================================================
+
+/// A fixed-size pool of fetch workers, which are fed from a shared work queue
 ///
-/// # Params
-/// - `nticks`: number of provided symbols (ticks)
-/// - `chunk_size`: chunk size, optimized for performance
-fn calc_num_chunks(nticks: usize, chunk_size: usize) -> usize {
-    (nticks / chunk_size) + (nticks % chunk_size).clamp(0, 1)
+/// It is an alternative to spawning a fresh [`FetchActor`] per chunk of symbols,
+/// and a fresh [`ProcessorActor`] per fetch actor, at every tick of the main loop.
+/// The number of tasks and the memory usage are stable this way, even with a large number
+/// of symbols, since at most `size` chunks are worked on at the same time.
+///
+/// Each worker handles the messages one at a time. It handles a [`FetchActorMsg::QuoteRequestsMsg`]
+/// by fetching the data of the chunk and then processing the data itself, without spawning
+/// a processor actor.
+///
+/// Unlike actor handles, the pool can't be cloned; it is owned by the main loop.
+/// Other pipelines put their messages in its work queue through a [`FetchQueue`].
+pub struct FetchPool {
+    sender: mpsc::Sender<FetchActorMsg>,
+    workers: TaskSet,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::calc_num_chunks;
+impl FetchPool {
+    /// Create a new [`FetchPool`] with `size` workers, and start them
+    ///
+    /// The work queue can hold `capacity` messages, after which sending blocks until a worker
+    /// takes a message off the queue.
+    ///
+    /// A worker gives up on a message that it couldn't handle within `handler_timeout`,
+    /// and takes the next one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` or `capacity` is zero.
+    pub fn new(size: usize, capacity: usize, handler_timeout: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let queue = Arc::new(Mutex::new(receiver));
 
-    #[test]
-    fn ticks_lt_chunk() {
-        assert_eq!(1, calc_num_chunks(4, 5));
+        let mut workers = TaskSet::default();
+        for id in 0..size {
+            workers.spawn(Self::run_worker(id, Arc::clone(&queue), handler_timeout));
+        }
+
+        Self { sender, workers }
     }
 
-    #[test]
-    fn ticks_eq_chunk() {
-        assert_eq!(1, calc_num_chunks(5, 5));
+    /// Returns the number of workers
+    pub fn size(&self) -> usize {
+        self.workers.len()
     }
 
-    #[test]
-    fn ticks_gt_chunk_1() {
-        assert_eq!(2, calc_num_chunks(7, 5));
+    /// Returns the number of messages waiting in the work queue
+    pub fn queue_depth(&self) -> usize {
+        self.sender.max_capacity() - self.sender.capacity()
     }
 
-    #[test]
-    fn ticks_gt_chunk_2() {
-        assert_eq!(2, calc_num_chunks(10, 5));
+    /// Put a message in the work queue
+    ///
+    /// It is handled by the first worker that becomes available.
+    pub async fn send(&self, msg: FetchActorMsg) -> Result<MsgResponseType, FetchMsgErrorType> {
+        self.sender.send(msg).await
     }
 
-    #[test]
-    fn ticks_gt_chunk_3() {
-        assert_eq!(3, calc_num_chunks(13, 5));
+    /// Returns a [`FetchQueue`], through which another pipeline shares the workers
+    pub fn queue(&self) -> FetchQueue {
+        FetchQueue {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// Stops the pool
+    ///
+    /// Puts a [`FetchActorMsg::Shutdown`] message, a poison pill, for every worker
+    /// in the work queue, and waits for the workers to handle all messages that are
+    /// left in the queue before the pills.
+    ///
+    /// The queue is FIFO, so every message before the pills is taken by a worker
+    /// before any worker takes a pill and stops.
+    pub async fn shutdown(self) {
+        let Self {
+            sender,
+            mut workers,
+        } = self;
+
+        Self::send_pills(&sender, workers.len()).await;
+        drop(sender);
+
+        for err in workers.join_all().await {
+            tracing::error!("A fetch worker failed: {}", err);
+        }
+        tracing::debug!("FetchPool is stopped.");
+    }
+
+    /// Stops the pool, as [`FetchPool::shutdown`] does, but gives up on the messages
+    /// that are left in the queue after `drain_timeout`, and cancels the workers
+    /// that are still running then
+    ///
+    /// It doesn't wait for the handler timeout of a message that never completes.
+    pub async fn shutdown_within(self, drain_timeout: Duration) {
+        let deadline = Instant::now() + drain_timeout;
+        let Self {
+            sender,
+            mut workers,
+        } = self;
+
+        // the queue can be full of messages that the workers don't get to
+        let _ = runtime::timeout(drain_timeout, Self::send_pills(&sender, workers.len())).await;
+        drop(sender);
+
+        let drain_timeout = deadline.saturating_duration_since(Instant::now());
+        for err in workers.join_all_within(drain_timeout).await {
+            match err {
+                JoinError::Cancelled => {
+                    tracing::warn!("A fetch worker was cancelled, as it didn't stop in time.")
+                }
+                JoinError::Panicked => tracing::error!("A fetch worker failed: {}", err),
+            }
+        }
+        tracing::debug!("FetchPool is stopped.");
+    }
+
+    /// Puts a [`FetchActorMsg::Shutdown`] message, a poison pill, for each of the `count` workers
+    /// in the work queue of the `sender`
+    async fn send_pills(sender: &mpsc::Sender<FetchActorMsg>, count: usize) {
+        for _ in 0..count {
+            // the pills are acknowledged, but the workers are waited for instead
+            let (ack_sender, _) = oneshot::channel();
+            let pill = FetchActorMsg::Shutdown { sender: ack_sender };
+            if sender.send(pill).await.is_err() {
+                // all workers have already stopped
+                break;
+            }
+        }
+    }
+
+    /// Run a single worker
+    ///
+    /// The worker takes messages off the shared `queue` until it takes a [`FetchActorMsg::Shutdown`]
+    /// message, a poison pill, which it acknowledges before it stops, or until the queue is closed and empty.
+    ///
+    /// A pill stops only the worker that has taken it; the whole pool is stopped through
+    /// [`FetchPool::shutdown`].
+    ///
+    /// Handling a message is cancelled after `handler_timeout`, and the timeout is reported;
+    /// the time spent waiting for the permits of the pipeline's `concurrency` semaphore
+    /// counts towards it.
+    async fn run_worker(
+        id: usize,
+        queue: Arc<Mutex<mpsc::Receiver<FetchActorMsg>>>,
+        handler_timeout: Duration,
+    ) {
+        tracing::debug!("Fetch worker {} is running.", id);
+
+        let mut fetcher = Fetcher::default();
+        loop {
+            // the lock is released as soon as a message has been taken
+            let msg = queue.lock().await.recv().await;
+            let Some(msg) = msg else {
+                break;
+            };
+
+            let (result, dead_letter_handle) = match msg {
+                FetchActorMsg::QuoteRequestsMsg {
+                    symbols,
+                    from,
+                    handles,
+                    iteration,
+                    chunk_id,
+                    window_size,
+                } => {
+                    let dead_letter_handle = handles.dead_letter_handle.clone();
+                    let result = with_timeout(
+                        handler_timeout,
+                        fetcher.fetch_and_process(
+                            symbols,
+                            from,
+                            handles,
+                            iteration,
+                            chunk_id,
+                            window_size,
+                        ),
+                    )
+                    .await;
+                    (result, Some(dead_letter_handle))
+                }
+                FetchActorMsg::Shutdown { sender } => {
+                    let _ = sender.send(());
+                    break;
+                }
+            };
+
+            if let Err(err) = result {
+                report_error("FetchWorker", &err, dead_letter_handle.as_ref()).await;
+            }
+        }
+
+        tracing::debug!("Fetch worker {} is stopped.", id);
+    }
+}
+
+/// The buffers of fetching and processing a chunk of symbols, which are reused for every chunk,
+/// instead of being allocated anew for every chunk of every iteration
+#[derive(Debug, Default)]
+struct ChunkScratch {
+    /// The closing prices of the chunk's symbols, which the processing drains
+    symbols_closes: HashMap<Symbol, Vec<f64>>,
+    /// The optional columns of the row that is being output
+    columns: String,
+}
+
+/// Fetches and processes chunks of symbols, one at a time, with the same buffers
+#[derive(Debug, Default)]
+struct Fetcher {
+    scratch: ChunkScratch,
+}
+
+impl Fetcher {
+    /// Fetches and processes a chunk of `symbols`, the way that a fetch worker handles
+    /// a [`FetchActorMsg::QuoteRequestsMsg`], without spawning a processor actor
+    async fn fetch_and_process(
+        &mut self,
+        symbols: Vec<Symbol>,
+        from: OffsetDateTime,
+        handles: PipelineHandles,
+        iteration: Iteration,
+        chunk_id: usize,
+        window_size: usize,
+    ) -> Result<MsgResponseType, ActorError> {
+        let fetch_span = tracing::debug_span!("fetch", iteration = iteration.id, chunk = chunk_id);
+        let process_span =
+            tracing::debug_span!("process", iteration = iteration.id, chunk = chunk_id);
+
+        let quality = FetchActor::fetch_symbols_closes(
+            symbols,
+            from,
+            iteration.to,
+            &handles,
+            &mut self.scratch.symbols_closes,
+        )
+        .instrument(fetch_span)
+        .await?;
+        ProcessorActor::handle_symbols_closes_msg(
+            &mut self.scratch,
+            quality,
+            from,
+            handles,
+            iteration,
+            chunk_id,
+            window_size,
+        )
+        .instrument(process_span)
+        .await
+    }
+}
+
+/// Fetches and processes the `symbols` of an `iteration` as a stream, at most `concurrency`
+/// of them at a time, which is the alternative to handing chunks of symbols over to a [`FetchPool`]
+///
+/// Every symbol is a chunk of its own, so the `iteration` must have a chunk per symbol.
+/// The next symbol is started as soon as any one has finished, so a slow symbol doesn't hold up
+/// the other symbols of its chunk, the way that it does in a fetch worker, which smooths out
+/// the concurrency and shortens the tail latency.
+///
+/// The symbols in flight also share the pipeline's limit of simultaneous data provider requests,
+/// if any, with the other fetchers; see [`PipelineSettings::concurrency`].
+///
+/// A symbol that hasn't been fetched and processed within `handler_timeout` is given up on,
+/// and the timeout is reported.
+///
+/// # Panics
+///
+/// Panics if `concurrency` is zero.
+pub async fn fetch_stream(
+    symbols: Vec<Symbol>,
+    from: OffsetDateTime,
+    handles: &PipelineHandles,
+    iteration: Iteration,
+    window_size: usize,
+    concurrency: usize,
+    handler_timeout: Duration,
+) {
+    assert!(
+        concurrency > 0,
+        "The stream's concurrency must be positive."
+    );
+
+    // a pool of fetchers, so that the symbols in flight reuse the buffers of the finished ones
+    let fetchers = StdMutex::new(Vec::<Fetcher>::with_capacity(concurrency));
+    let fetchers = &fetchers;
+
+    futures::stream::iter(symbols.into_iter().enumerate())
+        .map(|(chunk_id, symbol)| {
+            let handles = handles.clone();
+            async move {
+                let mut fetcher = fetchers
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .pop()
+                    .unwrap_or_default();
+                let dead_letter_handle = handles.dead_letter_handle.clone();
+                let handler = fetcher.fetch_and_process(
+                    vec![symbol],
+                    from,
+                    handles,
+                    iteration,
+                    chunk_id,
+                    window_size,
+                );
+                if let Err(err) = with_timeout(handler_timeout, handler).await {
+                    report_error("FetchStream", &err, Some(&dead_letter_handle)).await;
+                }
+                fetchers
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(fetcher);
+            }
+        })
+        .buffer_unordered(concurrency)
+        .for_each(|()| std::future::ready(()))
+        .await;
+}
+
+/// The work queue of a [`FetchPool`], which another pipeline puts its chunks of symbols in,
+/// so that the pipelines share the workers
+///
+/// The queue doesn't keep the workers running; the pool must only be shut down after the pipelines
+/// that share it have stopped putting messages in it.
+#[derive(Clone)]
+pub struct FetchQueue {
+    sender: mpsc::Sender<FetchActorMsg>,
+}
+
+impl FetchQueue {
+    /// Put a message in the work queue; see [`FetchPool::send`]
+    pub async fn send(&self, msg: FetchActorMsg) -> Result<MsgResponseType, FetchMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//  [`WriterActorMsg`], [`WriterActor`], [`WriterActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// A single row of calculated performance indicators for a symbol
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PerformanceIndicatorsRow {
+    #[schema(value_type = String)]
+    pub symbol: Symbol,
+    pub last_price: f64,
+    pub pct_change: f64,
+    pub period_min: f64,
+    pub period_max: f64,
+    pub sma: f64,
+    /// The asset class of the symbol; see [`crate::asset_classes`]
+    #[serde(default)]
+    pub asset_class: AssetClass,
+}
+
+impl PerformanceIndicatorsRow {
+    /// Calculates the performance indicators of the `symbol` over its `closes`,
+    /// with an SMA of `window_size` closes
+    ///
+    /// The row is annotated as an equity; see [`AssetClasses::tag`].
+    ///
+    /// # Returns
+    /// The row of indicators, or `None` if there are no closes
+    pub async fn calculate(
+        symbol: impl Into<Symbol>,
+        closes: &[f64],
+        window_size: usize,
+    ) -> Option<Self> {
+        let &last_price = closes.last()?;
+
+        let min = MinPrice {};
+        let max = MaxPrice {};
+        let price_diff = PriceDifference {};
+        let n_window_sma = WindowedSMA { window_size };
+
+        let (_, pct_change) = price_diff.calculate(closes).await.unwrap_or((0., 0.));
+        let pct_change = pct_change * 100.0;
+        let period_min: f64 = min.calculate(closes).await.unwrap_or_default();
+        let period_max: f64 = max.calculate(closes).await.unwrap_or_default();
+        let sma = n_window_sma.calculate(closes).await.unwrap_or(vec![]);
+        let sma = *sma.last().unwrap_or(&0.0);
+
+        Some(Self {
+            symbol: symbol.into(),
+            last_price,
+            pct_change,
+            period_min,
+            period_max,
+            sma,
+            asset_class: AssetClass::default(),
+        })
+    }
+
+    /// Zeroes the performance indicators that aren't among the `indicators`,
+    /// which are named as in [`INDICATORS`]
+    pub fn retain_indicators(&mut self, indicators: &[String]) {
+        let fields = [
+            (INDICATORS[0], &mut self.last_price),
+            (INDICATORS[1], &mut self.pct_change),
+            (INDICATORS[2], &mut self.period_min),
+            (INDICATORS[3], &mut self.period_max),
+            (INDICATORS[4], &mut self.sma),
+        ];
+        for (name, value) in fields {
+            if !indicators.iter().any(|indicator| indicator == name) {
+                *value = 0.0;
+            }
+        }
+    }
+
+    /// A row of the `symbol` for the tests, at the `last_price`, after a change of `pct_change`,
+    /// in a period between 90 and 110, whose SMA is 100
+    #[cfg(test)]
+    pub(crate) fn fixture(symbol: &str, last_price: f64, pct_change: f64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            last_price,
+            pct_change,
+            period_min: 90.0,
+            period_max: 110.0,
+            sma: 100.0,
+            asset_class: AssetClass::default(),
+        }
+    }
+}
+
+impl FromStr for PerformanceIndicatorsRow {
+    type Err = anyhow::Error;
+
+    /// Parses a row in the format of its [`Display`] implementation,
+    /// which is also how the rows are written to the output file, after the period start
+    ///
+    /// The format doesn't have the asset class, so the row is annotated as an equity.
+    fn from_str(s: &str) -> Result<Self> {
+        let fields: Vec<&str> = s.trim().split(',').collect();
+        let [symbol, last_price, pct_change, period_min, period_max, sma] = fields[..] else {
+            anyhow::bail!("Expected 6 comma-separated fields in \"{}\".", s.trim());
+        };
+        let number = |field: &str| {
+            field
+                .trim_start_matches('$')
+                .trim_end_matches('%')
+                .parse::<f64>()
+                .with_context(|| format!("Invalid number \"{}\".", field))
+        };
+
+        Ok(Self {
+            symbol: symbol.into(),
+            last_price: number(last_price)?,
+            pct_change: number(pct_change)?,
+            period_min: number(period_min)?,
+            period_max: number(period_max)?,
+            sma: number(sma)?,
+            asset_class: AssetClass::default(),
+        })
+    }
+}
+
+impl Display for PerformanceIndicatorsRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
+            self.symbol,
+            self.last_price,
+            self.pct_change,
+            self.period_min,
+            self.period_max,
+            self.sma
+        )
+    }
+}
+
+/// The [`PerformanceIndicatorsRowsMsg`] message
+///
+/// It contains a `from` date and time field,
+/// and calculated performance indicators for a **chunk** of symbols.
+///
+/// It also contains information about the chunk's iteration of the main loop,
+/// and the chunk's index in the iteration, for correlating the chunk's messages in the traces.
+///
+/// There is no expected response.
+///
+/// We could have an oneshot channel for sending the response back in general case.
+/// We simply don't need it in our specific (custom) case.
+#[derive(Clone)]
+pub struct PerformanceIndicatorsRowsMsg {
+    from: String,
+    rows: Vec<PerformanceIndicatorsRow>,
+    iteration: Iteration,
+    chunk_id: usize,
+}
+
+/// The [`WriterActorMsg`] enumeration
+///
+/// Supports four message types:
+/// - [`PerformanceIndicatorsChunk`],
+/// - [`Reconfigure`],
+/// - [`Ping`],
+/// - [`Shutdown`],
+///
+/// Only [`Ping`] and [`Shutdown`] expect a response, which is sent back through the provided `sender`.
+pub enum WriterActorMsg {
+    /// Wraps a [`PerformanceIndicatorsRowsMsg`] message
+    PerformanceIndicatorsChunk(PerformanceIndicatorsRowsMsg),
+    /// New runtime settings, whose flush interval applies to the chunks that follow it
+    Reconfigure(Reconfiguration),
+    /// A heartbeat from the [`WatchdogActor`], which is answered through the provided `sender`
+    Ping { sender: oneshot::Sender<()> },
+    /// A request to flush and close the output file, and to stop
+    ///
+    /// It is acknowledged through the provided `sender` only after the file has been closed,
+    /// with the result of the final flush, so that no data loss goes unnoticed.
+    Shutdown {
+        sender: oneshot::Sender<Result<MsgResponseType, ActorError>>,
+    },
+}
+
+/// Actor for writing calculated performance indicators for fetched stock data into output files,
+/// one per selected [`OutputFormat`], through its [`Sink`]s
+///
+/// The files are opened, written, flushed and closed on the runtime's blocking threads,
+/// through [`runtime::spawn_blocking`], so that a large write or flush doesn't block the thread
+/// that runs the actor, nor the other tasks on it. The sinks are handed over to the blocking thread,
+/// and back, for every chunk.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`WriterActorHandle`], which is public.
+struct WriterActor {
+    receiver: mpsc::Receiver<WriterActorMsg>,
+    /// The formats of the output files, a sink of each is opened on start
+    formats: Vec<OutputFormat>,
+    /// The stem of the output files' paths, if they aren't at the default paths
+    stem: Option<String>,
+    sinks: Vec<Box<dyn Sink>>,
+    /// Whether to append to the output files instead of truncating them, which is the case after a restart
+    append: bool,
+    /// Whether to only count the rows, and write nothing
+    dry_run: bool,
+    /// The output file is flushed after a chunk only if this much time has passed since the last flush
+    flush_interval: Duration,
+    last_flush: Instant,
+    /// For reporting errors
+    dead_letter_handle: Option<DeadLetterActorHandle>,
+    /// Shared with the [`WriterActorHandle`]
+    interceptors: Interceptors<WriterActorMsg>,
+}
+
+impl Actor<MsgResponseType> for WriterActor {
+    type Msg = WriterActorMsg;
+
+    /// Create a new [`WriterActor`]
+    fn new(receiver: mpsc::Receiver<WriterActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            formats: vec![OutputFormat::Csv],
+            stem: None,
+            sinks: Vec::new(),
+            append: false,
+            dry_run: false,
+            flush_interval: Duration::from_secs(FLUSH_INTERVAL_SECS),
+            last_flush: Instant::now(),
+            dead_letter_handle: None,
+            interceptors: Interceptors::default(),
+        }
+    }
+
+    /// Start the [`WriterActor`]
+    ///
+    /// This function is meant to be used directly in the [`WriterActorHandle`].
+    async fn start(&mut self) -> Result<MsgResponseType, ActorError> {
+        let formats = self.formats.clone();
+        let stem = self.stem.clone();
+        let (append, dry_run) = (self.append, self.dry_run);
+        self.sinks = runtime::spawn_blocking(move || {
+            Self::open_sinks(&formats, stem.as_deref(), append, dry_run)
+        })
+        .await??;
+        #[cfg(debug_assertions)]
+        tracing::debug!("The output formats are {:?}.", self.formats);
+        tracing::debug!("WriterActor is started.");
+
+        self.run().await?;
+
+        Ok(())
+    }
+
+    /// Run the [`WriterActor`]
+    ///
+    /// This function is meant to be used indirectly - only through the [`WriterActor::start`] function
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("WriterActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("WriterActor", &err, self.dead_letter_handle.as_ref()).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop the [`WriterActor`]
+    ///
+    /// Flushes and closes the output files.
+    ///
+    /// This function is meant to be called in the [`WriterActor`]'s destructor, in case the actor
+    /// wasn't shut down explicitly through [`WriterActorMsg::Shutdown`], in which case the files
+    /// are closed on the current thread, as the destructor can't wait for a blocking thread.
+    /// Only the first call has an effect.
+    fn stop(&mut self) {
+        if !self.sinks.is_empty() {
+            match self.flush() {
+                Ok(()) => tracing::debug!("WriterActor is flushed and properly stopped."),
+                Err(err) => tracing::error!("Failed to flush writer. Data loss :( {}", err),
+            }
+        }
+    }
+
+    /// Returns the [`Interceptors`] that are shared with the [`WriterActorHandle`]
+    fn interceptors(&self) -> Option<&Interceptors<WriterActorMsg>> {
+        Some(&self.interceptors)
+    }
+
+    /// The [`WriterActorMsg`] message handler for the [`WriterActor`] actor
+    async fn handle(&mut self, msg: WriterActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            WriterActorMsg::PerformanceIndicatorsChunk(msg) => {
+                let span = tracing::debug_span!(
+                    "write",
+                    iteration = msg.iteration.id,
+                    chunk = msg.chunk_id
+                );
+                self.handle_perf_ind_chunk(msg).instrument(span).await?;
+            }
+            WriterActorMsg::Reconfigure(reconfiguration) => {
+                tracing::debug!(
+                    "WriterActor now flushes every {:?}.",
+                    reconfiguration.flush_interval
+                );
+                self.flush_interval = reconfiguration.flush_interval;
+            }
+            WriterActorMsg::Ping { sender } => {
+                sender
+                    .send(())
+                    .map_err(|_| ActorError::Reply("the heartbeat"))?;
+            }
+            WriterActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                let sinks = std::mem::take(&mut self.sinks);
+                let flushed = runtime::spawn_blocking(move || Self::close_sinks(sinks))
+                    .await
+                    .unwrap_or_else(|err| Err(err.into()));
+                let _ = sender.send(flushed);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl WriterActor {
+    /// Opens a sink of each of the `formats`, at the paths of the `stem`, if provided,
+    /// which only counts the rows in a `dry_run`
+    ///
+    /// It blocks, so it's meant to be run on a blocking thread.
+    fn open_sinks(
+        formats: &[OutputFormat],
+        stem: Option<&str>,
+        append: bool,
+        dry_run: bool,
+    ) -> std::io::Result<Vec<Box<dyn Sink>>> {
+        formats
+            .iter()
+            .map(|format| {
+                Ok(match (stem, dry_run) {
+                    (None, true) => sinks::dry_run(format),
+                    (None, false) => sinks::open(format, append)?,
+                    (Some(stem), true) => sinks::dry_run_at(format, stem),
+                    (Some(stem), false) => sinks::open_at(format, stem, append)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Flushes and closes the output files, if they are still open
+    ///
+    /// It blocks; see [`WriterActor::stop`].
+    fn flush(&mut self) -> Result<MsgResponseType, ActorError> {
+        Self::close_sinks(std::mem::take(&mut self.sinks))
+    }
+
+    /// Flushes and closes the `sinks`
+    ///
+    /// All of them are closed, even if some fail, and the last failure is returned.
+    ///
+    /// It blocks, so it's meant to be run on a blocking thread.
+    fn close_sinks(sinks: Vec<Box<dyn Sink>>) -> Result<MsgResponseType, ActorError> {
+        let mut result = Ok(());
+        for sink in sinks {
+            if let Err(err) = sink.close() {
+                result = Err(err);
+            }
+        }
+
+        Ok(result?)
+    }
+
+    /// Handle a [`WriterActorMsg::PerformanceIndicatorsChunk`] message,
+    /// which wraps a [`PerformanceIndicatorsRowsMsg`] message
+    ///
+    /// Writes results to the sinks, on a blocking thread.
+    ///
+    /// The sinks are flushed if the flush interval has passed since the last flush.
+    /// If the blocking thread panics, the sinks are lost, and the following chunks aren't written.
+    async fn handle_perf_ind_chunk(
+        &mut self,
+        msg: PerformanceIndicatorsRowsMsg,
+    ) -> Result<MsgResponseType, ActorError> {
+        let from = msg.from;
+        let rows = msg.rows;
+        let flush = self.last_flush.elapsed() >= self.flush_interval;
+
+        let mut sinks = std::mem::take(&mut self.sinks);
+        let (sinks, result) = runtime::spawn_blocking(move || {
+            let result = Self::write_chunk(&mut sinks, &from, &rows, flush);
+            (sinks, result)
+        })
+        .await?;
+        self.sinks = sinks;
+        result?;
+
+        if flush {
+            self.last_flush = Instant::now();
+        }
+
+        Ok(())
+    }
+
+    /// Writes the `rows` of a chunk to the `sinks`, and flushes them if `flush` is set
+    ///
+    /// It blocks, so it's meant to be run on a blocking thread.
+    fn write_chunk(
+        sinks: &mut [Box<dyn Sink>],
+        from: &str,
+        rows: &[PerformanceIndicatorsRow],
+        flush: bool,
+    ) -> std::io::Result<()> {
+        for sink in sinks.iter_mut() {
+            sink.write(from, rows)?;
+        }
+        if flush {
+            for sink in sinks.iter_mut() {
+                sink.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for WriterActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`WriterActor`]
+///
+/// Only the handle is public; the [`WriterActor`] isn't.
+///
+/// We can only create [`WriterActor`]s through the [`WriterActorHandle`].
+///
+/// It contains the `sender` field, which represents
+/// a sender of the [`WriterActorMsg`] in an MPSC channel.
+///
+/// The handle is the sender, and the actor is the receiver
+/// of a message in the channel.
+///
+/// We only create a single [`WriterActor`] instance in a [`WriterActorHandle`].
+///
+/// The actor is supervised: if it crashes, it is restarted, and it appends
+/// to the output file.
+#[derive(Clone)]
+pub struct WriterActorHandle {
+    mailbox: Mailbox<WriterActorMsg>,
+    interceptors: Interceptors<WriterActorMsg>,
+}
+
+impl WriterActorHandle {
+    /// Create a new [`WriterActorHandle`] whose actor's mailbox can hold `capacity` messages,
+    /// and whose actor reports the errors that it runs into to the [`DeadLetterActor`]
+    ///
+    /// The actor writes to a file of each of the `formats`; with no formats, it writes nothing.
+    /// In a `dry_run`, it only counts the rows, and prints a summary of what would have been written
+    /// to each file when it stops.
+    ///
+    /// Otherwise, it is the same as [`WriterActorHandle::new`].
+    pub fn with_dead_letters(
+        nticks: usize,
+        capacity: usize,
+        formats: Vec<OutputFormat>,
+        dry_run: bool,
+        dead_letter_handle: DeadLetterActorHandle,
+    ) -> Self {
+        Self::supervised(nticks, capacity, formats, None, dry_run, dead_letter_handle)
+    }
+
+    /// Create a new [`WriterActorHandle`] whose actor writes to the files at the path of the `stem`,
+    /// with the extension of each of the `formats`, e.g., `intraday.csv`, instead of the default paths
+    ///
+    /// Otherwise, it is the same as [`WriterActorHandle::with_dead_letters`].
+    pub fn at_stem(
+        nticks: usize,
+        capacity: usize,
+        formats: Vec<OutputFormat>,
+        stem: &str,
+        dry_run: bool,
+        dead_letter_handle: DeadLetterActorHandle,
+    ) -> Self {
+        Self::supervised(
+            nticks,
+            capacity,
+            formats,
+            Some(stem.to_string()),
+            dry_run,
+            dead_letter_handle,
+        )
+    }
+
+    /// Spawns the supervised actor, which writes to the files at the path of the `stem`, if any
+    fn supervised(
+        nticks: usize,
+        capacity: usize,
+        formats: Vec<OutputFormat>,
+        stem: Option<String>,
+        dry_run: bool,
+        dead_letter_handle: DeadLetterActorHandle,
+    ) -> Self {
+        let (mailbox, receiver) = Mailbox::new(capacity);
+        let interceptors = Interceptors::default();
+        let actor_interceptors = interceptors.clone();
+        supervise(
+            "WriterActor",
+            &mailbox,
+            receiver,
+            move |receiver, restarted| {
+                let mut actor = WriterActor::new(receiver, nticks);
+                actor.formats = formats.clone();
+                actor.stem = stem.clone();
+                actor.append = restarted;
+                actor.dry_run = dry_run;
+                actor.dead_letter_handle = Some(dead_letter_handle.clone());
+                actor.interceptors = actor_interceptors.clone();
+                runtime::spawn(async move { actor.start().await })
+            },
+        );
+
+        Self {
+            mailbox,
+            interceptors,
+        }
+    }
+
+    /// Returns the number of messages that are waiting in the actor's mailbox
+    pub fn queue_depth(&self) -> usize {
+        self.mailbox.queue_depth()
+    }
+
+    /// Returns the number of sends that have found the actor's mailbox full since start
+    ///
+    /// Regular sends then wait for a free slot, while [`Self::try_send`] gives up.
+    pub fn queue_full_count(&self) -> u64 {
+        self.mailbox.queue_full_count()
+    }
+
+    /// Returns the number of [`Self::send_timeout`] sends that have timed out since start
+    pub fn send_timeout_count(&self) -> u64 {
+        self.mailbox.send_timeout_count()
+    }
+
+    /// Send a message to the [`WriterActor`] without waiting
+    ///
+    /// # Errors
+    /// - If the actor's mailbox is full, in which case the message is given back
+    /// - If the [`WriterActor`] isn't running
+    pub fn try_send(
+        &self,
+        msg: WriterActorMsg,
+    ) -> Result<MsgResponseType, TrySendError<WriterActorMsg>> {
+        self.mailbox.try_send(self.interceptors.on_send(msg))
+    }
+
+    /// Send a message to the [`WriterActor`], waiting at most `timeout` for a free slot
+    /// in its mailbox
+    ///
+    /// # Errors
+    /// - If the actor's mailbox is still full after `timeout`, in which case the message is given back
+    /// - If the [`WriterActor`] isn't running
+    pub async fn send_timeout(
+        &self,
+        msg: WriterActorMsg,
+        timeout: Duration,
+    ) -> Result<MsgResponseType, SendTimeoutError<WriterActorMsg>> {
+        self.mailbox
+            .send_timeout(self.interceptors.on_send(msg), timeout)
+            .await
+    }
+
+    /// Adds an [`Interceptor`] to the [`WriterActor`]'s messages
+    ///
+    /// It applies to the messages that are sent through any clone of the handle from now on,
+    /// and also to a restarted actor.
+    pub fn add_interceptor(&self, interceptor: impl Interceptor<WriterActorMsg> + 'static) {
+        self.interceptors.add(interceptor);
+    }
+
+    /// Checks that the [`WriterActor`] is alive, by waiting for it to answer a heartbeat
+    ///
+    /// # Errors
+    /// - If the [`WriterActor`] isn't running
+    pub async fn ping(&self) -> Result<()> {
+        self.call(|sender| WriterActorMsg::Ping { sender })
+            .await
+            .context("Couldn't call the WriterActor.")
+    }
+
+    /// Stops the [`WriterActor`], after it has flushed and closed the output file
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`WriterActor`] isn't running
+    /// - If the final flush of the output file failed
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| WriterActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the WriterActor.")?
+            .context("The WriterActor couldn't flush the output file.")
+    }
+}
+
+impl ActorHandle<MsgResponseType, WriterMsgErrorType> for WriterActorHandle {
+    type Msg = WriterActorMsg;
+
+    /// Create a new [`WriterActorHandle`]
+    ///
+    /// This function creates a single [`WriterActor`] instance,
+    /// and a MPSC channel for communicating with the actor,
+    /// which can hold [`WRITER_MAILBOX_CAPACITY`] messages.
+    ///
+    /// It also starts (runs) the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let (mailbox, receiver) = Mailbox::new(WRITER_MAILBOX_CAPACITY);
+        let interceptors = Interceptors::default();
+        let actor_interceptors = interceptors.clone();
+        supervise(
+            "WriterActor",
+            &mailbox,
+            receiver,
+            move |receiver, restarted| {
+                let mut actor = WriterActor::new(receiver, nticks);
+                actor.append = restarted;
+                actor.interceptors = actor_interceptors.clone();
+                runtime::spawn(async move { actor.start().await })
+            },
+        );
+
+        Self {
+            mailbox,
+            interceptors,
+        }
+    }
+
+    /// Send a message to an [`WriterActor`] instance through the [`WriterActorHandle`]
+    async fn send(&self, msg: WriterActorMsg) -> Result<MsgResponseType, WriterMsgErrorType> {
+        self.mailbox.send(self.interceptors.on_send(msg)).await
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//    [`CollectionActorMsg`], [`CollectionActor`], [`CollectionActorHandle`],
+//                        [`Batch`], [`TailResponse`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`CollectionActorMsg`] enumeration
+///
+/// Supports twenty message types:
+/// - [`TailRequest`],
+/// - [`SymbolTailRequest`],
+/// - [`RangeRequest`],
+/// - [`SymbolLatestRequest`],
+/// - [`LastFetchedRequest`],
+/// - [`QualityRequest`],
+/// - [`RiskRequest`],
+/// - [`MoversRequest`],
+/// - [`SectorsRequest`],
+/// - [`MemoryRequest`],
+/// - [`Ping`],
+/// - [`PerformanceIndicatorsChunk`],
+/// - [`QualityReports`],
+/// - [`Returns`],
+/// - [`WriteMovers`],
+/// - [`TagSectors`],
+/// - [`Restore`],
+/// - [`PurgeSymbol`],
+/// - [`Reconfigure`],
+/// - [`Shutdown`],
+///
+/// The requests and [`Shutdown`] expect a response, which is sent back through the provided `sender`.
+///
+/// The requests are sent through the actor's priority lane, so they are answered promptly
+/// even when a flood of chunks is waiting in the regular lane; see [`CollectionActorMsg::is_priority`].
+///
+/// We could have an oneshot channel for sending the response back in general case.
+/// It could be used for every message type.
+/// We simply don't need it in our specific (custom) case.
+pub enum CollectionActorMsg {
+    /// Wraps a [`PerformanceIndicatorsRowsMsg`] message
+    PerformanceIndicatorsChunk(PerformanceIndicatorsRowsMsg),
+    /// A request from web server for the last `n` batches of processed data
+    ///
+    /// If `if_none_match` is the current version of the buffer, the batches are not sent back,
+    /// because the web server's client already has them.
+    ///
+    /// The `request_id` is the web request's correlation ID, which is recorded
+    /// in the actor's tracing span for the request.
+    TailRequest {
+        sender: oneshot::Sender<VersionedTail>,
+        n: usize,
+        if_none_match: Option<u64>,
+        request_id: Option<String>,
+    },
+    /// A request from web server for the last `n` rows of processed data of a single symbol
+    SymbolTailRequest {
+        sender: oneshot::Sender<Batch>,
+        n: usize,
+        symbol: String,
+        request_id: Option<String>,
+    },
+    /// A request from web server for the batches that were completed between `start` and `end`,
+    /// both inclusive
+    RangeRequest {
+        sender: oneshot::Sender<RangeResponse>,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        request_id: Option<String>,
+    },
+    /// A request for the newest row of a single symbol, which is `None` if there is no data for it
+    SymbolLatestRequest {
+        sender: oneshot::Sender<Option<PerformanceIndicatorsRow>>,
+        symbol: String,
+        request_id: Option<String>,
+    },
+    /// A request for the end of the period of the newest fetched row of every symbol
+    ///
+    /// It goes through the regular lane, so the chunks that were sent before it are counted.
+    LastFetchedRequest {
+        sender: oneshot::Sender<HashMap<Symbol, OffsetDateTime>>,
+    },
+    /// A request from web server for the data-quality reports of the symbols with issues
+    QualityRequest {
+        sender: oneshot::Sender<Vec<QualityReport>>,
+        request_id: Option<String>,
+    },
+    /// The data-quality reports of a chunk's symbols, which replace their previous ones
+    QualityReports(Vec<QualityReport>),
+    /// A request from web server for the historical VaR and CVaR of every symbol, and of
+    /// the portfolio with the given `weights`, i.e., its holdings' market values, by symbol,
+    /// at the `confidence` level
+    RiskRequest {
+        sender: oneshot::Sender<RiskReport>,
+        confidence: f64,
+        weights: HashMap<String, f64>,
+        request_id: Option<String>,
+    },
+    /// The daily returns of a chunk's symbols, which replace their previous ones
+    Returns(Vec<(Symbol, Vec<f64>)>),
+    /// A request from web server for the top movers of the newest completed batch,
+    /// which are `None` if no batch has been completed yet
+    MoversRequest {
+        sender: oneshot::Sender<Option<Movers>>,
+        request_id: Option<String>,
+    },
+    /// The path of the CSV file that the top movers of every completed batch are written to,
+    /// which is created right away, truncating an existing one
+    WriteMovers(PathBuf),
+    /// A request from web server for the aggregates of the sectors' rows in the newest completed batch
+    SectorsRequest {
+        sender: oneshot::Sender<Vec<SectorAggregate>>,
+        request_id: Option<String>,
+    },
+    /// The sectors whose rows are aggregated in every completed batch
+    TagSectors(Vec<Sector>),
+    /// A request from web server for the size of the buffer of the newest batches
+    MemoryRequest {
+        sender: oneshot::Sender<BufferMemory>,
+        request_id: Option<String>,
+    },
+    /// The batches that were completed before a restart, oldest first, with their completion times,
+    /// which are put in the buffer as they are, without being published again
+    Restore(Vec<(OffsetDateTime, Batch)>),
+    /// A heartbeat from the [`WatchdogActor`], which is answered through the provided `sender`
+    Ping { sender: oneshot::Sender<()> },
+    /// A command to remove all rows of a symbol from the buffer and from the batch
+    /// that is being assembled, after the symbol has been removed at runtime
+    PurgeSymbol(String),
+    /// New runtime settings, whose indicator set applies to the chunks that follow it;
+    /// the indicators that aren't in the set are zeroed
+    Reconfigure(Reconfiguration),
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+impl CollectionActorMsg {
+    /// Returns whether the message goes through the actor's priority lane
+    ///
+    /// These are the requests from the web app and from the gRPC API, which someone is waiting on,
+    /// and which are cheap to handle, and the heartbeats, which mustn't time out during a burst.
+    pub fn is_priority(&self) -> bool {
+        matches!(
+            self,
+            Self::TailRequest { .. }
+                | Self::SymbolTailRequest { .. }
+                | Self::RangeRequest { .. }
+                | Self::SymbolLatestRequest { .. }
+                | Self::QualityRequest { .. }
+                | Self::RiskRequest { .. }
+                | Self::MoversRequest { .. }
+                | Self::SectorsRequest { .. }
+                | Self::MemoryRequest { .. }
+                | Self::Ping { .. }
+        )
+    }
+}
+
+/// A response to [`CollectionActorMsg::TailRequest`]
+///
+/// The `version` identifies the contents of the buffer; it changes whenever the buffer changes,
+/// so the web server can use it as an `ETag`.
+///
+/// The `tail` is `None` if the requester already has the current version.
+pub struct VersionedTail {
+    pub version: u64,
+    pub tail: Option<TailResponse>,
+}
+
+/// A batch that is being assembled out of the chunks of an iteration
+struct PendingBatch {
+    rows: Batch,
+    /// The moment at which the iteration, and with it its first chunk, started
+    started: Instant,
+    /// The indices of the chunks that have been received
+    chunk_ids: HashSet<usize>,
+    /// The total number of chunks of the iteration
+    num_chunks: usize,
+}
+
+impl PendingBatch {
+    /// Returns the indices of the chunks that haven't been received, in order
+    fn missing_chunks(&self) -> Vec<usize> {
+        (0..self.num_chunks)
+            .filter(|chunk_id| !self.chunk_ids.contains(chunk_id))
+            .collect()
+    }
+}
+
+/// Actor for collecting calculated performance indicators for fetched stock data into a buffer
+///
+/// It is used for storing the performance data in a buffer of capacity `N`,
+/// where `N` is the number of main loop iterations that occur at a fixed time interval.
+///
+/// This data can then be fetched by the web server.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`CollectionActorHandle`], which is public.
+struct CollectionActor {
+    receiver: mpsc::Receiver<CollectionActorMsg>,
+    /// The receiving side of the priority lane, which is always drained first
+    priority_receiver: Option<mpsc::Receiver<CollectionActorMsg>>,
+    /// A ring buffer of the newest batches, which never holds more than its capacity,
+    /// [`crate::constants::TAIL_BUFFER_SIZE`] batches by default,
+    /// and which spills the oldest ones to disk when they exceed its memory budget
+    ///
+    /// Every batch is stored with the moment of its completion, for time-range requests.
+    ///
+    /// It's shared with the blocking threads that spill and read back its batches,
+    /// see [`CollectionActor::with_buffer`], one at a time, as the actor awaits them.
+    buffer: Arc<StdMutex<TailBuffer>>,
+    version: u64,
+    /// The batches that are being assembled, by their iterations' sequence numbers
+    pending: BTreeMap<u64, PendingBatch>,
+    /// The sequence number of the newest completed iteration; the chunks of it and of the older
+    /// iterations are out of order
+    last_completed: Option<u64>,
+    /// The initial capacity of a batch that is being assembled
+    batch_capacity: usize,
+    /// The newest row of every symbol, which is updated on every chunk,
+    /// so that single-symbol queries don't have to scan the buffered batches
+    latest: HashMap<Symbol, PerformanceIndicatorsRow>,
+    /// The end of the period of the newest fetched row of every symbol
+    last_fetched: HashMap<Symbol, OffsetDateTime>,
+    /// The newest data-quality report of every symbol
+    quality: BTreeMap<String, QualityReport>,
+    /// The daily returns of every symbol, as of its newest fetch, for the risk metrics
+    /// and the volatility ranking
+    returns: BTreeMap<Symbol, Vec<f64>>,
+    /// The top movers of the newest completed batch
+    movers: Option<Movers>,
+    /// The CSV file of the top movers; set by a [`CollectionActorMsg::WriteMovers`] message
+    movers_csv: Option<MoversCsv>,
+    /// The sectors whose rows are aggregated; set by a [`CollectionActorMsg::TagSectors`] message
+    sectors: Vec<Sector>,
+    /// The aggregates of the sectors' rows in the newest completed batch
+    sector_aggregates: Vec<SectorAggregate>,
+    /// The performance indicators that are served; set by a [`CollectionActorMsg::Reconfigure`] message
+    indicators: Vec<String>,
+    stats_handle: Option<StatsActorHandle>,
+    webhook_handle: Option<WebhookActorHandle>,
+    dead_letter_handle: Option<DeadLetterActorHandle>,
+    broker_handle: Option<BrokerActorHandle>,
+    /// Shared with the [`CollectionActorHandle`]
+    interceptors: Interceptors<CollectionActorMsg>,
+}
+
+impl Actor<MsgResponseType> for CollectionActor {
+    type Msg = CollectionActorMsg;
+
+    /// Create a new [`CollectionActor`], whose buffer has the default limits
+    fn new(receiver: mpsc::Receiver<CollectionActorMsg>, nticks: usize) -> Self {
+        Self::with_tail_limits(receiver, nticks, TailBufferLimits::default())
+    }
+
+    /// Start the [`CollectionActor`]
+    ///
+    /// This function is meant to be used directly in the [`CollectionActorHandle`].
+    async fn start(&mut self) -> Result<MsgResponseType, ActorError> {
+        #[cfg(debug_assertions)]
+        tracing::debug!("CollectionActor is starting...");
+
+        tracing::debug!("CollectionActor is started.");
+
+        self.run().await?;
+
+        Ok(())
+    }
+
+    /// Run the [`CollectionActor`]
+    ///
+    /// This function is meant to be used indirectly - only through the [`CollectionActor::start`] function
+    ///
+    /// A message from the priority lane is always taken before a message from the regular lane.
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("CollectionActor is running.");
+
+        loop {
+            let msg = match self.priority_receiver.as_mut() {
+                Some(priority_receiver) => tokio::select! {
+                    biased;
+                    Some(msg) = priority_receiver.recv() => Some(msg),
+                    msg = self.receiver.recv() => msg,
+                },
+                None => self.receiver.recv().await,
+            };
+            let Some(msg) = msg else {
+                break;
+            };
+
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("CollectionActor", &err, self.dead_letter_handle.as_ref()).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop the [`CollectionActor`]
+    ///
+    /// This function is meant to be called in the [`CollectionActor`]'s destructor.
+    fn stop(&mut self) {
+        tracing::debug!("CollectionActor is stopped.");
+    }
+
+    /// Returns the [`Interceptors`] that are shared with the [`CollectionActorHandle`]
+    fn interceptors(&self) -> Option<&Interceptors<CollectionActorMsg>> {
+        Some(&self.interceptors)
+    }
+
+    /// The [`CollectionActorMsg`] message handler for the [`CollectionActor`] actor
+    ///
+    /// It collects chunks from [`ProcessorActor`]s, assembles them in batches,
+    /// and then stores the batches in the buffer.
+    ///
+    /// It also receives requests from web server for the last n batches of
+    /// processed data.
+    async fn handle(&mut self, msg: CollectionActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            CollectionActorMsg::PerformanceIndicatorsChunk(msg) => {
+                let span = tracing::debug_span!(
+                    "collect",
+                    iteration = msg.iteration.id,
+                    chunk = msg.chunk_id
+                );
+                Self::handle_perf_ind_chunk(self, msg)
+                    .instrument(span)
+                    .await;
+            }
+            CollectionActorMsg::TailRequest {
+                sender,
+                n,
+                if_none_match,
+                request_id,
+            } => {
+                let span = tracing::debug_span!("tail_request", request_id, n);
+                Self::handle_tail_request(self, sender, n, if_none_match)
+                    .instrument(span)
+                    .await?;
+            }
+            CollectionActorMsg::SymbolTailRequest {
+                sender,
+                n,
+                symbol,
+                request_id,
+            } => {
+                let span = tracing::debug_span!("symbol_tail_request", request_id, n, symbol);
+                Self::handle_symbol_tail_request(self, sender, n, &symbol)
+                    .instrument(span)
+                    .await?;
+            }
+            CollectionActorMsg::RangeRequest {
+                sender,
+                start,
+                end,
+                request_id,
+            } => {
+                let span = tracing::debug_span!("range_request", request_id, %start, %end);
+                Self::handle_range_request(self, sender, start, end)
+                    .instrument(span)
+                    .await?;
+            }
+            CollectionActorMsg::SymbolLatestRequest {
+                sender,
+                symbol,
+                request_id,
+            } => {
+                let span = tracing::debug_span!("symbol_latest_request", request_id, symbol);
+                let _entered = span.enter();
+                Self::handle_symbol_latest_request(self, sender, &symbol)?;
+            }
+            CollectionActorMsg::LastFetchedRequest { sender } => {
+                sender
+                    .send(self.last_fetched.clone())
+                    .map_err(|_| ActorError::Reply("the symbols' last-fetched times"))?;
+            }
+            CollectionActorMsg::QualityRequest { sender, request_id } => {
+                let span = tracing::debug_span!("quality_request", request_id);
+                let _entered = span.enter();
+                let flagged = self
+                    .quality
+                    .values()
+                    .filter(|report| !report.is_ok())
+                    .cloned()
+                    .collect();
+                sender
+                    .send(flagged)
+                    .map_err(|_| ActorError::Reply("the data-quality reports"))?;
+            }
+            CollectionActorMsg::QualityReports(reports) => {
+                for report in reports {
+                    self.quality.insert(report.symbol.clone(), report);
+                }
+            }
+            CollectionActorMsg::RiskRequest {
+                sender,
+                confidence,
+                weights,
+                request_id,
+            } => {
+                let span = tracing::debug_span!("risk_request", request_id);
+                let _entered = span.enter();
+                sender
+                    .send(risk::report(&self.returns, &weights, confidence))
+                    .map_err(|_| ActorError::Reply("the risk metrics"))?;
+            }
+            CollectionActorMsg::Returns(returns) => {
+                self.returns.extend(returns);
+            }
+            CollectionActorMsg::MoversRequest { sender, request_id } => {
+                let span = tracing::debug_span!("movers_request", request_id);
+                let _entered = span.enter();
+                sender
+                    .send(self.movers.clone())
+                    .map_err(|_| ActorError::Reply("the top movers"))?;
+            }
+            CollectionActorMsg::WriteMovers(path) => {
+                let csv = runtime::spawn_blocking(move || MoversCsv::create(path)).await??;
+                self.movers_csv = Some(csv);
+            }
+            CollectionActorMsg::SectorsRequest { sender, request_id } => {
+                let span = tracing::debug_span!("sectors_request", request_id);
+                let _entered = span.enter();
+                sender
+                    .send(self.sector_aggregates.clone())
+                    .map_err(|_| ActorError::Reply("the sectors' aggregates"))?;
+            }
+            CollectionActorMsg::TagSectors(sectors) => {
+                self.sectors = sectors;
+            }
+            CollectionActorMsg::Restore(batches) => {
+                for (_, batch) in &batches {
+                    for row in batch {
+                        self.latest.insert(row.symbol.clone(), row.clone());
+                    }
+                }
+                self.with_buffer(true, move |buffer| {
+                    batches
+                        .into_iter()
+                        .try_for_each(|(completed_at, batch)| buffer.push(batch, completed_at))
+                })
+                .await??;
+                self.version += 1;
+            }
+            CollectionActorMsg::MemoryRequest { sender, request_id } => {
+                let span = tracing::debug_span!("memory_request", request_id);
+                let _entered = span.enter();
+                let buffer = lock_buffer(&self.buffer);
+                let memory = BufferMemory {
+                    batches: buffer.len(),
+                    spilled: buffer.spilled_len(),
+                    estimated_bytes: buffer.memory(),
+                    pending_batches: self.pending.len(),
+                    latest_rows: self.latest.len(),
+                };
+                sender
+                    .send(memory)
+                    .map_err(|_| ActorError::Reply("the size of the buffer"))?;
+            }
+            CollectionActorMsg::Ping { sender } => {
+                sender
+                    .send(())
+                    .map_err(|_| ActorError::Reply("the heartbeat"))?;
+            }
+            CollectionActorMsg::PurgeSymbol(symbol) => {
+                Self::handle_purge_symbol(self, &symbol).await?;
+            }
+            CollectionActorMsg::Reconfigure(reconfiguration) => {
+                tracing::debug!(
+                    "CollectionActor now serves the indicators {:?}.",
+                    reconfiguration.indicators
+                );
+                self.indicators = reconfiguration.indicators;
+            }
+            CollectionActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                if let Some(priority_receiver) = self.priority_receiver.as_mut() {
+                    priority_receiver.close();
+                }
+                tracing::debug!("CollectionActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CollectionActor {
+    /// Create a new [`CollectionActor`], whose buffer holds up to `tail_limits.size` batches,
+    /// and keeps up to `tail_limits.memory_budget_mb` MiB of them in memory
+    fn with_tail_limits(
+        receiver: mpsc::Receiver<CollectionActorMsg>,
+        nticks: usize,
+        tail_limits: TailBufferLimits,
+    ) -> Self {
+        Self {
+            receiver,
+            priority_receiver: None,
+            buffer: Arc::new(StdMutex::new(TailBuffer::new(
+                tail_limits.size,
+                tail_limits.memory_budget_mb << 20,
+            ))),
+            version: 0,
+            pending: BTreeMap::new(),
+            last_completed: None,
+            batch_capacity: nticks,
+            latest: HashMap::new(),
+            last_fetched: HashMap::new(),
+            quality: BTreeMap::new(),
+            returns: BTreeMap::new(),
+            movers: None,
+            movers_csv: None,
+            sectors: Vec::new(),
+            sector_aggregates: Vec::new(),
+            indicators: INDICATORS.iter().map(|i| i.to_string()).collect(),
+            stats_handle: None,
+            webhook_handle: None,
+            dead_letter_handle: None,
+            broker_handle: None,
+            interceptors: Interceptors::default(),
+        }
+    }
+
+    /// Handle a [`CollectionActorMsg::PerformanceIndicatorsChunk`] message,
+    /// which wraps a [`PerformanceIndicatorsRowsMsg`] message
+    ///
+    /// Assembles chunks into complete batches and stores them in buffer.
+    ///
+    /// This ensures that a batch cannot be partially read by the web server.
+    /// It can only be fully-read, when it's ready.
+    /// Synchronization in the Actor model is accomplished via message passing.
+    ///
+    /// Takes care of keeping only the fresh data in the buffer, so that its
+    /// size doesn't ever grow, which prevents memory leaks.
+    /// When the buffer is full, the oldest batch is evicted to make room for the new one.
+    ///
+    /// The newest row of every symbol in the chunk is also cached right away.
+    ///
+    /// The performance indicators that aren't served are zeroed first; see [`CollectionActorMsg::Reconfigure`].
+    ///
+    /// A symbol can occur in more than one chunk of an iteration, e.g., after the symbols
+    /// have been edited at runtime, so a complete batch is deduplicated by symbol, keeping
+    /// the latest row of every symbol; see [`dedup_by_symbol`].
+    ///
+    /// The chunks are assembled by their iterations' sequence numbers, so the chunks of
+    /// overlapping iterations aren't mixed. When an iteration is completed, the older ones that
+    /// are still being assembled are missing chunks; they are abandoned, and the missing chunks
+    /// are logged. Duplicate chunks, and chunks of the completed or abandoned iterations,
+    /// which are out of order, are logged and dropped.
+    ///
+    /// A completed iteration's execution time, from its start until its batch is complete,
+    /// is reported once, instead of for every chunk.
+    ///
+    /// The *from* field is discarded.
+    ///
+    /// This message comes from a processing actor.
+    async fn handle_perf_ind_chunk(
+        &mut self,
+        msg: PerformanceIndicatorsRowsMsg,
+    ) -> MsgResponseType {
+        let iteration = msg.iteration;
+        let chunk_id = msg.chunk_id;
+
+        if self.last_completed.is_some_and(|last| iteration.id <= last) {
+            tracing::warn!(
+                "Dropped an out-of-order chunk {} of the iteration {}, which has already been \
+                 completed or abandoned.",
+                chunk_id,
+                iteration.id
+            );
+            return;
+        }
+
+        let batch_capacity = self.batch_capacity;
+        let buffer = &self.buffer;
+        let pending = self.pending.entry(iteration.id).or_insert_with(|| {
+            // the batch is assembled in the allocation of an evicted one, if there is one
+            let mut rows = lock_buffer(buffer).take_spare().unwrap_or_default();
+            rows.reserve(batch_capacity);
+            PendingBatch {
+                rows,
+                started: iteration.start,
+                chunk_ids: HashSet::with_capacity(iteration.num_chunks),
+                num_chunks: iteration.num_chunks,
+            }
+        });
+        if chunk_id >= pending.num_chunks || !pending.chunk_ids.insert(chunk_id) {
+            tracing::warn!(
+                "Dropped a duplicate or invalid chunk {} of the iteration {}.",
+                chunk_id,
+                iteration.id
+            );
+            return;
+        }
+
+        let mut rows = msg.rows;
+        for row in rows.iter_mut() {
+            row.retain_indicators(&self.indicators);
+        }
+        for row in &rows {
+            self.latest.insert(row.symbol.clone(), row.clone());
+            // the preloaded rows, which come before the main loop's iterations, weren't fetched
+            if iteration.id > 0 {
+                let last_fetched = self
+                    .last_fetched
+                    .entry(row.symbol.clone())
+                    .or_insert(iteration.to);
+                *last_fetched = (*last_fetched).max(iteration.to);
+            }
+        }
+        pending.rows.extend(rows);
+
+        // when all chunks have been received, assemble a new batch from them and store the batch in the buffer
+        if pending.chunk_ids.len() == pending.num_chunks {
+            let (mut batch, started) = self
+                .pending
+                .remove(&iteration.id)
+                .map(|pending| (pending.rows, pending.started))
+                .unwrap_or_else(|| (Vec::new(), iteration.start));
+
+            // the older iterations that are still being assembled won't be completed anymore
+            let newer = self.pending.split_off(&iteration.id);
+            for (id, abandoned) in std::mem::replace(&mut self.pending, newer) {
+                tracing::warn!(
+                    "Abandoned the iteration {}, which is missing the chunks {:?} out of {}.",
+                    id,
+                    abandoned.missing_chunks(),
+                    abandoned.num_chunks
+                );
+            }
+            self.last_completed = Some(iteration.id);
+
+            let duplicates = dedup_by_symbol(&mut batch);
+            if duplicates > 0 {
+                tracing::debug!("Dropped {} duplicate rows from the batch.", duplicates);
+                if let Some(stats_handle) = &self.stats_handle {
+                    if let Err(err) = stats_handle
+                        .send(StatsActorMsg::DuplicateRows(duplicates as u64))
+                        .await
+                    {
+                        self.undelivered("StatsActor", "DuplicateRows", err).await;
+                    }
+                }
+            }
+
+            self.rank_movers(&batch, iteration.id).await;
+            self.aggregate_sectors(&batch);
+
+            if let Some(webhook_handle) = &self.webhook_handle {
+                if let Err(err) = webhook_handle
+                    .send(WebhookActorMsg::Dispatch(batch.clone()))
+                    .await
+                {
+                    self.undelivered("WebhookActor", "Dispatch", err).await;
+                }
+            }
+
+            if let Some(broker_handle) = &self.broker_handle {
+                if let Err(err) = broker_handle
+                    .send(BrokerActorMsg::Publish(batch.clone()))
+                    .await
+                {
+                    self.undelivered("BrokerActor", "Publish", err).await;
+                }
+            }
+
+            let completed_at = OffsetDateTime::now_utc();
+            let blocking = lock_buffer(&self.buffer).push_touches_disk(&batch);
+            match self
+                .with_buffer(blocking, move |buffer| buffer.push(batch, completed_at))
+                .await
+            {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => tracing::warn!(
+                    "Couldn't spill the oldest batches to disk; they are kept in memory: {}",
+                    err
+                ),
+                Err(err) => tracing::warn!("Couldn't add the batch to the tail buffer: {}", err),
+            }
+            self.version += 1;
+
+            // the whole iteration is timed once, from its start until its batch is complete
+            let duration = started.elapsed();
+            tracing::info!(
+                "Took {:.3?} to complete the iteration {}.",
+                duration,
+                iteration.id
+            );
+
+            if let Some(stats_handle) = &self.stats_handle {
+                if let Err(err) = stats_handle
+                    .send(StatsActorMsg::IterationCompleted(duration))
+                    .await
+                {
+                    self.undelivered("StatsActor", "IterationCompleted", err)
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Ranks the top movers of a completed `batch` of the iteration `iteration_id`,
+    /// traces them along with the rows, and writes them to the CSV file, if there is one
+    ///
+    /// The CSV file is written and flushed on a blocking thread, through [`runtime::spawn_blocking`],
+    /// so that a slow disk doesn't stall the actor's thread. A CSV file that can't be written
+    /// is reported, but the batch is still served. If the blocking thread panics, the file is lost,
+    /// and the following movers aren't written.
+    async fn rank_movers(&mut self, batch: &Batch, iteration_id: u64) {
+        let time = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_default();
+        let movers = Movers::rank(batch, &self.returns, MOVERS_SIZE, iteration_id, time);
+        tracing::info!(target: ROWS_TRACING_TARGET, "Top movers: {}", movers);
+
+        if let Some(mut csv) = self.movers_csv.take() {
+            let written = movers.clone();
+            match runtime::spawn_blocking(move || {
+                let result = csv.write(&written);
+                (csv, result)
+            })
+            .await
+            {
+                Ok((csv, result)) => {
+                    self.movers_csv = Some(csv);
+                    if let Err(err) = result {
+                        tracing::warn!("Couldn't write the top movers: {}", err);
+                    }
+                }
+                Err(err) => tracing::warn!("Couldn't write the top movers: {}", err),
+            }
+        }
+        self.movers = Some(movers);
+    }
+
+    /// Aggregates the sectors' rows in a completed `batch`, and traces the aggregates
+    /// along with the rows
+    fn aggregate_sectors(&mut self, batch: &Batch) {
+        if self.sectors.is_empty() {
+            return;
+        }
+
+        self.sector_aggregates = sectors::aggregate(&self.sectors, batch);
+        for aggregate in &self.sector_aggregates {
+            tracing::info!(target: ROWS_TRACING_TARGET, "Sector {}", aggregate);
+        }
+    }
+
+    /// Forwards a `message` that couldn't be delivered to an `actor` to the [`DeadLetterActor`]
+    async fn undelivered(&self, actor: &str, message: &str, reason: impl Display) {
+        if let Some(dead_letter_handle) = &self.dead_letter_handle {
+            dead_letter_handle.undelivered(actor, message, reason).await;
+        }
+    }
+
+    /// Handle a [`CollectionActorMsg::TailRequest`]
+    ///
+    /// Gets the last fully-assembled `n` batches of performance indicators
+    /// and sends them to the web server.
+    ///
+    /// This message is basically a command - a request for the newest `n`
+    /// batches.
+    ///
+    /// The batches are not cloned and sent if `if_none_match` is equal to
+    /// the current version of the buffer; only the version is sent then.
+    ///
+    /// This message comes from the web server.
+    async fn handle_tail_request(
+        &mut self,
+        sender: oneshot::Sender<VersionedTail>,
+        n: usize,
+        if_none_match: Option<u64>,
+    ) -> Result<MsgResponseType, ActorError> {
+        let start = Instant::now();
+
+        let tail: Option<TailResponse> = if if_none_match == Some(self.version) {
+            None
+        } else {
+            // the last `n` batches, newest first
+            let blocking = lock_buffer(&self.buffer).spilled_len() > 0;
+            let newest = self.with_buffer(blocking, move |buffer| buffer.newest(n));
+            Some(newest.await?.map_err(ActorError::Spill)?)
+        };
+        tracing::debug!(
+            batches = tail.as_ref().map(|tail| tail.len()),
+            took = ?start.elapsed(),
+            "assembled the tail"
+        );
+
+        let response = VersionedTail {
+            version: self.version,
+            tail,
+        };
+        sender
+            .send(response)
+            .map_err(|_| ActorError::Reply("a response to the web application"))?;
+
+        Ok(())
+    }
+
+    /// Handle a [`CollectionActorMsg::SymbolTailRequest`]
+    ///
+    /// Picks the rows of the `symbol` out of the newest `n` batches, newest first,
+    /// so that the web server's client doesn't have to download whole batches.
+    ///
+    /// This message comes from the web server.
+    async fn handle_symbol_tail_request(
+        &mut self,
+        sender: oneshot::Sender<Batch>,
+        n: usize,
+        symbol: &str,
+    ) -> Result<MsgResponseType, ActorError> {
+        let start = Instant::now();
+
+        let symbol = symbol.to_string();
+        let blocking = lock_buffer(&self.buffer).spilled_len() > 0;
+        let response = self
+            .with_buffer(blocking, move |buffer| {
+                let mut response = Batch::new();
+                for batch in buffer.iter_newest() {
+                    if response.len() == n {
+                        break;
+                    }
+                    let batch = batch?;
+                    let rows = batch.iter().filter(|row| *row.symbol == *symbol);
+                    response.extend(rows.take(n - response.len()).cloned());
+                }
+                Ok::<_, std::io::Error>(response)
+            })
+            .await?
+            .map_err(ActorError::Spill)?;
+        tracing::debug!(
+            rows = response.len(),
+            took = ?start.elapsed(),
+            "assembled the symbol's tail"
+        );
+
+        sender
+            .send(response)
+            .map_err(|_| ActorError::Reply("a response to the web application"))?;
+
+        Ok(())
+    }
+
+    /// Handle a [`CollectionActorMsg::RangeRequest`]
+    ///
+    /// Gets the fully-assembled batches that were completed between `start` and `end`,
+    /// newest first, and sends them to the web server together with their completion times.
+    ///
+    /// This message comes from the web server.
+    async fn handle_range_request(
+        &self,
+        sender: oneshot::Sender<RangeResponse>,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<MsgResponseType, ActorError> {
+        let start_time = Instant::now();
+
+        let blocking = lock_buffer(&self.buffer).spilled_len() > 0;
+        let response = self
+            .with_buffer(blocking, move |buffer| buffer.range(start, end))
+            .await?
+            .map_err(ActorError::Spill)?;
+        tracing::debug!(
+            batches = response.len(),
+            took = ?start_time.elapsed(),
+            "assembled the range"
+        );
+
+        sender
+            .send(response)
+            .map_err(|_| ActorError::Reply("a response to the web application"))?;
+
+        Ok(())
+    }
+
+    /// Handle a [`CollectionActorMsg::SymbolLatestRequest`]
+    ///
+    /// Looks the newest row of the `symbol` up in the cache, instead of scanning the buffered batches.
+    ///
+    /// This message comes from the gRPC server.
+    fn handle_symbol_latest_request(
+        &self,
+        sender: oneshot::Sender<Option<PerformanceIndicatorsRow>>,
+        symbol: &str,
+    ) -> Result<MsgResponseType, ActorError> {
+        sender
+            .send(self.latest.get(symbol).cloned())
+            .map_err(|_| ActorError::Reply("the symbol's newest row"))?;
+
+        Ok(())
+    }
+
+    /// Handle a [`CollectionActorMsg::PurgeSymbol`] message
+    ///
+    /// Removes all rows of the `symbol` from the buffered batches, from the batches
+    /// that are currently being assembled, and from the cache of the newest rows,
+    /// so that a removed symbol doesn't show up in responses anymore.
+    ///
+    /// This message comes from the web server.
+    async fn handle_purge_symbol(&mut self, symbol: &str) -> Result<MsgResponseType, ActorError> {
+        self.latest.remove(symbol);
+        self.last_fetched.remove(symbol);
+        self.quality.remove(symbol);
+        self.returns.remove(symbol);
+        if let Some(movers) = self.movers.as_mut() {
+            movers.remove(symbol);
+        }
+        for pending in self.pending.values_mut() {
+            pending.rows.retain(|row| *row.symbol != *symbol);
+        }
+        self.version += 1;
+
+        let symbol = symbol.to_string();
+        let blocking = lock_buffer(&self.buffer).spilled_len() > 0;
+        self.with_buffer(blocking, move |buffer| buffer.purge(&symbol))
+            .await?
+            .map_err(ActorError::Spill)
+    }
+
+    /// Runs `f` on the tail buffer, on a blocking thread through [`runtime::spawn_blocking`]
+    /// if it's `blocking`, i.e., if `f` may spill batches to disk or read them back,
+    /// so that a slow disk doesn't stall the actor's thread, nor the other tasks on it
+    ///
+    /// The batches in memory are served in place, without the hop to a blocking thread.
+    ///
+    /// # Errors
+    /// - [`ActorError::Blocking`], if the blocking thread panicked
+    async fn with_buffer<T, F>(&self, blocking: bool, f: F) -> Result<T, ActorError>
+    where
+        F: FnOnce(&mut TailBuffer) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        if !blocking {
+            return Ok(f(&mut lock_buffer(&self.buffer)));
+        }
+
+        let buffer = Arc::clone(&self.buffer);
+        Ok(runtime::spawn_blocking(move || f(&mut lock_buffer(&buffer))).await?)
+    }
+}
+
+/// Locks the collection actor's tail `buffer`, which is never held across an await point,
+/// so it's only ever contended by the one blocking thread that the actor awaits
+fn lock_buffer(buffer: &StdMutex<TailBuffer>) -> std::sync::MutexGuard<'_, TailBuffer> {
+    buffer.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Drop for CollectionActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`CollectionActor`]
+///
+/// Only the handle is public; the [`CollectionActor`] isn't.
+///
+/// We can only create [`CollectionActor`]s through the [`CollectionActorHandle`].
+///
+/// It contains the `sender` field, which represents
+/// a sender of the [`CollectionActorMsg`] in an MPSC channel.
+///
+/// The handle is the sender, and the actor is the receiver
+/// of a message in the channel.
+///
+/// We only create a single [`CollectionActor`] instance in a [`CollectionActorHandle`].
+///
+/// The actor's mailbox has two lanes; the requests go through the priority lane,
+/// and all other messages through the regular lane.
+///
+/// The actor is supervised: if it crashes, it is restarted with an empty buffer.
+#[derive(Clone)]
+pub struct CollectionActorHandle {
+    mailbox: PriorityMailbox<CollectionActorMsg>,
+    interceptors: Interceptors<CollectionActorMsg>,
+}
+
+impl CollectionActorHandle {
+    /// Create a new [`CollectionActorHandle`] whose actor's mailbox can hold `capacity` messages,
+    /// and whose actor
+    /// - buffers up to `tail_limits.size` batches, keeps up to `tail_limits.memory_budget_mb` MiB
+    ///   of them in memory, and spills the rest to disk,
+    /// - reports completed iterations to the [`StatsActor`],
+    /// - hands every newly-assembled batch over to the [`WebhookActor`] for dispatching,
+    /// - publishes every newly-assembled batch through the [`BrokerActor`],
+    /// - forwards the messages that it can't deliver or handle to the [`DeadLetterActor`]
+    ///
+    /// Otherwise, it is the same as [`CollectionActorHandle::new`].
+    pub fn with_listeners(
+        nticks: usize,
+        capacity: usize,
+        tail_limits: TailBufferLimits,
+        stats_handle: StatsActorHandle,
+        webhook_handle: WebhookActorHandle,
+        broker_handle: BrokerActorHandle,
+        dead_letter_handle: DeadLetterActorHandle,
+    ) -> Self {
+        let (mailbox, receivers) =
+            PriorityMailbox::new(capacity, COLLECTION_PRIORITY_MAILBOX_CAPACITY);
+        let interceptors = Interceptors::default();
+        let actor_interceptors = interceptors.clone();
+        supervise(
+            "CollectionActor",
+            &mailbox,
+            receivers,
+            move |(receiver, priority_receiver), _| {
+                let mut actor = CollectionActor::with_tail_limits(receiver, nticks, tail_limits);
+                actor.priority_receiver = Some(priority_receiver);
+                actor.stats_handle = Some(stats_handle.clone());
+                actor.webhook_handle = Some(webhook_handle.clone());
+                actor.broker_handle = Some(broker_handle.clone());
+                actor.dead_letter_handle = Some(dead_letter_handle.clone());
+                actor.interceptors = actor_interceptors.clone();
+                runtime::spawn(async move { actor.start().await })
+            },
+        );
+
+        Self {
+            mailbox,
+            interceptors,
+        }
+    }
+
+    /// Hands over the `rows` that were calculated for the period that starts at `from`
+    /// to the [`CollectionActor`], as a batch of its own, e.g., the rows that are read back
+    /// from the output file
+    ///
+    /// # Errors
+    /// - If the [`CollectionActor`] isn't running
+    pub async fn preload(&self, from: String, rows: Vec<PerformanceIndicatorsRow>) -> Result<()> {
+        let msg = PerformanceIndicatorsRowsMsg {
+            from,
+            rows,
+            // the main loop's iterations start at 1, so this one comes before all of them
+            iteration: Iteration {
+                id: 0,
+                start: Instant::now(),
+                to: OffsetDateTime::now_utc(),
+                num_chunks: 1,
+            },
+            chunk_id: 0,
+        };
+
+        self.send(CollectionActorMsg::PerformanceIndicatorsChunk(msg))
+            .await
+            .context("Couldn't send the rows to the CollectionActor.")
+    }
+
+    /// Hands over the `batches` that were completed before a restart, oldest first,
+    /// with their completion times, to the [`CollectionActor`], which puts them in its buffer,
+    /// e.g., the newest batches of the event log
+    ///
+    /// # Errors
+    /// - If the [`CollectionActor`] isn't running
+    pub async fn restore(&self, batches: Vec<(OffsetDateTime, Batch)>) -> Result<()> {
+        self.send(CollectionActorMsg::Restore(batches))
+            .await
+            .context("Couldn't send the batches to the CollectionActor.")
+    }
+
+    /// Has the [`CollectionActor`] aggregate the rows of the `sectors` in every completed batch
+    ///
+    /// # Errors
+    /// - If the [`CollectionActor`] isn't running
+    pub async fn tag_sectors(&self, sectors: Vec<Sector>) -> Result<()> {
+        self.send(CollectionActorMsg::TagSectors(sectors))
+            .await
+            .context("Couldn't send the sectors to the CollectionActor.")
+    }
+
+    /// Has the [`CollectionActor`] write the top movers of every completed batch to a CSV file
+    /// at `path`, which is created right away
+    ///
+    /// # Errors
+    /// - If the [`CollectionActor`] isn't running
+    pub async fn write_movers(&self, path: PathBuf) -> Result<()> {
+        self.send(CollectionActorMsg::WriteMovers(path))
+            .await
+            .context("Couldn't send the movers' path to the CollectionActor.")
+    }
+
+    /// Returns the number of messages that are waiting in both lanes of the actor's mailbox
+    pub fn queue_depth(&self) -> usize {
+        self.mailbox.queue_depth()
+    }
+
+    /// Returns the number of sends that have found either lane of the actor's mailbox full since start
+    ///
+    /// Regular sends then wait for a free slot, while [`Self::try_send`] gives up.
+    pub fn queue_full_count(&self) -> u64 {
+        self.mailbox.queue_full_count()
+    }
+
+    /// Returns the number of [`Self::send_timeout`] sends that have timed out since start
+    pub fn send_timeout_count(&self) -> u64 {
+        self.mailbox.send_timeout_count()
+    }
+
+    /// Send a message to the [`CollectionActor`] without waiting
+    ///
+    /// # Errors
+    /// - If the actor's mailbox is full, in which case the message is given back
+    /// - If the [`CollectionActor`] isn't running
+    pub fn try_send(
+        &self,
+        msg: CollectionActorMsg,
+    ) -> Result<MsgResponseType, TrySendError<CollectionActorMsg>> {
+        let msg = self.interceptors.on_send(msg);
+        self.mailbox.lane(msg.is_priority()).try_send(msg)
+    }
+
+    /// Send a message to the [`CollectionActor`], waiting at most `timeout` for a free slot
+    /// in its mailbox
+    ///
+    /// # Errors
+    /// - If the actor's mailbox is still full after `timeout`, in which case the message is given back
+    /// - If the [`CollectionActor`] isn't running
+    pub async fn send_timeout(
+        &self,
+        msg: CollectionActorMsg,
+        timeout: Duration,
+    ) -> Result<MsgResponseType, SendTimeoutError<CollectionActorMsg>> {
+        let msg = self.interceptors.on_send(msg);
+        self.mailbox
+            .lane(msg.is_priority())
+            .send_timeout(msg, timeout)
+            .await
+    }
+
+    /// Adds an [`Interceptor`] to the [`CollectionActor`]'s messages
+    ///
+    /// It applies to the messages that are sent through any clone of the handle from now on,
+    /// and also to a restarted actor.
+    pub fn add_interceptor(&self, interceptor: impl Interceptor<CollectionActorMsg> + 'static) {
+        self.interceptors.add(interceptor);
+    }
+
+    /// Checks that the [`CollectionActor`] is alive, by waiting for it to answer a heartbeat
+    ///
+    /// The heartbeat goes through the priority lane, so it isn't held up by a flood of chunks.
+    ///
+    /// # Errors
+    /// - If the [`CollectionActor`] isn't running
+    pub async fn ping(&self) -> Result<()> {
+        self.call(|sender| CollectionActorMsg::Ping { sender })
+            .await
+            .context("Couldn't call the CollectionActor.")
+    }
+
+    /// Returns the end of the period of the newest fetched row of every symbol
+    ///
+    /// # Errors
+    /// - If the [`CollectionActor`] isn't running
+    pub async fn last_fetched(&self) -> Result<HashMap<Symbol, OffsetDateTime>> {
+        self.call(|sender| CollectionActorMsg::LastFetchedRequest { sender })
+            .await
+            .context("Couldn't call the CollectionActor.")
+    }
+
+    /// Stops the [`CollectionActor`]
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`CollectionActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| CollectionActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the CollectionActor.")
+    }
+}
+
+impl ActorHandle<MsgResponseType, CollectionMsgErrorType> for CollectionActorHandle {
+    type Msg = CollectionActorMsg;
+
+    /// Create a new [`CollectionActorHandle`]
+    ///
+    /// This function creates a single [`CollectionActor`] instance,
+    /// and two MPSC channels for communicating with the actor, the regular lane,
+    /// which can hold [`COLLECTION_MAILBOX_CAPACITY`] messages, and the priority lane,
+    /// which can hold [`COLLECTION_PRIORITY_MAILBOX_CAPACITY`] messages.
+    ///
+    /// It also starts (runs) the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let (mailbox, receivers) = PriorityMailbox::new(
+            COLLECTION_MAILBOX_CAPACITY,
+            COLLECTION_PRIORITY_MAILBOX_CAPACITY,
+        );
+        let interceptors = Interceptors::default();
+        let actor_interceptors = interceptors.clone();
+        supervise(
+            "CollectionActor",
+            &mailbox,
+            receivers,
+            move |(receiver, priority_receiver), _| {
+                let mut actor = CollectionActor::new(receiver, nticks);
+                actor.priority_receiver = Some(priority_receiver);
+                actor.interceptors = actor_interceptors.clone();
+                runtime::spawn(async move { actor.start().await })
+            },
+        );
+
+        Self {
+            mailbox,
+            interceptors,
+        }
+    }
+
+    /// Send a message to an [`CollectionActor`] instance through the [`CollectionActorHandle`]
+    async fn send(
+        &self,
+        msg: CollectionActorMsg,
+    ) -> Result<MsgResponseType, CollectionMsgErrorType> {
+        let msg = self.interceptors.on_send(msg);
+        self.mailbox.lane(msg.is_priority()).send(msg).await
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//          [`BrokerActorMsg`], [`BrokerActor`], [`BrokerActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`BrokerActorMsg`] enumeration
+///
+/// Supports three message types:
+/// - [`Publish`],
+/// - [`Subscribe`],
+/// - [`Shutdown`],
+///
+/// Only [`Shutdown`] expects a response, which is sent back through the provided `sender`.
+pub enum BrokerActorMsg {
+    /// A newly-assembled batch, which is sent to all subscribers
+    Publish(Batch),
+    /// A subscription to every newly-assembled batch
+    Subscribe(mpsc::Sender<Batch>),
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first, and then the subscriptions end.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that publishes newly-assembled batches to any number of subscribers
+///
+/// The [`CollectionActor`] publishes every batch to it, so new consumers of the batches,
+/// such as the gRPC subscription streams, only have to subscribe to it, instead of being
+/// wired into the pipeline.
+///
+/// Subscribers that have gone away are removed.
+/// A subscriber that is lagging behind doesn't get the batch, so that it can't hold up
+/// the pipeline.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`BrokerActorHandle`], which is public.
+struct BrokerActor {
+    receiver: mpsc::Receiver<BrokerActorMsg>,
+    subscribers: Vec<mpsc::Sender<Batch>>,
+}
+
+impl Actor<MsgResponseType> for BrokerActor {
+    type Msg = BrokerActorMsg;
+
+    /// Create a new [`BrokerActor`]
+    fn new(receiver: mpsc::Receiver<BrokerActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Run the [`BrokerActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("BrokerActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("BrokerActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`BrokerActorMsg`] message handler for the [`BrokerActor`] actor
+    async fn handle(&mut self, msg: BrokerActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            BrokerActorMsg::Publish(batch) => self.publish(batch),
+            BrokerActorMsg::Subscribe(sender) => self.subscribers.push(sender),
+            BrokerActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                // ends the subscriptions
+                self.subscribers.clear();
+                tracing::debug!("BrokerActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BrokerActor {
+    /// Sends a `batch` to all subscribers
+    fn publish(&mut self, batch: Batch) {
+        self.subscribers
+            .retain(|subscriber| match subscriber.try_send(batch.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    tracing::warn!("A subscriber is lagging behind; dropped a batch for it.");
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            });
+    }
+}
+
+impl Drop for BrokerActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`BrokerActor`]
+///
+/// Only the handle is public; the [`BrokerActor`] isn't.
+///
+/// We can only create [`BrokerActor`]s through the [`BrokerActorHandle`].
+///
+/// We only create a single [`BrokerActor`] instance in a [`BrokerActorHandle`].
+#[derive(Clone)]
+pub struct BrokerActorHandle {
+    sender: mpsc::Sender<BrokerActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, BrokerMsgErrorType> for BrokerActorHandle {
+    type Msg = BrokerActorMsg;
+
+    /// Create a new [`BrokerActorHandle`]
+    ///
+    /// This function creates a single [`BrokerActor`] instance,
+    /// and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(BrokerActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`BrokerActor`] instance through the [`BrokerActorHandle`]
+    async fn send(&self, msg: BrokerActorMsg) -> Result<MsgResponseType, BrokerMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl BrokerActorHandle {
+    /// Subscribes to every newly-assembled batch
+    ///
+    /// # Returns
+    /// The receiving side of the subscription, which can hold [`SUBSCRIBER_CHANNEL_CAPACITY`]
+    /// batches; the subscription ends when the [`BrokerActor`] stops
+    ///
+    /// # Errors
+    /// - If the [`BrokerActor`] isn't running
+    pub async fn subscribe(&self) -> Result<mpsc::Receiver<Batch>> {
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_CHANNEL_CAPACITY);
+        self.send(BrokerActorMsg::Subscribe(sender))
+            .await
+            .context("Couldn't send a subscription to the BrokerActor.")?;
+
+        Ok(receiver)
+    }
+
+    /// Stops the [`BrokerActor`], which ends all subscriptions
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`BrokerActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| BrokerActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the BrokerActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//         [`SymbolsActorMsg`], [`SymbolsActor`], [`SymbolsActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`SymbolsActorMsg`] enumeration
+///
+/// Supports four message types:
+/// - [`Add`],
+/// - [`Remove`],
+/// - [`Get`],
+/// - [`Shutdown`],
+///
+/// All message types expect a response, which is sent back through the provided `sender`.
+pub enum SymbolsActorMsg {
+    /// A request for adding new symbols to the registry
+    ///
+    /// The response contains only the symbols that were actually added,
+    /// i.e., the ones that were not already present in the registry.
+    Add {
+        symbols: Vec<String>,
+        sender: oneshot::Sender<SymbolsResponse>,
+    },
+    /// A request for removing a symbol from the registry
+    ///
+    /// The response tells whether the symbol was present in the registry.
+    Remove {
+        symbol: String,
+        sender: oneshot::Sender<bool>,
+    },
+    /// A request for the current symbols
+    Get {
+        sender: oneshot::Sender<SymbolsResponse>,
+    },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that owns the live set of symbols (tickers) that the main loop works with
+///
+/// The main loop asks it for the current symbols at every tick, and the web server
+/// can modify the symbols at runtime.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`SymbolsActorHandle`], which is public.
+struct SymbolsActor {
+    receiver: mpsc::Receiver<SymbolsActorMsg>,
+    /// Every symbol is created here once, and shared with the pipeline
+    symbols: Vec<Symbol>,
+}
+
+impl Actor<MsgResponseType> for SymbolsActor {
+    type Msg = SymbolsActorMsg;
+
+    /// Create a new [`SymbolsActor`] with an empty registry
+    fn new(receiver: mpsc::Receiver<SymbolsActorMsg>, nticks: usize) -> Self {
+        Self {
+            receiver,
+            symbols: Vec::with_capacity(nticks),
+        }
+    }
+
+    /// Run the [`SymbolsActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("SymbolsActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("SymbolsActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`SymbolsActorMsg`] message handler for the [`SymbolsActor`] actor
+    async fn handle(&mut self, msg: SymbolsActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            SymbolsActorMsg::Add { symbols, sender } => {
+                let added = self.add(symbols);
+                sender
+                    .send(added)
+                    .map_err(|_| ActorError::Reply("the added symbols"))?;
+            }
+            SymbolsActorMsg::Remove { symbol, sender } => {
+                let removed = self.remove(&symbol);
+                sender
+                    .send(removed)
+                    .map_err(|_| ActorError::Reply("the symbol removal result"))?;
+            }
+            SymbolsActorMsg::Get { sender } => {
+                sender
+                    .send(self.symbols.clone())
+                    .map_err(|_| ActorError::Reply("the current symbols"))?;
+            }
+            SymbolsActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("SymbolsActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SymbolsActor {
+    /// Adds the new `symbols` to the registry, skipping those that are already present
+    ///
+    /// Symbols are trimmed and uppercased, and empty ones are ignored.
+    ///
+    /// # Returns
+    /// The symbols that were actually added
+    fn add(&mut self, symbols: Vec<String>) -> SymbolsResponse {
+        let mut added = Vec::with_capacity(symbols.len());
+
+        for symbol in symbols {
+            let symbol: Symbol = symbol.trim().to_uppercase().into();
+            if !symbol.is_empty() && !self.symbols.contains(&symbol) {
+                self.symbols.push(Arc::clone(&symbol));
+                added.push(symbol);
+            }
+        }
+
+        if !added.is_empty() {
+            tracing::info!("Added symbols: {:?}", added);
+        }
+
+        added
+    }
+
+    /// Removes `symbol` from the registry
+    ///
+    /// The symbol is trimmed and uppercased before removal.
+    ///
+    /// # Returns
+    /// Whether the symbol was present in the registry
+    fn remove(&mut self, symbol: &str) -> bool {
+        let symbol = symbol.trim().to_uppercase();
+        let len = self.symbols.len();
+        self.symbols.retain(|s| **s != *symbol);
+        let removed = self.symbols.len() < len;
+
+        if removed {
+            tracing::info!("Removed symbol: {}", symbol);
+        }
+
+        removed
+    }
+}
+
+impl Drop for SymbolsActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`SymbolsActor`]
+///
+/// Only the handle is public; the [`SymbolsActor`] isn't.
+///
+/// We can only create [`SymbolsActor`]s through the [`SymbolsActorHandle`].
+///
+/// We only create a single [`SymbolsActor`] instance in a [`SymbolsActorHandle`].
+#[derive(Clone)]
+pub struct SymbolsActorHandle {
+    sender: mpsc::Sender<SymbolsActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, SymbolsMsgErrorType> for SymbolsActorHandle {
+    type Msg = SymbolsActorMsg;
+
+    /// Create a new [`SymbolsActorHandle`]
+    ///
+    /// This function creates a single [`SymbolsActor`] instance with an empty registry,
+    /// and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(SymbolsActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`SymbolsActor`] instance through the [`SymbolsActorHandle`]
+    async fn send(&self, msg: SymbolsActorMsg) -> Result<MsgResponseType, SymbolsMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl SymbolsActorHandle {
+    /// Adds `symbols` to the registry
+    ///
+    /// # Returns
+    /// The symbols that were actually added, i.e., the ones that weren't already present
+    ///
+    /// # Errors
+    /// - If the [`SymbolsActor`] isn't running
+    pub async fn add(&self, symbols: Vec<String>) -> Result<SymbolsResponse> {
+        self.call(|sender| SymbolsActorMsg::Add { symbols, sender })
+            .await
+            .context("Couldn't call the SymbolsActor.")
+    }
+
+    /// Removes `symbol` from the registry
+    ///
+    /// # Returns
+    /// Whether the symbol was present in the registry
+    ///
+    /// # Errors
+    /// - If the [`SymbolsActor`] isn't running
+    pub async fn remove(&self, symbol: String) -> Result<bool> {
+        self.call(|sender| SymbolsActorMsg::Remove { symbol, sender })
+            .await
+            .context("Couldn't call the SymbolsActor.")
+    }
+
+    /// Gets the current symbols from the registry
+    ///
+    /// # Errors
+    /// - If the [`SymbolsActor`] isn't running
+    pub async fn get(&self) -> Result<SymbolsResponse> {
+        self.call(|sender| SymbolsActorMsg::Get { sender })
+            .await
+            .context("Couldn't call the SymbolsActor.")
+    }
+
+    /// Stops the [`SymbolsActor`]
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`SymbolsActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| SymbolsActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the SymbolsActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//          [`ConfigActorMsg`], [`ConfigActor`], [`ConfigActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`ConfigActorMsg`] enumeration
+///
+/// Supports three message types:
+/// - [`Update`],
+/// - [`Get`],
+/// - [`Shutdown`],
+///
+/// All message types expect a response, which is sent back through the provided `sender`.
+pub enum ConfigActorMsg {
+    /// A request for updating a subset of the runtime settings
+    ///
+    /// The update is expected to be validated by the sender.
+    ///
+    /// The response contains the updated configuration.
+    Update {
+        update: ConfigUpdate,
+        sender: oneshot::Sender<RuntimeConfig>,
+    },
+    /// A request for the current configuration
+    Get {
+        sender: oneshot::Sender<RuntimeConfig>,
+    },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that owns the runtime configuration
+///
+/// The main loop asks it for the current configuration at every tick, and the web server
+/// can update the configuration at runtime.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`ConfigActorHandle`], which is public.
+struct ConfigActor {
+    receiver: mpsc::Receiver<ConfigActorMsg>,
+    config: RuntimeConfig,
+}
+
+impl Actor<MsgResponseType> for ConfigActor {
+    type Msg = ConfigActorMsg;
+
+    /// Create a new [`ConfigActor`] with the default configuration
+    fn new(receiver: mpsc::Receiver<ConfigActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            config: RuntimeConfig::default(),
+        }
+    }
+
+    /// Run the [`ConfigActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("ConfigActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("ConfigActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`ConfigActorMsg`] message handler for the [`ConfigActor`] actor
+    async fn handle(&mut self, msg: ConfigActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            ConfigActorMsg::Update { update, sender } => {
+                self.config.apply(update);
+                tracing::info!("Updated the runtime configuration: {:?}", self.config);
+                sender
+                    .send(self.config.clone())
+                    .map_err(|_| ActorError::Reply("the updated configuration"))?;
+            }
+            ConfigActorMsg::Get { sender } => {
+                sender
+                    .send(self.config.clone())
+                    .map_err(|_| ActorError::Reply("the current configuration"))?;
+            }
+            ConfigActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("ConfigActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for ConfigActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`ConfigActor`]
+///
+/// Only the handle is public; the [`ConfigActor`] isn't.
+///
+/// We can only create [`ConfigActor`]s through the [`ConfigActorHandle`].
+///
+/// We only create a single [`ConfigActor`] instance in a [`ConfigActorHandle`].
+#[derive(Clone)]
+pub struct ConfigActorHandle {
+    sender: mpsc::Sender<ConfigActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, ConfigMsgErrorType> for ConfigActorHandle {
+    type Msg = ConfigActorMsg;
+
+    /// Create a new [`ConfigActorHandle`]
+    ///
+    /// This function creates a single [`ConfigActor`] instance with the default configuration,
+    /// and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(ConfigActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`ConfigActor`] instance through the [`ConfigActorHandle`]
+    async fn send(&self, msg: ConfigActorMsg) -> Result<MsgResponseType, ConfigMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl ConfigActorHandle {
+    /// Create a new [`ConfigActorHandle`] whose actor starts with the provided `config`
+    ///
+    /// Otherwise, it is the same as [`ConfigActorHandle::new`].
+    pub fn with_config(nticks: usize, config: RuntimeConfig) -> Self {
+        let sender = spawn_actor!(ConfigActor, nticks, |actor| {
+            actor.config = config;
+        });
+
+        Self { sender }
+    }
+
+    /// Updates the runtime configuration with a validated `update`
+    ///
+    /// # Returns
+    /// The updated configuration
+    ///
+    /// # Errors
+    /// - If the [`ConfigActor`] isn't running
+    pub async fn update(&self, update: ConfigUpdate) -> Result<RuntimeConfig> {
+        self.call(|sender| ConfigActorMsg::Update { update, sender })
+            .await
+            .context("Couldn't call the ConfigActor.")
+    }
+
+    /// Gets the current runtime configuration
+    ///
+    /// # Errors
+    /// - If the [`ConfigActor`] isn't running
+    pub async fn get(&self) -> Result<RuntimeConfig> {
+        self.call(|sender| ConfigActorMsg::Get { sender })
+            .await
+            .context("Couldn't call the ConfigActor.")
+    }
+
+    /// Stops the [`ConfigActor`]
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`ConfigActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| ConfigActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the ConfigActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//     [`StatsActorMsg`], [`StatsActor`], [`StatsActorHandle`], [`Stats`]
+//
+//
+//
+//
+// ============================================================================
+
+/// Internal statistics of the actor system and of the pipeline
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct Stats {
+    /// The number of fetch and processor actors spawned since start
+    pub spawned_actors: u64,
+    /// The number of completed iterations of the main loop, i.e., of assembled batches
+    pub completed_iterations: u64,
+    /// The duration of the last completed iteration, in milliseconds
+    pub last_iteration_duration_ms: Option<f64>,
+    /// The number of iterations of the main loop that the watchdog cancelled since start,
+    /// because they missed too many ticks
+    pub cancelled_iterations: u64,
+    /// The number of symbols whose data couldn't be fetched since start
+    pub fetch_errors: u64,
+    /// The number of messages that actors failed to handle since start
+    pub actor_errors: u64,
+    /// The number of messages that couldn't be delivered or handled since start
+    pub dead_letters: u64,
+    /// The number of rows that were dropped from batches since start, because their symbol
+    /// occurred more than once in a batch
+    pub duplicate_rows: u64,
+    /// The number of messages waiting in the writer actor's mailbox
+    pub writer_queue_depth: usize,
+    /// The number of messages waiting in the collection actor's mailbox
+    pub collection_queue_depth: usize,
+    /// The number of sends that found the writer actor's mailbox full since start
+    pub writer_queue_full: u64,
+    /// The number of bounded-wait sends to the writer actor that timed out since start
+    pub writer_send_timeouts: u64,
+    /// The number of sends that found the collection actor's mailbox full since start
+    pub collection_queue_full: u64,
+    /// The number of bounded-wait sends to the collection actor that timed out since start
+    pub collection_send_timeouts: u64,
+}
+
+/// The [`StatsActorMsg`] enumeration
+///
+/// Supports nine message types:
+/// - [`ActorsSpawned`],
+/// - [`FetchErrors`],
+/// - [`ActorErrors`],
+/// - [`DeadLetters`],
+/// - [`DuplicateRows`],
+/// - [`IterationCompleted`],
+/// - [`IterationCancelled`],
+/// - [`Get`],
+/// - [`Shutdown`],
+///
+/// Only [`Get`] and [`Shutdown`] expect a response, which is sent back through the provided `sender`.
+pub enum StatsActorMsg {
+    /// The number of newly-spawned actors
+    ActorsSpawned(u64),
+    /// The symbols whose data couldn't be fetched, by their errors
+    FetchErrors(ErrorSummary),
+    /// The number of messages that actors failed to handle
+    ActorErrors(u64),
+    /// The number of messages that couldn't be delivered or handled
+    DeadLetters(u64),
+    /// The number of rows that were dropped from a batch as duplicates
+    DuplicateRows(u64),
+    /// The duration of a completed iteration of the main loop
+    IterationCompleted(Duration),
+    /// An iteration of the main loop that the watchdog cancelled
+    IterationCancelled,
+    /// A request for the current statistics
+    Get { sender: oneshot::Sender<Stats> },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that collects internal statistics of the actor system and of the pipeline,
+/// so that operators can diagnose slowdowns
+///
+/// Mailbox queue depths and backpressure counters are not known to it; they are read
+/// from the actor handles by whoever asks for the statistics.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`StatsActorHandle`], which is public.
+struct StatsActor {
+    receiver: mpsc::Receiver<StatsActorMsg>,
+    stats: Stats,
+    /// The fetch failures since the last completed or cancelled iteration, which are logged
+    /// as a summary then; see [`crate::error_summary`]
+    failures: ErrorSummary,
+}
+
+impl Actor<MsgResponseType> for StatsActor {
+    type Msg = StatsActorMsg;
+
+    /// Create a new [`StatsActor`]
+    fn new(receiver: mpsc::Receiver<StatsActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            stats: Stats::default(),
+            failures: ErrorSummary::default(),
+        }
+    }
+
+    /// Run the [`StatsActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("StatsActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("StatsActor", &err, None).await;
+                self.stats.actor_errors += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`StatsActorMsg`] message handler for the [`StatsActor`] actor
+    async fn handle(&mut self, msg: StatsActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            StatsActorMsg::ActorsSpawned(n) => self.stats.spawned_actors += n,
+            StatsActorMsg::FetchErrors(failures) => {
+                self.stats.fetch_errors += failures.count();
+                self.failures.merge(failures);
+            }
+            StatsActorMsg::ActorErrors(n) => self.stats.actor_errors += n,
+            StatsActorMsg::DeadLetters(n) => self.stats.dead_letters += n,
+            StatsActorMsg::DuplicateRows(n) => self.stats.duplicate_rows += n,
+            StatsActorMsg::IterationCompleted(duration) => {
+                self.stats.completed_iterations += 1;
+                self.stats.last_iteration_duration_ms = Some(duration.as_secs_f64() * 1000.0);
+                self.log_failures();
+            }
+            StatsActorMsg::IterationCancelled => {
+                self.stats.cancelled_iterations += 1;
+                self.log_failures();
+            }
+            StatsActorMsg::Get { sender } => {
+                sender
+                    .send(self.stats.clone())
+                    .map_err(|_| ActorError::Reply("the statistics"))?;
+            }
+            StatsActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("StatsActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl StatsActor {
+    /// Logs a warning per kind of the fetch failures since the last call, instead of one per symbol
+    fn log_failures(&mut self) {
+        for line in self.failures.take_lines() {
+            tracing::warn!("{}", line);
+        }
+    }
+}
+
+impl Drop for StatsActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`StatsActor`]
+///
+/// Only the handle is public; the [`StatsActor`] isn't.
+///
+/// We can only create [`StatsActor`]s through the [`StatsActorHandle`].
+///
+/// We only create a single [`StatsActor`] instance in a [`StatsActorHandle`].
+#[derive(Clone)]
+pub struct StatsActorHandle {
+    sender: mpsc::Sender<StatsActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, StatsMsgErrorType> for StatsActorHandle {
+    type Msg = StatsActorMsg;
+
+    /// Create a new [`StatsActorHandle`]
+    ///
+    /// This function creates a single [`StatsActor`] instance,
+    /// and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(StatsActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`StatsActor`] instance through the [`StatsActorHandle`]
+    async fn send(&self, msg: StatsActorMsg) -> Result<MsgResponseType, StatsMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl StatsActorHandle {
+    /// Gets the current statistics
+    ///
+    /// Mailbox queue depths and backpressure counters are not filled in.
+    ///
+    /// # Errors
+    /// - If the [`StatsActor`] isn't running
+    pub async fn get(&self) -> Result<Stats> {
+        self.call(|sender| StatsActorMsg::Get { sender })
+            .await
+            .context("Couldn't call the StatsActor.")
+    }
+
+    /// Stops the [`StatsActor`]
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`StatsActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| StatsActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the StatsActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//     [`WebhookActorMsg`], [`WebhookActor`], [`WebhookActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`WebhookActorMsg`] enumeration
+///
+/// Supports five message types:
+/// - [`Register`],
+/// - [`Unregister`],
+/// - [`List`],
+/// - [`Dispatch`],
+/// - [`Shutdown`],
+///
+/// All but [`Dispatch`] expect a response, which is sent back through the provided `sender`.
+pub enum WebhookActorMsg {
+    /// A request to register a new, validated, webhook; responds with the registered webhook
+    Register {
+        registration: WebhookRegistration,
+        sender: oneshot::Sender<Webhook>,
+    },
+    /// A request to unregister a webhook; responds with whether the webhook was registered
+    Unregister {
+        id: u64,
+        sender: oneshot::Sender<bool>,
+    },
+    /// A request for all registered webhooks
+    List {
+        sender: oneshot::Sender<Vec<Webhook>>,
+    },
+    /// A newly-assembled batch whose matching rows should be sent to the webhooks
+    Dispatch(Batch),
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that owns the registered webhooks and dispatches newly-assembled batches to them
+///
+/// Every webhook gets only the rows of a batch that match its filter rules,
+/// and only if there is at least one such row.
+///
+/// The requests are sent from separate tasks, so that a slow webhook endpoint
+/// doesn't hold up the actor, nor the [`CollectionActor`] that feeds it.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`WebhookActorHandle`], which is public.
+struct WebhookActor {
+    receiver: mpsc::Receiver<WebhookActorMsg>,
+    /// Whether to only log the requests, and send nothing
+    dry_run: bool,
+    webhooks: Vec<Webhook>,
+    next_id: u64,
+    client: reqwest::Client,
+}
+
+impl Actor<MsgResponseType> for WebhookActor {
+    type Msg = WebhookActorMsg;
+
+    /// Create a new [`WebhookActor`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't create an HTTP client.
+    fn new(receiver: mpsc::Receiver<WebhookActorMsg>, _: usize) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .build()
+            .expect("Failed to create an HTTP client for webhooks.");
+
+        Self {
+            receiver,
+            dry_run: false,
+            webhooks: Vec::new(),
+            next_id: 1,
+            client,
+        }
+    }
+
+    /// Run the [`WebhookActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("WebhookActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("WebhookActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`WebhookActorMsg`] message handler for the [`WebhookActor`] actor
+    async fn handle(&mut self, msg: WebhookActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            WebhookActorMsg::Register {
+                registration,
+                sender,
+            } => {
+                let webhook = Webhook::new(self.next_id, registration);
+                self.next_id += 1;
+                self.webhooks.push(webhook.clone());
+                sender
+                    .send(webhook)
+                    .map_err(|_| ActorError::Reply("the registered webhook"))?;
+            }
+            WebhookActorMsg::Unregister { id, sender } => {
+                let len = self.webhooks.len();
+                self.webhooks.retain(|webhook| webhook.id != id);
+                sender
+                    .send(self.webhooks.len() < len)
+                    .map_err(|_| ActorError::Reply("the unregistration result"))?;
+            }
+            WebhookActorMsg::List { sender } => {
+                sender
+                    .send(self.webhooks.clone())
+                    .map_err(|_| ActorError::Reply("the webhooks"))?;
+            }
+            WebhookActorMsg::Dispatch(batch) => self.dispatch(&batch),
+            WebhookActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("WebhookActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl WebhookActor {
+    /// Sends the rows of a `batch` that match a webhook's filter rules to the webhook,
+    /// for every registered webhook
+    ///
+    /// Failed requests are logged and not retried.
+    fn dispatch(&self, batch: &Batch) {
+        for webhook in &self.webhooks {
+            let rows: Batch = batch
+                .iter()
+                .filter(|row| webhook.matches(row))
+                .cloned()
+                .collect();
+            if rows.is_empty() {
+                continue;
+            }
+            if self.dry_run {
+                tracing::info!(
+                    "Dry run: {} rows would have been sent to the webhook {}.",
+                    rows.len(),
+                    webhook.url
+                );
+                continue;
+            }
+
+            let request = self.client.post(&webhook.url).json(&WebhookPayload {
+                webhook_id: webhook.id,
+                rows,
+            });
+            let url = webhook.url.clone();
+            runtime::spawn(async move {
+                if let Err(err) = request.send().await.and_then(|r| r.error_for_status()) {
+                    tracing::warn!("Webhook request to {} failed: {}", url, err);
+                }
+            });
+        }
+    }
+}
+
+impl Drop for WebhookActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`WebhookActor`]
+///
+/// Only the handle is public; the [`WebhookActor`] isn't.
+///
+/// We can only create [`WebhookActor`]s through the [`WebhookActorHandle`].
+///
+/// We only create a single [`WebhookActor`] instance in a [`WebhookActorHandle`].
+#[derive(Clone)]
+pub struct WebhookActorHandle {
+    sender: mpsc::Sender<WebhookActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, WebhookMsgErrorType> for WebhookActorHandle {
+    type Msg = WebhookActorMsg;
+
+    /// Create a new [`WebhookActorHandle`]
+    ///
+    /// This function creates a single [`WebhookActor`] instance,
+    /// and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(WebhookActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`WebhookActor`] instance through the [`WebhookActorHandle`]
+    async fn send(&self, msg: WebhookActorMsg) -> Result<MsgResponseType, WebhookMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl WebhookActorHandle {
+    /// Create a new [`WebhookActorHandle`] whose actor only logs the requests that it would send
+    ///
+    /// Otherwise, it is the same as [`WebhookActorHandle::new`].
+    pub fn dry_run(nticks: usize) -> Self {
+        let sender = spawn_actor!(WebhookActor, nticks, |actor| {
+            actor.dry_run = true;
+        });
+
+        Self { sender }
+    }
+
+    /// Registers a new webhook
+    ///
+    /// The `registration` is expected to be validated.
+    ///
+    /// # Errors
+    /// - If the [`WebhookActor`] isn't running
+    pub async fn register(&self, registration: WebhookRegistration) -> Result<Webhook> {
+        self.call(|sender| WebhookActorMsg::Register {
+            registration,
+            sender,
+        })
+        .await
+        .context("Couldn't call the WebhookActor.")
+    }
+
+    /// Unregisters the webhook with the given `id`
+    ///
+    /// Returns whether the webhook was registered.
+    ///
+    /// # Errors
+    /// - If the [`WebhookActor`] isn't running
+    pub async fn unregister(&self, id: u64) -> Result<bool> {
+        self.call(|sender| WebhookActorMsg::Unregister { id, sender })
+            .await
+            .context("Couldn't call the WebhookActor.")
+    }
+
+    /// Gets all registered webhooks
+    ///
+    /// # Errors
+    /// - If the [`WebhookActor`] isn't running
+    pub async fn list(&self) -> Result<Vec<Webhook>> {
+        self.call(|sender| WebhookActorMsg::List { sender })
+            .await
+            .context("Couldn't call the WebhookActor.")
+    }
+
+    /// Stops the [`WebhookActor`]
+    ///
+    /// Webhook requests that are already in flight aren't waited for.
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`WebhookActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| WebhookActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the WebhookActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//        [`AlertActorMsg`], [`AlertActor`], [`AlertActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`AlertActorMsg`] enumeration
+///
+/// Supports two message types:
+/// - [`Evaluate`],
+/// - [`Shutdown`],
+///
+/// Only [`Shutdown`] expects a response, which is sent back through the provided `sender`.
+pub enum AlertActorMsg {
+    /// A newly-assembled batch whose rows should be checked against the alert rules
+    Evaluate(Batch),
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that checks every newly-assembled batch against the alert rules,
+/// and dispatches the alerts to the alert channels
+///
+/// An alert is raised when a symbol starts to satisfy a rule, i.e., when it satisfies it
+/// in a batch, but it didn't in the previous one, so that a lasting condition
+/// doesn't raise the same alert at every iteration.
+///
+/// The webhook requests are sent from separate tasks, the same as in the [`WebhookActor`].
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`AlertActorHandle`], which is public.
+struct AlertActor {
+    receiver: mpsc::Receiver<AlertActorMsg>,
+    triggers: AlertTriggers,
+    channels: Vec<AlertChannel>,
+    /// Sends the email alerts; it's provided if there are email channels
+    mailer: Option<Mailer>,
+    /// Whether to only log the webhook requests and the emails, and send nothing
+    dry_run: bool,
+    /// The indices of the rules, and the symbols, that were satisfied in the previous batch
+    active: HashSet<(usize, Symbol)>,
+    /// The rate limiters of the chat channels, by their webhook URLs
+    chat_limiters: HashMap<String, RateLimiter>,
+    client: reqwest::Client,
+}
+
+impl Actor<MsgResponseType> for AlertActor {
+    type Msg = AlertActorMsg;
+
+    /// Create a new [`AlertActor`] without rules
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't create an HTTP client.
+    fn new(receiver: mpsc::Receiver<AlertActorMsg>, _: usize) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .build()
+            .expect("Failed to create an HTTP client for alerts.");
+
+        Self {
+            receiver,
+            triggers: AlertTriggers::default(),
+            channels: Vec::new(),
+            mailer: None,
+            dry_run: false,
+            active: HashSet::new(),
+            chat_limiters: HashMap::new(),
+            client,
+        }
+    }
+
+    /// Run the [`AlertActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("AlertActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("AlertActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`AlertActorMsg`] message handler for the [`AlertActor`] actor
+    async fn handle(&mut self, msg: AlertActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            AlertActorMsg::Evaluate(batch) => self.evaluate(&batch),
+            AlertActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("AlertActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl AlertActor {
+    /// Checks the rows of a `batch` against every rule, and dispatches an alert
+    /// for every symbol that has started to satisfy a rule, or whose row the trading strategy
+    /// signals to buy or sell
+    fn evaluate(&mut self, batch: &Batch) {
+        let time = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_default();
+        let mut active = HashSet::with_capacity(self.active.len());
+        let mut events = Vec::new();
+
+        for (id, rule) in self.triggers.rules.iter().enumerate() {
+            for row in batch.iter().filter(|row| rule.matches(row)) {
+                let key = (id, row.symbol.clone());
+                if !self.active.contains(&key) {
+                    events.push(AlertEvent {
+                        time: time.clone(),
+                        rule: rule.to_string(),
+                        row: row.clone(),
+                    });
+                }
+                active.insert(key);
+            }
+        }
+
+        self.active = active;
+        if let Some(engine) = &mut self.triggers.signals {
+            let strategy = engine.name().to_string();
+            for (row, signal) in engine.signals(batch) {
+                events.push(AlertEvent {
+                    time: time.clone(),
+                    rule: format!("{} {}", strategy, signal),
+                    row: row.clone(),
+                });
+            }
+        }
+
+        for event in events {
+            self.dispatch(event);
+        }
+    }
+
+    /// Sends an alert `event` to every alert channel
+    ///
+    /// Failed webhook requests and emails are logged and not retried.
+    /// The alerts that exceed a chat channel's rate limit are only counted,
+    /// and the count is mentioned in the channel's next message.
+    fn dispatch(&mut self, event: AlertEvent) {
+        for channel in &self.channels {
+            match channel {
+                AlertChannel::Log => tracing::warn!("Alert: {}", event),
+                AlertChannel::Stdout => println!("Alert: {}", event),
+                AlertChannel::Webhook(url) if self.dry_run => {
+                    tracing::info!("Dry run: an alert would have been sent to {}.", url);
+                }
+                AlertChannel::Webhook(url) => {
+                    let request = self.client.post(url).json(&event);
+                    let url = url.clone();
+                    runtime::spawn(async move {
+                        if let Err(err) = request.send().await.and_then(|r| r.error_for_status()) {
+                            tracing::warn!("Alert request to {} failed: {}", url, err);
+                        }
+                    });
+                }
+                AlertChannel::Email(address) if self.dry_run => {
+                    tracing::info!("Dry run: an alert would have been emailed to {}.", address);
+                }
+                AlertChannel::Email(address) => {
+                    let Some(mailer) = self.mailer.clone() else {
+                        tracing::warn!("There is no SMTP server to email an alert to {}.", address);
+                        continue;
+                    };
+                    let (address, event) = (address.clone(), event.clone());
+                    runtime::spawn(async move {
+                        if let Err(err) = mailer.send(address.clone(), &event).await {
+                            tracing::warn!("Alert email to {} failed: {:#}", address, err);
+                        }
+                    });
+                }
+                AlertChannel::Chat(chat, url) => {
+                    let Some(suppressed) = self
+                        .chat_limiters
+                        .get_mut(url)
+                        .map_or(Some(0), |limiter| limiter.acquire(Instant::now()))
+                    else {
+                        tracing::debug!("An alert to {} was suppressed by the rate limiting.", url);
+                        continue;
+                    };
+                    if self.dry_run {
+                        tracing::info!("Dry run: an alert would have been posted to {}.", url);
+                        continue;
+                    }
+
+                    let request = self
+                        .client
+                        .post(url)
+                        .json(&chat.payload(&event, suppressed));
+                    let url = url.clone();
+                    runtime::spawn(async move {
+                        if let Err(err) = request.send().await.and_then(|r| r.error_for_status()) {
+                            tracing::warn!("Alert message to {} failed: {}", url, err);
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl Drop for AlertActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`AlertActor`]
+///
+/// Only the handle is public; the [`AlertActor`] isn't.
+///
+/// We can only create [`AlertActor`]s through the [`AlertActorHandle`].
+///
+/// We only create a single [`AlertActor`] instance in a [`AlertActorHandle`].
+#[derive(Clone)]
+pub struct AlertActorHandle {
+    sender: mpsc::Sender<AlertActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, AlertMsgErrorType> for AlertActorHandle {
+    type Msg = AlertActorMsg;
+
+    /// Create a new [`AlertActorHandle`]
+    ///
+    /// This function creates a single [`AlertActor`] instance without rules,
+    /// and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(AlertActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to an [`AlertActor`] instance through the [`AlertActorHandle`]
+    async fn send(&self, msg: AlertActorMsg) -> Result<MsgResponseType, AlertMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl AlertActorHandle {
+    /// Create a new [`AlertActorHandle`] whose actor checks the batches of the `subscription`
+    /// against the rules of the `triggers`, feeds them to the trading strategy of the `triggers`,
+    /// if there is one, and dispatches the alerts to the `channels`
+    ///
+    /// The email alerts are sent by the `mailer`, and at most `chat_rate_limit` alerts per minute
+    /// are posted to every chat channel.
+    /// In a `dry_run`, the actor only logs the webhook requests and the emails that it would send.
+    ///
+    /// The batches are forwarded to the actor until the subscription ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    pub fn with_rules(
+        nticks: usize,
+        triggers: AlertTriggers,
+        channels: Vec<AlertChannel>,
+        mailer: Option<Mailer>,
+        chat_rate_limit: u32,
+        dry_run: bool,
+        mut subscription: mpsc::Receiver<Batch>,
+    ) -> Self {
+        let chat_limiters = channels
+            .iter()
+            .filter_map(|channel| match channel {
+                AlertChannel::Chat(_, url) => Some((
+                    url.clone(),
+                    RateLimiter::new(chat_rate_limit, Duration::from_secs(60)),
+                )),
+                _ => None,
+            })
+            .collect();
+        let sender = spawn_actor!(AlertActor, nticks, |actor| {
+            actor.chat_limiters = chat_limiters;
+            actor.triggers = triggers;
+            actor.channels = channels;
+            actor.mailer = mailer;
+            actor.dry_run = dry_run;
+        });
+
+        let forwarder = sender.clone();
+        runtime::spawn(async move {
+            while let Some(batch) = subscription.recv().await {
+                if forwarder
+                    .send(AlertActorMsg::Evaluate(batch))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Stops the [`AlertActor`]
+    ///
+    /// Alert requests that are already in flight aren't waited for.
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`AlertActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| AlertActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the AlertActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//  [`PaperTradingActorMsg`], [`PaperTradingActor`], [`PaperTradingActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`PaperTradingActorMsg`] enumeration
+///
+/// Supports four message types:
+/// - [`Trade`],
+/// - [`PlaceOrder`],
+/// - [`GetPortfolio`],
+/// - [`Shutdown`],
+///
+/// All but [`Trade`] expect a response, which is sent back through the provided `sender`.
+pub enum PaperTradingActorMsg {
+    /// A newly-assembled batch, whose prices become the fill prices,
+    /// and whose rows the trading strategy decides on
+    Trade(Batch),
+    /// An order from the web app, which is either filled or rejected
+    PlaceOrder {
+        order: Order,
+        sender: oneshot::Sender<Result<Fill>>,
+    },
+    /// A request for the cash, the positions and the newest fills
+    GetPortfolio { sender: oneshot::Sender<Portfolio> },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that keeps a paper-trading account across the iterations of the main loop
+///
+/// The orders are filled at the latest prices of the newly-assembled batches.
+/// For every row of a batch, the trading strategy is asked for a signal, given the symbol's
+/// prices of the previous batches; a buy signal buys shares for the order value,
+/// unless some are already held, and a sell signal sells all held shares.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`PaperTradingActorHandle`], which is public.
+struct PaperTradingActor {
+    receiver: mpsc::Receiver<PaperTradingActorMsg>,
+    account: PaperAccount,
+    /// The trading strategy, or `None` if only the web app places orders
+    engine: Option<StrategyEngine>,
+    /// The value of every buy order of the strategy
+    order_value: f64,
+}
+
+impl Actor<MsgResponseType> for PaperTradingActor {
+    type Msg = PaperTradingActorMsg;
+
+    /// Create a new [`PaperTradingActor`] with an empty account and without a strategy
+    fn new(receiver: mpsc::Receiver<PaperTradingActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            account: PaperAccount::new(0.0),
+            engine: None,
+            order_value: 0.0,
+        }
+    }
+
+    /// Run the [`PaperTradingActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("PaperTradingActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("PaperTradingActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`PaperTradingActorMsg`] message handler for the [`PaperTradingActor`] actor
+    async fn handle(&mut self, msg: PaperTradingActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            PaperTradingActorMsg::Trade(batch) => self.trade(&batch),
+            PaperTradingActorMsg::PlaceOrder { order, sender } => {
+                let fill = self.account.fill(order, OrderSource::Web, now());
+                if let Ok(fill) = &fill {
+                    tracing::info!("Paper trading: {:?} filled from the web app.", fill);
+                }
+                let _ = sender.send(fill);
+            }
+            PaperTradingActorMsg::GetPortfolio { sender } => {
+                let _ = sender.send(self.account.portfolio());
+            }
+            PaperTradingActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                let portfolio = self.account.portfolio();
+                tracing::info!(
+                    "Paper trading: the cash is ${:.2}, and the equity is ${:.2}, in {} positions.",
+                    portfolio.cash,
+                    portfolio.equity,
+                    portfolio.positions.len()
+                );
+                tracing::debug!("PaperTradingActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PaperTradingActor {
+    /// Takes the prices of a `batch`, and places the orders of the trading strategy for its rows
+    fn trade(&mut self, batch: &Batch) {
+        self.account.update_prices(batch);
+        let Some(engine) = &mut self.engine else {
+            return;
+        };
+
+        for (row, signal) in engine.signals(batch) {
+            let held = self.account.quantity(&row.symbol);
+            let (side, quantity) = match signal {
+                Signal::Buy if held == 0.0 && row.last_price > 0.0 => {
+                    (Side::Buy, self.order_value / row.last_price)
+                }
+                Signal::Sell if held > 0.0 => (Side::Sell, held),
+                _ => continue,
+            };
+            let order = Order {
+                symbol: row.symbol.to_string(),
+                side,
+                quantity,
+            };
+
+            match self.account.fill(order, OrderSource::Strategy, now()) {
+                Ok(fill) => tracing::info!("Paper trading: {:?} filled from the strategy.", fill),
+                Err(err) => tracing::info!(
+                    "Paper trading: the strategy's order for {} was rejected: {:#}",
+                    row.symbol,
+                    err
+                ),
+            }
+        }
+    }
+}
+
+/// The current time, in the RFC 3339 format
+fn now() -> String {
+    OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_default()
+}
+
+impl Drop for PaperTradingActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`PaperTradingActor`]
+///
+/// Only the handle is public; the [`PaperTradingActor`] isn't.
+///
+/// We can only create [`PaperTradingActor`]s through the [`PaperTradingActorHandle`].
+///
+/// We only create a single [`PaperTradingActor`] instance in a [`PaperTradingActorHandle`].
+#[derive(Clone)]
+pub struct PaperTradingActorHandle {
+    sender: mpsc::Sender<PaperTradingActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, PaperTradingMsgErrorType> for PaperTradingActorHandle {
+    type Msg = PaperTradingActorMsg;
+
+    /// Create a new [`PaperTradingActorHandle`]
+    ///
+    /// This function creates a single [`PaperTradingActor`] instance with an empty account
+    /// and without a strategy, and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(PaperTradingActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`PaperTradingActor`] instance through the [`PaperTradingActorHandle`]
+    async fn send(
+        &self,
+        msg: PaperTradingActorMsg,
+    ) -> Result<MsgResponseType, PaperTradingMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl PaperTradingActorHandle {
+    /// Create a new [`PaperTradingActorHandle`] whose actor opens an account with the `cash`,
+    /// and trades on the batches of the `subscription`, with the strategy of the `engine`,
+    /// whose buy orders are worth the `order_value` each
+    ///
+    /// The batches are forwarded to the actor until the subscription ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    pub fn with_account(
+        nticks: usize,
+        cash: f64,
+        engine: StrategyEngine,
+        order_value: f64,
+        mut subscription: mpsc::Receiver<Batch>,
+    ) -> Self {
+        let sender = spawn_actor!(PaperTradingActor, nticks, |actor| {
+            actor.account = PaperAccount::new(cash);
+            actor.engine = Some(engine);
+            actor.order_value = order_value;
+        });
+
+        let forwarder = sender.clone();
+        runtime::spawn(async move {
+            while let Some(batch) = subscription.recv().await {
+                if forwarder
+                    .send(PaperTradingActorMsg::Trade(batch))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Places an `order` in the paper-trading account
+    ///
+    /// # Returns
+    /// The fill, or the reason why the order was rejected
+    ///
+    /// # Errors
+    /// - If the [`PaperTradingActor`] isn't running
+    pub async fn place(&self, order: Order) -> Result<Result<Fill>> {
+        self.call(|sender| PaperTradingActorMsg::PlaceOrder { order, sender })
+            .await
+            .context("Couldn't call the PaperTradingActor.")
+    }
+
+    /// Returns the cash, the positions and the newest fills of the paper-trading account
+    ///
+    /// # Errors
+    /// - If the [`PaperTradingActor`] isn't running
+    pub async fn portfolio(&self) -> Result<Portfolio> {
+        self.call(|sender| PaperTradingActorMsg::GetPortfolio { sender })
+            .await
+            .context("Couldn't call the PaperTradingActor.")
+    }
+
+    /// Stops the [`PaperTradingActor`], and logs the state of its account
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`PaperTradingActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| PaperTradingActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the PaperTradingActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//      [`PortfolioActorMsg`], [`PortfolioActor`], [`PortfolioActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`PortfolioActorMsg`] enumeration
+///
+/// Supports five message types:
+/// - [`Value`],
+/// - [`SetHolding`],
+/// - [`RemoveHolding`],
+/// - [`GetValuation`],
+/// - [`Shutdown`],
+///
+/// All but [`Value`] expect a response, which is sent back through the provided `sender`.
+pub enum PortfolioActorMsg {
+    /// A newly-assembled batch, whose prices the holdings are valued at
+    Value(Batch),
+    /// A holding to add, or to replace the holding of its symbol with
+    SetHolding {
+        holding: Holding,
+        sender: oneshot::Sender<()>,
+    },
+    /// A request to remove the holding of a symbol, which is answered with whether there was one
+    RemoveHolding {
+        symbol: String,
+        sender: oneshot::Sender<bool>,
+    },
+    /// A request for the valuation of the holdings at the latest prices
+    GetValuation { sender: oneshot::Sender<Valuation> },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that values the holdings at the prices of every newly-assembled batch,
+/// and writes the valuations to the portfolio's CSV file
+///
+/// The file is created when there are holdings to value for the first time, so a run without
+/// holdings doesn't leave an empty file behind. It's created and written on the runtime's blocking
+/// threads, through [`runtime::spawn_blocking`], the way that the [`WriterActor`] writes its sinks.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`PortfolioActorHandle`], which is public.
+struct PortfolioActor {
+    receiver: mpsc::Receiver<PortfolioActorMsg>,
+    tracker: PortfolioTracker,
+    /// The path of the portfolio's CSV file, or `None` in a dry run
+    path: Option<PathBuf>,
+    csv: Option<ValuationCsv>,
+}
+
+impl Actor<MsgResponseType> for PortfolioActor {
+    type Msg = PortfolioActorMsg;
+
+    /// Create a new [`PortfolioActor`] without holdings, which writes no file
+    fn new(receiver: mpsc::Receiver<PortfolioActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            tracker: PortfolioTracker::default(),
+            path: None,
+            csv: None,
+        }
+    }
+
+    /// Run the [`PortfolioActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("PortfolioActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("PortfolioActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`PortfolioActorMsg`] message handler for the [`PortfolioActor`] actor
+    async fn handle(&mut self, msg: PortfolioActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            PortfolioActorMsg::Value(batch) => self.value(&batch).await?,
+            PortfolioActorMsg::SetHolding { holding, sender } => {
+                self.tracker.set(holding);
+                let _ = sender.send(());
+            }
+            PortfolioActorMsg::RemoveHolding { symbol, sender } => {
+                let _ = sender.send(self.tracker.remove(&symbol));
+            }
+            PortfolioActorMsg::GetValuation { sender } => {
+                let _ = sender.send(self.tracker.valuation(now()));
+            }
+            PortfolioActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("PortfolioActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PortfolioActor {
+    /// Takes the prices of a `batch`, and writes the valuation of the holdings at them,
+    /// on a blocking thread
+    ///
+    /// If the blocking thread panics, the file is lost, and it's created anew for the next batch.
+    ///
+    /// # Errors
+    /// - [std::io::Error] if the portfolio's CSV file can't be created or written
+    /// - [`ActorError::Blocking`], if the blocking thread panicked
+    async fn value(&mut self, batch: &Batch) -> Result<MsgResponseType, ActorError> {
+        self.tracker.update_prices(batch, OffsetDateTime::now_utc());
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        if self.tracker.is_empty() {
+            return Ok(());
+        }
+
+        let valuation = self.tracker.valuation(now());
+        let csv = self.csv.take();
+        let (csv, result) = runtime::spawn_blocking(move || {
+            let mut csv = match csv {
+                Some(csv) => csv,
+                None => match ValuationCsv::create(path) {
+                    Ok(csv) => csv,
+                    Err(err) => return (None, Err(err)),
+                },
+            };
+            let result = csv.write(&valuation);
+            (Some(csv), result)
+        })
+        .await?;
+        self.csv = csv;
+
+        Ok(result?)
+    }
+}
+
+impl Drop for PortfolioActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`PortfolioActor`]
+///
+/// Only the handle is public; the [`PortfolioActor`] isn't.
+///
+/// We can only create [`PortfolioActor`]s through the [`PortfolioActorHandle`].
+///
+/// We only create a single [`PortfolioActor`] instance in a [`PortfolioActorHandle`].
+#[derive(Clone)]
+pub struct PortfolioActorHandle {
+    sender: mpsc::Sender<PortfolioActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, PortfolioMsgErrorType> for PortfolioActorHandle {
+    type Msg = PortfolioActorMsg;
+
+    /// Create a new [`PortfolioActorHandle`]
+    ///
+    /// This function creates a single [`PortfolioActor`] instance without holdings,
+    /// which writes no file, and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(PortfolioActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`PortfolioActor`] instance through the [`PortfolioActorHandle`]
+    async fn send(&self, msg: PortfolioActorMsg) -> Result<MsgResponseType, PortfolioMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl PortfolioActorHandle {
+    /// Create a new [`PortfolioActorHandle`] whose actor values the `holdings`
+    /// at the prices of the batches of the `subscription`, and writes the valuations
+    /// to the CSV file at `path`, or to no file if it's `None`
+    ///
+    /// The batches are forwarded to the actor until the subscription ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    pub fn with_holdings(
+        nticks: usize,
+        holdings: Vec<Holding>,
+        path: Option<PathBuf>,
+        mut subscription: mpsc::Receiver<Batch>,
+    ) -> Self {
+        let sender = spawn_actor!(PortfolioActor, nticks, |actor| {
+            actor.tracker = PortfolioTracker::new(holdings);
+            actor.path = path;
+        });
+
+        let forwarder = sender.clone();
+        runtime::spawn(async move {
+            while let Some(batch) = subscription.recv().await {
+                if forwarder
+                    .send(PortfolioActorMsg::Value(batch))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Adds a `holding`, or replaces the holding of its symbol
+    ///
+    /// # Errors
+    /// - If the [`PortfolioActor`] isn't running
+    pub async fn set(&self, holding: Holding) -> Result<()> {
+        self.call(|sender| PortfolioActorMsg::SetHolding { holding, sender })
+            .await
+            .context("Couldn't call the PortfolioActor.")
+    }
+
+    /// Removes the holding of the `symbol`
+    ///
+    /// # Returns
+    /// Whether there was a holding of the symbol
+    ///
+    /// # Errors
+    /// - If the [`PortfolioActor`] isn't running
+    pub async fn remove(&self, symbol: String) -> Result<bool> {
+        self.call(|sender| PortfolioActorMsg::RemoveHolding { symbol, sender })
+            .await
+            .context("Couldn't call the PortfolioActor.")
+    }
+
+    /// Returns the valuation of the holdings at the latest prices
+    ///
+    /// # Errors
+    /// - If the [`PortfolioActor`] isn't running
+    pub async fn valuation(&self) -> Result<Valuation> {
+        self.call(|sender| PortfolioActorMsg::GetValuation { sender })
+            .await
+            .context("Couldn't call the PortfolioActor.")
+    }
+
+    /// Stops the [`PortfolioActor`]
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`PortfolioActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| PortfolioActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the PortfolioActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//      [`WatchlistActorMsg`], [`WatchlistActor`], [`WatchlistActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`WatchlistActorMsg`] enumeration
+///
+/// Supports four message types:
+/// - [`Pick`],
+/// - [`GetWatchlists`],
+/// - [`TailRequest`],
+/// - [`Shutdown`],
+///
+/// All but [`Pick`] expect a response, which is sent back through the provided `sender`.
+pub enum WatchlistActorMsg {
+    /// A newly-assembled batch, whose rows of every watchlist are picked out of it
+    Pick(Batch),
+    /// A request for the watchlists
+    GetWatchlists {
+        sender: oneshot::Sender<Vec<Watchlist>>,
+    },
+    /// A request for the newest `n` batches of a watchlist, newest first,
+    /// which is answered with `None` if there is no such watchlist
+    TailRequest {
+        name: String,
+        n: usize,
+        sender: oneshot::Sender<Option<TailResponse>>,
+    },
+    /// A request to stop, which is answered through the provided `sender`
+    /// with the result of closing the output files, so that no data loss goes unnoticed
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown {
+        sender: oneshot::Sender<Result<MsgResponseType, ActorError>>,
+    },
+}
+
+/// Actor that picks the rows of every watchlist out of every newly-assembled batch,
+/// keeps the newest batches of every watchlist, and writes them to the watchlist's own output files
+///
+/// The files are created when the first batch arrives, so a run without watchlists doesn't leave
+/// empty files behind. They are opened, written, flushed and closed on the runtime's blocking
+/// threads, through [`runtime::spawn_blocking`], the way that the [`WriterActor`] writes its sinks.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`WatchlistActorHandle`], which is public.
+struct WatchlistActor {
+    receiver: mpsc::Receiver<WatchlistActorMsg>,
+    tails: Vec<WatchlistBuffer>,
+    /// The start of the period, which the rows are written with
+    from: String,
+    /// The formats of the output files; none in a dry run
+    formats: Vec<OutputFormat>,
+    /// The sinks of every watchlist, in the order of the watchlists, once they've been opened;
+    /// none if they couldn't be opened
+    sinks: Option<Vec<Vec<Box<dyn Sink>>>>,
+}
+
+impl Actor<MsgResponseType> for WatchlistActor {
+    type Msg = WatchlistActorMsg;
+
+    /// Create a new [`WatchlistActor`] without watchlists, which writes no files
+    fn new(receiver: mpsc::Receiver<WatchlistActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            tails: Vec::new(),
+            from: String::new(),
+            formats: Vec::new(),
+            sinks: None,
+        }
+    }
+
+    /// Run the [`WatchlistActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("WatchlistActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("WatchlistActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`WatchlistActorMsg`] message handler for the [`WatchlistActor`] actor
+    async fn handle(&mut self, msg: WatchlistActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            WatchlistActorMsg::Pick(batch) => self.pick(&batch).await?,
+            WatchlistActorMsg::GetWatchlists { sender } => {
+                let watchlists = self
+                    .tails
+                    .iter()
+                    .map(|tail| tail.watchlist.clone())
+                    .collect();
+                let _ = sender.send(watchlists);
+            }
+            WatchlistActorMsg::TailRequest { name, n, sender } => {
+                let tail = self
+                    .tails
+                    .iter()
+                    .find(|tail| tail.watchlist.name == name)
+                    .map(|tail| tail.newest(n));
+                let _ = sender.send(tail);
+            }
+            WatchlistActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                let sinks = self.sinks.take().into_iter().flatten().flatten().collect();
+                let closed = runtime::spawn_blocking(move || WriterActor::close_sinks(sinks))
+                    .await
+                    .unwrap_or_else(|err| Err(err.into()));
+                tracing::debug!("WatchlistActor is shutting down.");
+                let _ = sender.send(closed);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl WatchlistActor {
+    /// Picks the rows of every watchlist out of a `batch`, keeps them in the watchlist's tail,
+    /// and then writes them to the watchlist's files, on a blocking thread
+    ///
+    /// The rows are kept in the tails even if the files can't be written. The files are opened
+    /// only once; if they can't be, the error is reported, and no files are written afterwards.
+    /// If the blocking thread panics, the sinks are lost, and the following batches aren't written.
+    ///
+    /// # Errors
+    /// - [std::io::Error] if an output file can't be created or written
+    /// - [`ActorError::Blocking`], if the blocking thread panicked
+    async fn pick(&mut self, batch: &Batch) -> Result<MsgResponseType, ActorError> {
+        let rows: Vec<Batch> = self
+            .tails
+            .iter_mut()
+            .map(|tail| tail.push(batch).clone())
+            .collect();
+
+        let stems: Vec<String> = self
+            .tails
+            .iter()
+            .map(|tail| tail.watchlist.file_stem())
+            .collect();
+        let formats = self.formats.clone();
+        let from = self.from.clone();
+        // the sinks are handed over to the blocking thread, and they're lost if it panics
+        let sinks = self.sinks.replace(Vec::new());
+        let (sinks, result) = runtime::spawn_blocking(move || {
+            let mut sinks = match sinks {
+                Some(sinks) => sinks,
+                None => match Self::open_sinks(&stems, &formats) {
+                    Ok(sinks) => sinks,
+                    Err(err) => return (Vec::new(), Err(err)),
+                },
+            };
+            let result = Self::write_rows(&mut sinks, &from, &rows);
+            (sinks, result)
+        })
+        .await?;
+        self.sinks = Some(sinks);
+
+        Ok(result?)
+    }
+
+    /// Opens a sink of each of the `formats` for every watchlist, at the paths of its file `stems`
+    ///
+    /// It blocks, so it's meant to be run on a blocking thread.
+    fn open_sinks(
+        stems: &[String],
+        formats: &[OutputFormat],
+    ) -> std::io::Result<Vec<Vec<Box<dyn Sink>>>> {
+        stems
+            .iter()
+            .map(|stem| {
+                formats
+                    .iter()
+                    .map(|format| sinks::open_at(format, stem, false))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Writes the `rows` of every watchlist to its `sinks`, and flushes them
+    ///
+    /// It blocks, so it's meant to be run on a blocking thread.
+    fn write_rows(
+        sinks: &mut [Vec<Box<dyn Sink>>],
+        from: &str,
+        rows: &[Batch],
+    ) -> std::io::Result<()> {
+        for (rows, sinks) in rows.iter().zip(sinks) {
+            for sink in sinks {
+                sink.write(from, rows)?;
+                sink.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for WatchlistActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`WatchlistActor`]
+///
+/// Only the handle is public; the [`WatchlistActor`] isn't.
+///
+/// We can only create [`WatchlistActor`]s through the [`WatchlistActorHandle`].
+///
+/// We only create a single [`WatchlistActor`] instance in a [`WatchlistActorHandle`].
+#[derive(Clone)]
+pub struct WatchlistActorHandle {
+    sender: mpsc::Sender<WatchlistActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, WatchlistMsgErrorType> for WatchlistActorHandle {
+    type Msg = WatchlistActorMsg;
+
+    /// Create a new [`WatchlistActorHandle`]
+    ///
+    /// This function creates a single [`WatchlistActor`] instance without watchlists,
+    /// which writes no files, and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(WatchlistActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`WatchlistActor`] instance through the [`WatchlistActorHandle`]
+    async fn send(&self, msg: WatchlistActorMsg) -> Result<MsgResponseType, WatchlistMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl WatchlistActorHandle {
+    /// Create a new [`WatchlistActorHandle`] whose actor picks the rows of the `watchlists`
+    /// out of the batches of the `subscription`, keeps up to `tail_size` of the newest batches
+    /// of every watchlist, and writes them, with the start of the period, `from`,
+    /// to a file of each of the `formats`
+    ///
+    /// The batches are forwarded to the actor until the subscription ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    pub fn with_watchlists(
+        nticks: usize,
+        watchlists: Vec<Watchlist>,
+        tail_size: usize,
+        from: String,
+        formats: Vec<OutputFormat>,
+        mut subscription: mpsc::Receiver<Batch>,
+    ) -> Self {
+        let sender = spawn_actor!(WatchlistActor, nticks, |actor| {
+            actor.tails = watchlists
+                .into_iter()
+                .map(|watchlist| WatchlistBuffer::new(watchlist, tail_size))
+                .collect();
+            actor.from = from;
+            actor.formats = formats;
+        });
+
+        let forwarder = sender.clone();
+        runtime::spawn(async move {
+            while let Some(batch) = subscription.recv().await {
+                if forwarder
+                    .send(WatchlistActorMsg::Pick(batch))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Returns the watchlists
+    ///
+    /// # Errors
+    /// - If the [`WatchlistActor`] isn't running
+    pub async fn watchlists(&self) -> Result<Vec<Watchlist>> {
+        self.call(|sender| WatchlistActorMsg::GetWatchlists { sender })
+            .await
+            .context("Couldn't call the WatchlistActor.")
+    }
+
+    /// Returns the newest `n` batches of the watchlist `name`, newest first,
+    /// or `None` if there is no such watchlist
+    ///
+    /// # Errors
+    /// - If the [`WatchlistActor`] isn't running
+    pub async fn tail(&self, name: String, n: usize) -> Result<Option<TailResponse>> {
+        self.call(|sender| WatchlistActorMsg::TailRequest { name, n, sender })
+            .await
+            .context("Couldn't call the WatchlistActor.")
+    }
+
+    /// Stops the [`WatchlistActor`], after it has closed its output files
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`WatchlistActor`] isn't running
+    /// - If an output file couldn't be flushed or closed
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| WatchlistActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the WatchlistActor.")?
+            .context("The WatchlistActor couldn't close the watchlists' output files.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//         [`AuditActorMsg`], [`AuditActor`], [`AuditActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`AuditActorMsg`] enumeration
+///
+/// Supports two message types:
+/// - [`Record`],
+/// - [`Shutdown`],
+///
+/// Only [`Shutdown`] expects a response, which is sent back through the provided `sender`.
+pub enum AuditActorMsg {
+    /// A web request that has been answered, which is appended to the audit log
+    Record(AuditRecord),
+    /// A request to stop, which is acknowledged through the provided `sender`
+    /// after the audit log has been closed
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that appends every recorded web request to the audit log
+///
+/// The records are written and flushed on the runtime's blocking threads, through
+/// [`runtime::spawn_blocking`], the way that the [`WriterActor`] writes its sinks.
+/// The audit log is handed over to the blocking thread, and back, for every record.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`AuditActorHandle`], which is public.
+struct AuditActor {
+    receiver: mpsc::Receiver<AuditActorMsg>,
+    /// The audit log, which is closed on shutdown
+    log: Option<AuditLog>,
+}
+
+impl Actor<MsgResponseType> for AuditActor {
+    type Msg = AuditActorMsg;
+
+    /// Create a new [`AuditActor`] without an audit log, which records nothing
+    fn new(receiver: mpsc::Receiver<AuditActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            log: None,
+        }
+    }
+
+    /// Run the [`AuditActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("AuditActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("AuditActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`AuditActorMsg`] message handler for the [`AuditActor`] actor
+    async fn handle(&mut self, msg: AuditActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            AuditActorMsg::Record(record) => {
+                if let Some(mut log) = self.log.take() {
+                    let (log, result) = runtime::spawn_blocking(move || {
+                        let result = log.write(&record);
+                        (log, result)
+                    })
+                    .await?;
+                    self.log = Some(log);
+                    result?;
+                }
+            }
+            AuditActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                self.log = None;
+                tracing::debug!("AuditActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for AuditActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`AuditActor`]
+///
+/// Only the handle is public; the [`AuditActor`] isn't.
+///
+/// We can only create [`AuditActor`]s through the [`AuditActorHandle`].
+///
+/// We only create a single [`AuditActor`] instance in a [`AuditActorHandle`].
+#[derive(Clone)]
+pub struct AuditActorHandle {
+    sender: mpsc::Sender<AuditActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, AuditMsgErrorType> for AuditActorHandle {
+    type Msg = AuditActorMsg;
+
+    /// Create a new [`AuditActorHandle`]
+    ///
+    /// This function creates a single [`AuditActor`] instance without an audit log,
+    /// which records nothing, and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(AuditActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to an [`AuditActor`] instance through the [`AuditActorHandle`]
+    async fn send(&self, msg: AuditActorMsg) -> Result<MsgResponseType, AuditMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl AuditActorHandle {
+    /// Create a new [`AuditActorHandle`] whose actor appends the recorded web requests
+    /// to the audit log at `path`, which is created if it doesn't exist
+    ///
+    /// # Errors
+    /// - [std::io::Error] if the audit log can't be opened
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    pub fn with_path(nticks: usize, path: &Path) -> Result<Self> {
+        let log = AuditLog::open(path)
+            .with_context(|| format!("Couldn't open the audit log {}.", path.display()))?;
+        let sender = spawn_actor!(AuditActor, nticks, |actor| {
+            actor.log = Some(log);
+        });
+
+        Ok(Self { sender })
+    }
+
+    /// Stops the [`AuditActor`], after it has closed the audit log
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`AuditActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| AuditActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the AuditActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//      [`EventLogActorMsg`], [`EventLogActor`], [`EventLogActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`EventLogActorMsg`] enumeration
+///
+/// Supports two message types:
+/// - [`Append`],
+/// - [`Shutdown`],
+///
+/// Only [`Shutdown`] expects a response, which is sent back through the provided `sender`.
+pub enum EventLogActorMsg {
+    /// A newly-assembled batch, which is appended to the event log
+    Append(Batch),
+    /// A request to stop, which is acknowledged through the provided `sender`
+    /// after the event log has been closed
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that appends every newly-assembled batch to the event log, with its sequence number
+///
+/// The batches are written and flushed on the runtime's blocking threads, through
+/// [`runtime::spawn_blocking`], the way that the [`WriterActor`] writes its sinks.
+/// The event log is handed over to the blocking thread, and back, for every batch.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`EventLogActorHandle`], which is public.
+struct EventLogActor {
+    receiver: mpsc::Receiver<EventLogActorMsg>,
+    /// The event log, which is closed on shutdown
+    log: Option<EventLog>,
+    /// The start of the period, which the batches are appended with
+    from: String,
+}
+
+impl Actor<MsgResponseType> for EventLogActor {
+    type Msg = EventLogActorMsg;
+
+    /// Create a new [`EventLogActor`] without an event log, which appends nothing
+    fn new(receiver: mpsc::Receiver<EventLogActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            log: None,
+            from: String::new(),
+        }
+    }
+
+    /// Run the [`EventLogActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("EventLogActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("EventLogActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`EventLogActorMsg`] message handler for the [`EventLogActor`] actor
+    async fn handle(&mut self, msg: EventLogActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            EventLogActorMsg::Append(batch) => {
+                if let Some(mut log) = self.log.take() {
+                    let from = self.from.clone();
+                    let (log, result) = runtime::spawn_blocking(move || {
+                        let result = log.append(&from, batch, OffsetDateTime::now_utc());
+                        (log, result)
+                    })
+                    .await?;
+                    self.log = Some(log);
+                    let seq = result?;
+                    tracing::debug!("Appended the batch {} to the event log.", seq);
+                }
+            }
+            EventLogActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                self.log = None;
+                tracing::debug!("EventLogActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for EventLogActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`EventLogActor`]
+///
+/// Only the handle is public; the [`EventLogActor`] isn't.
+///
+/// We can only create [`EventLogActor`]s through the [`EventLogActorHandle`].
+///
+/// We only create a single [`EventLogActor`] instance in a [`EventLogActorHandle`].
+#[derive(Clone)]
+pub struct EventLogActorHandle {
+    sender: mpsc::Sender<EventLogActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, EventLogMsgErrorType> for EventLogActorHandle {
+    type Msg = EventLogActorMsg;
+
+    /// Create a new [`EventLogActorHandle`]
+    ///
+    /// This function creates a single [`EventLogActor`] instance without an event log,
+    /// which appends nothing, and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(EventLogActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to an [`EventLogActor`] instance through the [`EventLogActorHandle`]
+    async fn send(&self, msg: EventLogActorMsg) -> Result<MsgResponseType, EventLogMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl EventLogActorHandle {
+    /// Create a new [`EventLogActorHandle`] whose actor appends the batches of the `subscription`,
+    /// with the start of the period, `from`, to the event log at `path`, which is created
+    /// if it doesn't exist
+    ///
+    /// The batches are forwarded to the actor until the subscription ends.
+    ///
+    /// # Errors
+    /// - [std::io::Error] if the event log can't be read or opened
+    /// - If an event of the event log isn't valid
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    pub fn with_path(
+        nticks: usize,
+        path: &Path,
+        from: String,
+        mut subscription: mpsc::Receiver<Batch>,
+    ) -> Result<Self> {
+        let log = EventLog::open(path)
+            .with_context(|| format!("Couldn't open the event log {}.", path.display()))?;
+        let sender = spawn_actor!(EventLogActor, nticks, |actor| {
+            actor.log = Some(log);
+            actor.from = from;
+        });
+
+        let forwarder = sender.clone();
+        runtime::spawn(async move {
+            while let Some(batch) = subscription.recv().await {
+                if forwarder
+                    .send(EventLogActorMsg::Append(batch))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self { sender })
+    }
+
+    /// Stops the [`EventLogActor`], after it has closed the event log
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`EventLogActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| EventLogActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the EventLogActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//          [`NewsActorMsg`], [`NewsActor`], [`NewsActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`NewsActorMsg`] enumeration
+///
+/// Supports three message types:
+/// - [`Advance`],
+/// - [`HeadlinesRequest`],
+/// - [`Shutdown`],
+///
+/// All but [`Advance`] expect a response, which is sent back through the provided `sender`.
+pub enum NewsActorMsg {
+    /// A newly-assembled batch has been published, so the cached headlines are dropped
+    Advance,
+    /// A request for the recent headlines of a `symbol`, which is answered with the sequence
+    /// number of the iteration that they are cached for and the headlines, or with the reason
+    /// why they couldn't be retrieved
+    HeadlinesRequest {
+        symbol: String,
+        sender: oneshot::Sender<Result<(u64, Vec<Headline>)>>,
+    },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that retrieves the recent headlines of symbols from a news provider,
+/// and caches them until the next batch is assembled
+///
+/// The requests are handled one at a time, so concurrent requests for the same symbol
+/// ask the provider only once.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`NewsActorHandle`], which is public.
+struct NewsActor {
+    receiver: mpsc::Receiver<NewsActorMsg>,
+    /// The news provider, or `None` if the headlines aren't retrieved
+    provider: Option<Box<dyn NewsProvider>>,
+    cache: NewsCache,
+}
+
+impl Actor<MsgResponseType> for NewsActor {
+    type Msg = NewsActorMsg;
+
+    /// Create a new [`NewsActor`] without a news provider, which retrieves no headlines
+    fn new(receiver: mpsc::Receiver<NewsActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            provider: None,
+            cache: NewsCache::default(),
+        }
+    }
+
+    /// Run the [`NewsActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("NewsActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("NewsActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`NewsActorMsg`] message handler for the [`NewsActor`] actor
+    async fn handle(&mut self, msg: NewsActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            NewsActorMsg::Advance => self.cache.advance(),
+            NewsActorMsg::HeadlinesRequest { symbol, sender } => {
+                let headlines = self.headlines(symbol).await;
+                sender
+                    .send(headlines.map(|headlines| (self.cache.iteration(), headlines)))
+                    .map_err(|_| ActorError::Reply("the headlines"))?;
+            }
+            NewsActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("NewsActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl NewsActor {
+    /// Returns the cached headlines of a `symbol`, or retrieves them from the news provider,
+    /// and caches them for the current iteration
+    ///
+    /// # Errors
+    /// - If there is no news provider
+    /// - If the provider fails, or doesn't respond in time
+    async fn headlines(&mut self, symbol: String) -> Result<Vec<Headline>> {
+        if let Some(headlines) = self.cache.get(&symbol) {
+            return Ok(headlines.clone());
+        }
+
+        let provider = self
+            .provider
+            .as_ref()
+            .context("There is no news provider.")?;
+        let timeout = Duration::from_secs(NEWS_TIMEOUT_SECS);
+        let mut headlines = runtime::timeout(timeout, provider.headlines(&symbol))
+            .await
+            .map_err(|_| ActorError::Timeout(timeout))??;
+        headlines.truncate(NEWS_SIZE);
+        tracing::debug!("Retrieved {} headlines of {}.", headlines.len(), symbol);
+
+        self.cache.insert(symbol, headlines.clone());
+
+        Ok(headlines)
+    }
+}
+
+impl Drop for NewsActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`NewsActor`]
+///
+/// Only the handle is public; the [`NewsActor`] isn't.
+///
+/// We can only create [`NewsActor`]s through the [`NewsActorHandle`].
+///
+/// We only create a single [`NewsActor`] instance in a [`NewsActorHandle`].
+#[derive(Clone)]
+pub struct NewsActorHandle {
+    sender: mpsc::Sender<NewsActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, NewsMsgErrorType> for NewsActorHandle {
+    type Msg = NewsActorMsg;
+
+    /// Create a new [`NewsActorHandle`]
+    ///
+    /// This function creates a single [`NewsActor`] instance without a news provider,
+    /// which retrieves no headlines, and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(NewsActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`NewsActor`] instance through the [`NewsActorHandle`]
+    async fn send(&self, msg: NewsActorMsg) -> Result<MsgResponseType, NewsMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl NewsActorHandle {
+    /// Create a new [`NewsActorHandle`] whose actor retrieves the headlines from the `provider`,
+    /// and drops the cached ones whenever a batch of the `subscription` arrives
+    ///
+    /// The batches are forwarded to the actor until the subscription ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    pub fn with_provider(
+        nticks: usize,
+        provider: Box<dyn NewsProvider>,
+        mut subscription: mpsc::Receiver<Batch>,
+    ) -> Self {
+        let sender = spawn_actor!(NewsActor, nticks, |actor| {
+            actor.provider = Some(provider);
+        });
+
+        let forwarder = sender.clone();
+        runtime::spawn(async move {
+            while subscription.recv().await.is_some() {
+                if forwarder.send(NewsActorMsg::Advance).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Returns the sequence number of the current iteration and the recent headlines of a `symbol`,
+    /// newest first, or the reason why they couldn't be retrieved
+    ///
+    /// # Errors
+    /// - If the [`NewsActor`] isn't running
+    pub async fn headlines(&self, symbol: String) -> Result<Result<(u64, Vec<Headline>)>> {
+        self.call(|sender| NewsActorMsg::HeadlinesRequest { symbol, sender })
+            .await
+            .context("Couldn't call the NewsActor.")
+    }
+
+    /// Stops the [`NewsActor`]
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`NewsActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| NewsActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the NewsActor.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//       [`OptionsActorMsg`], [`OptionsActor`], [`OptionsActorHandle`]
+//
+//
+//
+//
+// ============================================================================
+
+/// The [`OptionsActorMsg`] enumeration
+///
+/// Supports three message types:
+/// - [`Fetch`],
+/// - [`SummariesRequest`],
+/// - [`Shutdown`],
+///
+/// All but [`Fetch`] expect a response, which is sent back through the provided `sender`.
+pub enum OptionsActorMsg {
+    /// A newly-assembled batch has been published, so the option chains are fetched again
+    Fetch,
+    /// A request for the newest summaries of the underlyings' option chains
+    SummariesRequest {
+        sender: oneshot::Sender<Vec<OptionsSummary>>,
+    },
+    /// A request to stop, which is answered through the provided `sender`
+    /// with the result of closing the output file, so that no data loss goes unnoticed
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown {
+        sender: oneshot::Sender<Result<MsgResponseType, ActorError>>,
+    },
+}
+
+/// Actor that fetches the option chains of the underlyings whenever a batch has been assembled,
+/// summarizes them, keeps the newest summaries, and writes them to their own file
+///
+/// An underlying whose chain couldn't be fetched keeps its previous summary.
+///
+/// The file is written, flushed and closed on the runtime's blocking threads, through
+/// [`runtime::spawn_blocking`], the way that the [`WriterActor`] writes its sinks.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`OptionsActorHandle`], which is public.
+struct OptionsActor {
+    receiver: mpsc::Receiver<OptionsActorMsg>,
+    /// The options provider, or `None` if no chains are fetched
+    provider: Option<Box<dyn OptionsProvider>>,
+    underlyings: Vec<String>,
+    /// The newest summary of every underlying whose chain has been fetched
+    summaries: BTreeMap<String, OptionsSummary>,
+    /// The output file, which is closed on shutdown; none in a dry run
+    csv: Option<OptionsCsv>,
+}
+
+impl Actor<MsgResponseType> for OptionsActor {
+    type Msg = OptionsActorMsg;
+
+    /// Create a new [`OptionsActor`] without an options provider, which fetches nothing
+    fn new(receiver: mpsc::Receiver<OptionsActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            provider: None,
+            underlyings: Vec::new(),
+            summaries: BTreeMap::new(),
+            csv: None,
+        }
+    }
+
+    /// Run the [`OptionsActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("OptionsActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("OptionsActor", &err, None).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`OptionsActorMsg`] message handler for the [`OptionsActor`] actor
+    async fn handle(&mut self, msg: OptionsActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            OptionsActorMsg::Fetch => self.fetch().await?,
+            OptionsActorMsg::SummariesRequest { sender } => {
+                let summaries = self
+                    .underlyings
+                    .iter()
+                    .filter_map(|underlying| self.summaries.get(underlying).cloned())
+                    .collect();
+                sender
+                    .send(summaries)
+                    .map_err(|_| ActorError::Reply("the option chains' summaries"))?;
+            }
+            OptionsActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                let closed = match self.csv.take() {
+                    Some(csv) => runtime::spawn_blocking(move || csv.close())
+                        .await
+                        .map_err(ActorError::from)
+                        .and_then(|closed| Ok(closed?)),
+                    None => Ok(()),
+                };
+                tracing::debug!("OptionsActor is shutting down.");
+                let _ = sender.send(closed);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl OptionsActor {
+    /// Fetches and summarizes the option chains of the underlyings, and writes the summaries
+    ///
+    /// A chain that can't be fetched, or that isn't fetched in time, is logged at the warning level.
+    ///
+    /// The summaries are written on a blocking thread. If it panics, the file is lost,
+    /// and the following summaries aren't written.
+    ///
+    /// # Errors
+    /// - [std::io::Error] if the output file can't be written
+    /// - [`ActorError::Blocking`], if the blocking thread panicked
+    async fn fetch(&mut self) -> Result<MsgResponseType, ActorError> {
+        let Some(provider) = &self.provider else {
+            return Ok(());
+        };
+
+        let timeout = Duration::from_secs(OPTIONS_TIMEOUT_SECS);
+        let mut fetched = Vec::with_capacity(self.underlyings.len());
+        for underlying in &self.underlyings {
+            let chain = match runtime::timeout(timeout, provider.chain(underlying)).await {
+                Ok(Ok(chain)) => chain,
+                Ok(Err(err)) => {
+                    tracing::warn!("{:#}", err);
+                    continue;
+                }
+                Err(_) => {
+                    tracing::warn!(
+                        "Fetching the option chain of {} timed out after {:?}.",
+                        underlying,
+                        timeout
+                    );
+                    continue;
+                }
+            };
+            let summary = OptionsSummary::new(
+                underlying.clone(),
+                OffsetDateTime::now_utc().format(&Rfc3339)?,
+                &chain,
+            );
+            tracing::info!(
+                target: ROWS_TRACING_TARGET,
+                "Options of {}: put/call ratio {}, max pain {}",
+                underlying,
+                summary
+                    .put_call_ratio
+                    .map_or("n/a".to_string(), |ratio| format!("{:.2}", ratio)),
+                summary
+                    .max_pain
+                    .map_or("n/a".to_string(), |strike| format!("{:.2}", strike))
+            );
+            fetched.push(summary);
+        }
+
+        let fetched = match self.csv.take() {
+            Some(mut csv) => {
+                let (csv, fetched, result) = runtime::spawn_blocking(move || {
+                    let result = csv.write(&fetched);
+                    (csv, fetched, result)
+                })
+                .await?;
+                self.csv = Some(csv);
+                result?;
+                fetched
+            }
+            None => fetched,
+        };
+        for summary in fetched {
+            self.summaries.insert(summary.underlying.clone(), summary);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for OptionsActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`OptionsActor`]
+///
+/// Only the handle is public; the [`OptionsActor`] isn't.
+///
+/// We can only create [`OptionsActor`]s through the [`OptionsActorHandle`].
+///
+/// We only create a single [`OptionsActor`] instance in a [`OptionsActorHandle`].
+#[derive(Clone)]
+pub struct OptionsActorHandle {
+    sender: mpsc::Sender<OptionsActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, OptionsMsgErrorType> for OptionsActorHandle {
+    type Msg = OptionsActorMsg;
+
+    /// Create a new [`OptionsActorHandle`]
+    ///
+    /// This function creates a single [`OptionsActor`] instance without an options provider,
+    /// which fetches nothing, and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(OptionsActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to an [`OptionsActor`] instance through the [`OptionsActorHandle`]
+    async fn send(&self, msg: OptionsActorMsg) -> Result<MsgResponseType, OptionsMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl OptionsActorHandle {
+    /// Create a new [`OptionsActorHandle`] whose actor fetches the option chains of the `underlyings`
+    /// from the `provider` whenever a batch of the `subscription` arrives, and writes their summaries
+    /// to the file at `path`, unless it's `None`
+    ///
+    /// The batches are forwarded to the actor until the subscription ends.
+    ///
+    /// # Errors
+    /// - [std::io::Error] if the output file can't be created
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    pub fn with_underlyings(
+        nticks: usize,
+        provider: Box<dyn OptionsProvider>,
+        underlyings: Vec<String>,
+        path: Option<&Path>,
+        mut subscription: mpsc::Receiver<Batch>,
+    ) -> Result<Self> {
+        let csv = path
+            .map(|path| {
+                OptionsCsv::create(path).with_context(|| {
+                    format!("Couldn't create the options file {}.", path.display())
+                })
+            })
+            .transpose()?;
+        let sender = spawn_actor!(OptionsActor, nticks, |actor| {
+            actor.provider = Some(provider);
+            actor.underlyings = underlyings;
+            actor.csv = csv;
+        });
+
+        let forwarder = sender.clone();
+        runtime::spawn(async move {
+            while subscription.recv().await.is_some() {
+                if forwarder.send(OptionsActorMsg::Fetch).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self { sender })
+    }
+
+    /// Returns the newest summaries of the underlyings' option chains, in the order of the underlyings
+    ///
+    /// The underlyings whose chains haven't been fetched yet are left out.
+    ///
+    /// # Errors
+    /// - If the [`OptionsActor`] isn't running
+    pub async fn summaries(&self) -> Result<Vec<OptionsSummary>> {
+        self.call(|sender| OptionsActorMsg::SummariesRequest { sender })
+            .await
+            .context("Couldn't call the OptionsActor.")
+    }
+
+    /// Stops the [`OptionsActor`], after it has closed its output file
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`OptionsActor`] isn't running
+    /// - If the output file couldn't be flushed or closed
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| OptionsActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the OptionsActor.")?
+            .context("The OptionsActor couldn't close the options file.")
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//   [`DeadLetterActorMsg`], [`DeadLetterActor`], [`DeadLetterActorHandle`],
+//                        [`DeadLetter`], [`DeadLetters`]
+//
+//
+//
+//
+// ============================================================================
+
+/// A message that couldn't be delivered to an actor, or that an actor failed to handle
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct DeadLetter {
+    /// When the message was given up on, in the RFC 3339 format
+    pub time: String,
+    /// The actor that the message was meant for
+    pub actor: String,
+    /// The kind of the message
+    ///
+    /// It isn't known for messages that the actor failed to handle, because they are consumed.
+    pub message: Option<String>,
+    /// Why the message was neither delivered nor handled
+    pub reason: String,
+}
+
+impl DeadLetter {
+    /// Create a new [`DeadLetter`], timestamped now
+    fn new(actor: &str, message: Option<String>, reason: String) -> Self {
+        Self {
+            time: OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .unwrap_or_default(),
+            actor: actor.to_string(),
+            message,
+            reason,
+        }
+    }
+}
+
+impl Display for DeadLetter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{} to {}: {}", message, self.actor, self.reason),
+            None => write!(f, "a message to {}: {}", self.actor, self.reason),
+        }
+    }
+}
+
+/// The dead letters that the [`DeadLetterActor`] has recorded
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct DeadLetters {
+    /// The number of dead letters since start
+    pub total: u64,
+    /// The most recent dead letters, newest first
+    ///
+    /// At most [`DEAD_LETTER_BUFFER_SIZE`] of them are kept.
+    pub recent: Vec<DeadLetter>,
+}
+
+/// The [`DeadLetterActorMsg`] enumeration
+///
+/// Supports three message types:
+/// - [`DeadLetter`],
+/// - [`Get`],
+/// - [`Shutdown`],
+///
+/// Only [`Get`] and [`Shutdown`] expect a response, which is sent back through the provided `sender`.
+pub enum DeadLetterActorMsg {
+    /// A message that couldn't be delivered or handled
+    DeadLetter(DeadLetter),
+    /// A request for the recorded dead letters
+    Get {
+        sender: oneshot::Sender<DeadLetters>,
+    },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that records messages that couldn't be delivered to other actors,
+/// or that other actors failed to handle, so that they don't vanish
+///
+/// Every dead letter is logged, kept in a bounded buffer, which can be read
+/// through the web app, and counted in the statistics.
+///
+/// If the dead letter actor can't reach the [`StatsActor`], it records that as
+/// a dead letter of its own.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`DeadLetterActorHandle`], which is public.
+struct DeadLetterActor {
+    receiver: mpsc::Receiver<DeadLetterActorMsg>,
+    /// The most recent dead letters, oldest first
+    buffer: VecDeque<DeadLetter>,
+    total: u64,
+    stats_handle: Option<StatsActorHandle>,
+}
+
+impl Actor<MsgResponseType> for DeadLetterActor {
+    type Msg = DeadLetterActorMsg;
+
+    /// Create a new [`DeadLetterActor`]
+    fn new(receiver: mpsc::Receiver<DeadLetterActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            buffer: VecDeque::with_capacity(DEAD_LETTER_BUFFER_SIZE),
+            total: 0,
+            stats_handle: None,
+        }
+    }
+
+    /// Run the [`DeadLetterActor`]
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("DeadLetterActor is running.");
+
+        while let Some(msg) = self.receiver.recv().await {
+            if let Err(err) = self.handle_intercepted(msg).await {
+                report_error("DeadLetterActor", &err, None).await;
+                self.record(DeadLetter::new("DeadLetterActor", None, err.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`DeadLetterActorMsg`] message handler for the [`DeadLetterActor`] actor
+    async fn handle(&mut self, msg: DeadLetterActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            DeadLetterActorMsg::DeadLetter(letter) => {
+                let unhandled = letter.message.is_none();
+                self.record(letter);
+
+                if let Some(stats_handle) = self.stats_handle.clone() {
+                    let mut counts = vec![("DeadLetters", StatsActorMsg::DeadLetters(1))];
+                    if unhandled {
+                        counts.push(("ActorErrors", StatsActorMsg::ActorErrors(1)));
+                    }
+                    for (message, count) in counts {
+                        if let Err(err) = stats_handle.send(count).await {
+                            let letter = DeadLetter::new(
+                                "StatsActor",
+                                Some(message.into()),
+                                err.to_string(),
+                            );
+                            self.record(letter);
+                        }
+                    }
+                }
+            }
+            DeadLetterActorMsg::Get { sender } => {
+                let dead_letters = DeadLetters {
+                    total: self.total,
+                    recent: self.buffer.iter().rev().cloned().collect(),
+                };
+                sender
+                    .send(dead_letters)
+                    .map_err(|_| ActorError::Reply("the dead letters"))?;
+            }
+            DeadLetterActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("DeadLetterActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DeadLetterActor {
+    /// Logs a dead letter and stores it in the buffer, evicting the oldest one if the buffer is full
+    fn record(&mut self, letter: DeadLetter) {
+        tracing::warn!("Dead letter: {}.", letter);
+
+        if self.buffer.len() == DEAD_LETTER_BUFFER_SIZE {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(letter);
+        self.total += 1;
+    }
+}
+
+impl Drop for DeadLetterActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`DeadLetterActor`]
+///
+/// Only the handle is public; the [`DeadLetterActor`] isn't.
+///
+/// We can only create [`DeadLetterActor`]s through the [`DeadLetterActorHandle`].
+///
+/// We only create a single [`DeadLetterActor`] instance in a [`DeadLetterActorHandle`].
+#[derive(Clone)]
+pub struct DeadLetterActorHandle {
+    sender: mpsc::Sender<DeadLetterActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, DeadLetterMsgErrorType> for DeadLetterActorHandle {
+    type Msg = DeadLetterActorMsg;
+
+    /// Create a new [`DeadLetterActorHandle`]
+    ///
+    /// This function creates a single [`DeadLetterActor`] instance,
+    /// and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(DeadLetterActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`DeadLetterActor`] instance through the [`DeadLetterActorHandle`]
+    async fn send(
+        &self,
+        msg: DeadLetterActorMsg,
+    ) -> Result<MsgResponseType, DeadLetterMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl DeadLetterActorHandle {
+    /// Create a new [`DeadLetterActorHandle`] whose actor counts
+    /// the dead letters in the [`StatsActor`]
+    ///
+    /// Otherwise, it is the same as [`DeadLetterActorHandle::new`].
+    pub fn with_stats(nticks: usize, stats_handle: StatsActorHandle) -> Self {
+        let sender = spawn_actor!(DeadLetterActor, nticks, |actor| {
+            actor.stats_handle = Some(stats_handle);
+        });
+
+        Self { sender }
+    }
+
+    /// Records a `message` that couldn't be delivered to an `actor`, and the `reason`
+    pub async fn undelivered(&self, actor: &str, message: impl Into<String>, reason: impl Display) {
+        let letter = DeadLetter::new(actor, Some(message.into()), reason.to_string());
+        self.forward(letter).await;
+    }
+
+    /// Records an error that an `actor` has run into while handling a message
+    pub async fn unhandled(&self, actor: &str, err: &ActorError) {
+        let letter = DeadLetter::new(actor, None, err.to_string());
+        self.forward(letter).await;
+    }
+
+    /// Gets the recorded dead letters
+    ///
+    /// # Errors
+    /// - If the [`DeadLetterActor`] isn't running
+    pub async fn get(&self) -> Result<DeadLetters> {
+        self.call(|sender| DeadLetterActorMsg::Get { sender })
+            .await
+            .context("Couldn't call the DeadLetterActor.")
+    }
+
+    /// Stops the [`DeadLetterActor`]
+    ///
+    /// All messages that were sent to the actor before are handled first.
+    ///
+    /// # Errors
+    /// - If the [`DeadLetterActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| DeadLetterActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the DeadLetterActor.")
+    }
+
+    /// Sends a dead letter to the [`DeadLetterActor`]
+    ///
+    /// If the actor isn't running, the dead letter is only logged, as a last resort.
+    async fn forward(&self, letter: DeadLetter) {
+        if let Err(SendError(DeadLetterActorMsg::DeadLetter(letter))) =
+            self.send(DeadLetterActorMsg::DeadLetter(letter)).await
+        {
+            tracing::error!(
+                "DeadLetterActor isn't running; lost a dead letter: {}.",
+                letter
+            );
+        }
+    }
+}
+
+// ============================================================================
+//
+//
+//
+//
+//      [`WatchdogActorMsg`], [`WatchdogActor`], [`WatchdogActorHandle`],
+//                        [`Monitored`], [`Health`]
+//
+//
+//
+//
+// ============================================================================
+
+/// A singleton actor that the [`WatchdogActor`] monitors
+#[derive(Clone)]
+pub enum Monitored {
+    Writer(WriterActorHandle),
+    Collection(CollectionActorHandle),
+}
+
+impl Monitored {
+    /// Returns the name of the monitored actor
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Writer(_) => "WriterActor",
+            Self::Collection(_) => "CollectionActor",
+        }
+    }
+
+    /// Pings the monitored actor, and returns whether it has answered within `timeout`
+    async fn is_responsive(&self, timeout: Duration) -> bool {
+        let ping = async {
+            match self {
+                Self::Writer(handle) => handle.ping().await,
+                Self::Collection(handle) => handle.ping().await,
+            }
+        };
+
+        matches!(runtime::timeout(timeout, ping).await, Ok(Ok(())))
+    }
+}
+
+/// The liveness of the monitored actors, as of the [`WatchdogActor`]'s latest check
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct Health {
+    /// Whether all monitored actors have answered the latest heartbeat
+    pub healthy: bool,
+    /// The monitored actors that haven't answered the latest heartbeat
+    pub unresponsive: Vec<String>,
+}
+
+/// The [`WatchdogActorMsg`] enumeration
+///
+/// Supports two message types:
+/// - [`Get`],
+/// - [`Shutdown`],
+///
+/// Both expect a response, which is sent back through the provided `sender`.
+pub enum WatchdogActorMsg {
+    /// A request for the liveness of the monitored actors
+    Get { sender: oneshot::Sender<Health> },
+    /// A request to stop, which is acknowledged through the provided `sender`
+    ///
+    /// All messages that were sent before it are handled first.
+    Shutdown { sender: oneshot::Sender<()> },
+}
+
+/// Actor that periodically pings the singleton actors of the pipeline, so that an actor
+/// that has stopped responding, e.g., after an unnoticed panic, doesn't go unnoticed
+///
+/// An actor that doesn't answer a heartbeat within [`HEARTBEAT_TIMEOUT_SECS`] is reported
+/// at the error level, and the app is reported as unhealthy until the actor answers again.
+///
+/// It is not made public on purpose.
+///
+/// It can only be created through [`WatchdogActorHandle`], which is public.
+struct WatchdogActor {
+    receiver: mpsc::Receiver<WatchdogActorMsg>,
+    monitored: Vec<Monitored>,
+    /// How often the monitored actors are pinged
+    interval: Duration,
+    /// The names of the actors that haven't answered the latest heartbeat
+    unresponsive: Vec<&'static str>,
+}
+
+impl Actor<MsgResponseType> for WatchdogActor {
+    type Msg = WatchdogActorMsg;
+
+    /// Create a new [`WatchdogActor`]
+    fn new(receiver: mpsc::Receiver<WatchdogActorMsg>, _: usize) -> Self {
+        Self {
+            receiver,
+            monitored: Vec::new(),
+            interval: Duration::from_secs(HEARTBEAT_INTERVAL_SECS),
+            unresponsive: Vec::new(),
+        }
+    }
+
+    /// Run the [`WatchdogActor`]
+    ///
+    /// The monitored actors are pinged at every tick of the interval, in between the messages.
+    async fn run(&mut self) -> Result<MsgResponseType, ActorError> {
+        tracing::debug!("WatchdogActor is running.");
+
+        let mut interval = Interval::new(self.interval);
+
+        loop {
+            tokio::select! {
+                msg = self.receiver.recv() => {
+                    let Some(msg) = msg else {
+                        break;
+                    };
+                    if let Err(err) = self.handle_intercepted(msg).await {
+                        report_error("WatchdogActor", &err, None).await;
+                    }
+                }
+                _ = interval.tick() => self.check().await,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The [`WatchdogActorMsg`] message handler for the [`WatchdogActor`] actor
+    async fn handle(&mut self, msg: WatchdogActorMsg) -> Result<MsgResponseType, ActorError> {
+        match msg {
+            WatchdogActorMsg::Get { sender } => {
+                let health = Health {
+                    healthy: self.unresponsive.is_empty(),
+                    unresponsive: self.unresponsive.iter().map(|&name| name.into()).collect(),
+                };
+                sender
+                    .send(health)
+                    .map_err(|_| ActorError::Reply("the health"))?;
+            }
+            WatchdogActorMsg::Shutdown { sender } => {
+                self.receiver.close();
+                tracing::debug!("WatchdogActor is shutting down.");
+                let _ = sender.send(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl WatchdogActor {
+    /// Pings all monitored actors, and reports the ones that have stopped or resumed responding
+    async fn check(&mut self) {
+        let timeout = Duration::from_secs(HEARTBEAT_TIMEOUT_SECS);
+
+        let mut unresponsive = Vec::new();
+        for monitored in &self.monitored {
+            if !monitored.is_responsive(timeout).await {
+                unresponsive.push(monitored.name());
+            }
+        }
+
+        for name in &unresponsive {
+            if !self.unresponsive.contains(name) {
+                tracing::error!("{} isn't responding to heartbeats.", name);
+            }
+        }
+        for name in &self.unresponsive {
+            if !unresponsive.contains(name) {
+                tracing::info!("{} is responding to heartbeats again.", name);
+            }
+        }
+
+        self.unresponsive = unresponsive;
+    }
+}
+
+impl Drop for WatchdogActor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A handle for the [`WatchdogActor`]
+///
+/// Only the handle is public; the [`WatchdogActor`] isn't.
+///
+/// We can only create [`WatchdogActor`]s through the [`WatchdogActorHandle`].
+///
+/// We only create a single [`WatchdogActor`] instance in a [`WatchdogActorHandle`].
+#[derive(Clone)]
+pub struct WatchdogActorHandle {
+    sender: mpsc::Sender<WatchdogActorMsg>,
+}
+
+impl ActorHandle<MsgResponseType, WatchdogMsgErrorType> for WatchdogActorHandle {
+    type Msg = WatchdogActorMsg;
+
+    /// Create a new [`WatchdogActorHandle`]
+    ///
+    /// This function creates a single [`WatchdogActor`] instance, which doesn't monitor any actors,
+    /// and a MPSC channel for communicating with the actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it can't run the actor.
+    fn new(nticks: usize) -> Self {
+        let sender = spawn_actor!(WatchdogActor, nticks);
+
+        Self { sender }
+    }
+
+    /// Send a message to a [`WatchdogActor`] instance through the [`WatchdogActorHandle`]
+    async fn send(&self, msg: WatchdogActorMsg) -> Result<MsgResponseType, WatchdogMsgErrorType> {
+        self.sender.send(msg).await
+    }
+}
+
+impl WatchdogActorHandle {
+    /// Create a new [`WatchdogActorHandle`] whose actor pings the `monitored` actors
+    /// every `interval`
+    ///
+    /// Otherwise, it is the same as [`WatchdogActorHandle::new`].
+    pub fn with_monitored(nticks: usize, monitored: Vec<Monitored>, interval: Duration) -> Self {
+        let sender = spawn_actor!(WatchdogActor, nticks, |actor| {
+            actor.monitored = monitored;
+            actor.interval = interval;
+        });
+
+        Self { sender }
+    }
+
+    /// Gets the liveness of the monitored actors, as of the latest check
+    ///
+    /// # Errors
+    /// - If the [`WatchdogActor`] isn't running
+    pub async fn get(&self) -> Result<Health> {
+        self.call(|sender| WatchdogActorMsg::Get { sender })
+            .await
+            .context("Couldn't call the WatchdogActor.")
+    }
+
+    /// Stops the [`WatchdogActor`]
+    ///
+    /// # Errors
+    /// - If the [`WatchdogActor`] isn't running
+    pub async fn shutdown(&self) -> Result<()> {
+        self.call(|sender| WatchdogActorMsg::Shutdown { sender })
+            .await
+            .context("Couldn't call the WatchdogActor.")
+    }
+}
+
+/// Removes the rows of the symbols that occur more than once in the `batch`, except for the latest
+/// row of every symbol, and returns the number of removed rows
+///
+/// The remaining rows keep their order.
+fn dedup_by_symbol(batch: &mut Batch) -> usize {
+    let len = batch.len();
+    let mut seen = HashSet::with_capacity(len);
+
+    batch.reverse();
+    batch.retain(|row| seen.insert(row.symbol.clone()));
+    batch.reverse();
+
+    len - batch.len()
+}
+
+/// Helper function for calculating number of chunks in the current run of the program
+///
+/// # Params
+/// - `nticks`: number of provided symbols (ticks)
+/// - `chunk_size`: chunk size, optimized for performance
+pub(crate) fn calc_num_chunks(nticks: usize, chunk_size: usize) -> usize {
+    (nticks / chunk_size) + (nticks % chunk_size).clamp(0, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TAIL_BUFFER_SIZE;
+    use crate::option_chains::OptionContract;
+    use crate::quality::QualityIssue;
+    use crate::quotes::FixtureQuotes;
+    #[test]
+    fn ticks_lt_chunk() {
+        assert_eq!(1, calc_num_chunks(4, 5));
+    }
+
+    #[test]
+    fn ticks_eq_chunk() {
+        assert_eq!(1, calc_num_chunks(5, 5));
+    }
+
+    #[test]
+    fn ticks_gt_chunk_1() {
+        assert_eq!(2, calc_num_chunks(7, 5));
+    }
+
+    #[test]
+    fn ticks_gt_chunk_2() {
+        assert_eq!(2, calc_num_chunks(10, 5));
+    }
+
+    #[test]
+    fn ticks_gt_chunk_3() {
+        assert_eq!(3, calc_num_chunks(13, 5));
+    }
+
+    #[tokio::test]
+    async fn symbols_are_normalized_and_deduplicated() {
+        let handle = SymbolsActorHandle::new(0);
+
+        let added = handle
+            .add(vec![
+                "aapl".to_string(),
+                " MSFT ".to_string(),
+                "".to_string(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(vec![Symbol::from("AAPL"), Symbol::from("MSFT")], added);
+
+        let added = handle
+            .add(vec!["MSFT".to_string(), "nvda".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(vec![Symbol::from("NVDA")], added);
+
+        assert_eq!(
+            vec![
+                Symbol::from("AAPL"),
+                Symbol::from("MSFT"),
+                Symbol::from("NVDA")
+            ],
+            handle.get().await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn symbols_are_shared_rather_than_copied() {
+        let handle = SymbolsActorHandle::new(0);
+        let added = handle.add(vec!["AAPL".to_string()]).await.unwrap();
+
+        let first = handle.get().await.unwrap();
+        let second = handle.get().await.unwrap();
+        assert!(Arc::ptr_eq(&added[0], &first[0]));
+        assert!(Arc::ptr_eq(&first[0], &second[0]));
+    }
+
+    #[tokio::test]
+    async fn symbols_are_removed() {
+        let handle = SymbolsActorHandle::new(0);
+        handle
+            .add(vec!["AAPL".to_string(), "MSFT".to_string()])
+            .await
+            .unwrap();
+
+        assert!(handle.remove(" aapl".to_string()).await.unwrap());
+        assert!(!handle.remove("AAPL".to_string()).await.unwrap());
+        assert_eq!(vec![Symbol::from("MSFT")], handle.get().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn webhooks_are_registered_and_unregistered() {
+        let handle = WebhookActorHandle::new(0);
+        let registration = WebhookRegistration {
+            url: "http://localhost:8080/hook".to_string(),
+            symbol: Some("aapl".to_string()),
+            threshold: None,
+        };
+
+        let first = handle.register(registration.clone()).await.unwrap();
+        let second = handle.register(registration).await.unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(Some("AAPL".to_string()), first.symbol);
+
+        assert!(handle.unregister(first.id).await.unwrap());
+        assert!(!handle.unregister(first.id).await.unwrap());
+        assert_eq!(vec![second], handle.list().await.unwrap());
+    }
+
+    /// Waits until the collection actor has handled all queued chunks
+    ///
+    /// The tail requests overtake the chunks that are still queued, and the actor handles a chunk
+    /// without yielding, so the chunks have been handled once the mailbox is empty.
+    ///
+    /// This only holds if the actor runs on the test's thread, i.e., without the `smol` feature.
+    async fn wait_for_chunks(handle: &CollectionActorHandle) {
+        while handle.queue_depth() > 0 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn symbol_tail_contains_only_rows_of_the_symbol() {
+        let handle = CollectionActorHandle::new(0);
+        let row = |symbol: &str, last_price: f64| PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price,
+            pct_change: 0.0,
+            period_min: 0.0,
+            period_max: 0.0,
+            sma: 0.0,
+            asset_class: Default::default(),
+        };
+        let iteration = Iteration {
+            id: 1,
+            start: Instant::now(),
+            to: OffsetDateTime::now_utc(),
+            num_chunks: 1,
+        };
+
+        for (id, last_price) in [(1, 1.0), (2, 2.0), (3, 3.0)] {
+            handle
+                .send(CollectionActorMsg::PerformanceIndicatorsChunk(
+                    PerformanceIndicatorsRowsMsg {
+                        from: String::new(),
+                        rows: vec![row("AAPL", last_price), row("MSFT", last_price * 10.0)],
+                        iteration: Iteration { id, ..iteration },
+                        chunk_id: 0,
+                    },
+                ))
+                .await
+                .unwrap();
+        }
+
+        wait_for_chunks(&handle).await;
+        let tail = handle
+            .call(|sender| CollectionActorMsg::SymbolTailRequest {
+                sender,
+                n: 2,
+                symbol: "AAPL".into(),
+                request_id: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![3.0, 2.0],
+            tail.iter().map(|row| row.last_price).collect::<Vec<_>>()
+        );
+        assert!(tail.iter().all(|row| &*row.symbol == "AAPL"));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn newest_row_of_a_symbol_is_cached() {
+        let handle = CollectionActorHandle::new(0);
+        let row = PerformanceIndicatorsRow {
+            symbol: "AAPL".into(),
+            last_price: 1.0,
+            pct_change: 0.0,
+            period_min: 0.0,
+            period_max: 0.0,
+            sma: 0.0,
+            asset_class: Default::default(),
+        };
+        // the first of two chunks, so the batch isn't complete
+        let iteration = Iteration {
+            id: 1,
+            start: Instant::now(),
+            to: OffsetDateTime::now_utc(),
+            num_chunks: 2,
+        };
+
+        handle
+            .send(CollectionActorMsg::PerformanceIndicatorsChunk(
+                PerformanceIndicatorsRowsMsg {
+                    from: String::new(),
+                    rows: vec![row],
+                    iteration,
+                    chunk_id: 0,
+                },
+            ))
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+
+        let latest = |symbol: &str| {
+            let symbol = symbol.to_string();
+            handle.call(move |sender| CollectionActorMsg::SymbolLatestRequest {
+                sender,
+                symbol,
+                request_id: None,
+            })
+        };
+
+        let newest = latest("AAPL").await.unwrap();
+        assert_eq!(Some(1.0), newest.map(|row| row.last_price));
+        assert!(latest("MSFT").await.unwrap().is_none());
+        // the end of the period is recorded for the state file, too
+        let last_fetched = handle.last_fetched().await.unwrap();
+        assert_eq!(Some(&iteration.to), last_fetched.get("AAPL"));
+
+        handle
+            .send(CollectionActorMsg::PurgeSymbol("AAPL".to_string()))
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+        assert!(latest("AAPL").await.unwrap().is_none());
+        assert!(handle.last_fetched().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn restored_batches_are_served_with_their_completion_times() {
+        let handle = CollectionActorHandle::new(0);
+        let row = |symbol: &str, last_price: f64| PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price,
+            pct_change: 0.0,
+            period_min: 0.0,
+            period_max: 0.0,
+            sma: 0.0,
+            asset_class: Default::default(),
+        };
+        let completed_at = |day: u8| {
+            OffsetDateTime::parse(&format!("2024-01-0{}T00:00:00Z", day), &Rfc3339).unwrap()
+        };
+
+        handle
+            .restore(vec![
+                (completed_at(1), vec![row("AAPL", 1.0)]),
+                (completed_at(2), vec![row("AAPL", 2.0), row("MSFT", 3.0)]),
+            ])
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+
+        let range = handle
+            .call(|sender| CollectionActorMsg::RangeRequest {
+                sender,
+                start: completed_at(2),
+                end: completed_at(3),
+                request_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(1, range.len());
+        assert_eq!(completed_at(2), range[0].0);
+        assert_eq!(2, range[0].1.len());
+
+        let latest = handle
+            .call(|sender| CollectionActorMsg::SymbolLatestRequest {
+                sender,
+                symbol: "AAPL".into(),
+                request_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(2.0, latest.unwrap().last_price);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn only_symbols_with_quality_issues_are_reported() {
+        let handle = CollectionActorHandle::new(0);
+        let report = |symbol: &str, issues: Vec<QualityIssue>| QualityReport {
+            symbol: symbol.into(),
+            issues,
+        };
+        let quality = || {
+            handle.call(|sender| CollectionActorMsg::QualityRequest {
+                sender,
+                request_id: None,
+            })
+        };
+
+        handle
+            .send(CollectionActorMsg::QualityReports(vec![
+                report("AAPL", vec![QualityIssue::InvalidPrices { count: 1 }]),
+                report("MSFT", vec![]),
+            ]))
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+        let flagged = quality().await.unwrap();
+        assert_eq!(
+            vec!["AAPL"],
+            flagged.iter().map(|r| &r.symbol).collect::<Vec<_>>()
+        );
+
+        // a newer report replaces the previous one, so a recovered symbol isn't reported anymore
+        handle
+            .send(CollectionActorMsg::QualityReports(vec![
+                report("AAPL", vec![]),
+                report("MSFT", vec![QualityIssue::MissingDays { days: 3 }]),
+            ]))
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+        let flagged = quality().await.unwrap();
+        assert_eq!(
+            vec!["MSFT"],
+            flagged.iter().map(|r| &r.symbol).collect::<Vec<_>>()
+        );
+
+        handle
+            .send(CollectionActorMsg::PurgeSymbol("MSFT".to_string()))
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+        assert!(quality().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn risk_is_reported_for_the_symbols_with_returns() {
+        let handle = CollectionActorHandle::new(0);
+        let risk = || {
+            handle.call(|sender| CollectionActorMsg::RiskRequest {
+                sender,
+                confidence: 0.5,
+                weights: HashMap::from([("AAPL".to_string(), 1.0)]),
+                request_id: None,
+            })
+        };
+
+        handle
+            .send(CollectionActorMsg::Returns(vec![
+                ("AAPL".into(), vec![-0.02, 0.01]),
+                ("MSFT".into(), vec![0.01, 0.03]),
+            ]))
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+        let report = risk().await.unwrap();
+        assert_eq!(
+            vec!["AAPL", "MSFT"],
+            report.symbols.iter().map(|r| &r.symbol).collect::<Vec<_>>()
+        );
+        assert_eq!(2.0, report.portfolio.unwrap().var_pct);
+
+        // the portfolio's holding is gone with its symbol
+        handle
+            .send(CollectionActorMsg::PurgeSymbol("AAPL".to_string()))
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+        let report = risk().await.unwrap();
+        assert_eq!(1, report.symbols.len());
+        assert_eq!(None, report.portfolio);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn movers_of_the_newest_batch_are_ranked() {
+        let handle = CollectionActorHandle::new(0);
+        let row = |symbol: &str, pct_change: f64| PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price: 1.0,
+            pct_change,
+            period_min: 1.0,
+            period_max: 1.0,
+            sma: 1.0,
+            asset_class: Default::default(),
+        };
+        let movers = || {
+            handle.call(|sender| CollectionActorMsg::MoversRequest {
+                sender,
+                request_id: None,
+            })
+        };
+
+        // no batch has been completed yet
+        assert_eq!(None, movers().await.unwrap());
+
+        handle
+            .preload(
+                String::new(),
+                vec![row("AAPL", 2.0), row("MSFT", -3.0), row("NVDA", 1.0)],
+            )
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+        let ranked = movers().await.unwrap().unwrap();
+        assert_eq!(
+            vec!["AAPL", "NVDA"],
+            ranked
+                .gainers
+                .iter()
+                .map(|m| &*m.symbol)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["MSFT"],
+            ranked.losers.iter().map(|m| &*m.symbol).collect::<Vec<_>>()
+        );
+        // there are no returns without fetching
+        assert!(ranked.most_volatile.is_empty());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn sectors_of_the_newest_batch_are_aggregated() {
+        let handle = CollectionActorHandle::new(0);
+        let row = |symbol: &str, pct_change: f64| PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price: 1.0,
+            pct_change,
+            period_min: 1.0,
+            period_max: 1.0,
+            sma: 1.0,
+            asset_class: Default::default(),
+        };
+
+        handle
+            .tag_sectors(vec!["technology:AAPL+MSFT".parse().unwrap()])
+            .await
+            .unwrap();
+        handle
+            .preload(
+                String::new(),
+                vec![row("AAPL", 2.0), row("MSFT", -1.0), row("XOM", 5.0)],
+            )
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+        let aggregates = handle
+            .call(|sender| CollectionActorMsg::SectorsRequest {
+                sender,
+                request_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(1, aggregates.len());
+        assert_eq!(2, aggregates[0].symbols);
+        assert_eq!(0.5, aggregates[0].avg_pct_change);
+        assert_eq!(0.0, aggregates[0].breadth_pct);
+    }
+
+    #[test]
+    fn only_the_served_indicators_are_retained() {
+        let mut row = PerformanceIndicatorsRow {
+            symbol: "AAPL".into(),
+            last_price: 1.0,
+            pct_change: 2.0,
+            period_min: 3.0,
+            period_max: 4.0,
+            sma: 5.0,
+            asset_class: Default::default(),
+        };
+
+        row.retain_indicators(&INDICATORS.map(String::from));
+        assert_eq!(
+            [1.0, 2.0, 3.0, 4.0, 5.0],
+            [
+                row.last_price,
+                row.pct_change,
+                row.period_min,
+                row.period_max,
+                row.sma
+            ]
+        );
+
+        row.retain_indicators(&["price".to_string(), "sma".to_string()]);
+        assert_eq!(
+            [1.0, 0.0, 0.0, 0.0, 5.0],
+            [
+                row.last_price,
+                row.pct_change,
+                row.period_min,
+                row.period_max,
+                row.sma
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn alerts_are_raised_when_a_rule_starts_to_match() {
+        let (_sender, receiver) = mpsc::channel(1);
+        let mut actor = AlertActor::new(receiver, 0);
+        actor.triggers.rules = vec!["pct_change > 5".parse().unwrap()];
+        let batch = |pct_changes: &[(&str, f64)]| -> Batch {
+            pct_changes
+                .iter()
+                .map(|&(symbol, pct_change)| {
+                    PerformanceIndicatorsRow::fixture(symbol, 100.0, pct_change)
+                })
+                .collect()
+        };
+        let active = |actor: &AlertActor| {
+            let mut symbols: Vec<String> =
+                actor.active.iter().map(|(_, s)| s.to_string()).collect();
+            symbols.sort();
+            symbols
+        };
+
+        actor.evaluate(&batch(&[("AAPL", 6.0), ("MSFT", 1.0)]));
+        assert_eq!(vec!["AAPL"], active(&actor));
+
+        actor.evaluate(&batch(&[("AAPL", 7.0), ("MSFT", 8.0)]));
+        assert_eq!(vec!["AAPL", "MSFT"], active(&actor));
+
+        actor.evaluate(&batch(&[("AAPL", 2.0), ("MSFT", 9.0)]));
+        assert_eq!(vec!["MSFT"], active(&actor));
+    }
+
+    #[test]
+    fn row_is_parsed_back_from_its_output_format() {
+        let row: PerformanceIndicatorsRow = "AAPL,$183.13,-4.85%,$166.89,$198.11,$187.16"
+            .parse()
+            .unwrap();
+        assert_eq!("AAPL", &*row.symbol);
+        assert_eq!(
+            [183.13, -4.85, 166.89, 198.11, 187.16],
+            [
+                row.last_price,
+                row.pct_change,
+                row.period_min,
+                row.period_max,
+                row.sma
+            ]
+        );
+        assert_eq!(
+            "AAPL,$183.13,-4.85%,$166.89,$198.11,$187.16",
+            row.to_string()
+        );
+
+        assert!("AAPL,$183.13".parse::<PerformanceIndicatorsRow>().is_err());
+        assert!("AAPL,x,1%,$1,$1,$1"
+            .parse::<PerformanceIndicatorsRow>()
+            .is_err());
+    }
+
+    #[test]
+    fn duplicate_symbols_are_dropped_keeping_the_latest_row() {
+        let row = |symbol: &str, last_price: f64| PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price,
+            pct_change: 0.0,
+            period_min: 0.0,
+            period_max: 0.0,
+            sma: 0.0,
+            asset_class: Default::default(),
+        };
+        let mut batch = vec![row("AAPL", 1.0), row("MSFT", 1.0), row("AAPL", 2.0)];
+
+        assert_eq!(1, dedup_by_symbol(&mut batch));
+        assert_eq!(
+            vec![("MSFT", 1.0), ("AAPL", 2.0)],
+            batch
+                .iter()
+                .map(|row| (&*row.symbol, row.last_price))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, dedup_by_symbol(&mut batch));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn chunks_are_assembled_by_iteration() {
+        let handle = CollectionActorHandle::new(0);
+        let chunk = |id, chunk_id, symbol: &str| {
+            CollectionActorMsg::PerformanceIndicatorsChunk(PerformanceIndicatorsRowsMsg {
+                from: String::new(),
+                rows: vec![PerformanceIndicatorsRow {
+                    symbol: symbol.into(),
+                    last_price: 1.0,
+                    pct_change: 0.0,
+                    period_min: 0.0,
+                    period_max: 0.0,
+                    sma: 0.0,
+                    asset_class: Default::default(),
+                }],
+                iteration: Iteration {
+                    id,
+                    start: Instant::now(),
+                    to: OffsetDateTime::now_utc(),
+                    num_chunks: 2,
+                },
+                chunk_id,
+            })
+        };
+
+        // the iteration 1 is missing its second chunk when the iteration 2 is completed,
+        // and the second chunk comes only afterwards, out of order
+        for msg in [
+            chunk(1, 0, "AAPL"),
+            chunk(2, 0, "MSFT"),
+            chunk(2, 0, "MSFT"),
+            chunk(2, 1, "NVDA"),
+            chunk(1, 1, "UBER"),
+        ] {
+            handle.send(msg).await.unwrap();
+        }
+        wait_for_chunks(&handle).await;
+
+        let tail = handle
+            .call(|sender| CollectionActorMsg::TailRequest {
+                sender,
+                n: 3,
+                if_none_match: None,
+                request_id: None,
+            })
+            .await
+            .unwrap()
+            .tail
+            .unwrap();
+        assert_eq!(1, tail.len());
+        assert_eq!(
+            vec!["MSFT", "NVDA"],
+            tail[0].iter().map(|row| &*row.symbol).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn tail_requests_overtake_queued_chunks() {
+        let handle = CollectionActorHandle::new(0);
+        let iteration = Iteration {
+            id: 1,
+            start: Instant::now(),
+            to: OffsetDateTime::now_utc(),
+            num_chunks: 1,
+        };
+
+        // the actor doesn't get to run before the test yields, so all chunks are still queued
+        for id in 1..=3 {
+            handle
+                .try_send(CollectionActorMsg::PerformanceIndicatorsChunk(
+                    PerformanceIndicatorsRowsMsg {
+                        from: String::new(),
+                        rows: Vec::new(),
+                        iteration: Iteration { id, ..iteration },
+                        chunk_id: 0,
+                    },
+                ))
+                .unwrap();
+        }
+
+        let tail = |n| {
+            handle.call(move |sender| CollectionActorMsg::TailRequest {
+                sender,
+                n,
+                if_none_match: None,
+                request_id: None,
+            })
+        };
+
+        // the request is answered before the queued chunks have been assembled into batches
+        assert!(tail(3).await.unwrap().tail.unwrap().is_empty());
+        assert_eq!(3, tail(3).await.unwrap().tail.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn shutdown_is_acknowledged_after_pending_messages() {
+        let broker_handle = BrokerActorHandle::new(0);
+        let handle = CollectionActorHandle::with_listeners(
+            0,
+            COLLECTION_MAILBOX_CAPACITY,
+            TailBufferLimits::default(),
+            StatsActorHandle::new(0),
+            WebhookActorHandle::new(0),
+            broker_handle.clone(),
+            DeadLetterActorHandle::new(0),
+        );
+        let mut subscription = broker_handle.subscribe().await.unwrap();
+        handle
+            .send(CollectionActorMsg::PerformanceIndicatorsChunk(
+                PerformanceIndicatorsRowsMsg {
+                    from: String::new(),
+                    rows: vec![],
+                    iteration: Iteration {
+                        id: 1,
+                        start: Instant::now(),
+                        to: OffsetDateTime::now_utc(),
+                        num_chunks: 1,
+                    },
+                    chunk_id: 0,
+                },
+            ))
+            .await
+            .unwrap();
+
+        handle.shutdown().await.unwrap();
+        broker_handle.shutdown().await.unwrap();
+
+        // the pending batch is published, and then the subscription ends
+        assert!(subscription.recv().await.is_some());
+        assert!(subscription.recv().await.is_none());
+        assert!(handle.shutdown().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn batches_are_published_to_all_subscribers() {
+        let handle = BrokerActorHandle::new(0);
+        let mut first = handle.subscribe().await.unwrap();
+        let mut second = handle.subscribe().await.unwrap();
+        // a subscriber that has gone away is removed
+        drop(handle.subscribe().await.unwrap());
+
+        let row = PerformanceIndicatorsRow {
+            symbol: "AAPL".into(),
+            last_price: 1.0,
+            pct_change: 0.0,
+            period_min: 0.0,
+            period_max: 0.0,
+            sma: 0.0,
+            asset_class: Default::default(),
+        };
+        handle
+            .send(BrokerActorMsg::Publish(vec![row]))
+            .await
+            .unwrap();
+        handle.shutdown().await.unwrap();
+
+        for subscription in [&mut first, &mut second] {
+            let batch = subscription.recv().await.unwrap();
+            assert_eq!("AAPL", &*batch[0].symbol);
+            assert!(subscription.recv().await.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn headlines_are_cached_until_the_next_batch() {
+        struct CountingProvider(Arc<AtomicU64>);
+
+        impl NewsProvider for CountingProvider {
+            fn headlines<'a>(
+                &'a self,
+                symbol: &'a str,
+            ) -> futures::future::BoxFuture<'a, Result<Vec<Headline>>> {
+                let calls = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+                Box::pin(async move {
+                    Ok(vec![Headline {
+                        title: format!("{} news #{}", symbol, calls),
+                        publisher: "Reuters".to_string(),
+                        link: "https://example.com".to_string(),
+                        published: "2024-01-01T00:00:00Z".to_string(),
+                    }])
+                })
+            }
+        }
+
+        let calls = Arc::new(AtomicU64::new(0));
+        let (publisher, subscription) = mpsc::channel(1);
+        let handle = NewsActorHandle::with_provider(
+            0,
+            Box::new(CountingProvider(Arc::clone(&calls))),
+            subscription,
+        );
+
+        for _ in 0..3 {
+            let (iteration, headlines) =
+                handle.headlines("AAPL".to_string()).await.unwrap().unwrap();
+            assert_eq!(0, iteration);
+            assert_eq!("AAPL news #1", headlines[0].title);
+        }
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+
+        // the batch is forwarded to the actor by another task
+        publisher.send(vec![]).await.unwrap();
+        let headlines = loop {
+            match handle.headlines("AAPL".to_string()).await.unwrap().unwrap() {
+                (1, headlines) => break headlines,
+                _ => runtime::sleep(Duration::from_millis(1)).await,
+            }
+        };
+        assert_eq!("AAPL news #2", headlines[0].title);
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn option_chains_are_summarized_at_every_batch() {
+        struct FixedProvider;
+
+        impl OptionsProvider for FixedProvider {
+            fn chain<'a>(
+                &'a self,
+                underlying: &'a str,
+            ) -> futures::future::BoxFuture<'a, Result<Vec<OptionContract>>> {
+                Box::pin(async move {
+                    anyhow::ensure!(underlying == "AAPL", "No option chain of {}.", underlying);
+                    Ok(vec![
+                        OptionContract::from_occ("AAPL240119C00100000", 1.0, 10, 5).unwrap(),
+                        OptionContract::from_occ("AAPL240119P00100000", 1.0, 5, 5).unwrap(),
+                    ])
+                })
+            }
+        }
+
+        let (publisher, subscription) = mpsc::channel(1);
+        let handle = OptionsActorHandle::with_underlyings(
+            0,
+            Box::new(FixedProvider),
+            vec!["AAPL".to_string(), "MSFT".to_string()],
+            None,
+            subscription,
+        )
+        .unwrap();
+        assert!(handle.summaries().await.unwrap().is_empty());
+
+        // the batch is forwarded to the actor by another task
+        publisher.send(vec![]).await.unwrap();
+        let summaries = loop {
+            let summaries = handle.summaries().await.unwrap();
+            if !summaries.is_empty() {
+                break summaries;
+            }
+            runtime::sleep(Duration::from_millis(1)).await;
+        };
+
+        // MSFT's chain couldn't be fetched
+        assert_eq!(1, summaries.len());
+        assert_eq!("AAPL", summaries[0].underlying);
+        assert_eq!(Some(0.5), summaries[0].put_call_ratio);
+        assert_eq!(Some(100.0), summaries[0].max_pain);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn crashed_actor_is_restarted() {
+        let (mailbox, receiver) = Mailbox::<mpsc::Sender<()>>::new(ACTOR_CHANNEL_CAPACITY);
+        supervise(
+            "TestActor",
+            &mailbox,
+            receiver,
+            |mut receiver, restarted| {
+                runtime::spawn(async move {
+                    while let Some(sender) = receiver.recv().await {
+                        assert!(restarted, "The first instance crashes.");
+                        let _ = sender.send(()).await;
+                    }
+                    Ok(())
+                })
+            },
+        );
+
+        // the first message crashes the actor, and the supervisor restarts it in the meantime
+        let mut acknowledged = false;
+        for _ in 0..10 {
+            let (sender, mut receiver) = mpsc::channel(ACTOR_CHANNEL_CAPACITY);
+            if mailbox.sender().send(sender).await.is_ok() && receiver.recv().await.is_some() {
+                acknowledged = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert!(acknowledged);
+    }
+
+    #[tokio::test]
+    async fn backpressure_is_counted() {
+        // nobody reads from the mailbox, so it stays full after the first message
+        let (mailbox, _receiver) = Mailbox::<u8>::new(1);
+
+        assert!(mailbox.try_send(0).is_ok());
+        assert!(matches!(mailbox.try_send(1), Err(TrySendError::Full(1))));
+        assert!(matches!(
+            mailbox.send_timeout(2, Duration::from_millis(10)).await,
+            Err(SendTimeoutError::Timeout(2))
+        ));
+
+        assert_eq!(1, mailbox.queue_depth());
+        assert_eq!(2, mailbox.queue_full_count());
+        assert_eq!(1, mailbox.send_timeout_count());
+    }
+
+    #[tokio::test]
+    async fn errors_are_counted_and_the_actor_keeps_running() {
+        let handle = StatsActorHandle::new(0);
+
+        // the requester stops waiting, so the response can't be sent
+        let (sender, receiver) = oneshot::channel();
+        drop(receiver);
+        handle.send(StatsActorMsg::Get { sender }).await.unwrap();
+
+        assert_eq!(1, handle.get().await.unwrap().actor_errors);
+    }
+
+    #[tokio::test]
+    async fn dead_letters_are_recorded_and_counted() {
+        let stats_handle = StatsActorHandle::new(0);
+        let handle = DeadLetterActorHandle::with_stats(0, stats_handle.clone());
+
+        handle
+            .undelivered("WebhookActor", "Dispatch", "channel closed")
+            .await;
+        handle
+            .unhandled("WriterActor", &ActorError::Reply("the statistics"))
+            .await;
+
+        let dead_letters = handle.get().await.unwrap();
+        assert_eq!(2, dead_letters.total);
+        assert_eq!("WriterActor", dead_letters.recent[0].actor);
+        assert_eq!(None, dead_letters.recent[0].message);
+        assert_eq!("WebhookActor", dead_letters.recent[1].actor);
+        assert_eq!(Some("Dispatch"), dead_letters.recent[1].message.as_deref());
+
+        let stats = stats_handle.get().await.unwrap();
+        assert_eq!(2, stats.dead_letters);
+        assert_eq!(1, stats.actor_errors);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn tail_holds_the_newest_batches() {
+        let handle = CollectionActorHandle::new(0);
+        let iteration = Iteration {
+            id: 1,
+            start: Instant::now(),
+            to: OffsetDateTime::now_utc(),
+            num_chunks: 1,
+        };
+
+        for last_price in 0..TAIL_BUFFER_SIZE + 2 {
+            handle
+                .send(CollectionActorMsg::PerformanceIndicatorsChunk(
+                    PerformanceIndicatorsRowsMsg {
+                        from: String::new(),
+                        rows: vec![PerformanceIndicatorsRow {
+                            symbol: "AAPL".into(),
+                            last_price: last_price as f64,
+                            pct_change: 0.0,
+                            period_min: 0.0,
+                            period_max: 0.0,
+                            sma: 0.0,
+                            asset_class: Default::default(),
+                        }],
+                        iteration: Iteration {
+                            id: last_price as u64 + 1,
+                            ..iteration
+                        },
+                        chunk_id: 0,
+                    },
+                ))
+                .await
+                .unwrap();
+        }
+
+        let last_prices = |tail: TailResponse| {
+            tail.iter()
+                .map(|batch| batch[0].last_price)
+                .collect::<Vec<_>>()
+        };
+        let tail = |n| {
+            handle.call(move |sender| CollectionActorMsg::TailRequest {
+                sender,
+                n,
+                if_none_match: None,
+                request_id: None,
+            })
+        };
+
+        wait_for_chunks(&handle).await;
+        let newest = tail(2).await.unwrap().tail.unwrap();
+        assert_eq!(
+            vec![(TAIL_BUFFER_SIZE + 1) as f64, TAIL_BUFFER_SIZE as f64],
+            last_prices(newest)
+        );
+
+        // the two oldest batches have been evicted
+        let all = tail(TAIL_BUFFER_SIZE + 2).await.unwrap().tail.unwrap();
+        assert_eq!(TAIL_BUFFER_SIZE, all.len());
+        assert_eq!(Some(&2.0), last_prices(all).last());
+    }
+
+    #[tokio::test]
+    async fn fetch_pool_workers_stop_on_poison_pills() {
+        let pool = FetchPool::new(2, 2, Duration::from_secs(HANDLER_TIMEOUT_SECS));
+        assert_eq!(2, pool.size());
+
+        // a pill stops only the worker that takes it off the shared queue,
+        // and the other worker takes the next one
+        for _ in 0..2 {
+            let (sender, receiver) = oneshot::channel();
+            pool.send(FetchActorMsg::Shutdown { sender }).await.unwrap();
+            assert!(receiver.await.is_ok());
+        }
+
+        // the pool's own pills aren't taken anymore, but the shutdown completes
+        runtime::timeout(Duration::from_secs(1), pool.shutdown())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn fetch_queue_shares_the_pool_workers() {
+        let pool = FetchPool::new(2, 2, Duration::from_secs(HANDLER_TIMEOUT_SECS));
+        let queue = pool.queue();
+
+        let (sender, receiver) = oneshot::channel();
+        queue
+            .send(FetchActorMsg::Shutdown { sender })
+            .await
+            .unwrap();
+        assert!(receiver.await.is_ok());
+
+        // the queue doesn't keep the remaining worker running
+        runtime::timeout(Duration::from_secs(1), pool.shutdown())
+            .await
+            .unwrap();
+        drop(queue);
+    }
+
+    #[tokio::test]
+    async fn fetches_wait_for_the_shared_request_limit() {
+        let semaphore = Arc::new(Semaphore::new(0));
+        let stats_handle = StatsActorHandle::new(0);
+        let handles = PipelineHandles {
+            writer_handle: WriterActorHandle::new(0),
+            collection_handle: CollectionActorHandle::new(0),
+            dead_letter_handle: DeadLetterActorHandle::with_stats(0, stats_handle.clone()),
+            stats_handle,
+            settings: Arc::new(PipelineSettings {
+                concurrency: Some(Arc::clone(&semaphore)),
+                quotes: Some(Arc::new(FixtureQuotes::default())),
+                ..Default::default()
+            }),
+        };
+        let now = OffsetDateTime::now_utc();
+        let mut symbols_closes = HashMap::new();
+        let mut fetch = Box::pin(FetchActor::fetch_symbols_closes(
+            vec!["AAPL".into()],
+            now - time::Duration::days(7),
+            now,
+            &handles,
+            &mut symbols_closes,
+        ));
+
+        // no request is sent without a permit
+        let waiting = runtime::timeout(Duration::from_millis(100), &mut fetch).await;
+        assert!(waiting.is_err());
+
+        semaphore.add_permits(1);
+        runtime::timeout(Duration::from_secs(HANDLER_TIMEOUT_SECS), fetch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(symbols_closes.contains_key("AAPL"));
+        assert_eq!(1, semaphore.available_permits());
+    }
+
+    #[tokio::test]
+    async fn pipeline_runs_end_to_end_against_fixtures() {
+        let to = OffsetDateTime::now_utc();
+        let from = to - time::Duration::days(60);
+        let fixtures = [("AAPL", 100.0), ("MSFT", 200.0)]
+            .into_iter()
+            .map(|(symbol, base)| {
+                let bars = (0..40)
+                    .map(|day| (from + time::Duration::days(day), base + day as f64))
+                    .collect();
+                (symbol.to_string(), bars)
+            })
+            .collect();
+
+        let stem = std::env::temp_dir()
+            .join(format!("e2e-{}", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let stats_handle = StatsActorHandle::new(0);
+        let dead_letter_handle = DeadLetterActorHandle::with_stats(0, stats_handle.clone());
+        let handles = PipelineHandles {
+            writer_handle: WriterActorHandle::at_stem(
+                0,
+                WRITER_MAILBOX_CAPACITY,
+                vec![OutputFormat::Csv],
+                &stem,
+                false,
+                dead_letter_handle.clone(),
+            ),
+            collection_handle: CollectionActorHandle::new(0),
+            stats_handle,
+            dead_letter_handle,
+            settings: Arc::new(PipelineSettings {
+                quotes: Some(Arc::new(FixtureQuotes::new(fixtures))),
+                ..Default::default()
+            }),
+        };
+
+        let pool = FetchPool::new(2, 2, Duration::from_secs(HANDLER_TIMEOUT_SECS));
+        let iteration = Iteration {
+            id: 1,
+            start: Instant::now(),
+            to,
+            num_chunks: 2,
+        };
+        for (chunk_id, symbol) in ["AAPL", "MSFT"].into_iter().enumerate() {
+            pool.send(FetchActorMsg::QuoteRequestsMsg {
+                symbols: vec![symbol.into()],
+                from,
+                handles: handles.clone(),
+                iteration,
+                chunk_id,
+                window_size: crate::constants::WINDOW_SIZE,
+            })
+            .await
+            .unwrap();
+        }
+        pool.shutdown().await;
+
+        // the batch is completed once the collection actor has handled both chunks
+        let tail = runtime::timeout(Duration::from_secs(5), async {
+            loop {
+                let tail = handles
+                    .collection_handle
+                    .call(|sender| CollectionActorMsg::TailRequest {
+                        sender,
+                        n: 1,
+                        if_none_match: None,
+                        request_id: None,
+                    })
+                    .await
+                    .unwrap()
+                    .tail
+                    .unwrap();
+                if !tail.is_empty() {
+                    break tail;
+                }
+                runtime::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let mut rows: Vec<_> = tail[0]
+            .iter()
+            .map(|row| (row.symbol.to_string(), row.last_price, row.period_min))
+            .collect();
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            vec![
+                ("AAPL".to_string(), 139.0, 100.0),
+                ("MSFT".to_string(), 239.0, 200.0)
+            ],
+            rows
+        );
+
+        handles.writer_handle.shutdown().await.unwrap();
+        let path = format!("{}.csv", stem);
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(3, csv.lines().count());
+        assert!(csv.contains("AAPL") && csv.contains("MSFT"));
+    }
+
+    #[tokio::test]
+    async fn hung_handler_is_timed_out() {
+        let timeout = Duration::from_millis(10);
+
+        let result = with_timeout(timeout, std::future::pending()).await;
+        assert!(matches!(result, Err(ActorError::Timeout(t)) if t == timeout));
+
+        assert!(with_timeout(timeout, async { Ok(()) }).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unresponsive_actor_is_reported() {
+        let collection_handle = CollectionActorHandle::new(0);
+        let watchdog_handle = WatchdogActorHandle::with_monitored(
+            0,
+            vec![Monitored::Collection(collection_handle.clone())],
+            Duration::from_millis(10),
+        );
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(watchdog_handle.get().await.unwrap().healthy);
+
+        collection_handle.shutdown().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let health = watchdog_handle.get().await.unwrap();
+        assert!(!health.healthy);
+        assert_eq!(vec!["CollectionActor".to_string()], health.unresponsive);
+
+        watchdog_handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[cfg_attr(
+        feature = "smol",
+        ignore = "relies on the current-thread scheduling of Tokio"
+    )]
+    async fn interceptors_run_around_every_message() {
+        // counts the hooks' calls, and replaces every tail request with a request for no batches
+        #[derive(Clone, Default)]
+        struct Counter(Arc<[AtomicU64; 3]>);
+
+        impl Interceptor<CollectionActorMsg> for Counter {
+            fn on_send(&self, msg: CollectionActorMsg) -> CollectionActorMsg {
+                self.0[0].fetch_add(1, Ordering::Relaxed);
+                match msg {
+                    CollectionActorMsg::TailRequest {
+                        sender,
+                        if_none_match,
+                        request_id,
+                        ..
+                    } => CollectionActorMsg::TailRequest {
+                        sender,
+                        n: 0,
+                        if_none_match,
+                        request_id,
+                    },
+                    msg => msg,
+                }
+            }
+
+            fn on_handle(&self, msg: CollectionActorMsg) -> CollectionActorMsg {
+                self.0[1].fetch_add(1, Ordering::Relaxed);
+                msg
+            }
+
+            fn on_handled(&self, _elapsed: Duration, err: Option<&ActorError>) {
+                assert!(err.is_none());
+                self.0[2].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let handle = CollectionActorHandle::new(0);
+        let counter = Counter::default();
+        handle.add_interceptor(counter.clone());
+
+        handle
+            .send(CollectionActorMsg::PerformanceIndicatorsChunk(
+                PerformanceIndicatorsRowsMsg {
+                    from: String::new(),
+                    rows: Vec::new(),
+                    iteration: Iteration {
+                        id: 1,
+                        start: Instant::now(),
+                        to: OffsetDateTime::now_utc(),
+                        num_chunks: 1,
+                    },
+                    chunk_id: 0,
+                },
+            ))
+            .await
+            .unwrap();
+        wait_for_chunks(&handle).await;
+
+        let tail = handle
+            .call(|sender| CollectionActorMsg::TailRequest {
+                sender,
+                n: TAIL_BUFFER_SIZE,
+                if_none_match: None,
+                request_id: None,
+            })
+            .await
+            .unwrap();
+        assert!(tail.tail.unwrap().is_empty());
+
+        let counts = counter
+            .0
+            .each_ref()
+            .map(|count| count.load(Ordering::Relaxed));
+        assert_eq!([2, 2, 2], counts);
     }
 }
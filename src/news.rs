@@ -0,0 +1,166 @@
+//! News headlines
+//!
+//! With the `news` option, the web app serves the recent headlines of a symbol alongside its
+//! newest performance indicators. The headlines are retrieved from a [`NewsProvider`], which is
+//! selected by the option, by the news actor, [`crate::my_async_actors::NewsActorHandle`].
+//!
+//! A symbol's headlines are retrieved when they are first asked for, and then cached until the
+//! next batch is assembled, so the provider is asked at most once per symbol and iteration
+//! of the main loop, however often the headlines are asked for.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use yahoo_finance_api as yahoo;
+
+use crate::cli::NewsSource;
+use crate::my_async_actors::PerformanceIndicatorsRow;
+
+/// A news headline about a symbol
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Headline {
+    pub title: String,
+    pub publisher: String,
+    pub link: String,
+    /// When the article was published, in the RFC 3339 format
+    pub published: String,
+}
+
+/// The recent headlines of a symbol, alongside its newest performance indicators
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct SymbolNews {
+    pub symbol: String,
+    /// The symbol's row of the newest batch, or `None` if no batch has the symbol yet
+    pub indicators: Option<PerformanceIndicatorsRow>,
+    /// The sequence number of the iteration that the headlines are cached for
+    pub iteration: u64,
+    /// The headlines, newest first
+    pub headlines: Vec<Headline>,
+}
+
+/// A source of news headlines
+pub trait NewsProvider: Send + Sync {
+    /// Retrieves the recent headlines of a `symbol`, newest first
+    fn headlines<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<Vec<Headline>>>;
+}
+
+/// Creates the news provider of the `source`
+///
+/// # Errors
+/// - If the provider can't be created
+pub fn provider(source: &NewsSource) -> Result<Box<dyn NewsProvider>> {
+    Ok(match source {
+        NewsSource::Yahoo => Box::new(YahooNews::new()?),
+    })
+}
+
+/// The headlines of Yahoo! Finance, which are the news results of a search for the symbol
+pub struct YahooNews {
+    connector: yahoo::YahooConnector,
+}
+
+impl YahooNews {
+    /// Creates the provider
+    ///
+    /// # Errors
+    /// - If the `YahooConnector` can't be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            connector: yahoo::YahooConnector::new()
+                .context("Couldn't construct the `YahooConnector` for the news.")?,
+        })
+    }
+}
+
+impl NewsProvider for YahooNews {
+    fn headlines<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<Vec<Headline>>> {
+        Box::pin(async move {
+            let result = self
+                .connector
+                .search_ticker(symbol)
+                .await
+                .with_context(|| format!("Couldn't retrieve the news of {}.", symbol))?;
+
+            let mut news = result.news;
+            news.sort_by_key(|item| Reverse(item.provider_publish_time));
+
+            news.into_iter()
+                .map(|item| {
+                    let published =
+                        OffsetDateTime::from_unix_timestamp(item.provider_publish_time as i64)?
+                            .format(&Rfc3339)?;
+                    Ok(Headline {
+                        title: item.title,
+                        publisher: item.publisher,
+                        link: item.link,
+                        published,
+                    })
+                })
+                .collect()
+        })
+    }
+}
+
+/// The headlines of the symbols that have been asked for in the current iteration
+#[derive(Debug, Default)]
+pub struct NewsCache {
+    iteration: u64,
+    headlines: HashMap<String, Vec<Headline>>,
+}
+
+impl NewsCache {
+    /// Returns the sequence number of the current iteration
+    pub fn iteration(&self) -> u64 {
+        self.iteration
+    }
+
+    /// Returns the cached headlines of a `symbol`, if it has been asked for in the current iteration
+    pub fn get(&self, symbol: &str) -> Option<&Vec<Headline>> {
+        self.headlines.get(symbol)
+    }
+
+    /// Caches the `headlines` of a `symbol` for the current iteration
+    pub fn insert(&mut self, symbol: String, headlines: Vec<Headline>) {
+        self.headlines.insert(symbol, headlines);
+    }
+
+    /// Starts the next iteration, which drops the cached headlines
+    pub fn advance(&mut self) {
+        self.iteration += 1;
+        self.headlines.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headline(title: &str) -> Headline {
+        Headline {
+            title: title.to_string(),
+            publisher: "Reuters".to_string(),
+            link: "https://example.com".to_string(),
+            published: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn headlines_are_cached_until_the_next_iteration() {
+        let mut cache = NewsCache::default();
+        assert_eq!(0, cache.iteration());
+        assert_eq!(None, cache.get("AAPL"));
+
+        cache.insert("AAPL".to_string(), vec![headline("Apple")]);
+        assert_eq!(Some(&vec![headline("Apple")]), cache.get("AAPL"));
+
+        cache.advance();
+        assert_eq!(1, cache.iteration());
+        assert_eq!(None, cache.get("AAPL"));
+    }
+}
@@ -0,0 +1,328 @@
+//! Option chains
+//!
+//! With the `options` option, the option chains of the given underlyings are fetched whenever
+//! a batch has been assembled, by the options actor, [`crate::my_async_actors::OptionsActorHandle`],
+//! and summarized with a few derived metrics:
+//! - the put/call ratio, i.e., the volume of the puts divided by the volume of the calls,
+//! - the max pain, i.e., the strike at which the options of the nearest expiry would cause
+//!   their holders the greatest loss, if the underlying expired at it.
+//!
+//! The summaries are written to their own CSV file, a row per underlying and iteration,
+//! and served by the web app.
+//!
+//! The chains are retrieved from an [`OptionsProvider`]; they are current, so they aren't
+//! fetched when backfilling, nor when serving or replaying recorded batches.
+
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use utoipa::ToSchema;
+use yahoo_finance_api as yahoo;
+
+use crate::constants::OPTIONS_CSV_HEADER;
+
+/// The kind of an option contract
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+/// An option contract of a chain
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct OptionContract {
+    /// The OCC symbol of the contract, e.g., `AAPL240119C00150000`
+    pub contract: String,
+    pub kind: OptionKind,
+    /// The expiration date, in the `YYYY-MM-DD` format
+    pub expiry: String,
+    pub strike: f64,
+    pub last_price: f64,
+    pub volume: u64,
+    pub open_interest: u64,
+}
+
+impl OptionContract {
+    /// Creates a contract out of its OCC symbol, which ends with the expiration date as `YYMMDD`,
+    /// the kind as `C` or `P`, and the strike in thousandths of a dollar as eight digits
+    ///
+    /// # Returns
+    /// `None` if the symbol isn't an OCC symbol
+    pub fn from_occ(
+        contract: &str,
+        last_price: f64,
+        volume: u64,
+        open_interest: u64,
+    ) -> Option<Self> {
+        let suffix = contract.get(contract.len().checked_sub(15)?..)?;
+        let (date, rest) = suffix.split_at(6);
+        let (kind, strike) = rest.split_at(1);
+        if !date.bytes().all(|b| b.is_ascii_digit()) || !strike.bytes().all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+
+        Some(Self {
+            contract: contract.to_string(),
+            kind: match kind {
+                "C" => OptionKind::Call,
+                "P" => OptionKind::Put,
+                _ => return None,
+            },
+            expiry: format!("20{}-{}-{}", &date[..2], &date[2..4], &date[4..]),
+            strike: strike.parse::<u64>().ok()? as f64 / 1000.0,
+            last_price,
+            volume,
+            open_interest,
+        })
+    }
+}
+
+/// The derived metrics of an underlying's option chain
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct OptionsSummary {
+    pub underlying: String,
+    /// When the chain was fetched, in the RFC 3339 format
+    pub time: String,
+    /// The number of contracts in the chain
+    pub contracts: usize,
+    /// The nearest expiration date, or `None` if the chain is empty
+    pub nearest_expiry: Option<String>,
+    pub call_volume: u64,
+    pub put_volume: u64,
+    /// The put volume divided by the call volume, or `None` if no calls have been traded
+    pub put_call_ratio: Option<f64>,
+    /// The max pain of the nearest expiry, or `None` if its contracts have no open interest
+    pub max_pain: Option<f64>,
+}
+
+impl OptionsSummary {
+    /// Summarizes the option `chain` of an `underlying`, which was fetched at `time`
+    pub fn new(underlying: String, time: String, chain: &[OptionContract]) -> Self {
+        let volume = |kind| {
+            chain
+                .iter()
+                .filter(|contract| contract.kind == kind)
+                .map(|contract| contract.volume)
+                .sum::<u64>()
+        };
+        let call_volume = volume(OptionKind::Call);
+        let put_volume = volume(OptionKind::Put);
+        let nearest_expiry = chain.iter().map(|contract| &contract.expiry).min().cloned();
+        let nearest: Vec<&OptionContract> = chain
+            .iter()
+            .filter(|contract| Some(&contract.expiry) == nearest_expiry.as_ref())
+            .collect();
+
+        Self {
+            underlying,
+            time,
+            contracts: chain.len(),
+            nearest_expiry,
+            call_volume,
+            put_volume,
+            put_call_ratio: (call_volume > 0).then(|| put_volume as f64 / call_volume as f64),
+            max_pain: max_pain(&nearest),
+        }
+    }
+}
+
+impl Display for OptionsSummary {
+    /// Formats the summary as a CSV row
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let optional = |value: Option<f64>| {
+            value
+                .map(|value| format!("{:.2}", value))
+                .unwrap_or_default()
+        };
+
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{}",
+            self.time,
+            self.underlying,
+            self.nearest_expiry.as_deref().unwrap_or_default(),
+            self.contracts,
+            self.call_volume,
+            self.put_volume,
+            optional(self.put_call_ratio),
+            optional(self.max_pain)
+        )
+    }
+}
+
+/// Returns the strike at which the `contracts`, which expire at the same date, are worth the least
+/// to their holders, i.e., at which their writers pay out the least, weighted by the open interest
+///
+/// # Returns
+/// `None` if the contracts have no open interest
+pub fn max_pain(contracts: &[&OptionContract]) -> Option<f64> {
+    let payout = |price: f64| -> f64 {
+        contracts
+            .iter()
+            .map(|contract| {
+                let intrinsic = match contract.kind {
+                    OptionKind::Call => (price - contract.strike).max(0.0),
+                    OptionKind::Put => (contract.strike - price).max(0.0),
+                };
+                intrinsic * contract.open_interest as f64
+            })
+            .sum()
+    };
+
+    contracts
+        .iter()
+        .filter(|contract| contract.open_interest > 0)
+        .map(|contract| (contract.strike, payout(contract.strike)))
+        // the lowest strike wins a tie
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0)))
+        .map(|(strike, _)| strike)
+}
+
+/// A source of option chains
+pub trait OptionsProvider: Send + Sync {
+    /// Retrieves the option chain of an `underlying`
+    fn chain<'a>(&'a self, underlying: &'a str) -> BoxFuture<'a, Result<Vec<OptionContract>>>;
+}
+
+/// The option chains of Yahoo! Finance, which are scraped from an underlying's options page,
+/// so they only hold the nearest expiry
+pub struct YahooOptions {
+    connector: yahoo::YahooConnector,
+}
+
+impl YahooOptions {
+    /// Creates the provider
+    ///
+    /// # Errors
+    /// - If the `YahooConnector` can't be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            connector: yahoo::YahooConnector::new()
+                .context("Couldn't construct the `YahooConnector` for the option chains.")?,
+        })
+    }
+}
+
+impl OptionsProvider for YahooOptions {
+    fn chain<'a>(&'a self, underlying: &'a str) -> BoxFuture<'a, Result<Vec<OptionContract>>> {
+        Box::pin(async move {
+            let results = self
+                .connector
+                .search_options(underlying)
+                .await
+                .with_context(|| format!("Couldn't fetch the option chain of {}.", underlying))?;
+
+            Ok(results
+                .options
+                .iter()
+                .filter_map(|option| {
+                    OptionContract::from_occ(
+                        &option.name,
+                        option.last_price,
+                        option.volume.max(0) as u64,
+                        option.open_interest.max(0) as u64,
+                    )
+                })
+                .collect())
+        })
+    }
+}
+
+/// Writes the summaries as CSV, a row per underlying and iteration, after a header
+pub struct OptionsCsv {
+    writer: BufWriter<File>,
+}
+
+impl OptionsCsv {
+    /// Creates a CSV file at `path`, truncating an existing one
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", OPTIONS_CSV_HEADER)?;
+
+        Ok(Self { writer })
+    }
+
+    /// Writes the summaries of an iteration, and flushes them to the file
+    pub fn write<'a>(
+        &mut self,
+        summaries: impl IntoIterator<Item = &'a OptionsSummary>,
+    ) -> io::Result<()> {
+        for summary in summaries {
+            writeln!(self.writer, "{}", summary)?;
+        }
+
+        self.writer.flush()
+    }
+
+    /// Flushes and closes the file
+    pub fn close(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(occ: &str, volume: u64, open_interest: u64) -> OptionContract {
+        OptionContract::from_occ(occ, 1.0, volume, open_interest).unwrap()
+    }
+
+    #[test]
+    fn contracts_are_parsed_from_occ_symbols() {
+        let call = contract("AAPL240119C00150000", 10, 20);
+        assert_eq!(OptionKind::Call, call.kind);
+        assert_eq!("2024-01-19", call.expiry);
+        assert_eq!(150.0, call.strike);
+
+        let put = contract("BRK.B240119P00352500", 0, 0);
+        assert_eq!(OptionKind::Put, put.kind);
+        assert_eq!(352.5, put.strike);
+
+        assert_eq!(None, OptionContract::from_occ("AAPL", 1.0, 0, 0));
+        assert_eq!(
+            None,
+            OptionContract::from_occ("AAPL240119X00150000", 1.0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn chains_are_summarized() {
+        let chain = vec![
+            contract("AAPL240119C00100000", 30, 100),
+            contract("AAPL240119C00110000", 10, 300),
+            contract("AAPL240119P00100000", 20, 200),
+            contract("AAPL240119P00110000", 40, 50),
+            // a later expiry counts towards the ratio, but not towards the max pain
+            contract("AAPL240216C00090000", 20, 1000),
+        ];
+
+        let summary = OptionsSummary::new(
+            "AAPL".to_string(),
+            "2024-01-01T00:00:00Z".to_string(),
+            &chain,
+        );
+        assert_eq!(5, summary.contracts);
+        assert_eq!(Some("2024-01-19".to_string()), summary.nearest_expiry);
+        assert_eq!(60, summary.call_volume);
+        assert_eq!(60, summary.put_volume);
+        assert_eq!(Some(1.0), summary.put_call_ratio);
+        // at 100, the writers pay 50 puts * 10 = 500, and at 110, 100 calls * 10 = 1000
+        assert_eq!(Some(100.0), summary.max_pain);
+        assert_eq!(
+            "2024-01-01T00:00:00Z,AAPL,2024-01-19,5,60,60,1.00,100.00",
+            summary.to_string()
+        );
+
+        let empty = OptionsSummary::new("MSFT".to_string(), String::new(), &[]);
+        assert_eq!(None, empty.put_call_ratio);
+        assert_eq!(None, empty.max_pain);
+    }
+}
@@ -0,0 +1,275 @@
+//! Paper trading
+//!
+//! A simulated account, whose orders are filled at the latest fetched prices, instead of being
+//! sent to a broker. The [`crate::my_async_actors::PaperTradingActorHandle`]'s actor keeps
+//! the account across the iterations of the main loop.
+//!
+//! The orders come from the trading strategy, which is asked for a signal for every symbol
+//! of every completed batch, and from the web app. The account is long-only: a buy order
+//! must be covered by the cash, and a sell order by the held shares.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::constants::PAPER_FILLS_SIZE;
+use crate::my_async_actors::PerformanceIndicatorsRow;
+
+/// The side of an order
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// Where an order comes from
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderSource {
+    /// The trading strategy
+    Strategy,
+    /// The web app
+    Web,
+}
+
+/// An order to buy or sell a number of shares of a symbol
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct Order {
+    pub symbol: String,
+    pub side: Side,
+    /// The number of shares, which can be fractional
+    pub quantity: f64,
+}
+
+/// A filled order
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Fill {
+    /// The time of the fill, in the RFC 3339 format
+    pub time: String,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    /// The latest fetched price of the symbol, at which the order was filled
+    pub price: f64,
+    pub source: OrderSource,
+}
+
+/// The held shares of a symbol
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct Position {
+    pub symbol: String,
+    pub quantity: f64,
+    /// The average price at which the held shares were bought
+    pub average_price: f64,
+    /// The latest fetched price of the symbol
+    pub last_price: f64,
+}
+
+/// The state of the paper-trading account
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct Portfolio {
+    pub cash: f64,
+    /// The cash, and the held shares at their latest fetched prices
+    pub equity: f64,
+    pub positions: Vec<Position>,
+    /// The newest fills, oldest first
+    pub fills: Vec<Fill>,
+}
+
+/// A simulated account, which fills orders at the latest fetched prices
+#[derive(Debug)]
+pub struct PaperAccount {
+    cash: f64,
+    positions: BTreeMap<String, Position>,
+    /// The latest fetched prices, by symbols
+    prices: HashMap<String, f64>,
+    /// The newest fills, at most [`PAPER_FILLS_SIZE`] of them
+    fills: VecDeque<Fill>,
+}
+
+impl PaperAccount {
+    /// Opens an account with the initial `cash` and without positions
+    pub fn new(cash: f64) -> Self {
+        Self {
+            cash,
+            positions: BTreeMap::new(),
+            prices: HashMap::new(),
+            fills: VecDeque::new(),
+        }
+    }
+
+    /// Takes the latest prices of the symbols from the `rows`
+    pub fn update_prices(&mut self, rows: &[PerformanceIndicatorsRow]) {
+        for row in rows {
+            self.prices.insert(row.symbol.to_string(), row.last_price);
+            if let Some(position) = self.positions.get_mut(&*row.symbol) {
+                position.last_price = row.last_price;
+            }
+        }
+    }
+
+    /// Returns the number of the held shares of the `symbol`
+    pub fn quantity(&self, symbol: &str) -> f64 {
+        self.positions
+            .get(symbol)
+            .map_or(0.0, |position| position.quantity)
+    }
+
+    /// Fills the `order` at the latest price of its symbol, at the `time`
+    ///
+    /// # Errors
+    /// - If the quantity isn't positive, or if there is no price of the symbol yet
+    /// - If there isn't enough cash to buy, or if there aren't enough shares to sell
+    pub fn fill(&mut self, order: Order, source: OrderSource, time: String) -> Result<Fill> {
+        let symbol = order.symbol.trim().to_uppercase();
+        ensure!(
+            order.quantity.is_finite() && order.quantity > 0.0,
+            "The quantity must be positive."
+        );
+        let price = *self
+            .prices
+            .get(&symbol)
+            .with_context(|| format!("There is no price of {} yet.", symbol))?;
+        let value = order.quantity * price;
+
+        match order.side {
+            Side::Buy => {
+                ensure!(
+                    value <= self.cash,
+                    "There isn't enough cash: the order costs ${:.2}, and the cash is ${:.2}.",
+                    value,
+                    self.cash
+                );
+                self.cash -= value;
+                let position = self
+                    .positions
+                    .entry(symbol.clone())
+                    .or_insert_with(|| Position {
+                        symbol: symbol.clone(),
+                        ..Position::default()
+                    });
+                let cost = position.quantity * position.average_price + value;
+                position.quantity += order.quantity;
+                position.average_price = cost / position.quantity;
+                position.last_price = price;
+            }
+            Side::Sell => {
+                let held = self.quantity(&symbol);
+                ensure!(
+                    order.quantity <= held,
+                    "There aren't enough shares: {} of {} are held.",
+                    held,
+                    symbol
+                );
+                self.cash += value;
+                if order.quantity == held {
+                    self.positions.remove(&symbol);
+                } else if let Some(position) = self.positions.get_mut(&symbol) {
+                    position.quantity -= order.quantity;
+                    position.last_price = price;
+                }
+            }
+        }
+
+        let fill = Fill {
+            time,
+            symbol,
+            side: order.side,
+            quantity: order.quantity,
+            price,
+            source,
+        };
+        if self.fills.len() == PAPER_FILLS_SIZE {
+            self.fills.pop_front();
+        }
+        self.fills.push_back(fill.clone());
+
+        Ok(fill)
+    }
+
+    /// Returns the cash, the positions and the newest fills
+    pub fn portfolio(&self) -> Portfolio {
+        let positions: Vec<Position> = self.positions.values().cloned().collect();
+        let equity = self.cash
+            + positions
+                .iter()
+                .map(|position| position.quantity * position.last_price)
+                .sum::<f64>();
+
+        Portfolio {
+            cash: self.cash,
+            equity,
+            positions,
+            fills: self.fills.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(symbol: &str, last_price: f64) -> PerformanceIndicatorsRow {
+        PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price,
+            pct_change: 0.0,
+            period_min: 0.0,
+            period_max: 0.0,
+            sma: 0.0,
+            asset_class: Default::default(),
+        }
+    }
+
+    fn order(symbol: &str, side: Side, quantity: f64) -> Order {
+        Order {
+            symbol: symbol.into(),
+            side,
+            quantity,
+        }
+    }
+
+    #[test]
+    fn orders_are_filled_at_the_latest_prices() {
+        let mut account = PaperAccount::new(1000.0);
+        let time = || "2024-01-01T00:00:00Z".to_string();
+
+        let err = account
+            .fill(order("AAPL", Side::Buy, 1.0), OrderSource::Web, time())
+            .unwrap_err();
+        assert_eq!("There is no price of AAPL yet.", err.to_string());
+
+        account.update_prices(&[row("AAPL", 100.0)]);
+        account
+            .fill(order("aapl", Side::Buy, 4.0), OrderSource::Web, time())
+            .unwrap();
+        account.update_prices(&[row("AAPL", 200.0)]);
+        let fill = account
+            .fill(order("AAPL", Side::Buy, 2.0), OrderSource::Strategy, time())
+            .unwrap();
+        assert_eq!(200.0, fill.price);
+        assert!(account
+            .fill(order("AAPL", Side::Buy, 1.5), OrderSource::Web, time())
+            .is_err());
+
+        let portfolio = account.portfolio();
+        assert_eq!(200.0, portfolio.cash);
+        assert_eq!(1400.0, portfolio.equity);
+        assert_eq!(6.0, portfolio.positions[0].quantity);
+        assert!((portfolio.positions[0].average_price - 800.0 / 6.0).abs() < 1e-9);
+        assert_eq!(2, portfolio.fills.len());
+
+        assert!(account
+            .fill(order("AAPL", Side::Sell, 7.0), OrderSource::Web, time())
+            .is_err());
+        account
+            .fill(order("AAPL", Side::Sell, 6.0), OrderSource::Web, time())
+            .unwrap();
+        let portfolio = account.portfolio();
+        assert_eq!(1400.0, portfolio.cash);
+        assert!(portfolio.positions.is_empty());
+    }
+}
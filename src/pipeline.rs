@@ -0,0 +1,566 @@
+//! The library API
+//!
+//! Other Rust programs can embed the pipeline, instead of shelling out to the binary.
+//! A [`StockPipelineBuilder`] takes the symbols, the quote provider, the served indicators,
+//! the output files and the intervals, and builds a [`StockPipeline`], whose actors are running,
+//! but which fetches nothing until it's asked to:
+//!
+//! ```no_run
+//! # async fn embed() -> anyhow::Result<()> {
+//! use stock_trading_cli_with_async_streams::prelude::*;
+//!
+//! let mut pipeline = StockPipeline::builder()
+//!     .symbols(["AAPL", "MSFT"])
+//!     .tick_interval_secs(60)
+//!     .build()
+//!     .await?;
+//!
+//! // a batch right away, and then one every tick
+//! let batch = pipeline.trigger_once().await?;
+//! pipeline.start();
+//! let newest = pipeline.tail(10).await?;
+//! pipeline.shutdown().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The pipeline is made of the same actors as the binary's main pipeline: the fetch workers,
+//! the writer, the collection, the broker, the statistics and the dead letter actors,
+//! but not the web app, the alerts, nor the other consumers of the batches, which the binary
+//! configures through its options.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+
+use crate::cli::{check_period, normalize_symbols, BarInterval, OutputFormat};
+use crate::config::{ConfigUpdate, RuntimeConfig};
+use crate::constants::{
+    COLLECTION_MAILBOX_CAPACITY, FETCH_POOL_SIZE, FETCH_QUEUE_CAPACITY, HANDLER_TIMEOUT_SECS,
+    TRIGGER_TIMEOUT_SECS, WRITER_MAILBOX_CAPACITY,
+};
+use crate::my_async_actors::{
+    calc_num_chunks, ActorHandle, BrokerActorHandle, CollectionActorHandle, CollectionActorMsg,
+    DeadLetterActorHandle, FetchActorMsg, FetchPool, FetchQueue, Iteration, PipelineHandles,
+    PipelineSettings, StatsActorHandle, WebhookActorHandle, WriterActorHandle,
+};
+use crate::quotes::QuoteProvider;
+use crate::runtime::{self, Interval, JoinHandle};
+use crate::tail_buffer::TailBufferLimits;
+use crate::types::{Batch, Symbol, TailResponse};
+
+/// Builds a [`StockPipeline`]; only the symbols are required
+#[derive(Debug, Default)]
+pub struct StockPipelineBuilder {
+    symbols: Vec<String>,
+    quotes: Option<Arc<dyn QuoteProvider>>,
+    config: RuntimeConfig,
+    formats: Vec<OutputFormat>,
+    file_stem: String,
+    bars: BarInterval,
+    from: Option<OffsetDateTime>,
+    trigger_timeout_secs: Option<u64>,
+}
+
+impl StockPipelineBuilder {
+    /// The symbols to fetch, which are normalized as the `symbols` option is
+    pub fn symbols<S: Into<String>>(mut self, symbols: impl IntoIterator<Item = S>) -> Self {
+        self.symbols = symbols.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The provider of the bars; Yahoo! Finance by default
+    pub fn quotes(mut self, quotes: Arc<dyn QuoteProvider>) -> Self {
+        self.quotes = Some(quotes);
+        self
+    }
+
+    /// The served and written performance indicators, out of [`crate::constants::INDICATORS`];
+    /// the other ones are reported as zero
+    pub fn indicators<S: Into<String>>(mut self, indicators: impl IntoIterator<Item = S>) -> Self {
+        self.config.indicators = indicators.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Writes the rows to a file of each of the `formats`, at the path of the `stem`,
+    /// e.g., `./stocks` for `./stocks.csv`; nothing is written by default
+    pub fn sinks(mut self, formats: Vec<OutputFormat>, stem: impl Into<String>) -> Self {
+        self.formats = formats;
+        self.file_stem = stem.into();
+        self
+    }
+
+    /// How often the started pipeline fetches the symbols
+    pub fn tick_interval_secs(mut self, tick_interval_secs: u64) -> Self {
+        self.config.tick_interval_secs = tick_interval_secs;
+        self
+    }
+
+    /// The interval of the bars that the data provider is asked for; a day by default
+    pub fn bars(mut self, bars: BarInterval) -> Self {
+        self.bars = bars;
+        self
+    }
+
+    /// How often the output files are flushed; after every chunk by default
+    pub fn flush_interval_secs(mut self, flush_interval_secs: u64) -> Self {
+        self.config.flush_interval_secs = flush_interval_secs;
+        self
+    }
+
+    /// The number of symbols that a single fetch worker works with
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.config.chunk_size = chunk_size;
+        self
+    }
+
+    /// The simple moving average's window size
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.config.window_size = window_size;
+        self
+    }
+
+    /// The start of the period; a year before the pipeline is built by default
+    pub fn from(mut self, from: OffsetDateTime) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// How long [`StockPipeline::trigger_once`] waits for its batch, which is never assembled
+    /// if a chunk of it is lost, e.g., to a fetch that times out; a minute by default
+    pub fn trigger_timeout_secs(mut self, trigger_timeout_secs: u64) -> Self {
+        self.trigger_timeout_secs = Some(trigger_timeout_secs);
+        self
+    }
+
+    /// Spawns the pipeline's actors, which are configured to serve and write the indicators
+    ///
+    /// # Errors
+    /// - If there are no symbols, or if some aren't valid
+    /// - If a setting is out of its bounds, as in the runtime configuration
+    /// - If the start of the period is in the future
+    /// - If the trigger timeout is zero
+    pub async fn build(self) -> Result<StockPipeline> {
+        let symbols: Vec<Symbol> = normalize_symbols(self.symbols)?
+            .into_iter()
+            .map(Symbol::from)
+            .collect();
+        let config = self.config;
+        ConfigUpdate {
+            tick_interval_secs: Some(config.tick_interval_secs),
+            chunk_size: Some(config.chunk_size),
+            window_size: Some(config.window_size),
+            indicators: Some(config.indicators.clone()),
+            flush_interval_secs: Some(config.flush_interval_secs),
+        }
+        .validate()?;
+        let now = OffsetDateTime::now_utc();
+        let from = self.from.unwrap_or(now - time::Duration::days(365));
+        check_period(from, now, now)?;
+        let trigger_timeout_secs = self.trigger_timeout_secs.unwrap_or(TRIGGER_TIMEOUT_SECS);
+        ensure!(
+            trigger_timeout_secs > 0,
+            "The trigger timeout must be positive."
+        );
+
+        // used only for the initial capacities of actors' buffers
+        let nticks = symbols.len();
+        let stats_handle = StatsActorHandle::new(nticks);
+        let dead_letter_handle = DeadLetterActorHandle::with_stats(nticks, stats_handle.clone());
+        let broker_handle = BrokerActorHandle::new(nticks);
+        let webhook_handle = WebhookActorHandle::new(nticks);
+        let writer_handle = if self.formats.is_empty() {
+            WriterActorHandle::with_dead_letters(
+                nticks,
+                WRITER_MAILBOX_CAPACITY,
+                Vec::new(),
+                false,
+                dead_letter_handle.clone(),
+            )
+        } else {
+            WriterActorHandle::at_stem(
+                nticks,
+                WRITER_MAILBOX_CAPACITY,
+                self.formats,
+                &self.file_stem,
+                false,
+                dead_letter_handle.clone(),
+            )
+        };
+        let handles = PipelineHandles {
+            writer_handle,
+            collection_handle: CollectionActorHandle::with_listeners(
+                nticks,
+                COLLECTION_MAILBOX_CAPACITY,
+                TailBufferLimits::default(),
+                stats_handle.clone(),
+                webhook_handle.clone(),
+                broker_handle.clone(),
+                dead_letter_handle.clone(),
+            ),
+            stats_handle,
+            dead_letter_handle,
+            settings: Arc::new(PipelineSettings {
+                bars: self.bars,
+                quotes: self.quotes,
+                ..Default::default()
+            }),
+        };
+        handles.reconfigure(config.reconfiguration()).await;
+
+        let fetch_pool = FetchPool::new(
+            FETCH_POOL_SIZE,
+            FETCH_QUEUE_CAPACITY,
+            Duration::from_secs(HANDLER_TIMEOUT_SECS),
+        );
+
+        Ok(StockPipeline {
+            dispatcher: Dispatcher {
+                handles,
+                fetch_queue: fetch_pool.queue(),
+                symbols: symbols.into(),
+                from,
+                chunk_size: config.chunk_size,
+                window_size: config.window_size,
+                iteration_id: Arc::new(AtomicU64::new(0)),
+            },
+            fetch_pool,
+            broker_handle,
+            webhook_handle,
+            tick_interval: Duration::from_secs(config.tick_interval_secs),
+            trigger_timeout: Duration::from_secs(trigger_timeout_secs),
+            stop_token: CancellationToken::new(),
+            task: None,
+        })
+    }
+}
+
+/// An embedded pipeline, which fetches its symbols once on a [`StockPipeline::trigger_once`],
+/// and at every tick once it's [`StockPipeline::start`]ed
+pub struct StockPipeline {
+    dispatcher: Dispatcher,
+    fetch_pool: FetchPool,
+    broker_handle: BrokerActorHandle,
+    webhook_handle: WebhookActorHandle,
+    tick_interval: Duration,
+    trigger_timeout: Duration,
+    stop_token: CancellationToken,
+    task: Option<JoinHandle<()>>,
+}
+
+impl StockPipeline {
+    /// Returns a builder of the pipeline
+    pub fn builder() -> StockPipelineBuilder {
+        StockPipelineBuilder::default()
+    }
+
+    /// Starts the pipeline's loop, which fetches the symbols at every tick, the first one right away
+    ///
+    /// The pipeline is started only once; starting it again does nothing.
+    pub fn start(&mut self) {
+        if self.task.is_none() {
+            self.task = Some(runtime::spawn(Self::run(
+                self.dispatcher.clone(),
+                self.tick_interval,
+                self.stop_token.clone(),
+            )));
+        }
+    }
+
+    /// Fetches the symbols right away, regardless of the tick interval, and waits for their batch
+    ///
+    /// If the pipeline is started, the batch of a tick can be assembled first, in which case
+    /// it's that batch that is returned.
+    ///
+    /// # Errors
+    /// - If the actors aren't running
+    /// - If the batch isn't assembled within the trigger timeout, e.g., because a chunk of it
+    ///   timed out, or was abandoned for a gap in the data
+    pub async fn trigger_once(&self) -> Result<Batch> {
+        // subscribed before fetching, so the batch can't be missed
+        let mut batches = self.broker_handle.subscribe().await?;
+        self.dispatcher.dispatch(OffsetDateTime::now_utc()).await?;
+
+        runtime::timeout(self.trigger_timeout, batches.recv())
+            .await
+            .with_context(|| {
+                format!(
+                    "The batch wasn't assembled within {} s.",
+                    self.trigger_timeout.as_secs()
+                )
+            })?
+            .context("The pipeline stopped before the batch was assembled.")
+    }
+
+    /// Returns the last `n` assembled batches, newest first
+    ///
+    /// # Errors
+    /// - If the [`crate::my_async_actors::CollectionActor`] isn't running
+    pub async fn tail(&self, n: usize) -> Result<TailResponse> {
+        let response = self
+            .dispatcher
+            .handles
+            .collection_handle
+            .call(|sender| CollectionActorMsg::TailRequest {
+                sender,
+                n,
+                if_none_match: None,
+                request_id: None,
+            })
+            .await
+            .context("Couldn't call the CollectionActor.")?;
+
+        Ok(response.tail.unwrap_or_default())
+    }
+
+    /// Stops the pipeline's loop, waits for the symbols that are being fetched,
+    /// and shuts the actors down, after the writer has flushed and closed the output files
+    ///
+    /// The symbols that are still being fetched after the trigger timeout are given up on,
+    /// and their fetch workers are cancelled.
+    ///
+    /// # Errors
+    /// - If the pipeline's loop panicked, or if an actor couldn't be shut down;
+    ///   the actors are still shut down, and the other errors are logged
+    pub async fn shutdown(mut self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        self.stop_token.cancel();
+        if let Some(task) = self.task.take() {
+            if let Err(err) = task.await {
+                errors.push(anyhow!(err).context("The embedded pipeline's loop failed."));
+            }
+        }
+
+        self.fetch_pool.shutdown_within(self.trigger_timeout).await;
+
+        let handles = self.dispatcher.handles;
+        let results = [
+            handles.collection_handle.shutdown().await,
+            self.broker_handle.shutdown().await,
+            handles.writer_handle.shutdown().await,
+            self.webhook_handle.shutdown().await,
+            handles.dead_letter_handle.shutdown().await,
+            handles.stats_handle.shutdown().await,
+        ];
+        errors.extend(results.into_iter().filter_map(Result::err));
+
+        let mut errors = errors.into_iter();
+        match errors.next() {
+            Some(err) => {
+                for err in errors {
+                    tracing::warn!("{:#}", err);
+                }
+                Err(err)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// The pipeline's loop, which fetches the symbols at every tick, until the `stop_token`
+    /// is cancelled
+    async fn run(dispatcher: Dispatcher, tick_interval: Duration, stop_token: CancellationToken) {
+        let mut interval = Interval::new(tick_interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = stop_token.cancelled() => break,
+            }
+
+            let to = OffsetDateTime::now_utc();
+            // the local clock can be set back before the start of the period while running
+            if let Err(err) = check_period(dispatcher.from, to, to) {
+                tracing::error!("Skipping the iteration: {:#}", err);
+                continue;
+            }
+
+            // a stop doesn't wait for a full work queue
+            tokio::select! {
+                result = dispatcher.dispatch(to) => {
+                    if let Err(err) = result {
+                        tracing::warn!("{:#}", err);
+                    }
+                }
+                _ = stop_token.cancelled() => break,
+            }
+        }
+
+        tracing::debug!("The embedded pipeline's loop is stopped.");
+    }
+}
+
+/// What the pipeline's loop and its triggers fetch the symbols with
+#[derive(Clone)]
+struct Dispatcher {
+    handles: PipelineHandles,
+    /// The work queue of the pipeline's fetch pool, which the pipeline owns and shuts down
+    fetch_queue: FetchQueue,
+    symbols: Arc<[Symbol]>,
+    from: OffsetDateTime,
+    chunk_size: usize,
+    window_size: usize,
+    /// The sequence number of the latest iteration, of the loop's and the triggers' together
+    iteration_id: Arc<AtomicU64>,
+}
+
+impl Dispatcher {
+    /// Hands the chunks of the symbols over to the fetch workers, in a new iteration,
+    /// for the period that ends at `to`
+    ///
+    /// # Errors
+    /// - If the fetch workers have stopped
+    async fn dispatch(&self, to: OffsetDateTime) -> Result<()> {
+        let iteration = Iteration {
+            id: self.iteration_id.fetch_add(1, Ordering::Relaxed) + 1,
+            start: Instant::now(),
+            to,
+            num_chunks: calc_num_chunks(self.symbols.len(), self.chunk_size),
+        };
+
+        for (chunk_id, chunk) in self.symbols.chunks(self.chunk_size).enumerate() {
+            self.fetch_queue
+                .send(FetchActorMsg::QuoteRequestsMsg {
+                    symbols: chunk.into(),
+                    from: self.from,
+                    handles: self.handles.clone(),
+                    iteration,
+                    chunk_id,
+                    window_size: self.window_size,
+                })
+                .await
+                .context("Couldn't hand the symbols over to the fetch workers.")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use futures::future::BoxFuture;
+
+    use crate::price_windows::Bars;
+    use crate::quotes::{FixtureQuotes, QuoteProvider};
+
+    /// A provider whose requests never get an answer
+    struct SilentQuotes;
+
+    impl QuoteProvider for SilentQuotes {
+        fn bars<'a>(
+            &'a self,
+            _symbol: &'a str,
+            _from: OffsetDateTime,
+            _to: OffsetDateTime,
+            _interval: BarInterval,
+        ) -> BoxFuture<'a, Result<Bars>> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    #[tokio::test]
+    async fn embedded_pipeline_fetches_on_demand_and_on_ticks() {
+        let to = OffsetDateTime::now_utc();
+        let from = to - time::Duration::days(60);
+        let fixtures: HashMap<_, _> = [("AAPL", 100.0), ("MSFT", 200.0), ("NVDA", 300.0)]
+            .into_iter()
+            .map(|(symbol, base)| {
+                let bars = (0..40)
+                    .map(|day| (from + time::Duration::days(day), base + day as f64))
+                    .collect();
+                (symbol.to_string(), bars)
+            })
+            .collect();
+
+        assert!(StockPipeline::builder().build().await.is_err());
+        assert!(StockPipeline::builder()
+            .symbols(["AAPL"])
+            .indicators(["price", "volume"])
+            .build()
+            .await
+            .is_err());
+
+        let mut pipeline = StockPipeline::builder()
+            .symbols(["aapl", "MSFT", "NVDA"])
+            .quotes(Arc::new(FixtureQuotes::new(fixtures)))
+            .indicators(["price", "min"])
+            .chunk_size(2)
+            .tick_interval_secs(1)
+            .from(from)
+            .build()
+            .await
+            .unwrap();
+
+        let mut batch = pipeline.trigger_once().await.unwrap();
+        batch.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        let rows: Vec<_> = batch
+            .iter()
+            .map(|row| (row.symbol.to_string(), row.last_price, row.period_min))
+            .collect();
+        assert_eq!(
+            vec![
+                ("AAPL".to_string(), 139.0, 100.0),
+                ("MSFT".to_string(), 239.0, 200.0),
+                ("NVDA".to_string(), 339.0, 300.0),
+            ],
+            rows
+        );
+        // the indicators that aren't served are zeroed
+        let tail = pipeline.tail(10).await.unwrap();
+        assert_eq!(1, tail.len());
+        assert!(tail[0].iter().all(|row| row.period_max == 0.0));
+
+        // the first tick comes right away
+        pipeline.start();
+        let tail = runtime::timeout(Duration::from_secs(5), async {
+            loop {
+                let tail = pipeline.tail(10).await.unwrap();
+                if tail.len() == 2 {
+                    break tail;
+                }
+                runtime::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(3, tail[0].len());
+
+        pipeline.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn trigger_gives_up_on_a_batch_that_is_never_assembled() {
+        assert!(StockPipeline::builder()
+            .symbols(["AAPL"])
+            .trigger_timeout_secs(0)
+            .build()
+            .await
+            .is_err());
+
+        let pipeline = StockPipeline::builder()
+            .symbols(["AAPL", "MSFT"])
+            .quotes(Arc::new(SilentQuotes))
+            .trigger_timeout_secs(1)
+            .build()
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+        let err = pipeline.trigger_once().await.unwrap_err();
+        assert!(err.to_string().contains("wasn't assembled"), "{err:#}");
+        assert!(start.elapsed() < Duration::from_secs(HANDLER_TIMEOUT_SECS));
+        assert!(pipeline.tail(10).await.unwrap().is_empty());
+
+        // the fetch workers are cancelled after the trigger timeout, not after the handler timeout
+        let start = Instant::now();
+        pipeline.shutdown().await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(HANDLER_TIMEOUT_SECS));
+    }
+}
@@ -0,0 +1,363 @@
+//! Portfolio tracking
+//!
+//! The holdings, i.e., the symbols with their quantities and cost bases, are declared with
+//! the `holdings` option, e.g., `AAPL:10:150.25`, or through the web app. The
+//! [`crate::my_async_actors::PortfolioActorHandle`]'s actor values them at the latest prices
+//! of every completed batch, and writes the [`Valuation`] to a dedicated CSV file.
+//!
+//! The daily change of a holding is relative to the last price of the symbol that was seen
+//! on a previous day (in UTC), or to its first price that was seen, until the day changes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
+use utoipa::ToSchema;
+
+use crate::constants::PORTFOLIO_CSV_HEADER;
+use crate::my_async_actors::PerformanceIndicatorsRow;
+
+/// A number of shares of a symbol, and what they cost
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct Holding {
+    pub symbol: String,
+    /// The number of shares, which can be fractional
+    pub quantity: f64,
+    /// The cost of a share
+    pub cost_basis: f64,
+}
+
+impl Holding {
+    /// Checks that the quantity and the cost basis are positive
+    ///
+    /// # Errors
+    /// - If the quantity or the cost basis isn't a positive number
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.symbol.trim().is_empty(), "The symbol is empty.");
+        ensure!(
+            self.quantity.is_finite() && self.quantity > 0.0,
+            "The quantity of {} must be positive.",
+            self.symbol
+        );
+        ensure!(
+            self.cost_basis.is_finite() && self.cost_basis > 0.0,
+            "The cost basis of {} must be positive.",
+            self.symbol
+        );
+
+        Ok(())
+    }
+}
+
+impl FromStr for Holding {
+    type Err = anyhow::Error;
+
+    /// Parses a holding in the `SYMBOL:QUANTITY:COST_BASIS` format, e.g., `AAPL:10:150.25`
+    fn from_str(s: &str) -> Result<Self> {
+        let [symbol, quantity, cost_basis] = s.split(':').collect::<Vec<_>>()[..] else {
+            bail!(
+                "`{}` isn't a holding in the `SYMBOL:QUANTITY:COST_BASIS` format, such as `AAPL:10:150.25`.",
+                s
+            );
+        };
+        let holding = Self {
+            symbol: symbol.trim().to_uppercase(),
+            quantity: quantity
+                .trim()
+                .parse()
+                .with_context(|| format!("The quantity of `{}` isn't a number.", s))?,
+            cost_basis: cost_basis
+                .trim()
+                .parse()
+                .with_context(|| format!("The cost basis of `{}` isn't a number.", s))?,
+        };
+        holding.validate()?;
+
+        Ok(holding)
+    }
+}
+
+/// A holding, valued at the latest price of its symbol
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct HoldingValue {
+    pub symbol: String,
+    pub quantity: f64,
+    pub cost_basis: f64,
+    /// The latest price, or `None` if the symbol hasn't been fetched yet,
+    /// in which case the holding is valued at its cost
+    pub price: Option<f64>,
+    pub market_value: f64,
+    pub unrealized_pnl: f64,
+    pub unrealized_pnl_pct: f64,
+    /// The change of the market value since the previous day
+    pub daily_change: f64,
+    pub daily_change_pct: f64,
+}
+
+impl Display for HoldingValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let price = self
+            .price
+            .map(|price| format!("${:.2}", price))
+            .unwrap_or_default();
+        write!(
+            f,
+            "{},{},${:.2},{},${:.2},${:.2},{:.2}%,${:.2},{:.2}%",
+            self.symbol,
+            self.quantity,
+            self.cost_basis,
+            price,
+            self.market_value,
+            self.unrealized_pnl,
+            self.unrealized_pnl_pct,
+            self.daily_change,
+            self.daily_change_pct
+        )
+    }
+}
+
+/// The values of all holdings, and their totals
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct Valuation {
+    /// When the holdings were valued, in the RFC 3339 format
+    pub time: String,
+    pub market_value: f64,
+    pub cost: f64,
+    pub unrealized_pnl: f64,
+    pub unrealized_pnl_pct: f64,
+    pub daily_change: f64,
+    pub daily_change_pct: f64,
+    pub holdings: Vec<HoldingValue>,
+}
+
+/// The latest price of a symbol, and the price that its daily change is relative to
+#[derive(Clone, Copy, Debug)]
+struct Quote {
+    price: f64,
+    reference: f64,
+    date: Date,
+}
+
+/// The holdings, and the prices of their symbols
+#[derive(Debug, Default)]
+pub struct PortfolioTracker {
+    holdings: BTreeMap<String, Holding>,
+    quotes: HashMap<String, Quote>,
+}
+
+impl PortfolioTracker {
+    /// Creates a tracker of the `holdings`; a later holding of a symbol replaces an earlier one
+    pub fn new(holdings: Vec<Holding>) -> Self {
+        let mut tracker = Self::default();
+        for holding in holdings {
+            tracker.set(holding);
+        }
+
+        tracker
+    }
+
+    /// Returns whether there are no holdings
+    pub fn is_empty(&self) -> bool {
+        self.holdings.is_empty()
+    }
+
+    /// Adds a `holding`, or replaces the holding of its symbol
+    pub fn set(&mut self, mut holding: Holding) {
+        holding.symbol = holding.symbol.trim().to_uppercase();
+        self.holdings.insert(holding.symbol.clone(), holding);
+    }
+
+    /// Removes the holding of the `symbol`
+    ///
+    /// # Returns
+    /// Whether there was a holding of the symbol
+    pub fn remove(&mut self, symbol: &str) -> bool {
+        self.holdings
+            .remove(&symbol.trim().to_uppercase())
+            .is_some()
+    }
+
+    /// Takes the latest prices of the symbols from the `rows`, which were fetched at `now`
+    pub fn update_prices(&mut self, rows: &[PerformanceIndicatorsRow], now: OffsetDateTime) {
+        let today = now.date();
+        for row in rows {
+            let quote = self.quotes.entry(row.symbol.to_string()).or_insert(Quote {
+                price: row.last_price,
+                reference: row.last_price,
+                date: today,
+            });
+            if quote.date < today {
+                quote.reference = quote.price;
+                quote.date = today;
+            }
+            quote.price = row.last_price;
+        }
+    }
+
+    /// Values the holdings at the latest prices, at the `time`
+    pub fn valuation(&self, time: String) -> Valuation {
+        let holdings: Vec<HoldingValue> = self
+            .holdings
+            .values()
+            .map(|holding| {
+                let cost = holding.quantity * holding.cost_basis;
+                let quote = self.quotes.get(&holding.symbol);
+                let market_value = quote.map_or(cost, |quote| holding.quantity * quote.price);
+                let daily_change = quote.map_or(0.0, |quote| {
+                    holding.quantity * (quote.price - quote.reference)
+                });
+
+                HoldingValue {
+                    symbol: holding.symbol.clone(),
+                    quantity: holding.quantity,
+                    cost_basis: holding.cost_basis,
+                    price: quote.map(|quote| quote.price),
+                    market_value,
+                    unrealized_pnl: market_value - cost,
+                    unrealized_pnl_pct: percentage(market_value - cost, cost),
+                    daily_change,
+                    daily_change_pct: percentage(daily_change, market_value - daily_change),
+                }
+            })
+            .collect();
+
+        let market_value: f64 = holdings.iter().map(|value| value.market_value).sum();
+        let cost: f64 = self
+            .holdings
+            .values()
+            .map(|holding| holding.quantity * holding.cost_basis)
+            .sum();
+        let daily_change: f64 = holdings.iter().map(|value| value.daily_change).sum();
+
+        Valuation {
+            time,
+            market_value,
+            cost,
+            unrealized_pnl: market_value - cost,
+            unrealized_pnl_pct: percentage(market_value - cost, cost),
+            daily_change,
+            daily_change_pct: percentage(daily_change, market_value - daily_change),
+            holdings,
+        }
+    }
+}
+
+/// Returns the `change` as a percentage of the `base`, or zero if the base is zero
+fn percentage(change: f64, base: f64) -> f64 {
+    if base == 0.0 {
+        0.0
+    } else {
+        change / base * 100.0
+    }
+}
+
+/// Writes the valuations as CSV, a row per holding and a total row per valuation, after a header
+pub struct ValuationCsv {
+    writer: BufWriter<File>,
+}
+
+impl ValuationCsv {
+    /// Creates a CSV file at `path`, truncating an existing one
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", PORTFOLIO_CSV_HEADER)?;
+
+        Ok(Self { writer })
+    }
+
+    /// Writes a `valuation`, and flushes it to the file
+    pub fn write(&mut self, valuation: &Valuation) -> io::Result<()> {
+        for value in &valuation.holdings {
+            writeln!(self.writer, "{},{}", valuation.time, value)?;
+        }
+        writeln!(
+            self.writer,
+            "{},total,,,,${:.2},${:.2},{:.2}%,${:.2},{:.2}%",
+            valuation.time,
+            valuation.market_value,
+            valuation.unrealized_pnl,
+            valuation.unrealized_pnl_pct,
+            valuation.daily_change,
+            valuation.daily_change_pct
+        )?;
+
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::format_description::well_known::Rfc3339;
+    use time::Duration;
+
+    use super::*;
+
+    fn row(symbol: &str, last_price: f64) -> PerformanceIndicatorsRow {
+        PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price,
+            pct_change: 0.0,
+            period_min: 0.0,
+            period_max: 0.0,
+            sma: 0.0,
+            asset_class: Default::default(),
+        }
+    }
+
+    #[test]
+    fn holdings_are_parsed() {
+        let holding: Holding = " aapl:10:150.25".parse().unwrap();
+        assert_eq!(
+            Holding {
+                symbol: "AAPL".into(),
+                quantity: 10.0,
+                cost_basis: 150.25,
+            },
+            holding
+        );
+
+        assert!("AAPL:10".parse::<Holding>().is_err());
+        assert!("AAPL:ten:150".parse::<Holding>().is_err());
+        assert!("AAPL:-10:150".parse::<Holding>().is_err());
+    }
+
+    #[test]
+    fn holdings_are_valued_with_daily_changes() {
+        let mut tracker = PortfolioTracker::new(vec![
+            "AAPL:10:100".parse().unwrap(),
+            "MSFT:2:300".parse().unwrap(),
+        ]);
+        let monday = OffsetDateTime::parse("2024-01-01T15:00:00Z", &Rfc3339).unwrap();
+
+        tracker.update_prices(&[row("AAPL", 110.0)], monday);
+        tracker.update_prices(&[row("AAPL", 120.0)], monday + Duration::hours(1));
+        let valuation = tracker.valuation("monday".to_string());
+        // MSFT hasn't been fetched, so it's valued at its cost
+        assert_eq!(None, valuation.holdings[1].price);
+        assert_eq!(1200.0 + 600.0, valuation.market_value);
+        assert_eq!(200.0, valuation.unrealized_pnl);
+        assert_eq!(100.0, valuation.daily_change);
+
+        tracker.update_prices(&[row("AAPL", 90.0)], monday + Duration::days(1));
+        let valuation = tracker.valuation("tuesday".to_string());
+        let aapl = &valuation.holdings[0];
+        assert_eq!(-100.0, aapl.unrealized_pnl);
+        assert_eq!(-10.0, aapl.unrealized_pnl_pct);
+        assert_eq!(-300.0, aapl.daily_change);
+        assert_eq!(-25.0, aapl.daily_change_pct);
+        assert_eq!(
+            "AAPL,10,$100.00,$90.00,$900.00,$-100.00,-10.00%,$-300.00,-25.00%",
+            aapl.to_string()
+        );
+
+        assert!(tracker.remove("msft"));
+        assert!(!tracker.remove("MSFT"));
+        assert_eq!(1, tracker.valuation(String::new()).holdings.len());
+    }
+}
@@ -0,0 +1,34 @@
+//! The prelude
+//!
+//! The types that the crate is used through as a dependency: the embedded pipeline,
+//! the rows and batches that it produces, the signals that the rows are calculated with,
+//! the quote providers, and the settings, so that
+//! `use stock_trading_cli_with_async_streams::prelude::*;` is all that an embedding program needs.
+//!
+//! The actors, their messages and handles, which the binary wires together, aren't part of it.
+//! They stay in their modules, such as [`crate::my_async_actors`], and can change between releases.
+//!
+//! ```
+//! use stock_trading_cli_with_async_streams::prelude::*;
+//!
+//! # futures::executor::block_on(async {
+//! let closes = [10.0, 11.0, 12.0, 9.0];
+//! assert_eq!(Some(9.0), MinPrice {}.calculate(&closes).await);
+//!
+//! let row = PerformanceIndicatorsRow::calculate("AAPL", &closes, 2).await.unwrap();
+//! assert_eq!(12.0, row.period_max);
+//! # });
+//! ```
+
+pub use crate::async_signals::{
+    AsyncStockSignal, MaxPrice, MinPrice, PriceDifference, WindowedSMA,
+};
+pub use crate::cli::{BarInterval, OutputFormat};
+pub use crate::config::{ConfigUpdate, RuntimeConfig};
+pub use crate::my_async_actors::PerformanceIndicatorsRow;
+pub use crate::pipeline::{StockPipeline, StockPipelineBuilder};
+pub use crate::price_windows::Bars;
+pub use crate::quality::{QualityIssue, QualityReport};
+pub use crate::quotes::{FixtureQuotes, QuoteProvider, YahooQuotes};
+pub use crate::sync_signals::StockSignal;
+pub use crate::types::{Batch, Symbol, TailResponse};
@@ -0,0 +1,172 @@
+//! Rolling price windows
+//!
+//! By default, every tick fetches each symbol's whole series, from the start of the period.
+//! With the `rolling-windows` option, the fetch actors keep a window of every symbol's bars
+//! in memory instead, and fetch only the newest bars at every tick: from the newest bar
+//! of the window, which is fetched again, as it may still change, e.g., during a trading day,
+//! to the end of the period. The indicators and the data-quality checks are then calculated
+//! over the window, whose bars outside of the period are dropped.
+//!
+//! A symbol's window is fetched from scratch again when it doesn't cover the period anymore,
+//! i.e., when the period starts before the window, or ends before its newest bar.
+//!
+//! The data provider's adjusted closes of the older bars can change, e.g., after a split,
+//! which a window doesn't pick up until it's fetched from scratch again.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, PoisonError};
+
+use time::OffsetDateTime;
+
+use crate::types::Symbol;
+
+/// The bars of a symbol, i.e., its closes by the times of their bars, in the order of the times
+pub type Bars = Vec<(OffsetDateTime, f64)>;
+
+/// The bars of a symbol since the start of the period that they were fetched for
+#[derive(Debug)]
+struct Window {
+    from: OffsetDateTime,
+    bars: Bars,
+}
+
+/// The rolling price windows of the symbols, which the fetch actors share
+#[derive(Debug, Default)]
+pub struct PriceWindows {
+    windows: Mutex<HashMap<Symbol, Window>>,
+}
+
+impl PriceWindows {
+    /// Returns when the bars of a `symbol` are to be fetched from, for the period from `from` to `to`
+    ///
+    /// It's the time of the newest bar of the symbol's window, if the window covers the period,
+    /// or `from`, if the symbol has to be fetched from scratch.
+    pub fn fetch_from(
+        &self,
+        symbol: &str,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> OffsetDateTime {
+        let windows = self.windows.lock().unwrap_or_else(PoisonError::into_inner);
+
+        windows
+            .get(symbol)
+            .filter(|window| window.from <= from)
+            .and_then(|window| window.bars.last())
+            .map(|&(newest, _)| newest)
+            .filter(|&newest| from <= newest && newest <= to)
+            .unwrap_or(from)
+    }
+
+    /// Merges the `fetched` bars of a `symbol`, which were fetched from the time that
+    /// [`PriceWindows::fetch_from`] returned, into its window for the period from `from` to `to`
+    ///
+    /// The fetched bars replace the window's bars at and after the oldest fetched bar,
+    /// and the bars before `from` or after `to` are dropped, even if nothing was fetched,
+    /// e.g., after the clock has been set back, or with a fixed end of the period.
+    ///
+    /// # Returns
+    /// The bars of the symbol's window
+    pub fn update(
+        &self,
+        symbol: &Symbol,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        fetched: Bars,
+    ) -> Bars {
+        let mut windows = self.windows.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let window = windows
+            .entry(Symbol::clone(symbol))
+            .or_insert_with(|| Window {
+                from,
+                bars: Vec::new(),
+            });
+        // the window doesn't cover an earlier start of the period, so it was fetched from scratch
+        if from < window.from {
+            window.bars.clear();
+        }
+
+        if let Some(&(oldest, _)) = fetched.first() {
+            let kept = window.bars.partition_point(|&(time, _)| time < oldest);
+            window.bars.truncate(kept);
+            window.bars.extend(fetched);
+        }
+        let ahead = window.bars.partition_point(|&(time, _)| time <= to);
+        window.bars.truncate(ahead);
+        let stale = window.bars.partition_point(|&(time, _)| time < from);
+        window.bars.drain(..stale);
+        window.from = from;
+
+        window.bars.clone()
+    }
+
+    /// Drops the windows of the symbols that aren't among the `symbols` anymore,
+    /// e.g., after they have been removed at runtime
+    pub fn retain(&self, symbols: &[Symbol]) {
+        let symbols: HashSet<&str> = symbols.iter().map(|symbol| &**symbol).collect();
+        self.windows
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|symbol, _| symbols.contains(&**symbol));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: i64) -> OffsetDateTime {
+        OffsetDateTime::UNIX_EPOCH + time::Duration::days(day)
+    }
+
+    fn bars(days: &[(i64, f64)]) -> Bars {
+        days.iter().map(|&(d, close)| (day(d), close)).collect()
+    }
+
+    #[test]
+    fn windows_are_rolled_with_the_newest_bars() {
+        let windows = PriceWindows::default();
+        let aapl: Symbol = "AAPL".into();
+
+        assert_eq!(day(0), windows.fetch_from("AAPL", day(0), day(3)));
+        let window = windows.update(&aapl, day(0), day(3), bars(&[(0, 1.0), (1, 2.0), (2, 3.0)]));
+        assert_eq!(bars(&[(0, 1.0), (1, 2.0), (2, 3.0)]), window);
+
+        // the newest bar is fetched again, as it may have changed, along with the new ones
+        assert_eq!(day(2), windows.fetch_from("AAPL", day(0), day(4)));
+        let window = windows.update(&aapl, day(0), day(4), bars(&[(2, 3.5), (3, 4.0)]));
+        assert_eq!(bars(&[(0, 1.0), (1, 2.0), (2, 3.5), (3, 4.0)]), window);
+
+        // a later start of the period drops the older bars
+        assert_eq!(day(3), windows.fetch_from("AAPL", day(1), day(5)));
+        let window = windows.update(&aapl, day(1), day(5), Vec::new());
+        assert_eq!(bars(&[(1, 2.0), (2, 3.5), (3, 4.0)]), window);
+
+        // an earlier start of the period, or an earlier end, is fetched from scratch
+        assert_eq!(day(0), windows.fetch_from("AAPL", day(0), day(5)));
+        assert_eq!(day(1), windows.fetch_from("AAPL", day(1), day(2)));
+        let window = windows.update(&aapl, day(0), day(5), bars(&[(0, 1.0), (1, 2.0)]));
+        assert_eq!(bars(&[(0, 1.0), (1, 2.0)]), window);
+
+        windows.retain(&[]);
+        assert_eq!(day(0), windows.fetch_from("AAPL", day(0), day(5)));
+    }
+
+    #[test]
+    fn windows_are_clipped_to_the_period_when_nothing_is_fetched() {
+        let windows = PriceWindows::default();
+        let aapl: Symbol = "AAPL".into();
+        windows.update(
+            &aapl,
+            day(0),
+            day(3),
+            bars(&[(0, 1.0), (1, 2.0), (2, 3.0), (3, 4.0)]),
+        );
+
+        // the period now ends before the newest bar, and the refetch comes back empty
+        assert_eq!(day(0), windows.fetch_from("AAPL", day(0), day(1)));
+        let window = windows.update(&aapl, day(0), day(1), Vec::new());
+        assert_eq!(bars(&[(0, 1.0), (1, 2.0)]), window);
+    }
+}
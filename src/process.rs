@@ -1,4 +1,7 @@
 //! Implementation without actors
+//!
+//! The output file is written on the runtime's blocking threads, through
+//! [`runtime::spawn_blocking`], so that a large write or flush doesn't block the executor's threads.
 
 use std::fs::File;
 use std::io::{BufWriter, Write};
@@ -11,6 +14,7 @@ use yahoo_finance_api as yahoo;
 
 use crate::async_signals::{AsyncStockSignal, MaxPrice, MinPrice, PriceDifference, WindowedSMA};
 use crate::constants::{CSV_FILE_PATH, CSV_HEADER, WINDOW_SIZE};
+use crate::runtime;
 
 /// Retrieves data for a single symbol from a data provider and extracts the closing prices
 ///
@@ -97,12 +101,15 @@ pub async fn handle_symbol_data(
     Ok(rows)
 }
 
-pub fn start_writer() -> Result<Option<BufWriter<File>>> {
-    let file_name = CSV_FILE_PATH.to_string();
-    let mut file = File::create(&file_name)
-        .context(format!("Could not open target file \"{}\".", file_name))?;
-    let _ = writeln!(&mut file, "{}", CSV_HEADER);
-    let writer = Some(BufWriter::new(file));
+pub async fn start_writer() -> Result<Option<BufWriter<File>>> {
+    let writer = runtime::spawn_blocking(|| {
+        let file_name = CSV_FILE_PATH.to_string();
+        let mut file = File::create(&file_name)
+            .context(format!("Could not open target file \"{}\".", file_name))?;
+        let _ = writeln!(&mut file, "{}", CSV_HEADER);
+        anyhow::Ok(Some(BufWriter::new(file)))
+    })
+    .await??;
     #[cfg(debug_assertions)]
     println!("Writer is started.");
 
@@ -114,21 +121,30 @@ pub fn start_writer() -> Result<Option<BufWriter<File>>> {
 /// We don't have to measure execution time in this function.
 /// We can measure it in the main loop instead.
 /// Still, to be consistent with the asynchronous Actor implementations, we measure it here.
-pub fn write_to_csv(
-    mut writer: &mut Option<BufWriter<File>>,
-    all_rows: Vec<&Result<Vec<String>>>,
+///
+/// The writer is handed over to a blocking thread, and back; it's lost if the thread panics.
+pub async fn write_to_csv(
+    writer: &mut Option<BufWriter<File>>,
+    all_rows: Vec<Result<Vec<String>>>,
     start: Instant,
 ) -> Result<()> {
-    if let Some(file) = &mut writer {
-        // let rows = all_rows.into_iter().flatten();
-        for rows in all_rows {
-            let rows = rows.as_ref().expect("Expected some rows to write.");
-            for row in rows {
-                let _ = writeln!(file, "{}", row);
+    if let Some(mut file) = writer.take() {
+        let (file, result) = runtime::spawn_blocking(move || {
+            // let rows = all_rows.into_iter().flatten();
+            for rows in all_rows {
+                let rows = rows.expect("Expected some rows to write.");
+                for row in rows {
+                    let _ = writeln!(file, "{}", row);
+                }
             }
-        }
-        file.flush()
-            .context("Failed to flush to file. Data loss :/")?;
+            let result = file
+                .flush()
+                .context("Failed to flush to file. Data loss :/");
+            (file, result)
+        })
+        .await?;
+        *writer = Some(file);
+        result?;
     }
 
     println!("Took {:.3?} to complete.\n", start.elapsed());
@@ -137,10 +153,10 @@ pub fn write_to_csv(
 }
 
 // Currently unused, but also unneeded.
-pub fn stop_writer(mut writer: Option<BufWriter<File>>) -> Result<()> {
-    if let Some(writer) = &mut writer {
-        writer
-            .flush()
+pub async fn stop_writer(writer: Option<BufWriter<File>>) -> Result<()> {
+    if let Some(mut writer) = writer {
+        runtime::spawn_blocking(move || writer.flush())
+            .await?
             .context("Failed to flush writer. Data loss :(")?;
     };
     #[cfg(debug_assertions)]
@@ -0,0 +1,219 @@
+//! Data-quality checks
+//!
+//! The daily bars of every symbol are checked right after they have been fetched, before
+//! the performance indicators are calculated over them:
+//! - the prices that are zero, negative or not a number are dropped, so that they don't end up
+//!   in the indicators,
+//! - the trading days, i.e., the weekdays, that are missing between two bars are counted,
+//!   but only in gaps that are longer than [`QUALITY_MAX_GAP_DAYS`], so that the market holidays
+//!   aren't counted,
+//! - a series whose newest bar is more than [`QUALITY_STALE_DAYS`] older than the end
+//!   of the period is stale,
+//! - the bars that are more than [`QUALITY_CLOCK_SKEW_SECS`] after the end of the period,
+//!   i.e., ahead of the local time, are dropped, so that a clock skew between the data provider
+//!   and the local machine doesn't bring bars of the future into the percentage change.
+//!   The bars within that tolerance are kept, as the local clock is merely a little behind.
+//!
+//! The issues are collected in a [`QualityReport`] per symbol, which flags the symbol's row
+//! in the output, and which the web app serves.
+
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime, Weekday};
+use utoipa::ToSchema;
+
+use crate::constants::{QUALITY_CLOCK_SKEW_SECS, QUALITY_MAX_GAP_DAYS, QUALITY_STALE_DAYS};
+
+/// A data-quality issue of a symbol's series
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum QualityIssue {
+    /// Trading days without a bar, in the gaps that are too long to be holidays
+    MissingDays { days: usize },
+    /// Prices that are zero, negative or not a number, and which were dropped
+    InvalidPrices { count: usize },
+    /// The newest bar, at the `last` time, in the RFC 3339 format, is too old
+    Stale { last: String },
+    /// Bars that are ahead of the local time, and which were dropped
+    AheadOfTime { count: usize },
+}
+
+impl Display for QualityIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QualityIssue::MissingDays { days } => write!(f, "{} missing trading days", days),
+            QualityIssue::InvalidPrices { count } => write!(f, "{} invalid prices", count),
+            QualityIssue::Stale { last } => write!(f, "stale since {}", last),
+            QualityIssue::AheadOfTime { count } => write!(f, "{} bars ahead of time", count),
+        }
+    }
+}
+
+/// The data-quality issues of a symbol's series, as of its newest fetch
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct QualityReport {
+    pub symbol: String,
+    pub issues: Vec<QualityIssue>,
+}
+
+impl QualityReport {
+    /// Returns whether the series has no issues
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The issues, separated by semicolons, for the output
+    pub fn flags(&self) -> String {
+        self.issues
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Returns the number of weekdays strictly between the days of `earlier` and `later`
+fn weekdays_between(earlier: OffsetDateTime, later: OffsetDateTime) -> usize {
+    let mut day = earlier.date().next_day();
+    let mut weekdays = 0;
+    while let Some(date) = day.filter(|&date| date < later.date()) {
+        if !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday) {
+            weekdays += 1;
+        }
+        day = date.next_day();
+    }
+
+    weekdays
+}
+
+/// Checks the daily `bars` of the `symbol`, i.e., its closes by the times of their bars,
+/// in the order of the times, that were fetched for a period that ends at `to`
+///
+/// # Returns
+/// The report of the issues, and the valid closes, which the indicators are calculated over
+pub fn check(
+    symbol: &str,
+    bars: &[(OffsetDateTime, f64)],
+    to: OffsetDateTime,
+) -> (QualityReport, Vec<f64>) {
+    let mut issues = Vec::new();
+
+    let in_period =
+        bars.partition_point(|&(time, _)| time <= to + Duration::seconds(QUALITY_CLOCK_SKEW_SECS));
+    let (bars, ahead) = bars.split_at(in_period);
+
+    let valid: Vec<_> = bars
+        .iter()
+        .filter(|(_, close)| close.is_finite() && *close > 0.0)
+        .collect();
+
+    let missing_days: usize = bars
+        .windows(2)
+        .map(|pair| weekdays_between(pair[0].0, pair[1].0))
+        .filter(|&missing| missing > QUALITY_MAX_GAP_DAYS)
+        .sum();
+    if missing_days > 0 {
+        issues.push(QualityIssue::MissingDays { days: missing_days });
+    }
+
+    if valid.len() < bars.len() {
+        issues.push(QualityIssue::InvalidPrices {
+            count: bars.len() - valid.len(),
+        });
+    }
+
+    if !ahead.is_empty() {
+        issues.push(QualityIssue::AheadOfTime { count: ahead.len() });
+    }
+
+    // a series without bars isn't stale, it's missing, which is reported on its own
+    if let Some(&(last, _)) = bars.last() {
+        if to - last > Duration::days(QUALITY_STALE_DAYS) {
+            issues.push(QualityIssue::Stale {
+                last: last.format(&Rfc3339).unwrap_or_default(),
+            });
+        }
+    }
+
+    let report = QualityReport {
+        symbol: symbol.to_string(),
+        issues,
+    };
+
+    (report, valid.into_iter().map(|&(_, close)| close).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(time: &str) -> OffsetDateTime {
+        OffsetDateTime::parse(time, &Rfc3339).unwrap()
+    }
+
+    #[test]
+    fn clean_series_has_no_issues() {
+        // a Thursday, a Friday, and the Monday after a weekend
+        let bars = [
+            (time("2024-01-04T14:30:00Z"), 10.0),
+            (time("2024-01-05T14:30:00Z"), 11.0),
+            (time("2024-01-08T14:30:00Z"), 12.0),
+            // the Wednesday after a single-day holiday
+            (time("2024-01-10T14:30:00Z"), 13.0),
+        ];
+
+        let (report, closes) = check("AAPL", &bars, time("2024-01-11T00:00:00Z"));
+        assert!(report.is_ok());
+        assert_eq!(vec![10.0, 11.0, 12.0, 13.0], closes);
+    }
+
+    #[test]
+    fn gaps_invalid_prices_and_stale_series_are_flagged() {
+        let bars = [
+            (time("2024-01-01T14:30:00Z"), 10.0),
+            // four weekdays are missing in between
+            (time("2024-01-08T14:30:00Z"), 0.0),
+            (time("2024-01-09T14:30:00Z"), -1.0),
+            (time("2024-01-10T14:30:00Z"), 12.0),
+        ];
+
+        let (report, closes) = check("AAPL", &bars, time("2024-01-20T00:00:00Z"));
+        assert_eq!(
+            vec![
+                QualityIssue::MissingDays { days: 4 },
+                QualityIssue::InvalidPrices { count: 2 },
+                QualityIssue::Stale {
+                    last: "2024-01-10T14:30:00Z".to_string()
+                },
+            ],
+            report.issues
+        );
+        assert_eq!(
+            "4 missing trading days; 2 invalid prices; stale since 2024-01-10T14:30:00Z",
+            report.flags()
+        );
+        assert_eq!(vec![10.0, 12.0], closes);
+
+        let (report, closes) = check("AAPL", &[], time("2024-01-20T00:00:00Z"));
+        assert!(report.is_ok());
+        assert!(closes.is_empty());
+    }
+
+    #[test]
+    fn bars_ahead_of_time_are_dropped_beyond_the_clock_skew() {
+        let bars = [
+            (time("2024-01-10T14:30:00Z"), 10.0),
+            // the local clock is a minute behind
+            (time("2024-01-11T00:01:00Z"), 11.0),
+            (time("2024-01-11T14:30:00Z"), 20.0),
+            (time("2024-01-12T14:30:00Z"), 30.0),
+        ];
+
+        let (report, closes) = check("AAPL", &bars, time("2024-01-11T00:00:00Z"));
+        assert_eq!(vec![QualityIssue::AheadOfTime { count: 2 }], report.issues);
+        assert_eq!("2 bars ahead of time", report.flags());
+        assert_eq!(vec![10.0, 11.0], closes);
+    }
+}
@@ -0,0 +1,218 @@
+//! Quote providers
+//!
+//! The fetch actors and the backtest get the symbols' bars from a [`QuoteProvider`]:
+//! Yahoo! Finance by default, or, with the `quotes-fixtures` option, local fixtures, which are
+//! served instead of reaching the network. The fixtures let the whole app, i.e., the actors,
+//! the writer and the web app, run offline, e.g., in end-to-end tests in CI, without their
+//! results depending on the network, or on the provider's data of the day.
+//!
+//! A fixtures file is a CSV file of `symbol,time,close` rows, with the times in the RFC 3339
+//! format, and an optional header row. The bars of a symbol are served regardless of the
+//! interval that they are asked for in, and a symbol without fixtures is an error,
+//! the way that an unknown symbol is with the data provider.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use futures::future::BoxFuture;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use yahoo_finance_api as yahoo;
+
+use crate::cli::BarInterval;
+use crate::price_windows::Bars;
+
+/// A source of the symbols' bars
+pub trait QuoteProvider: Send + Sync {
+    /// Retrieves the bars of a `symbol` from `from` to `to`, in bars of the `interval`,
+    /// oldest first
+    fn bars<'a>(
+        &'a self,
+        symbol: &'a str,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        interval: BarInterval,
+    ) -> BoxFuture<'a, Result<Bars>>;
+}
+
+impl std::fmt::Debug for dyn QuoteProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("QuoteProvider")
+    }
+}
+
+/// Creates the quote provider: the fixtures in the `fixtures` file, if provided,
+/// or Yahoo! Finance
+///
+/// # Errors
+/// - If the fixtures can't be read, or if the `YahooConnector` can't be created
+pub fn provider(fixtures: Option<&Path>) -> Result<Arc<dyn QuoteProvider>> {
+    Ok(match fixtures {
+        Some(path) => Arc::new(FixtureQuotes::load(path)?),
+        None => Arc::new(
+            YahooQuotes::new()
+                .context("Couldn't construct the `YahooConnector` for the quotes.")?,
+        ),
+    })
+}
+
+/// The bars of Yahoo! Finance, whose closes are adjusted for splits and dividends
+pub struct YahooQuotes {
+    connector: yahoo::YahooConnector,
+}
+
+impl YahooQuotes {
+    /// Creates the provider
+    ///
+    /// # Errors
+    /// - [`yahoo::YahooError`](https://docs.rs/yahoo_finance_api/2.2.1/yahoo_finance_api/enum.YahooError.html)
+    ///   if the `YahooConnector` can't be created
+    pub fn new() -> Result<Self, yahoo::YahooError> {
+        Ok(Self {
+            connector: yahoo::YahooConnector::new()?,
+        })
+    }
+}
+
+impl QuoteProvider for YahooQuotes {
+    fn bars<'a>(
+        &'a self,
+        symbol: &'a str,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        interval: BarInterval,
+    ) -> BoxFuture<'a, Result<Bars>> {
+        Box::pin(async move {
+            // The crate that we're using doesn't contain a function that works with a chunk of symbols.
+            let mut quotes = self
+                .connector
+                .get_quote_history_interval(symbol, from, to, interval.as_str())
+                .await?
+                .quotes()?;
+            quotes.sort_by_cached_key(|quote| quote.timestamp);
+
+            Ok(quotes
+                .iter()
+                .filter_map(|quote| {
+                    let time = OffsetDateTime::from_unix_timestamp(quote.timestamp as i64).ok()?;
+                    Some((time, quote.adjclose))
+                })
+                .collect())
+        })
+    }
+}
+
+/// The bars of local fixtures, which are served instead of reaching the network
+#[derive(Debug, Default)]
+pub struct FixtureQuotes {
+    bars: HashMap<String, Bars>,
+}
+
+impl FixtureQuotes {
+    /// Creates the provider out of the `bars` of the symbols, which needn't be in order
+    pub fn new(mut bars: HashMap<String, Bars>) -> Self {
+        for symbol_bars in bars.values_mut() {
+            symbol_bars.sort_by_key(|&(time, _)| time);
+        }
+
+        Self { bars }
+    }
+
+    /// Reads the fixtures from a CSV file of `symbol,time,close` rows
+    ///
+    /// # Errors
+    /// - [std::io::Error] if the file can't be read
+    /// - If a row isn't in the `symbol,time,close` format
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read the quotes fixtures {:?}.", path))?;
+        let rows = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with("symbol,"));
+
+        let mut bars: HashMap<String, Bars> = HashMap::new();
+        for (index, row) in rows.enumerate() {
+            let (symbol, time, close) = Self::parse_row(row)
+                .with_context(|| format!("Invalid fixture row {} of {:?}.", index + 1, path))?;
+            bars.entry(symbol).or_default().push((time, close));
+        }
+
+        Ok(Self::new(bars))
+    }
+
+    /// Parses a `symbol,time,close` row
+    fn parse_row(row: &str) -> Result<(String, OffsetDateTime, f64)> {
+        let mut fields = row.split(',').map(str::trim);
+        let (Some(symbol), Some(time), Some(close), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(anyhow!("`{}` isn't a `symbol,time,close` row.", row));
+        };
+
+        Ok((
+            symbol.to_uppercase(),
+            OffsetDateTime::parse(time, &Rfc3339)?,
+            close.parse()?,
+        ))
+    }
+}
+
+impl QuoteProvider for FixtureQuotes {
+    fn bars<'a>(
+        &'a self,
+        symbol: &'a str,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        _interval: BarInterval,
+    ) -> BoxFuture<'a, Result<Bars>> {
+        Box::pin(async move {
+            let bars = self
+                .bars
+                .get(symbol)
+                .ok_or_else(|| anyhow!("There are no fixtures of {}.", symbol))?;
+
+            Ok(bars
+                .iter()
+                .filter(|&&(time, _)| from <= time && time <= to)
+                .copied()
+                .collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fixtures_are_served_within_the_period() {
+        let path = std::env::temp_dir().join(format!("quotes-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "symbol,time,close\n\
+             aapl,2024-01-03T00:00:00Z,3.0\n\
+             AAPL,2024-01-01T00:00:00Z,1.0\n\
+             AAPL,2024-01-02T00:00:00Z,2.0\n",
+        )
+        .unwrap();
+        let quotes = FixtureQuotes::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let day =
+            |d: u8| OffsetDateTime::parse(&format!("2024-01-0{}T00:00:00Z", d), &Rfc3339).unwrap();
+        let bars = quotes
+            .bars("AAPL", day(2), day(3), BarInterval::OneDay)
+            .await
+            .unwrap();
+        assert_eq!(vec![(day(2), 2.0), (day(3), 3.0)], bars);
+
+        assert!(quotes
+            .bars("MSFT", day(1), day(3), BarInterval::OneDay)
+            .await
+            .is_err());
+        assert!(FixtureQuotes::parse_row("AAPL,2024-01-01T00:00:00Z").is_err());
+        assert!(FixtureQuotes::parse_row("AAPL,yesterday,1.0").is_err());
+    }
+}
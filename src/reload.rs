@@ -0,0 +1,215 @@
+//! Hot-reload of the configuration file
+//!
+//! On a `SIGHUP` signal, the command line is parsed again, together with the config file,
+//! and the settings that have changed since they were last loaded are applied to the running app,
+//! without restarting it:
+//! - the symbols, which are added to, or removed from, the symbols actor,
+//! - the tick interval, the chunk size, the window size and the indicators, which update
+//!   the runtime configuration, and are pushed to the long-lived actors.
+//!
+//! Only the changed settings are applied, so the changes that were made through the web app
+//! in the meantime are kept, unless the same settings have changed in the file, too.
+//! The other settings are only taken into account at startup.
+
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use crate::cli::{Args, Cli};
+use crate::config::{ConfigUpdate, RuntimeConfig};
+use crate::handlers::WebAppState;
+use crate::my_async_actors::{ActorHandle, CollectionActorMsg};
+
+/// The settings that can be changed at runtime, as the command line and the config file provide them
+#[derive(Clone, Debug, PartialEq)]
+struct ReloadableSettings {
+    symbols: Vec<String>,
+    tick_interval_secs: u64,
+    chunk_size: usize,
+    window_size: usize,
+    indicators: Vec<String>,
+}
+
+impl ReloadableSettings {
+    /// Takes the reloadable settings from the `args`
+    ///
+    /// # Errors
+    /// - [std::io::Error] if the symbols file can't be read
+    /// - If there are no symbols, or if some of them aren't valid
+    fn from_args(args: &Args) -> Result<Self> {
+        Ok(Self {
+            symbols: args.symbols()?,
+            tick_interval_secs: args.tick_interval_secs,
+            chunk_size: args.chunk_size,
+            window_size: args.window_size,
+            indicators: args
+                .indicators
+                .clone()
+                .unwrap_or_else(|| RuntimeConfig::default().indicators),
+        })
+    }
+
+    /// Returns an update of the runtime configuration with the settings that differ
+    /// in the `newer` ones, or `None` if none of them do
+    fn config_update(&self, newer: &Self) -> Option<ConfigUpdate> {
+        let update = ConfigUpdate {
+            tick_interval_secs: changed(&self.tick_interval_secs, &newer.tick_interval_secs),
+            chunk_size: changed(&self.chunk_size, &newer.chunk_size),
+            window_size: changed(&self.window_size, &newer.window_size),
+            indicators: changed(&self.indicators, &newer.indicators),
+            flush_interval_secs: None,
+        };
+
+        let unchanged = update.tick_interval_secs.is_none()
+            && update.chunk_size.is_none()
+            && update.window_size.is_none()
+            && update.indicators.is_none();
+        if unchanged {
+            None
+        } else {
+            Some(update)
+        }
+    }
+
+    /// Returns the symbols that were added in the `newer` settings, and those that were removed
+    fn symbol_changes(&self, newer: &Self) -> (Vec<String>, Vec<String>) {
+        let added = newer
+            .symbols
+            .iter()
+            .filter(|symbol| !self.symbols.contains(symbol))
+            .cloned()
+            .collect();
+        let removed = self
+            .symbols
+            .iter()
+            .filter(|symbol| !newer.symbols.contains(symbol))
+            .cloned()
+            .collect();
+
+        (added, removed)
+    }
+}
+
+/// Returns the `new` value if it differs from the `old` one
+fn changed<T: PartialEq + Clone>(old: &T, new: &T) -> Option<T> {
+    if old == new {
+        None
+    } else {
+        Some(new.clone())
+    }
+}
+
+/// Reloads the config file at every `SIGHUP` signal, until the `shutdown_token` is cancelled
+///
+/// The settings that have changed since the previous load, or since startup,
+/// are applied through the actors of the `state`.
+/// A config file that can't be loaded is reported, and nothing is changed.
+///
+/// # Errors
+/// - If the `SIGHUP` signal can't be listened for
+/// - If the initial settings can't be taken from the `args`
+#[cfg(unix)]
+pub async fn reload_on_hangup(
+    args: Args,
+    state: WebAppState,
+    shutdown_token: CancellationToken,
+) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    let mut settings = ReloadableSettings::from_args(&args)?;
+
+    loop {
+        tokio::select! {
+            _ = hangups.recv() => {}
+            _ = shutdown_token.cancelled() => break,
+        }
+
+        tracing::info!("SIGHUP received. Reloading the config file...");
+        match reload(&settings, &state).await {
+            Ok(reloaded) => settings = reloaded,
+            Err(err) => tracing::error!("Couldn't reload the config file: {:#}", err),
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the command line and the config file again, and applies the settings that differ
+/// from the `previous` ones
+///
+/// # Returns
+/// The reloaded settings
+///
+/// # Errors
+/// - If the command line and the config file can't be parsed; nothing is changed then
+/// - If the symbols or the configuration actor isn't running
+async fn reload(previous: &ReloadableSettings, state: &WebAppState) -> Result<ReloadableSettings> {
+    let args = Cli::try_parse_from_with_config(std::env::args_os())?
+        .command
+        .into_args();
+    let settings = ReloadableSettings::from_args(&args)?;
+
+    let (added, removed) = previous.symbol_changes(&settings);
+    if !added.is_empty() {
+        state.symbols_handle.add(added).await?;
+    }
+    for symbol in removed {
+        // the symbol's rows are purged from the buffered batches, the same as through the web app
+        if state.symbols_handle.remove(symbol.clone()).await? {
+            if let Err(err) = state
+                .handles
+                .collection_handle
+                .send(CollectionActorMsg::PurgeSymbol(symbol.clone()))
+                .await
+            {
+                state
+                    .handles
+                    .dead_letter_handle
+                    .undelivered("CollectionActor", format!("PurgeSymbol({})", symbol), err)
+                    .await;
+            }
+        }
+    }
+
+    if let Some(update) = previous.config_update(&settings) {
+        let config = state.config_handle.update(update).await?;
+        state.handles.reconfigure(config.reconfiguration()).await;
+    }
+
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changed_settings_are_applied() {
+        let symbols = |symbols: &[&str]| symbols.iter().map(|s| s.to_string()).collect();
+        let previous = ReloadableSettings {
+            symbols: symbols(&["AAPL", "MSFT", "GOOG"]),
+            tick_interval_secs: 30,
+            chunk_size: 5,
+            window_size: 30,
+            indicators: RuntimeConfig::default().indicators,
+        };
+        assert!(previous.config_update(&previous.clone()).is_none());
+
+        let reloaded = ReloadableSettings {
+            symbols: symbols(&["MSFT", "NVDA", "AAPL"]),
+            window_size: 10,
+            indicators: symbols(&["price", "sma"]),
+            ..previous.clone()
+        };
+        let update = previous.config_update(&reloaded).unwrap();
+        assert_eq!(None, update.tick_interval_secs);
+        assert_eq!(None, update.chunk_size);
+        assert_eq!(Some(10), update.window_size);
+        assert_eq!(Some(symbols(&["price", "sma"])), update.indicators);
+
+        assert_eq!(
+            (symbols(&["NVDA"]), symbols(&["GOOG"])),
+            previous.symbol_changes(&reloaded)
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Accelerated replay
+//!
+//! The `replay` subcommand reads the batches that an earlier run recorded in the output file,
+//! or in the event log, see [`crate::event_log`], and hands them over, one by one, to the writer and collection actors, as if they had just been
+//! fetched and processed. They are then published by the broker actor to the alerts, paper trading,
+//! portfolio and watchlists, and they are served by the web app and the gRPC API, so that the
+//! integrations can be exercised against realistic data without fetching anything.
+//!
+//! The output file doesn't record when its batches were completed, only their rows, so they are
+//! replayed at the tick interval divided by the speed multiplier, see [`pace`].
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::my_async_actors::PerformanceIndicatorsRow;
+use crate::types::Batch;
+
+/// A recorded batch: the start of its period, in the RFC 3339 format, and its rows
+pub type RecordedBatch = (String, Batch);
+
+/// Splits the `contents` of an output file, i.e., its header and its rows, into the recorded batches,
+/// in the order in which they were written
+///
+/// Every symbol is in a batch at most once, so a batch ends right before its first symbol repeats.
+///
+/// # Errors
+/// - If a row isn't in the format that the writer actor writes
+pub fn recorded_batches(contents: &str) -> Result<Vec<RecordedBatch>> {
+    let mut batches: Vec<RecordedBatch> = Vec::new();
+    let mut current: Option<RecordedBatch> = None;
+
+    for line in contents
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+    {
+        // the period start comes before the row
+        let (from, row) = line
+            .split_once(',')
+            .with_context(|| format!("Invalid row \"{}\".", line))?;
+        let row: PerformanceIndicatorsRow = row.parse()?;
+
+        match current.as_mut() {
+            Some((_, rows)) if !rows.iter().any(|r| r.symbol == row.symbol) => rows.push(row),
+            _ => {
+                batches.extend(current.take());
+                current = Some((from.to_string(), vec![row]));
+            }
+        }
+    }
+    batches.extend(current);
+
+    Ok(batches)
+}
+
+/// Reads the output file at `path`, and splits it into the recorded batches, see [`recorded_batches`]
+///
+/// # Errors
+/// - [std::io::Error] if the file can't be read
+/// - If a row isn't in the format that the writer actor writes
+pub fn read(path: &str) -> Result<Vec<RecordedBatch>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read the output file {}.", path))?;
+
+    recorded_batches(&contents).with_context(|| format!("Couldn't replay {}.", path))
+}
+
+/// Returns the time between two replayed batches, which is the tick interval, in seconds,
+/// divided by the `speed` multiplier
+pub fn pace(tick_interval_secs: u64, speed: f64) -> Duration {
+    Duration::from_secs(tick_interval_secs).div_f64(speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CSV_HEADER;
+
+    #[test]
+    fn output_file_is_split_into_batches() {
+        let contents = format!(
+            "{}\n\
+             2024-01-01T00:00:00Z,AAPL,$1.00,1.00%,$1.00,$1.00,$1.00\n\
+             2024-01-01T00:00:00Z,MSFT,$2.00,1.00%,$2.00,$2.00,$2.00\n\
+             \n\
+             2024-01-01T00:00:00Z,MSFT,$3.00,1.00%,$3.00,$3.00,$3.00\n\
+             2024-01-01T00:00:00Z,AAPL,$4.00,1.00%,$4.00,$4.00,$4.00\n\
+             2024-01-01T00:00:00Z,AAPL,$5.00,1.00%,$5.00,$5.00,$5.00\n",
+            CSV_HEADER
+        );
+
+        let batches = recorded_batches(&contents).unwrap();
+        assert_eq!(
+            vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0]],
+            batches
+                .iter()
+                .map(|(_, rows)| rows.iter().map(|row| row.last_price).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("2024-01-01T00:00:00Z", batches[0].0);
+
+        assert!(recorded_batches(CSV_HEADER).unwrap().is_empty());
+        assert!(recorded_batches(&format!("{}\nAAPL", CSV_HEADER)).is_err());
+    }
+
+    #[test]
+    fn batches_are_replayed_faster_at_higher_speeds() {
+        assert_eq!(Duration::from_secs(30), pace(30, 1.0));
+        assert_eq!(Duration::from_millis(500), pace(30, 60.0));
+        assert_eq!(Duration::from_secs(60), pace(30, 0.5));
+    }
+}
@@ -0,0 +1,223 @@
+//! Risk metrics
+//!
+//! The historical Value-at-Risk (VaR) and Conditional Value-at-Risk (CVaR), which is also known
+//! as the expected shortfall, are calculated over the daily returns of the fetched closes,
+//! at a confidence level, [`RISK_CONFIDENCE`] by default:
+//! - the VaR is the daily loss that isn't exceeded with the given confidence, i.e., the loss
+//!   at the `1 - confidence` quantile of the returns,
+//! - the CVaR is the average loss in the days that are at least as bad as the VaR.
+//!
+//! Both are positive percentages of loss; a negative one means a gain even in the bad days.
+//!
+//! They are calculated per symbol, and for the portfolio, whose daily returns are the returns
+//! of its holdings, weighted by their market values, over the days that all of them have.
+//!
+//! [`RISK_CONFIDENCE`]: crate::constants::RISK_CONFIDENCE
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::types::Symbol;
+
+/// The VaR and CVaR of a series of daily returns
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct RiskMetrics {
+    /// The Value-at-Risk, as a percentage of loss
+    pub var_pct: f64,
+    /// The Conditional Value-at-Risk, as a percentage of loss
+    pub cvar_pct: f64,
+    /// The number of daily returns that the metrics were calculated over
+    pub observations: usize,
+}
+
+impl RiskMetrics {
+    /// Calculates the VaR and CVaR of the daily `returns` at the `confidence` level
+    ///
+    /// # Returns
+    /// `None` if there are no returns
+    pub fn calculate(returns: &[f64], confidence: f64) -> Option<Self> {
+        if returns.is_empty() {
+            return None;
+        }
+
+        let mut sorted = returns.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        // the number of the worst returns, which are at or below the quantile;
+        // the tolerance keeps, e.g., 10% of 20 returns from being rounded up to three
+        let tail_len = (((1.0 - confidence) * sorted.len() as f64 - 1e-9).ceil() as usize).max(1);
+        let tail = &sorted[..tail_len];
+
+        Some(Self {
+            var_pct: -tail[tail_len - 1] * 100.0,
+            cvar_pct: -tail.iter().sum::<f64>() / tail_len as f64 * 100.0,
+            observations: sorted.len(),
+        })
+    }
+}
+
+/// The VaR and CVaR of a symbol
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct SymbolRisk {
+    pub symbol: String,
+    pub var_pct: f64,
+    pub cvar_pct: f64,
+    pub observations: usize,
+}
+
+/// The risk metrics of all symbols, and of the portfolio, at a confidence level
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct RiskReport {
+    pub confidence: f64,
+    pub symbols: Vec<SymbolRisk>,
+    /// The metrics of the portfolio, or `None` if none of its holdings have returns
+    pub portfolio: Option<RiskMetrics>,
+}
+
+/// Returns the daily returns of the `closes`, oldest first, as fractions
+pub fn daily_returns(closes: &[f64]) -> Vec<f64> {
+    closes
+        .windows(2)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect()
+}
+
+/// Returns the sample standard deviation of the daily `returns`, as a fraction,
+/// or `None` if there are fewer than two of them
+pub fn volatility(returns: &[f64]) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+
+    Some(variance.sqrt())
+}
+
+/// Returns the daily returns of a portfolio of symbols with the given `weights`, which
+/// don't have to add up to one, over the newest days that all of its symbols have returns for
+///
+/// The symbols that have no returns are left out, and the weights of the rest are normalized.
+pub fn portfolio_returns(
+    returns: &BTreeMap<Symbol, Vec<f64>>,
+    weights: &HashMap<String, f64>,
+) -> Vec<f64> {
+    let weighted: Vec<(&[f64], f64)> = weights
+        .iter()
+        .filter_map(|(symbol, &weight)| {
+            returns
+                .get(symbol.as_str())
+                .filter(|returns| !returns.is_empty() && weight > 0.0)
+                .map(|returns| (returns.as_slice(), weight))
+        })
+        .collect();
+    let total_weight: f64 = weighted.iter().map(|(_, weight)| weight).sum();
+    let Some(days) = weighted.iter().map(|(returns, _)| returns.len()).min() else {
+        return Vec::new();
+    };
+
+    (0..days)
+        .map(|day| {
+            weighted
+                .iter()
+                .map(|(returns, weight)| returns[returns.len() - days + day] * weight)
+                .sum::<f64>()
+                / total_weight
+        })
+        .collect()
+}
+
+/// Returns the risk metrics of the symbols with `returns`, and of the portfolio
+/// with the given `weights`, at the `confidence` level
+pub fn report(
+    returns: &BTreeMap<Symbol, Vec<f64>>,
+    weights: &HashMap<String, f64>,
+    confidence: f64,
+) -> RiskReport {
+    let symbols = returns
+        .iter()
+        .filter_map(|(symbol, returns)| {
+            RiskMetrics::calculate(returns, confidence).map(|metrics| SymbolRisk {
+                symbol: symbol.to_string(),
+                var_pct: metrics.var_pct,
+                cvar_pct: metrics.cvar_pct,
+                observations: metrics.observations,
+            })
+        })
+        .collect();
+
+    RiskReport {
+        confidence,
+        symbols,
+        portfolio: RiskMetrics::calculate(&portfolio_returns(returns, weights), confidence),
+    }
+}
+
+/// Returns whether the `confidence` is a valid confidence level, strictly between zero and one
+pub fn is_valid_confidence(confidence: f64) -> bool {
+    confidence > 0.0 && confidence < 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn var_and_cvar_are_the_losses_in_the_worst_days() {
+        // -10%, -5%, and then eighteen days of 1% gains
+        let mut returns = vec![-0.10, -0.05];
+        returns.extend([0.01; 18]);
+
+        // the worst 5% of twenty days is a single day
+        let metrics = RiskMetrics::calculate(&returns, 0.95).unwrap();
+        assert!((metrics.var_pct - 10.0).abs() < 1e-9);
+        assert!((metrics.cvar_pct - 10.0).abs() < 1e-9);
+        assert_eq!(20, metrics.observations);
+
+        // the worst 10% are two days
+        let metrics = RiskMetrics::calculate(&returns, 0.9).unwrap();
+        assert!((metrics.var_pct - 5.0).abs() < 1e-9);
+        assert!((metrics.cvar_pct - 7.5).abs() < 1e-9);
+
+        assert_eq!(None, RiskMetrics::calculate(&[], 0.95));
+    }
+
+    #[test]
+    fn daily_returns_are_relative_changes() {
+        let returns = daily_returns(&[100.0, 110.0, 99.0]);
+        assert_eq!(2, returns.len());
+        assert!((returns[0] - 0.1).abs() < 1e-9);
+        assert!((returns[1] + 0.1).abs() < 1e-9);
+        assert!(daily_returns(&[100.0]).is_empty());
+    }
+
+    #[test]
+    fn portfolio_returns_are_weighted_over_the_common_days() {
+        let returns = BTreeMap::from([
+            ("AAPL".into(), vec![0.5, 0.02, -0.04]),
+            ("MSFT".into(), vec![0.04, 0.0]),
+            ("NVDA".into(), vec![]),
+        ]);
+        let weights = HashMap::from([
+            ("AAPL".to_string(), 300.0),
+            ("MSFT".to_string(), 100.0),
+            ("NVDA".to_string(), 600.0),
+        ]);
+
+        // AAPL's oldest return isn't in MSFT's series, and NVDA has no returns
+        let portfolio = portfolio_returns(&returns, &weights);
+        assert_eq!(2, portfolio.len());
+        assert!((portfolio[0] - 0.025).abs() < 1e-9);
+        assert!((portfolio[1] + 0.03).abs() < 1e-9);
+
+        let report = report(&returns, &weights, 0.95);
+        assert_eq!(2, report.symbols.len());
+        assert!((report.portfolio.unwrap().var_pct - 3.0).abs() < 1e-9);
+
+        assert!(portfolio_returns(&returns, &HashMap::new()).is_empty());
+    }
+}
@@ -0,0 +1,332 @@
+//! A small runtime shim for the custom actor framework and the main loop
+//!
+//! They spawn tasks, and wait for timers, only through this module, so that they can run
+//! on either of two executors:
+//! - [Tokio](https://tokio.rs/), which is the default,
+//! - [smol](https://crates.io/crates/smol), which is selected by the `smol` cargo feature.
+//!
+//! Blocking work, such as writing files, is moved off the executor's threads through
+//! [`spawn_blocking`], onto Tokio's, or smol's, pool of blocking threads.
+//!
+//! The actors' channels are Tokio's, which don't depend on the Tokio runtime, so they work
+//! with both executors.
+//!
+//! The web app and the gRPC API are built on Tokio (`axum`, `tonic`), and the data provider
+//! is reached through `reqwest`, which requires Tokio's reactor, so the app always starts
+//! a Tokio runtime. With the `smol` feature, the tasks are spawned on smol's executor,
+//! but they enter the Tokio runtime that they were spawned from, if any, whenever they are polled.
+
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::FutureExt;
+
+/// The reason why a task didn't run to completion
+#[derive(Debug, thiserror::Error)]
+pub enum JoinError {
+    #[error("The task panicked.")]
+    Panicked,
+    #[error("The task was cancelled.")]
+    Cancelled,
+}
+
+/// An owned permission to await a spawned task
+///
+/// Dropping it detaches the task, which keeps running.
+pub struct JoinHandle<T> {
+    #[cfg(not(feature = "smol"))]
+    task: tokio::task::JoinHandle<std::thread::Result<T>>,
+    #[cfg(feature = "smol")]
+    task: Option<smol::Task<std::thread::Result<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Returns whether the task has finished
+    pub fn is_finished(&self) -> bool {
+        #[cfg(not(feature = "smol"))]
+        return self.task.is_finished();
+        #[cfg(feature = "smol")]
+        return self.task.as_ref().is_none_or(smol::Task::is_finished);
+    }
+
+    /// Cancels the task, which stops at its next await point
+    ///
+    /// A blocking task can't be cancelled; it keeps running, detached.
+    pub fn abort(self) {
+        #[cfg(not(feature = "smol"))]
+        self.task.abort();
+        #[cfg(feature = "smol")]
+        {
+            let mut this = self;
+            // dropping a smol task, rather than detaching it, cancels it
+            drop(this.task.take());
+        }
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        #[cfg(not(feature = "smol"))]
+        let result = match std::task::ready!(Pin::new(&mut self.task).poll(cx)) {
+            Ok(result) => result,
+            // a panic is caught before it reaches the runtime
+            Err(_) => return Poll::Ready(Err(JoinError::Cancelled)),
+        };
+        #[cfg(feature = "smol")]
+        let result = match self.task.as_mut() {
+            Some(task) => std::task::ready!(Pin::new(task).poll(cx)),
+            None => return Poll::Ready(Err(JoinError::Cancelled)),
+        };
+
+        Poll::Ready(result.map_err(|_| JoinError::Panicked))
+    }
+}
+
+#[cfg(feature = "smol")]
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        // a smol task is cancelled when its handle is dropped, while a Tokio task is detached
+        if let Some(task) = self.task.take() {
+            task.detach();
+        }
+    }
+}
+
+/// Spawns a new task, which runs the `future`
+///
+/// A panic of the task is caught, and reported as [`JoinError::Panicked`] when it is awaited.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let future = AssertUnwindSafe(future).catch_unwind();
+
+    #[cfg(not(feature = "smol"))]
+    return JoinHandle {
+        task: tokio::spawn(future),
+    };
+    #[cfg(feature = "smol")]
+    return JoinHandle {
+        task: Some(smol::spawn(EnterTokio::new(future))),
+    };
+}
+
+/// Runs the blocking function `f` on a thread of the pool of blocking threads,
+/// so that it doesn't block the executor's threads, which run the tasks
+///
+/// A panic of `f` is caught, and reported as [`JoinError::Panicked`] when it is awaited.
+pub fn spawn_blocking<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let f = move || std::panic::catch_unwind(AssertUnwindSafe(f));
+
+    #[cfg(not(feature = "smol"))]
+    return JoinHandle {
+        task: tokio::task::spawn_blocking(f),
+    };
+    #[cfg(feature = "smol")]
+    return JoinHandle {
+        task: Some(smol::unblock(f)),
+    };
+}
+
+/// A set of tasks that are awaited together
+///
+/// The finished tasks can be reaped, so that a long-lived set doesn't grow with short-lived tasks.
+#[derive(Default)]
+pub struct TaskSet {
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl TaskSet {
+    /// Spawns a new task in the set, which runs the `future`
+    pub fn spawn<F>(&mut self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.push(spawn(future));
+    }
+
+    /// Removes the tasks that have finished from the set
+    pub fn reap(&mut self) {
+        self.tasks.retain(|task| !task.is_finished());
+    }
+
+    /// Returns the number of tasks in the set
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Waits for all tasks in the set to finish, and empties the set
+    ///
+    /// Returns the errors of the tasks that didn't run to completion.
+    pub async fn join_all(&mut self) -> Vec<JoinError> {
+        let mut errors = Vec::new();
+        for task in self.tasks.drain(..) {
+            if let Err(err) = task.await {
+                errors.push(err);
+            }
+        }
+
+        errors
+    }
+
+    /// Waits for all tasks in the set to finish, but only for `duration`, after which the tasks
+    /// that are still running are aborted; empties the set
+    ///
+    /// Returns the errors of the tasks that didn't run to completion, including the aborted ones,
+    /// which are reported as [`JoinError::Cancelled`].
+    pub async fn join_all_within(&mut self, duration: Duration) -> Vec<JoinError> {
+        let deadline = Instant::now() + duration;
+        let mut errors = Vec::new();
+        for mut task in self.tasks.drain(..) {
+            // a task that has finished is joined even after the deadline
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match timeout(remaining, &mut task).await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => errors.push(err),
+                Err(Elapsed) => {
+                    task.abort();
+                    errors.push(JoinError::Cancelled);
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+/// Waits until `duration` has elapsed
+pub async fn sleep(duration: Duration) {
+    #[cfg(not(feature = "smol"))]
+    tokio::time::sleep(duration).await;
+    #[cfg(feature = "smol")]
+    smol::Timer::after(duration).await;
+}
+
+/// The error of a [`timeout`] that has elapsed
+#[derive(Debug, thiserror::Error)]
+#[error("The deadline has elapsed.")]
+pub struct Elapsed;
+
+/// Runs the `future`, but gives up on it after `duration`, in which case the future is dropped
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+    #[cfg(not(feature = "smol"))]
+    return tokio::time::timeout(duration, future)
+        .await
+        .map_err(|_| Elapsed);
+    #[cfg(feature = "smol")]
+    return smol::future::or(async { Ok(future.await) }, async {
+        smol::Timer::after(duration).await;
+        Err(Elapsed)
+    })
+    .await;
+}
+
+/// A timer that ticks every `period`
+///
+/// A tick that is missed, because the ticks weren't waited for in time, is delayed,
+/// i.e., the next tick comes a full `period` after the late one.
+pub struct Interval {
+    period: Duration,
+    next: Instant,
+}
+
+impl Interval {
+    /// Create a new [`Interval`], whose first tick completes immediately
+    pub fn new(period: Duration) -> Self {
+        Self::starting_at(Instant::now(), period)
+    }
+
+    /// Create a new [`Interval`], whose first tick completes at `start`
+    pub fn starting_at(start: Instant, period: Duration) -> Self {
+        Self {
+            period,
+            next: start,
+        }
+    }
+
+    /// Waits for the next tick
+    pub async fn tick(&mut self) {
+        sleep(self.next.saturating_duration_since(Instant::now())).await;
+        self.next = Instant::now() + self.period;
+    }
+}
+
+/// A future that enters the Tokio runtime that it was created in, if any, whenever it is polled,
+/// so that it can use Tokio's reactor and timers on another executor
+#[cfg(feature = "smol")]
+struct EnterTokio<F> {
+    future: Pin<Box<F>>,
+    handle: Option<tokio::runtime::Handle>,
+}
+
+#[cfg(feature = "smol")]
+impl<F> EnterTokio<F> {
+    fn new(future: F) -> Self {
+        Self {
+            future: Box::pin(future),
+            handle: tokio::runtime::Handle::try_current().ok(),
+        }
+    }
+}
+
+#[cfg(feature = "smol")]
+impl<F: Future> Future for EnterTokio<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let _guard = this.handle.as_ref().map(tokio::runtime::Handle::enter);
+        this.future.as_mut().poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn panic_is_reported_and_timeout_elapses() {
+        let task = spawn(async { panic!("boom") });
+        assert!(matches!(task.await, Err(JoinError::Panicked)));
+        let task = spawn_blocking(|| panic!("boom"));
+        assert!(matches!(task.await, Err(JoinError::Panicked)));
+        assert!(matches!(spawn_blocking(|| 42).await, Ok(42)));
+
+        let mut tasks = TaskSet::default();
+        tasks.spawn(async {});
+        tasks.spawn(async { sleep(Duration::from_millis(10)).await });
+        assert!(tasks.join_all().await.is_empty());
+        assert!(tasks.is_empty());
+
+        tasks.spawn(async {});
+        tasks.spawn(std::future::pending());
+        let errors = tasks.join_all_within(Duration::from_millis(10)).await;
+        assert!(matches!(errors[..], [JoinError::Cancelled]));
+        assert!(tasks.is_empty());
+
+        assert!(
+            timeout(Duration::from_millis(10), std::future::pending::<()>())
+                .await
+                .is_err()
+        );
+        assert!(matches!(
+            timeout(Duration::from_secs(1), async { 42 }).await,
+            Ok(42)
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+//! Sector aggregation
+//!
+//! Symbols are tagged with sectors, or with industries, through the `sectors` option, e.g.,
+//! `technology:AAPL+MSFT+NVDA`; a symbol can be tagged with more than one of them. The data
+//! provider isn't asked for the sectors. Tagging a symbol doesn't fetch it; only the tracked
+//! symbols are aggregated.
+//!
+//! Whenever the collection actor completes a batch, it aggregates the rows of every sector:
+//! the average percentage change, and the breadth, i.e., the numbers of advancing and declining
+//! symbols. The aggregates of the newest batch are traced along with the rows, a line per sector,
+//! and served by the web app.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Result};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::types::Batch;
+
+/// A named group of symbols, such as a sector or an industry
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Sector {
+    /// The name, which consists of letters, digits, hyphens and underscores
+    pub name: String,
+    pub symbols: Vec<String>,
+}
+
+impl FromStr for Sector {
+    type Err = anyhow::Error;
+
+    /// Parses a sector in the `NAME:SYMBOLS` format, whose symbols are separated by plus signs,
+    /// e.g., `technology:AAPL+MSFT+NVDA`
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((name, symbols)) = s.split_once(':') else {
+            bail!(
+                "`{}` isn't a sector in the `NAME:SYMBOLS` format, such as `technology:AAPL+MSFT`.",
+                s
+            );
+        };
+
+        let name = name.trim().to_string();
+        ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "The name of the sector `{}` must consist of letters, digits, hyphens and underscores.",
+            s
+        );
+
+        let mut list: Vec<String> = Vec::new();
+        for symbol in symbols
+            .split('+')
+            .map(|symbol| symbol.trim().to_uppercase())
+        {
+            if !symbol.is_empty() && !list.contains(&symbol) {
+                list.push(symbol);
+            }
+        }
+        ensure!(!list.is_empty(), "The sector `{}` has no symbols.", name);
+
+        Ok(Self {
+            name,
+            symbols: list,
+        })
+    }
+}
+
+/// The aggregate indicators of a sector's rows in a batch
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct SectorAggregate {
+    pub sector: String,
+    /// The number of the sector's symbols in the batch
+    pub symbols: usize,
+    pub avg_pct_change: f64,
+    /// The number of symbols whose price has gone up
+    pub advancers: usize,
+    /// The number of symbols whose price has gone down
+    pub decliners: usize,
+    /// The advancers less the decliners, as a percentage of the symbols
+    pub breadth_pct: f64,
+}
+
+impl Display for SectorAggregate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} symbols, average change {:+.2}%, {} advancing, {} declining, breadth {:+.2}%",
+            self.sector,
+            self.symbols,
+            self.avg_pct_change,
+            self.advancers,
+            self.decliners,
+            self.breadth_pct
+        )
+    }
+}
+
+/// Returns the aggregates of the `sectors`' rows in a `batch`, in the order of the sectors
+///
+/// The sectors without rows in the batch are left out.
+pub fn aggregate(sectors: &[Sector], batch: &Batch) -> Vec<SectorAggregate> {
+    sectors
+        .iter()
+        .filter_map(|sector| {
+            let changes: Vec<f64> = batch
+                .iter()
+                .filter(|row| sector.symbols.iter().any(|symbol| **symbol == *row.symbol))
+                .map(|row| row.pct_change)
+                .collect();
+            if changes.is_empty() {
+                return None;
+            }
+
+            let advancers = changes.iter().filter(|&&change| change > 0.0).count();
+            let decliners = changes.iter().filter(|&&change| change < 0.0).count();
+            let symbols = changes.len();
+
+            Some(SectorAggregate {
+                sector: sector.name.clone(),
+                symbols,
+                avg_pct_change: changes.iter().sum::<f64>() / symbols as f64,
+                advancers,
+                decliners,
+                breadth_pct: (advancers as f64 - decliners as f64) / symbols as f64 * 100.0,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::my_async_actors::PerformanceIndicatorsRow;
+
+    fn row(symbol: &str, pct_change: f64) -> PerformanceIndicatorsRow {
+        PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price: 1.0,
+            pct_change,
+            period_min: 1.0,
+            period_max: 1.0,
+            sma: 1.0,
+            asset_class: Default::default(),
+        }
+    }
+
+    #[test]
+    fn sectors_are_parsed() {
+        let sector: Sector = "technology: aapl + MSFT+aapl".parse().unwrap();
+        assert_eq!("technology", sector.name);
+        assert_eq!(vec!["AAPL", "MSFT"], sector.symbols);
+
+        assert!("technology".parse::<Sector>().is_err());
+        assert!("tech nology:AAPL".parse::<Sector>().is_err());
+        assert!("technology:+".parse::<Sector>().is_err());
+    }
+
+    #[test]
+    fn rows_are_aggregated_per_sector() {
+        let sectors: Vec<Sector> = ["technology:AAPL+MSFT+NVDA", "chips:NVDA", "energy:XOM"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let batch = vec![row("AAPL", 3.0), row("MSFT", -1.0), row("NVDA", 4.0)];
+
+        // energy has no rows in the batch
+        let aggregates = aggregate(&sectors, &batch);
+        assert_eq!(2, aggregates.len());
+        assert_eq!(
+            SectorAggregate {
+                sector: "technology".to_string(),
+                symbols: 3,
+                avg_pct_change: 2.0,
+                advancers: 2,
+                decliners: 1,
+                breadth_pct: 1.0 / 3.0 * 100.0,
+            },
+            aggregates[0]
+        );
+        assert_eq!(
+            "chips: 1 symbols, average change +4.00%, 1 advancing, 0 declining, breadth +100.00%",
+            aggregates[1].to_string()
+        );
+    }
+}
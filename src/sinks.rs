@@ -0,0 +1,643 @@
+//! The writer actor's output sinks
+//!
+//! The writer actor writes every chunk of calculated performance indicators to each of its sinks,
+//! one per selected [`OutputFormat`]:
+//! - CSV, which is the default, and which is the only format that the `serve` subcommand reads back,
+//! - JSON Lines, a JSON object per row,
+//! - Parquet, a columnar format for analytics tools.
+//!
+//! A restarted writer actor appends to the CSV and JSON Lines files. A Parquet file can't be
+//! appended to, so it starts a new Parquet file instead, whose name contains the restart time.
+//!
+//! In a dry run, the writer actor's sinks write nothing; they only count the rows, and print
+//! a summary of what would have been written when they are closed.
+//!
+//! Other reports, such as a backtest's trades, are written in the same formats as [`Record`]s.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::cli::OutputFormat;
+use crate::constants::{CSV_FILE_PATH, CSV_HEADER, JSONL_FILE_PATH, PARQUET_FILE_PATH};
+use crate::my_async_actors::PerformanceIndicatorsRow;
+use crate::types::Symbol;
+
+/// The Parquet schema of the rows
+const PARQUET_SCHEMA: &str = "
+    message performance_indicators {
+        REQUIRED BYTE_ARRAY period_start (UTF8);
+        REQUIRED BYTE_ARRAY symbol (UTF8);
+        REQUIRED DOUBLE price;
+        REQUIRED DOUBLE change_pct;
+        REQUIRED DOUBLE min;
+        REQUIRED DOUBLE max;
+        REQUIRED DOUBLE sma;
+    }
+";
+
+/// A destination of the calculated performance indicators
+pub trait Sink: Send {
+    /// Writes the `rows` of a chunk, whose period starts at `from`
+    fn write(&mut self, from: &str, rows: &[PerformanceIndicatorsRow]) -> io::Result<()>;
+
+    /// Flushes the written rows to the file
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Flushes the written rows, and closes the file
+    fn close(self: Box<Self>) -> io::Result<()>;
+}
+
+/// Opens a sink of the `format` at its default path
+///
+/// If `append` is set, the sink appends to an existing file instead of truncating it,
+/// if the format allows it.
+///
+/// # Errors
+/// - [std::io::Error] if the file can't be opened
+pub fn open(format: &OutputFormat, append: bool) -> io::Result<Box<dyn Sink>> {
+    Ok(match format {
+        OutputFormat::Csv => Box::new(CsvSink::open(CSV_FILE_PATH, append)?),
+        OutputFormat::Jsonl => Box::new(JsonlSink::open(JSONL_FILE_PATH, append)?),
+        OutputFormat::Parquet => Box::new(ParquetSink::create(parquet_path(
+            PARQUET_FILE_PATH,
+            append,
+        ))?),
+    })
+}
+
+/// Opens a sink of the `format` at the path of the `stem`, with the format's extension
+///
+/// If `append` is set, the sink appends to an existing file instead of truncating it,
+/// if the format allows it.
+///
+/// # Errors
+/// - [std::io::Error] if the file can't be opened
+pub fn open_at(format: &OutputFormat, stem: &str, append: bool) -> io::Result<Box<dyn Sink>> {
+    let path = format!("{}.{}", stem, extension(format));
+
+    Ok(match format {
+        OutputFormat::Csv => Box::new(CsvSink::open(path, append)?),
+        OutputFormat::Jsonl => Box::new(JsonlSink::open(path, append)?),
+        OutputFormat::Parquet => Box::new(ParquetSink::create(parquet_path(&path, append))?),
+    })
+}
+
+/// Returns the path of a Parquet file, which can't be appended to, so a restarted sink
+/// writes to a new file, whose name has the restart time
+fn parquet_path(path: &str, append: bool) -> PathBuf {
+    if append {
+        let restarted_at = OffsetDateTime::now_utc().unix_timestamp();
+        Path::new(path).with_extension(format!("{}.parquet", restarted_at))
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+/// The file extension of the `format`
+fn extension(format: &OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Csv => "csv",
+        OutputFormat::Jsonl => "jsonl",
+        OutputFormat::Parquet => "parquet",
+    }
+}
+
+/// Returns a dry-run sink of the `format` at the path of the `stem`, which writes nothing
+pub fn dry_run_at(format: &OutputFormat, stem: &str) -> Box<dyn Sink> {
+    Box::new(DryRunSink::new(format!("{}.{}", stem, extension(format))))
+}
+
+/// Returns a dry-run sink of the `format`, which writes nothing
+pub fn dry_run(format: &OutputFormat) -> Box<dyn Sink> {
+    let path = match format {
+        OutputFormat::Csv => CSV_FILE_PATH,
+        OutputFormat::Jsonl => JSONL_FILE_PATH,
+        OutputFormat::Parquet => PARQUET_FILE_PATH,
+    };
+
+    Box::new(DryRunSink::new(path))
+}
+
+/// A record of a report, which can be written in every [`OutputFormat`]
+///
+/// Its CSV line is its [`Display`] output, and its JSON Lines object is its serialization.
+/// In Parquet, its text fields come first, then its integers, and then its decimal numbers.
+pub trait Record: Display + Serialize {
+    /// The header of the CSV file
+    const CSV_HEADER: &'static str;
+    /// The Parquet schema of the records
+    const PARQUET_SCHEMA: &'static str;
+
+    /// Returns the text fields
+    fn texts(&self) -> Vec<&str>;
+
+    /// Returns the integer fields
+    fn integers(&self) -> Vec<i64> {
+        Vec::new()
+    }
+
+    /// Returns the decimal fields
+    fn decimals(&self) -> Vec<f64>;
+}
+
+/// Writes the `records` of a report to the file of the `format` at `stem`, with the format's
+/// extension, truncating an existing file
+///
+/// In a dry run, nothing is written; a summary of what would have been written is printed instead.
+///
+/// # Returns
+/// The path of the file
+///
+/// # Errors
+/// - [std::io::Error] if the file can't be written
+pub fn write_records<R: Record>(
+    format: &OutputFormat,
+    stem: &str,
+    records: &[R],
+    dry_run: bool,
+) -> io::Result<PathBuf> {
+    let path = PathBuf::from(format!("{}.{}", stem, extension(format)));
+
+    if dry_run {
+        println!(
+            "Dry run: {} records would have been written to {}.",
+            records.len(),
+            path.display()
+        );
+        return Ok(path);
+    }
+
+    match format {
+        OutputFormat::Csv => {
+            let mut writer = BufWriter::new(File::create(&path)?);
+            writeln!(writer, "{}", R::CSV_HEADER)?;
+            for record in records {
+                writeln!(writer, "{}", record)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Jsonl => {
+            let mut writer = BufWriter::new(File::create(&path)?);
+            for record in records {
+                serde_json::to_writer(&mut writer, record)?;
+                writeln!(writer)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Parquet => {
+            let schema = Arc::new(parse_message_type(R::PARQUET_SCHEMA).map_err(io::Error::other)?);
+            let properties = Arc::new(WriterProperties::builder().build());
+            let mut writer = SerializedFileWriter::new(File::create(&path)?, schema, properties)
+                .map_err(io::Error::other)?;
+            if let Some(first) = records.first() {
+                let texts = (0..first.texts().len())
+                    .map(|i| {
+                        records
+                            .iter()
+                            .map(|record| ByteArray::from(record.texts()[i]))
+                            .collect()
+                    })
+                    .collect();
+                let integers = (0..first.integers().len())
+                    .map(|i| records.iter().map(|record| record.integers()[i]).collect())
+                    .collect();
+                let decimals = (0..first.decimals().len())
+                    .map(|i| records.iter().map(|record| record.decimals()[i]).collect())
+                    .collect();
+                write_row_group(&mut writer, texts, integers, decimals)
+                    .map_err(io::Error::other)?;
+            }
+            writer.close().map_err(io::Error::other)?;
+        }
+    }
+
+    Ok(path)
+}
+
+/// Writes a row group of the `texts`, `integers` and `decimals` columns, in that order
+fn write_row_group(
+    writer: &mut SerializedFileWriter<File>,
+    texts: Vec<Vec<ByteArray>>,
+    integers: Vec<Vec<i64>>,
+    decimals: Vec<Vec<f64>>,
+) -> parquet::errors::Result<()> {
+    let mut row_group = writer.next_row_group()?;
+    for values in texts {
+        let mut column = row_group.next_column()?.ok_or_else(missing_column)?;
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&values, None, None)?;
+        column.close()?;
+    }
+    for values in integers {
+        let mut column = row_group.next_column()?.ok_or_else(missing_column)?;
+        column
+            .typed::<Int64Type>()
+            .write_batch(&values, None, None)?;
+        column.close()?;
+    }
+    for values in decimals {
+        let mut column = row_group.next_column()?.ok_or_else(missing_column)?;
+        column
+            .typed::<DoubleType>()
+            .write_batch(&values, None, None)?;
+        column.close()?;
+    }
+    row_group.close()?;
+
+    Ok(())
+}
+
+/// Opens the file at `path` for appending, or creates it, truncating an existing one
+fn open_file(path: impl AsRef<Path>, append: bool) -> io::Result<File> {
+    if append {
+        OpenOptions::new().create(true).append(true).open(path)
+    } else {
+        File::create(path)
+    }
+}
+
+/// Writes the rows as CSV, after a header
+pub struct CsvSink {
+    writer: BufWriter<File>,
+}
+
+impl CsvSink {
+    /// Opens a CSV sink at `path`; the header is written only to a new file
+    pub fn open(path: impl AsRef<Path>, append: bool) -> io::Result<Self> {
+        let mut writer = BufWriter::new(open_file(path, append)?);
+        if !append {
+            writeln!(writer, "{}", CSV_HEADER)?;
+        }
+
+        Ok(Self { writer })
+    }
+}
+
+impl Sink for CsvSink {
+    fn write(&mut self, from: &str, rows: &[PerformanceIndicatorsRow]) -> io::Result<()> {
+        for row in rows {
+            writeln!(self.writer, "{},{}", from, row)?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn close(mut self: Box<Self>) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+/// A row of the JSON Lines output
+#[derive(Serialize)]
+struct JsonlRow<'a> {
+    period_start: &'a str,
+    #[serde(flatten)]
+    row: &'a PerformanceIndicatorsRow,
+}
+
+/// Writes the rows as JSON Lines, i.e., a JSON object per line
+pub struct JsonlSink {
+    writer: BufWriter<File>,
+}
+
+impl JsonlSink {
+    /// Opens a JSON Lines sink at `path`
+    pub fn open(path: impl AsRef<Path>, append: bool) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(open_file(path, append)?),
+        })
+    }
+}
+
+impl Sink for JsonlSink {
+    fn write(&mut self, from: &str, rows: &[PerformanceIndicatorsRow]) -> io::Result<()> {
+        for row in rows {
+            let row = JsonlRow {
+                period_start: from,
+                row,
+            };
+            serde_json::to_writer(&mut self.writer, &row)?;
+            writeln!(self.writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn close(mut self: Box<Self>) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+/// Writes the rows to a Parquet file
+///
+/// The rows are buffered, and every flush writes them as a row group.
+pub struct ParquetSink {
+    writer: SerializedFileWriter<File>,
+    /// The rows that haven't been written yet, with the starts of their periods
+    rows: Vec<(String, PerformanceIndicatorsRow)>,
+}
+
+impl ParquetSink {
+    /// Creates a Parquet sink at `path`, truncating an existing file
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let schema = Arc::new(parse_message_type(PARQUET_SCHEMA).map_err(io::Error::other)?);
+        let properties = Arc::new(WriterProperties::builder().build());
+        let writer = SerializedFileWriter::new(File::create(path)?, schema, properties)
+            .map_err(io::Error::other)?;
+
+        Ok(Self {
+            writer,
+            rows: Vec::new(),
+        })
+    }
+
+    /// Writes the buffered rows as a row group, column by column
+    fn write_buffered_rows(&mut self) -> parquet::errors::Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+
+        let strings = |value: fn(&(String, PerformanceIndicatorsRow)) -> &str| {
+            self.rows
+                .iter()
+                .map(|row| ByteArray::from(value(row)))
+                .collect::<Vec<_>>()
+        };
+        let numbers = |value: fn(&PerformanceIndicatorsRow) -> f64| {
+            self.rows
+                .iter()
+                .map(|(_, row)| value(row))
+                .collect::<Vec<_>>()
+        };
+        let period_starts = strings(|(from, _)| from);
+        let symbols = strings(|(_, row)| &row.symbol);
+        let columns = vec![
+            numbers(|row| row.last_price),
+            numbers(|row| row.pct_change),
+            numbers(|row| row.period_min),
+            numbers(|row| row.period_max),
+            numbers(|row| row.sma),
+        ];
+
+        write_row_group(
+            &mut self.writer,
+            vec![period_starts, symbols],
+            Vec::new(),
+            columns,
+        )?;
+
+        self.rows.clear();
+
+        Ok(())
+    }
+}
+
+/// The error of a row group that has fewer columns than the rows have fields
+fn missing_column() -> ParquetError {
+    ParquetError::General("The row group has fewer columns than the rows have fields.".to_string())
+}
+
+impl Sink for ParquetSink {
+    fn write(&mut self, from: &str, rows: &[PerformanceIndicatorsRow]) -> io::Result<()> {
+        self.rows
+            .extend(rows.iter().map(|row| (from.to_string(), row.clone())));
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buffered_rows().map_err(io::Error::other)?;
+        self.writer.inner_mut().flush()
+    }
+
+    fn close(mut self: Box<Self>) -> io::Result<()> {
+        self.write_buffered_rows().map_err(io::Error::other)?;
+        self.writer.close().map_err(io::Error::other)?;
+
+        Ok(())
+    }
+}
+
+/// Counts the rows instead of writing them, and prints a summary of what would have been written
+/// to its file when it's closed
+pub struct DryRunSink {
+    path: String,
+    rows: usize,
+    symbols: HashSet<Symbol>,
+    periods: HashSet<String>,
+}
+
+impl DryRunSink {
+    /// Creates a dry-run sink, which pretends to write to `path`
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            rows: 0,
+            symbols: HashSet::new(),
+            periods: HashSet::new(),
+        }
+    }
+
+    /// Returns the summary of what would have been written
+    pub fn summary(&self) -> String {
+        format!(
+            "Dry run: {} rows of {} symbols, for {} period starts, would have been written to {}.",
+            self.rows,
+            self.symbols.len(),
+            self.periods.len(),
+            self.path
+        )
+    }
+}
+
+impl Sink for DryRunSink {
+    fn write(&mut self, from: &str, rows: &[PerformanceIndicatorsRow]) -> io::Result<()> {
+        self.rows += rows.len();
+        self.symbols
+            .extend(rows.iter().map(|row| row.symbol.clone()));
+        if !rows.is_empty() {
+            self.periods.insert(from.to_string());
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn close(self: Box<Self>) -> io::Result<()> {
+        println!("{}", self.summary());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use super::*;
+
+    #[test]
+    fn every_sink_writes_all_rows() {
+        let dir = std::env::temp_dir().join(format!("stock-sinks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let row = |symbol: &str| PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price: 1.0,
+            pct_change: 2.0,
+            period_min: 3.0,
+            period_max: 4.0,
+            sma: 5.0,
+            asset_class: Default::default(),
+        };
+        let from = "2024-01-01T00:00:00Z";
+
+        let mut sinks: Vec<Box<dyn Sink>> = vec![
+            Box::new(CsvSink::open(dir.join("output.csv"), false).unwrap()),
+            Box::new(JsonlSink::open(dir.join("output.jsonl"), false).unwrap()),
+            Box::new(ParquetSink::create(dir.join("output.parquet")).unwrap()),
+        ];
+        for sink in &mut sinks {
+            sink.write(from, &[row("AAPL"), row("MSFT")]).unwrap();
+            sink.flush().unwrap();
+            sink.write(from, &[row("NVDA")]).unwrap();
+        }
+        for sink in sinks {
+            sink.close().unwrap();
+        }
+
+        let csv = std::fs::read_to_string(dir.join("output.csv")).unwrap();
+        assert_eq!(4, csv.lines().count());
+        assert_eq!(
+            "2024-01-01T00:00:00Z,NVDA,$1.00,2.00%,$3.00,$4.00,$5.00",
+            csv.lines().last().unwrap()
+        );
+
+        let jsonl = std::fs::read_to_string(dir.join("output.jsonl")).unwrap();
+        assert_eq!(3, jsonl.lines().count());
+        assert!(jsonl.starts_with(r#"{"period_start":"2024-01-01T00:00:00Z","symbol":"AAPL""#));
+
+        let parquet = SerializedFileReader::new(File::open(dir.join("output.parquet")).unwrap());
+        let metadata = parquet.unwrap().metadata().file_metadata().clone();
+        assert_eq!(3, metadata.num_rows());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn records_are_written_in_every_format() {
+        #[derive(Serialize)]
+        struct Count {
+            name: String,
+            count: i64,
+            share: f64,
+        }
+
+        impl Display for Count {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{},{},{}", self.name, self.count, self.share)
+            }
+        }
+
+        impl Record for Count {
+            const CSV_HEADER: &'static str = "name,count,share";
+            const PARQUET_SCHEMA: &'static str = "
+                message counts {
+                    REQUIRED BYTE_ARRAY name (UTF8);
+                    REQUIRED INT64 count;
+                    REQUIRED DOUBLE share;
+                }
+            ";
+
+            fn texts(&self) -> Vec<&str> {
+                vec![&self.name]
+            }
+
+            fn integers(&self) -> Vec<i64> {
+                vec![self.count]
+            }
+
+            fn decimals(&self) -> Vec<f64> {
+                vec![self.share]
+            }
+        }
+
+        let dir = std::env::temp_dir().join(format!("stock-records-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let stem = dir.join("counts").display().to_string();
+        let count = |name: &str, count| Count {
+            name: name.to_string(),
+            count,
+            share: 0.5,
+        };
+        let records = [count("a", 1), count("b", 2)];
+
+        let path = write_records(&OutputFormat::Csv, &stem, &records, false).unwrap();
+        assert_eq!(
+            "name,count,share\na,1,0.5\nb,2,0.5\n",
+            std::fs::read_to_string(path).unwrap()
+        );
+
+        let path = write_records(&OutputFormat::Jsonl, &stem, &records, false).unwrap();
+        let jsonl = std::fs::read_to_string(path).unwrap();
+        assert!(jsonl.starts_with(r#"{"name":"a","count":1,"share":0.5}"#));
+
+        let path = write_records(&OutputFormat::Parquet, &stem, &records, false).unwrap();
+        let parquet = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+        assert_eq!(2, parquet.metadata().file_metadata().num_rows());
+
+        let path = write_records(&OutputFormat::Csv, &stem, &records[..0], true).unwrap();
+        assert!(path.ends_with("counts.csv"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn dry_run_sink_only_counts_rows() {
+        let row = |symbol: &str| PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price: 1.0,
+            pct_change: 2.0,
+            period_min: 3.0,
+            period_max: 4.0,
+            sma: 5.0,
+            asset_class: Default::default(),
+        };
+
+        let mut sink = DryRunSink::new("./nowhere.csv");
+        sink.write("2024-01-01T00:00:00Z", &[row("AAPL"), row("MSFT")])
+            .unwrap();
+        sink.write("2024-01-02T00:00:00Z", &[row("AAPL")]).unwrap();
+        sink.write("2024-01-03T00:00:00Z", &[]).unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(
+            "Dry run: 3 rows of 2 symbols, for 2 period starts, would have been written to ./nowhere.csv.",
+            sink.summary()
+        );
+        assert!(!std::path::Path::new("./nowhere.csv").exists());
+    }
+}
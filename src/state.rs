@@ -0,0 +1,299 @@
+//! Persistent application state
+//!
+//! With the `state-file` option, the state that a restart would otherwise lose is saved to
+//! a small JSON file after every iteration of the main loop, and at the shutdown, and it's
+//! restored from the file at startup:
+//! - the sequence number of the newest iteration, so that the sequence numbers keep increasing,
+//! - the symbols that were added or removed at runtime, relative to the symbols of the arguments,
+//! - the runtime configuration, as long as the arguments' one hasn't changed since,
+//! - the end of the period of the newest fetched row of every symbol, from which a backfill
+//!   of the same start of the period resumes.
+//!
+//! The file is replaced atomically, so a crash while it's being saved leaves the previous state.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::config::RuntimeConfig;
+use crate::runtime;
+use crate::types::Symbol;
+
+/// The state that is saved to the state file
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct AppState {
+    /// The sequence number of the newest iteration
+    pub iteration: u64,
+    /// The symbols that were added at runtime, which aren't among the arguments' symbols
+    pub added_symbols: Vec<String>,
+    /// The arguments' symbols that were removed at runtime
+    pub removed_symbols: Vec<String>,
+    /// The runtime configuration that the arguments gave, and the one that was in effect
+    pub config: Option<SavedConfig>,
+    /// The start of the period that the rows were fetched for, in the RFC 3339 format
+    pub from: Option<String>,
+    /// The end of the period of the newest fetched row of every symbol, in the RFC 3339 format
+    pub last_fetched: BTreeMap<String, String>,
+}
+
+/// A runtime configuration, and the arguments' one that it was changed from
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SavedConfig {
+    pub initial: RuntimeConfig,
+    pub current: RuntimeConfig,
+}
+
+/// The state file, and the arguments that the saved state is relative to
+pub struct StateFile {
+    /// The path of the file, or `None` if the state isn't saved
+    path: Option<PathBuf>,
+    state: AppState,
+    /// The symbols of the arguments
+    symbols: Vec<String>,
+    /// The runtime configuration of the arguments
+    config: RuntimeConfig,
+    /// The start of the period of the arguments, in the RFC 3339 format
+    from: String,
+}
+
+impl StateFile {
+    /// Restores the state from the file at `path`, if it exists, relative to the `symbols`,
+    /// the runtime `config` and the start of the period, `from`, of the arguments
+    ///
+    /// Without a `path`, or if the state is `read_only`, nothing is ever saved.
+    ///
+    /// # Errors
+    /// - If the file can't be read, or if it isn't a valid state file
+    pub fn load(
+        path: Option<PathBuf>,
+        read_only: bool,
+        symbols: Vec<String>,
+        config: RuntimeConfig,
+        from: OffsetDateTime,
+    ) -> Result<Self> {
+        let state = match &path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(json) => serde_json::from_str(&json)
+                    .with_context(|| format!("The state file {} isn't valid.", path.display()))?,
+                Err(err) if err.kind() == ErrorKind::NotFound => AppState::default(),
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("Couldn't read the state file {}.", path.display())
+                    })
+                }
+            },
+            None => AppState::default(),
+        };
+
+        Ok(Self {
+            path: path.filter(|_| !read_only),
+            state,
+            symbols,
+            config,
+            from: from.format(&Rfc3339)?,
+        })
+    }
+
+    /// Returns whether the state is saved
+    pub fn is_saved(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// The sequence number of the newest iteration
+    pub fn iteration(&self) -> u64 {
+        self.state.iteration
+    }
+
+    /// The arguments' symbols, without the removed ones, and with the added ones
+    pub fn symbols(&self) -> Vec<String> {
+        self.symbols
+            .iter()
+            .filter(|symbol| !self.state.removed_symbols.contains(symbol))
+            .chain(&self.state.added_symbols)
+            .cloned()
+            .collect()
+    }
+
+    /// The saved runtime configuration, unless the arguments' one has changed since,
+    /// in which case the arguments' one takes precedence
+    pub fn config(&self) -> RuntimeConfig {
+        match &self.state.config {
+            Some(saved) if saved.initial == self.config => saved.current.clone(),
+            _ => self.config.clone(),
+        }
+    }
+
+    /// Returns the earliest end of the period of the newest fetched rows of the `symbols`,
+    /// if all of them were fetched for the same start of the period as the arguments' one,
+    /// and if it's before `end`
+    ///
+    /// A backfill to `end` resumes from it.
+    pub fn resume_backfill(
+        &self,
+        symbols: &[Symbol],
+        end: OffsetDateTime,
+    ) -> Option<OffsetDateTime> {
+        if self.state.from.as_ref() != Some(&self.from) {
+            return None;
+        }
+        let last_fetched = symbols
+            .iter()
+            .map(|symbol| {
+                let time = self.state.last_fetched.get(&**symbol)?;
+                OffsetDateTime::parse(time, &Rfc3339).ok()
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        last_fetched
+            .into_iter()
+            .min()
+            .filter(|&resumed| resumed < end)
+    }
+
+    /// Takes the sequence number of the newest `iteration`, the current `symbols` and `config`,
+    /// and the symbols' `last_fetched` times, and saves the state, if it's saved
+    ///
+    /// The state is serialized on the calling task, and the file is written and replaced
+    /// on the runtime's blocking threads, through [`runtime::spawn_blocking`].
+    ///
+    /// # Errors
+    /// - If the file can't be written
+    /// - If the blocking thread panicked
+    pub async fn save(
+        &mut self,
+        iteration: u64,
+        symbols: &[Symbol],
+        config: &RuntimeConfig,
+        last_fetched: HashMap<Symbol, OffsetDateTime>,
+    ) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        self.state.iteration = iteration;
+        self.state.added_symbols = symbols
+            .iter()
+            .filter(|symbol| !self.symbols.iter().any(|saved| **saved == ***symbol))
+            .map(|symbol| symbol.to_string())
+            .collect();
+        self.state.removed_symbols = self
+            .symbols
+            .iter()
+            .filter(|saved| !symbols.iter().any(|symbol| **symbol == ***saved))
+            .cloned()
+            .collect();
+        self.state.config = (*config != self.config).then(|| SavedConfig {
+            initial: self.config.clone(),
+            current: config.clone(),
+        });
+        // the times of another start of the period don't apply anymore,
+        // and the symbols that weren't fetched since the restart keep their saved times
+        if self.state.from.as_ref() != Some(&self.from) {
+            self.state.from = Some(self.from.clone());
+            self.state.last_fetched.clear();
+        }
+        for (symbol, time) in last_fetched {
+            self.state
+                .last_fetched
+                .insert(symbol.to_string(), time.format(&Rfc3339)?);
+        }
+
+        let contents = serde_json::to_string_pretty(&self.state)?;
+        let path = path.clone();
+        runtime::spawn_blocking(move || {
+            // the file is replaced only after the new state has been written in full
+            let temporary = path.with_extension("tmp");
+            fs::write(&temporary, contents)
+                .and_then(|_| fs::rename(&temporary, &path))
+                .with_context(|| format!("Couldn't save the state file {}.", path.display()))
+        })
+        .await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(symbols: &[&str]) -> Vec<String> {
+        symbols.iter().map(|symbol| symbol.to_string()).collect()
+    }
+
+    fn interned(symbols: &[&str]) -> Vec<Symbol> {
+        symbols.iter().map(|&symbol| symbol.into()).collect()
+    }
+
+    fn time(time: &str) -> OffsetDateTime {
+        OffsetDateTime::parse(time, &Rfc3339).unwrap()
+    }
+
+    #[tokio::test]
+    async fn state_is_restored_after_a_restart() {
+        let path = std::env::temp_dir().join(format!("state-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let initial = RuntimeConfig::default();
+        let load = |config: &RuntimeConfig, from: &str| {
+            StateFile::load(
+                Some(path.clone()),
+                false,
+                symbols(&["AAPL", "MSFT"]),
+                config.clone(),
+                time(from),
+            )
+            .unwrap()
+        };
+
+        let mut state_file = load(&initial, "2024-01-01T00:00:00Z");
+        assert_eq!(0, state_file.iteration());
+        assert_eq!(symbols(&["AAPL", "MSFT"]), state_file.symbols());
+
+        let changed = RuntimeConfig {
+            chunk_size: 2,
+            ..initial.clone()
+        };
+        let last_fetched = HashMap::from([
+            ("AAPL".into(), time("2024-01-05T00:00:00Z")),
+            ("GOOG".into(), time("2024-01-03T00:00:00Z")),
+        ]);
+        state_file
+            .save(7, &interned(&["AAPL", "GOOG"]), &changed, last_fetched)
+            .await
+            .unwrap();
+
+        let state_file = load(&initial, "2024-01-01T00:00:00Z");
+        assert_eq!(7, state_file.iteration());
+        assert_eq!(symbols(&["AAPL", "GOOG"]), state_file.symbols());
+        assert_eq!(changed, state_file.config());
+        let end = time("2024-02-01T00:00:00Z");
+        assert_eq!(
+            Some(time("2024-01-03T00:00:00Z")),
+            state_file.resume_backfill(&interned(&["AAPL", "GOOG"]), end)
+        );
+        // MSFT was never fetched, so a backfill of it starts over
+        assert_eq!(
+            None,
+            state_file.resume_backfill(&interned(&["AAPL", "MSFT"]), end)
+        );
+        // and so does a backfill of another period
+        assert_eq!(
+            None,
+            load(&initial, "2023-01-01T00:00:00Z")
+                .resume_backfill(&interned(&["AAPL", "GOOG"]), end)
+        );
+
+        // the arguments' configuration takes precedence once it has changed
+        let window = RuntimeConfig {
+            window_size: 10,
+            ..initial
+        };
+        assert_eq!(window, load(&window, "2024-01-01T00:00:00Z").config());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
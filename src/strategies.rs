@@ -0,0 +1,277 @@
+//! Trading strategies
+//!
+//! A [`Strategy`] is fed the performance indicators rows of a symbol, one per iteration,
+//! and decides on a [`Signal`] at each of them. The backtests, the paper trading and the alerts
+//! share the strategies, through a [`StrategyEngine`], which keeps a strategy instance and
+//! the recent prices for every symbol.
+//!
+//! The strategies are registered in [`STRATEGIES`], which is where the `strategy` option
+//! takes its values from; a new strategy implements [`Strategy`], and is registered there.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use anyhow::{ensure, Context, Result};
+use clap::builder::{PossibleValue, PossibleValuesParser};
+
+use crate::cli::StrategyArgs;
+use crate::constants::MAX_WINDOW_SIZE;
+use crate::my_async_actors::PerformanceIndicatorsRow;
+use crate::sync_signals::{StockSignal, WindowedSMA};
+
+/// What a strategy decides to do at a row
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Signal {
+    /// Open a position, unless one is already open
+    Buy,
+    /// Close the open position, if there is one
+    Sell,
+    /// Do nothing
+    Hold,
+}
+
+impl Display for Signal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let signal = match self {
+            Signal::Buy => "buy",
+            Signal::Sell => "sell",
+            Signal::Hold => "hold",
+        };
+        write!(f, "{}", signal)
+    }
+}
+
+/// A trading strategy, which is asked for a [`Signal`] at every row of a symbol
+pub trait Strategy: Send {
+    /// The name of the strategy, as in the `strategy` option
+    fn name(&self) -> &'static str;
+
+    /// Decides on the newest of the `closes`, which are the symbol's recent prices,
+    /// given the performance indicators of the `row`, which is the newest one
+    fn signal(&mut self, closes: &[f64], row: &PerformanceIndicatorsRow) -> Signal;
+}
+
+/// A registered trading strategy
+pub struct StrategyEntry {
+    /// The name of the strategy, as in the `strategy` option
+    pub name: &'static str,
+    /// What the strategy does, for the help of the `strategy` option
+    pub about: &'static str,
+    /// Creates an instance of the strategy with the parameters of the arguments
+    pub create: fn(&StrategyArgs) -> Result<Box<dyn Strategy>>,
+}
+
+/// The registry of the trading strategies
+pub const STRATEGIES: &[StrategyEntry] = &[StrategyEntry {
+    name: "sma-crossover",
+    about: "Buy when the fast SMA crosses above the slow one, and sell when it crosses below it",
+    create: sma_crossover,
+}];
+
+/// Creates the strategy that the `args` select
+///
+/// # Errors
+/// - If the strategy isn't registered, or if its parameters aren't valid
+pub fn create(args: &StrategyArgs) -> Result<Box<dyn Strategy>> {
+    let entry = STRATEGIES
+        .iter()
+        .find(|entry| entry.name == args.strategy)
+        .with_context(|| format!("There is no trading strategy `{}`.", args.strategy))?;
+
+    (entry.create)(args)
+}
+
+/// The parser of the `strategy` option, which accepts the names of the registered strategies
+pub fn parser() -> PossibleValuesParser {
+    PossibleValuesParser::new(
+        STRATEGIES
+            .iter()
+            .map(|entry| PossibleValue::new(entry.name).help(entry.about)),
+    )
+}
+
+/// Buys when the fast simple moving average crosses above the slow one,
+/// and sells when it crosses below it
+pub struct SmaCrossover {
+    fast: WindowedSMA,
+    slow: WindowedSMA,
+}
+
+impl SmaCrossover {
+    /// Creates the strategy with the windows of the `fast` and the `slow` averages, in prices
+    ///
+    /// # Errors
+    /// - If the fast window isn't smaller than the slow one
+    pub fn new(fast: usize, slow: usize) -> Result<Self> {
+        ensure!(
+            fast < slow,
+            "The fast window ({}) must be smaller than the slow window ({}).",
+            fast,
+            slow
+        );
+
+        Ok(Self {
+            fast: WindowedSMA { window_size: fast },
+            slow: WindowedSMA { window_size: slow },
+        })
+    }
+}
+
+impl Strategy for SmaCrossover {
+    fn name(&self) -> &'static str {
+        "sma-crossover"
+    }
+
+    fn signal(&mut self, closes: &[f64], _row: &PerformanceIndicatorsRow) -> Signal {
+        // the averages at the previous price are needed, too, to tell a crossing
+        let slow_window = self.slow.window_size;
+        if closes.len() <= slow_window {
+            return Signal::Hold;
+        }
+        let recent = &closes[closes.len() - slow_window - 1..];
+
+        let fast = self.fast.calculate(recent).unwrap_or_default();
+        let slow = self.slow.calculate(recent).unwrap_or_default();
+        let (&[.., fast_before, fast_now], &[slow_before, slow_now]) =
+            (fast.as_slice(), slow.as_slice())
+        else {
+            return Signal::Hold;
+        };
+
+        if fast_before <= slow_before && fast_now > slow_now {
+            Signal::Buy
+        } else if fast_before >= slow_before && fast_now < slow_now {
+            Signal::Sell
+        } else {
+            Signal::Hold
+        }
+    }
+}
+
+/// Creates an [`SmaCrossover`] with the windows of the `args`
+fn sma_crossover(args: &StrategyArgs) -> Result<Box<dyn Strategy>> {
+    Ok(Box::new(SmaCrossover::new(
+        args.fast_window,
+        args.slow_window,
+    )?))
+}
+
+/// Feeds the rows of every symbol to an instance of a trading strategy of its own
+pub struct StrategyEngine {
+    args: StrategyArgs,
+    /// The strategy's instances, and the recent prices, by symbols
+    symbols: HashMap<String, (Box<dyn Strategy>, VecDeque<f64>)>,
+}
+
+impl StrategyEngine {
+    /// Creates an engine of the strategy that the `args` select
+    ///
+    /// # Errors
+    /// - If the strategy isn't registered, or if its parameters aren't valid
+    pub fn new(args: StrategyArgs) -> Result<Self> {
+        create(&args)?;
+
+        Ok(Self {
+            args,
+            symbols: HashMap::new(),
+        })
+    }
+
+    /// The name of the strategy
+    pub fn name(&self) -> &str {
+        &self.args.strategy
+    }
+
+    /// Feeds a `row` to the strategy of its symbol, and returns the strategy's signal
+    pub fn signal(&mut self, row: &PerformanceIndicatorsRow) -> Signal {
+        let (strategy, prices) = match self.symbols.entry(row.symbol.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            // the strategy's parameters were validated when the engine was created
+            Entry::Vacant(entry) => match create(&self.args) {
+                Ok(strategy) => entry.insert((strategy, VecDeque::new())),
+                Err(err) => {
+                    tracing::error!("Couldn't create the trading strategy: {:#}", err);
+                    return Signal::Hold;
+                }
+            },
+        };
+        // the longest window of a strategy needs one more price, to tell a crossing
+        if prices.len() > MAX_WINDOW_SIZE {
+            prices.pop_front();
+        }
+        prices.push_back(row.last_price);
+
+        strategy.signal(prices.make_contiguous(), row)
+    }
+
+    /// Feeds the `rows` of an iteration to the strategies of their symbols
+    ///
+    /// # Returns
+    /// The rows whose signals aren't [`Signal::Hold`], with their signals
+    pub fn signals<'a>(
+        &mut self,
+        rows: &'a [PerformanceIndicatorsRow],
+    ) -> Vec<(&'a PerformanceIndicatorsRow, Signal)> {
+        rows.iter()
+            .map(|row| (row, self.signal(row)))
+            .filter(|&(_, signal)| signal != Signal::Hold)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(symbol: &str, last_price: f64) -> PerformanceIndicatorsRow {
+        PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price,
+            pct_change: 0.0,
+            period_min: 0.0,
+            period_max: 0.0,
+            sma: 0.0,
+            asset_class: Default::default(),
+        }
+    }
+
+    fn args(strategy: &str, fast_window: usize, slow_window: usize) -> StrategyArgs {
+        StrategyArgs {
+            strategy: strategy.to_string(),
+            fast_window,
+            slow_window,
+        }
+    }
+
+    #[test]
+    fn strategies_are_created_from_the_registry() {
+        assert_eq!(
+            "sma-crossover",
+            create(&args("sma-crossover", 10, 30)).unwrap().name()
+        );
+        assert!(create(&args("sma-crossover", 30, 10)).is_err());
+        assert!(create(&args("sma-crossover", 10, 10)).is_err());
+        assert!(create(&args("buy-and-hold", 10, 30)).is_err());
+    }
+
+    #[test]
+    fn every_symbol_has_a_strategy_of_its_own() {
+        let mut engine = StrategyEngine::new(args("sma-crossover", 2, 3)).unwrap();
+
+        let mut signals = Vec::new();
+        for (aapl, msft) in [(10.0, 50.0), (10.0, 50.0), (10.0, 50.0), (12.0, 50.0)] {
+            let rows = [row("AAPL", aapl), row("MSFT", msft)];
+            signals.extend(
+                engine
+                    .signals(&rows)
+                    .into_iter()
+                    .map(|(row, signal)| (row.symbol.to_string(), signal)),
+            );
+        }
+        assert_eq!(vec![("AAPL".to_string(), Signal::Buy)], signals);
+
+        assert_eq!(Signal::Sell, engine.signal(&row("AAPL", 5.0)));
+        assert_eq!(Signal::Hold, engine.signal(&row("MSFT", 50.0)));
+    }
+}
@@ -0,0 +1,497 @@
+//! The collection actor's buffer of the newest batches
+//!
+//! It holds up to a configurable number of batches, which is [`TAIL_BUFFER_SIZE`] by default.
+//! The batches that don't fit in its memory budget, the oldest ones first, are spilled
+//! to temporary files, and they are transparently read back whenever they are asked for,
+//! e.g., by `/tail` requests.
+//!
+//! The newest batch is always kept in memory, so that a batch that alone exceeds the budget
+//! doesn't have to be read back for every request.
+//!
+//! Every batch is stored with the moment of its completion, which stays in memory even when
+//! the batch is spilled, so that only the batches within a requested time range are read back.
+//!
+//! Spilling, reading back and rewriting the spilled batches block on the file system, so the
+//! collection actor runs them on a blocking thread, see [`TailBuffer::push_touches_disk`],
+//! and serves the batches in memory in place. A push spills only as many of the oldest batches
+//! as it takes to get back within the budget, usually one, and a read stops at the spilled
+//! batches that it needs.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use time::OffsetDateTime;
+
+use crate::constants::{TAIL_BUFFER_SIZE, TAIL_MEMORY_BUDGET_MB};
+use crate::my_async_actors::PerformanceIndicatorsRow;
+use crate::types::{Batch, RangeResponse, TailResponse};
+
+/// Distinguishes the spill directories of the buffers in the same process,
+/// e.g., of a restarted collection actor
+static SPILL_DIRS: AtomicU64 = AtomicU64::new(0);
+
+/// The limits of a [`TailBuffer`]
+#[derive(Clone, Copy, Debug)]
+pub struct TailBufferLimits {
+    /// The maximum number of batches
+    pub size: usize,
+    /// How much memory the batches may take up before the oldest ones are spilled, in MiB
+    pub memory_budget_mb: usize,
+}
+
+impl Default for TailBufferLimits {
+    fn default() -> Self {
+        Self {
+            size: TAIL_BUFFER_SIZE,
+            memory_budget_mb: TAIL_MEMORY_BUDGET_MB,
+        }
+    }
+}
+
+/// A ring buffer of the newest batches, which spills the oldest ones to disk
+/// when it exceeds its memory budget
+pub struct TailBuffer {
+    /// The batches that are kept in memory, with their completion times, oldest first;
+    /// they are all newer than the spilled ones
+    in_memory: VecDeque<(OffsetDateTime, Batch)>,
+    /// The maximum number of batches, both in memory and spilled
+    capacity: usize,
+    /// The estimated size of the batches in memory, in bytes
+    memory: usize,
+    /// The maximum estimated size of the batches in memory, in bytes
+    memory_budget: usize,
+    /// The files of the spilled batches, with the batches' completion times, oldest first
+    spilled: VecDeque<(OffsetDateTime, PathBuf)>,
+    /// The directory of the spilled batches' files, which is created when the first batch is spilled
+    spill_dir: PathBuf,
+    /// The sequential number of the next spilled batch's file
+    next_file: u64,
+    /// The emptied allocation of a batch that has left memory, which is reused for the next batch
+    spare: Option<Batch>,
+}
+
+impl TailBuffer {
+    /// Create a new, empty [`TailBuffer`], which holds up to `capacity` batches,
+    /// whose batches in memory may take up about `memory_budget` bytes
+    pub fn new(capacity: usize, memory_budget: usize) -> Self {
+        let spill_dir = std::env::temp_dir().join(format!(
+            "stock-trading-cli-{}-{}",
+            std::process::id(),
+            SPILL_DIRS.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        Self {
+            in_memory: VecDeque::new(),
+            capacity,
+            memory: 0,
+            memory_budget,
+            spilled: VecDeque::new(),
+            spill_dir,
+            next_file: 0,
+            spare: None,
+        }
+    }
+
+    /// Returns the number of batches, both in memory and spilled
+    pub fn len(&self) -> usize {
+        self.in_memory.len() + self.spilled.len()
+    }
+
+    /// Returns whether the buffer holds no batches
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of spilled batches
+    pub fn spilled_len(&self) -> usize {
+        self.spilled.len()
+    }
+
+    /// Returns the estimated size of the batches in memory, in bytes
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    /// Returns whether pushing the `batch` may touch the disk, i.e., spill a batch,
+    /// or remove the file of an evicted one, so that it has to be done on a blocking thread
+    pub fn push_touches_disk(&self, batch: &Batch) -> bool {
+        let evicts_spilled = self.len() == self.capacity && !self.spilled.is_empty();
+        let spills =
+            !self.in_memory.is_empty() && self.memory + batch_size(batch) > self.memory_budget;
+
+        evicts_spilled || spills
+    }
+
+    /// Adds the newest `batch`, which was completed at `completed_at`
+    ///
+    /// When the buffer is full, the oldest batch is evicted to make room for the new one.
+    /// The oldest batches in memory are then spilled until the rest fit in the memory budget.
+    ///
+    /// # Errors
+    /// - If a batch couldn't be spilled, in which case it is kept in memory
+    pub fn push(&mut self, batch: Batch, completed_at: OffsetDateTime) -> io::Result<()> {
+        if self.len() == self.capacity {
+            self.evict_oldest();
+        }
+        self.memory += batch_size(&batch);
+        self.in_memory.push_back((completed_at, batch));
+
+        while self.memory > self.memory_budget && self.in_memory.len() > 1 {
+            self.spill_oldest()?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes the emptied allocation of a batch that has been evicted or spilled, if there is one,
+    /// so that the next batch can be assembled in it instead of in a newly allocated one
+    pub fn take_spare(&mut self) -> Option<Batch> {
+        self.spare.take()
+    }
+
+    /// Keeps the allocation of a `batch` that has left memory as the spare, unless the spare
+    /// is already larger
+    fn recycle(&mut self, mut batch: Batch) {
+        if self
+            .spare
+            .as_ref()
+            .is_none_or(|spare| spare.capacity() < batch.capacity())
+        {
+            batch.clear();
+            self.spare = Some(batch);
+        }
+    }
+
+    /// Returns an iterator over all batches, newest first, which reads the spilled batches back
+    pub fn iter_newest(&self) -> impl Iterator<Item = io::Result<Cow<'_, Batch>>> {
+        let in_memory = self
+            .in_memory
+            .iter()
+            .rev()
+            .map(|(_, batch)| Ok(Cow::Borrowed(batch)));
+        let spilled = self
+            .spilled
+            .iter()
+            .rev()
+            .map(|(_, path)| read_batch(path).map(Cow::Owned));
+
+        in_memory.chain(spilled)
+    }
+
+    /// Returns the newest `n` batches, newest first
+    ///
+    /// # Errors
+    /// - If a spilled batch couldn't be read back
+    pub fn newest(&self, n: usize) -> io::Result<TailResponse> {
+        self.iter_newest()
+            .take(n)
+            .map(|batch| batch.map(Cow::into_owned))
+            .collect()
+    }
+
+    /// Returns the batches that were completed between `start` and `end`, both inclusive,
+    /// newest first, with their completion times
+    ///
+    /// Only the spilled batches within the range are read back.
+    ///
+    /// # Errors
+    /// - If a spilled batch couldn't be read back
+    pub fn range(&self, start: OffsetDateTime, end: OffsetDateTime) -> io::Result<RangeResponse> {
+        let within = |completed_at: &OffsetDateTime| (start..=end).contains(completed_at);
+
+        let in_memory = self
+            .in_memory
+            .iter()
+            .rev()
+            .filter(|(completed_at, _)| within(completed_at))
+            .map(|(completed_at, batch)| Ok((*completed_at, batch.clone())));
+        let spilled = self
+            .spilled
+            .iter()
+            .rev()
+            .filter(|(completed_at, _)| within(completed_at))
+            .map(|(completed_at, path)| Ok((*completed_at, read_batch(path)?)));
+
+        in_memory.chain(spilled).collect()
+    }
+
+    /// Removes all rows of the `symbol` from all batches, including the spilled ones
+    ///
+    /// # Errors
+    /// - If a spilled batch couldn't be read back or rewritten
+    pub fn purge(&mut self, symbol: &str) -> io::Result<()> {
+        for (_, batch) in self.in_memory.iter_mut() {
+            batch.retain(|row| *row.symbol != *symbol);
+        }
+        self.memory = self
+            .in_memory
+            .iter()
+            .map(|(_, batch)| batch_size(batch))
+            .sum();
+
+        for (_, path) in &self.spilled {
+            let mut batch = read_batch(path)?;
+            batch.retain(|row| *row.symbol != *symbol);
+            write_batch(path, &batch)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the oldest batch, which is a spilled one if there are any
+    fn evict_oldest(&mut self) {
+        if let Some((_, path)) = self.spilled.pop_front() {
+            if let Err(err) = fs::remove_file(&path) {
+                tracing::warn!("Couldn't remove the spilled batch {:?}: {}", path, err);
+            }
+        } else if let Some((_, batch)) = self.in_memory.pop_front() {
+            self.memory -= batch_size(&batch);
+            self.recycle(batch);
+        }
+    }
+
+    /// Moves the oldest batch in memory to a file
+    fn spill_oldest(&mut self) -> io::Result<()> {
+        let Some((completed_at, batch)) = self.in_memory.front() else {
+            return Ok(());
+        };
+        let completed_at = *completed_at;
+
+        fs::create_dir_all(&self.spill_dir)?;
+        let path = self.spill_dir.join(format!("{}.csv", self.next_file));
+        write_batch(&path, batch)?;
+        self.next_file += 1;
+
+        if let Some((_, batch)) = self.in_memory.pop_front() {
+            self.memory -= batch_size(&batch);
+            self.recycle(batch);
+        }
+        self.spilled.push_back((completed_at, path));
+        tracing::debug!("Spilled the oldest batch in memory to disk.");
+
+        Ok(())
+    }
+}
+
+impl Drop for TailBuffer {
+    fn drop(&mut self) {
+        if self.spill_dir.exists() {
+            let _ = fs::remove_dir_all(&self.spill_dir);
+        }
+    }
+}
+
+/// Returns the estimated size of a `batch` in memory, in bytes
+fn batch_size(batch: &Batch) -> usize {
+    batch
+        .iter()
+        .map(|row| size_of::<PerformanceIndicatorsRow>() + row.symbol.len())
+        .sum()
+}
+
+/// Writes a `batch` to the file at `path`, one row per line
+///
+/// The symbol comes last, after the asset class, so that it can't be confused with the other fields.
+/// The numbers are written with the shortest representation that reads back to the same value.
+fn write_batch(path: &Path, batch: &Batch) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    for row in batch {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{}",
+            row.last_price,
+            row.pct_change,
+            row.period_min,
+            row.period_max,
+            row.sma,
+            row.asset_class,
+            row.symbol
+        )?;
+    }
+
+    file.flush()
+}
+
+/// Reads a batch back from the file at `path`, which was written by [`write_batch`]
+fn read_batch(path: &Path) -> io::Result<Batch> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "A spilled row is malformed.");
+    let number = |field: Option<&str>| -> io::Result<f64> {
+        field
+            .and_then(|field| field.parse().ok())
+            .ok_or_else(invalid)
+    };
+
+    fs::read_to_string(path)?
+        .lines()
+        .map(|line| {
+            let mut fields = line.splitn(7, ',');
+            Ok(PerformanceIndicatorsRow {
+                last_price: number(fields.next())?,
+                pct_change: number(fields.next())?,
+                period_min: number(fields.next())?,
+                period_max: number(fields.next())?,
+                sma: number(fields.next())?,
+                asset_class: fields
+                    .next()
+                    .and_then(|field| field.parse().ok())
+                    .ok_or_else(invalid)?,
+                symbol: fields.next().ok_or_else(invalid)?.into(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_classes::AssetClass;
+
+    fn batch(last_price: f64) -> Batch {
+        vec![
+            PerformanceIndicatorsRow {
+                symbol: "AAPL".into(),
+                last_price,
+                pct_change: -1.25,
+                period_min: 0.1,
+                period_max: 1e9,
+                sma: 1.0 / 3.0,
+                asset_class: Default::default(),
+            },
+            PerformanceIndicatorsRow {
+                symbol: "MSFT".into(),
+                last_price: last_price * 10.0,
+                pct_change: 0.0,
+                period_min: 0.0,
+                period_max: 0.0,
+                sma: 0.0,
+                asset_class: AssetClass::Etf,
+            },
+        ]
+    }
+
+    fn at(second: usize) -> OffsetDateTime {
+        OffsetDateTime::UNIX_EPOCH + time::Duration::seconds(second as i64)
+    }
+
+    fn last_prices(tail: TailResponse) -> Vec<f64> {
+        tail.iter().map(|batch| batch[0].last_price).collect()
+    }
+
+    #[test]
+    fn spilled_batches_are_read_back() {
+        // only the newest batch fits in memory
+        let mut buffer = TailBuffer::new(TAIL_BUFFER_SIZE, batch_size(&batch(0.0)));
+        for last_price in 1..=TAIL_BUFFER_SIZE + 2 {
+            buffer
+                .push(batch(last_price as f64), at(last_price))
+                .unwrap();
+        }
+
+        assert_eq!(TAIL_BUFFER_SIZE, buffer.len());
+        assert_eq!(TAIL_BUFFER_SIZE - 1, buffer.spilled_len());
+
+        let tail = buffer.newest(TAIL_BUFFER_SIZE + 2).unwrap();
+        let expected: Vec<f64> = (3..=TAIL_BUFFER_SIZE + 2).rev().map(|p| p as f64).collect();
+        assert_eq!(expected, last_prices(tail.clone()));
+        // the numbers survive the round trip exactly
+        assert_eq!(1.0 / 3.0, tail[TAIL_BUFFER_SIZE - 1][0].sma);
+        assert_eq!(-1.25, tail[TAIL_BUFFER_SIZE - 1][0].pct_change);
+        assert_eq!(AssetClass::Etf, tail[TAIL_BUFFER_SIZE - 1][1].asset_class);
+
+        // the range spans both spilled batches and the batch in memory
+        let end = TAIL_BUFFER_SIZE + 2;
+        let range = buffer.range(at(end - 2), at(end)).unwrap();
+        assert_eq!(
+            vec![
+                (at(end), end as f64),
+                (at(end - 1), (end - 1) as f64),
+                (at(end - 2), (end - 2) as f64)
+            ],
+            range
+                .iter()
+                .map(|(completed_at, batch)| (*completed_at, batch[0].last_price))
+                .collect::<Vec<_>>()
+        );
+        // the evicted batches are out of range
+        assert!(buffer.range(at(0), at(2)).unwrap().is_empty());
+
+        buffer.purge("AAPL").unwrap();
+        let tail = buffer.newest(TAIL_BUFFER_SIZE).unwrap();
+        assert!(tail
+            .iter()
+            .all(|batch| batch.len() == 1 && &*batch[0].symbol == "MSFT"));
+
+        let spill_dir = buffer.spill_dir.clone();
+        assert!(spill_dir.exists());
+        drop(buffer);
+        assert!(!spill_dir.exists());
+    }
+
+    #[test]
+    fn pushes_that_spill_or_evict_spilled_batches_touch_the_disk() {
+        let mut buffer = TailBuffer::new(2, batch_size(&batch(0.0)));
+        assert!(!buffer.push_touches_disk(&batch(1.0)));
+        buffer.push(batch(1.0), at(1)).unwrap();
+
+        // the first batch is spilled to make room for the second one
+        assert!(buffer.push_touches_disk(&batch(2.0)));
+        buffer.push(batch(2.0), at(2)).unwrap();
+        assert_eq!(1, buffer.spilled_len());
+
+        // the buffer is full, so the spilled batch is evicted
+        assert!(buffer.push_touches_disk(&batch(3.0)));
+
+        let buffer = TailBuffer::new(TAIL_BUFFER_SIZE, usize::MAX);
+        assert!(!buffer.push_touches_disk(&batch(1.0)));
+    }
+
+    #[test]
+    fn nothing_is_spilled_within_the_budget() {
+        let mut buffer = TailBuffer::new(TAIL_BUFFER_SIZE, usize::MAX);
+        for last_price in 1..=3 {
+            buffer
+                .push(batch(last_price as f64), at(last_price))
+                .unwrap();
+        }
+
+        assert_eq!(0, buffer.spilled_len());
+        assert_eq!(vec![3.0, 2.0], last_prices(buffer.newest(2).unwrap()));
+        assert!(!buffer.spill_dir.exists());
+    }
+
+    #[test]
+    fn capacity_is_configurable() {
+        let mut buffer = TailBuffer::new(TAIL_BUFFER_SIZE * 3, usize::MAX);
+        for last_price in 1..=TAIL_BUFFER_SIZE * 4 {
+            buffer
+                .push(batch(last_price as f64), at(last_price))
+                .unwrap();
+        }
+
+        assert_eq!(TAIL_BUFFER_SIZE * 3, buffer.len());
+        let tail = buffer.newest(usize::MAX).unwrap();
+        assert_eq!(
+            (TAIL_BUFFER_SIZE + 1) as f64,
+            tail.back().unwrap()[0].last_price
+        );
+    }
+
+    #[test]
+    fn evicted_batches_are_recycled() {
+        let mut buffer = TailBuffer::new(2, usize::MAX);
+        buffer.push(batch(1.0), at(1)).unwrap();
+        buffer.push(batch(2.0), at(2)).unwrap();
+        assert!(buffer.take_spare().is_none());
+
+        buffer.push(batch(3.0), at(3)).unwrap();
+        let spare = buffer.take_spare().unwrap();
+        assert!(spare.is_empty());
+        assert!(spare.capacity() >= 2);
+        assert!(buffer.take_spare().is_none());
+    }
+}
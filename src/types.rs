@@ -1,15 +1,37 @@
 use std::collections::VecDeque;
+use std::sync::Arc;
 
+use time::OffsetDateTime;
 use tokio::sync::mpsc::error::SendError;
 
 use crate::my_async_actors::{
-    ActorMessage, CollectionActorMsg, PerformanceIndicatorsRow, PerformanceIndicatorsRowsMsg,
+    AlertActorMsg, AuditActorMsg, BrokerActorMsg, CollectionActorMsg, ConfigActorMsg,
+    DeadLetterActorMsg, EventLogActorMsg, FetchActorMsg, NewsActorMsg, OptionsActorMsg,
+    PaperTradingActorMsg, PerformanceIndicatorsRow, PortfolioActorMsg, ProcessorActorMsg,
+    StatsActorMsg, SymbolsActorMsg, WatchdogActorMsg, WatchlistActorMsg, WebhookActorMsg,
+    WriterActorMsg,
 };
 
 pub type MsgResponseType = ();
-pub type UniversalMsgErrorType = SendError<ActorMessage>;
-pub type WriterMsgErrorType = SendError<PerformanceIndicatorsRowsMsg>;
+pub type FetchMsgErrorType = SendError<FetchActorMsg>;
+pub type ProcessorMsgErrorType = SendError<ProcessorActorMsg>;
+pub type WriterMsgErrorType = SendError<WriterActorMsg>;
 pub type CollectionMsgErrorType = SendError<CollectionActorMsg>;
+pub type SymbolsMsgErrorType = SendError<SymbolsActorMsg>;
+pub type ConfigMsgErrorType = SendError<ConfigActorMsg>;
+pub type StatsMsgErrorType = SendError<StatsActorMsg>;
+pub type WebhookMsgErrorType = SendError<WebhookActorMsg>;
+pub type BrokerMsgErrorType = SendError<BrokerActorMsg>;
+pub type DeadLetterMsgErrorType = SendError<DeadLetterActorMsg>;
+pub type WatchdogMsgErrorType = SendError<WatchdogActorMsg>;
+pub type AlertMsgErrorType = SendError<AlertActorMsg>;
+pub type PaperTradingMsgErrorType = SendError<PaperTradingActorMsg>;
+pub type PortfolioMsgErrorType = SendError<PortfolioActorMsg>;
+pub type WatchlistMsgErrorType = SendError<WatchlistActorMsg>;
+pub type AuditMsgErrorType = SendError<AuditActorMsg>;
+pub type EventLogMsgErrorType = SendError<EventLogActorMsg>;
+pub type NewsMsgErrorType = SendError<NewsActorMsg>;
+pub type OptionsMsgErrorType = SendError<OptionsActorMsg>;
 
 /// A single iteration of the main loop, which contains processed data
 /// for all S&P 500 symbols
@@ -19,6 +41,20 @@ pub type Batch = Vec<PerformanceIndicatorsRow>;
 /// of processed symbol data in form of [`PerformanceIndicatorsRow`] data
 pub type TailResponse = VecDeque<Batch>;
 
+/// A response for the web server which contains the batches that were completed
+/// within a requested time range, newest first, together with their completion times
+pub type RangeResponse = Vec<(OffsetDateTime, Batch)>;
+
 /// A response for the web server which contains the requested last `n` batches
 /// of processed symbol data in form of [`String`] data
 pub type TailResponseString = Vec<Vec<String>>;
+
+/// A symbol (ticker), which is shared instead of cloned along the pipeline
+///
+/// The symbol registry creates every symbol once, and the chunks, the fetched closes,
+/// the rows and the batches only share it, so that a large universe of symbols at a short tick
+/// interval doesn't allocate a string per symbol at every step of every iteration.
+pub type Symbol = Arc<str>;
+
+/// A response from the symbol registry which contains symbols (tickers)
+pub type SymbolsResponse = Vec<Symbol>;
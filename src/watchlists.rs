@@ -0,0 +1,210 @@
+//! Watchlists
+//!
+//! A watchlist is a named group of symbols, with the performance indicators that it shows, which is
+//! declared with the `watchlists` option, e.g., `tech:AAPL+MSFT+NVDA:price+sma`. The symbols of all
+//! watchlists are fetched and processed in the same run as the other symbols, and the
+//! [`crate::my_async_actors::WatchlistActorHandle`]'s actor picks the rows of every watchlist
+//! out of every completed batch. It keeps the newest batches of every watchlist for the web app,
+//! and writes them to the watchlist's own output files, e.g., `output-tech.csv`.
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Result};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::constants::{INDICATORS, WATCHLIST_FILE_STEM};
+use crate::types::{Batch, TailResponse};
+
+/// A named group of symbols, with the performance indicators that it shows
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Watchlist {
+    /// The name, which consists of letters, digits, hyphens and underscores
+    pub name: String,
+    pub symbols: Vec<String>,
+    /// The performance indicators that are shown, out of the served ones; the others are zeroed
+    pub indicators: Vec<String>,
+}
+
+impl FromStr for Watchlist {
+    type Err = anyhow::Error;
+
+    /// Parses a watchlist in the `NAME:SYMBOLS[:INDICATORS]` format, whose symbols and indicators
+    /// are separated by plus signs, e.g., `tech:AAPL+MSFT+NVDA:price+sma`;
+    /// all indicators are shown if they're omitted
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, symbols, indicators) = match s.split(':').collect::<Vec<_>>()[..] {
+            [name, symbols] => (name, symbols, None),
+            [name, symbols, indicators] => (name, symbols, Some(indicators)),
+            _ => bail!(
+                "`{}` isn't a watchlist in the `NAME:SYMBOLS[:INDICATORS]` format, \
+                 such as `tech:AAPL+MSFT+NVDA:price+sma`.",
+                s
+            ),
+        };
+
+        let name = name.trim().to_string();
+        ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "The name of the watchlist `{}` must consist of letters, digits, hyphens and underscores.",
+            s
+        );
+
+        let mut list: Vec<String> = Vec::new();
+        for symbol in symbols
+            .split('+')
+            .map(|symbol| symbol.trim().to_uppercase())
+        {
+            if !symbol.is_empty() && !list.contains(&symbol) {
+                list.push(symbol);
+            }
+        }
+        ensure!(!list.is_empty(), "The watchlist `{}` has no symbols.", name);
+
+        let indicators = match indicators {
+            Some(indicators) => {
+                let indicators: Vec<String> = indicators
+                    .split('+')
+                    .map(|indicator| indicator.trim().to_lowercase())
+                    .collect();
+                if let Some(unknown) = indicators
+                    .iter()
+                    .find(|indicator| !INDICATORS.contains(&indicator.as_str()))
+                {
+                    bail!(
+                        "Unknown indicator \"{}\" in the watchlist `{}`; the indicators are: {}.",
+                        unknown,
+                        name,
+                        INDICATORS.join(", ")
+                    );
+                }
+                indicators
+            }
+            None => INDICATORS.iter().map(|i| i.to_string()).collect(),
+        };
+
+        Ok(Self {
+            name,
+            symbols: list,
+            indicators,
+        })
+    }
+}
+
+impl Watchlist {
+    /// The path of the watchlist's output files, without the extensions of their formats
+    pub fn file_stem(&self) -> String {
+        format!("{}-{}", WATCHLIST_FILE_STEM, self.name)
+    }
+
+    /// Returns the rows of the watchlist's symbols out of a `batch`, with the watchlist's indicators
+    pub fn rows(&self, batch: &Batch) -> Batch {
+        batch
+            .iter()
+            .filter(|row| self.symbols.iter().any(|symbol| **symbol == *row.symbol))
+            .cloned()
+            .map(|mut row| {
+                row.retain_indicators(&self.indicators);
+                row
+            })
+            .collect()
+    }
+}
+
+/// The newest batches of a watchlist, newest first
+pub struct WatchlistBuffer {
+    pub watchlist: Watchlist,
+    tail: TailResponse,
+    capacity: usize,
+}
+
+impl WatchlistBuffer {
+    /// Creates an empty tail of the `watchlist`, which keeps up to `capacity` batches
+    pub fn new(watchlist: Watchlist, capacity: usize) -> Self {
+        Self {
+            watchlist,
+            tail: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Picks the watchlist's rows out of a `batch`, and keeps them as the newest batch
+    ///
+    /// # Returns
+    /// The watchlist's rows
+    pub fn push(&mut self, batch: &Batch) -> &Batch {
+        if self.tail.len() == self.capacity {
+            self.tail.pop_back();
+        }
+        self.tail.push_front(self.watchlist.rows(batch));
+
+        &self.tail[0]
+    }
+
+    /// Returns the newest `n` batches, newest first
+    pub fn newest(&self, n: usize) -> TailResponse {
+        self.tail.iter().take(n).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::my_async_actors::PerformanceIndicatorsRow;
+
+    fn row(symbol: &str, last_price: f64) -> PerformanceIndicatorsRow {
+        PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price,
+            pct_change: 1.0,
+            period_min: 2.0,
+            period_max: 3.0,
+            sma: 4.0,
+            asset_class: Default::default(),
+        }
+    }
+
+    #[test]
+    fn watchlists_are_parsed() {
+        let watchlist: Watchlist = "tech: aapl+MSFT+aapl :price+SMA".parse().unwrap();
+        assert_eq!("tech", watchlist.name);
+        assert_eq!(vec!["AAPL", "MSFT"], watchlist.symbols);
+        assert_eq!(vec!["price", "sma"], watchlist.indicators);
+        assert_eq!("./output-tech", watchlist.file_stem());
+
+        let watchlist: Watchlist = "banks:JPM".parse().unwrap();
+        assert_eq!(INDICATORS.to_vec(), watchlist.indicators);
+
+        assert!("tech".parse::<Watchlist>().is_err());
+        assert!("te ch:AAPL".parse::<Watchlist>().is_err());
+        assert!("tech:+".parse::<Watchlist>().is_err());
+        assert!("tech:AAPL:rsi".parse::<Watchlist>().is_err());
+        assert!("tech:AAPL:price:sma".parse::<Watchlist>().is_err());
+    }
+
+    #[test]
+    fn tail_keeps_the_newest_rows_of_the_watchlist() {
+        let watchlist: Watchlist = "tech:AAPL+MSFT:price".parse().unwrap();
+        let mut tail = WatchlistBuffer::new(watchlist, 2);
+
+        for price in [1.0, 2.0, 3.0] {
+            let rows = tail.push(&vec![row("AAPL", price), row("JPM", price)]);
+            assert_eq!(1, rows.len());
+        }
+
+        let newest = tail.newest(5);
+        assert_eq!(
+            vec![3.0, 2.0],
+            newest
+                .iter()
+                .map(|batch| batch[0].last_price)
+                .collect::<Vec<_>>()
+        );
+        // only the watchlist's indicators are shown
+        assert_eq!(0.0, newest[0][0].sma);
+    }
+}
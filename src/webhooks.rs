@@ -0,0 +1,159 @@
+//! Webhooks
+//!
+//! Clients can register webhooks with filter rules through the web app.
+//! Whenever a new batch has been assembled, the rows that match a webhook's filter rules
+//! are POSTed to the webhook's URL in the JSON format.
+//!
+//! The webhooks are owned by the [`crate::my_async_actors::WebhookActorHandle`]'s actor,
+//! which also dispatches them.
+
+use anyhow::{bail, Context, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::my_async_actors::PerformanceIndicatorsRow;
+use crate::types::Batch;
+
+/// A request for registering a new webhook
+///
+/// Both filter rules are optional; a row has to satisfy all provided rules to be sent.
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct WebhookRegistration {
+    /// The URL that matching rows are POSTed to; must be `http` or `https`
+    pub url: String,
+    /// Only rows of this symbol (ticker) are sent
+    pub symbol: Option<String>,
+    /// Only rows whose absolute percentage change is at least this large are sent
+    pub threshold: Option<f64>,
+}
+
+impl WebhookRegistration {
+    /// Checks that the URL and the filter rules are valid
+    ///
+    /// # Errors
+    /// - If the URL can't be parsed, or if its scheme isn't `http` or `https`
+    /// - If the threshold is negative or not a finite number
+    pub fn validate(&self) -> Result<()> {
+        let url = Url::parse(&self.url).context("The webhook URL is invalid.")?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("The webhook URL must be an http or https URL.");
+        }
+        if let Some(threshold) = self.threshold {
+            if !threshold.is_finite() || threshold < 0.0 {
+                bail!("The threshold must be a non-negative number.");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A registered webhook
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Webhook {
+    /// The webhook's identifier, which is used for unregistering it
+    pub id: u64,
+    /// The URL that matching rows are POSTed to
+    pub url: String,
+    /// Only rows of this symbol (ticker) are sent
+    pub symbol: Option<String>,
+    /// Only rows whose absolute percentage change is at least this large are sent
+    pub threshold: Option<f64>,
+}
+
+impl Webhook {
+    /// Creates a new webhook with the given `id` out of a validated `registration`
+    ///
+    /// The symbol is trimmed and uppercased, the same as in the symbol registry.
+    pub fn new(id: u64, registration: WebhookRegistration) -> Self {
+        Self {
+            id,
+            url: registration.url,
+            symbol: registration
+                .symbol
+                .map(|symbol| symbol.trim().to_uppercase()),
+            threshold: registration.threshold,
+        }
+    }
+
+    /// Checks whether a `row` satisfies all filter rules of the webhook
+    pub fn matches(&self, row: &PerformanceIndicatorsRow) -> bool {
+        let symbol_matches = self
+            .symbol
+            .as_ref()
+            .is_none_or(|symbol| **symbol == *row.symbol);
+        let threshold_matches = self
+            .threshold
+            .is_none_or(|threshold| row.pct_change.abs() >= threshold);
+
+        symbol_matches && threshold_matches
+    }
+}
+
+/// The body of a webhook request
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload {
+    /// The identifier of the webhook that the rows matched
+    pub webhook_id: u64,
+    /// The matching rows of the newest batch
+    pub rows: Batch,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(symbol: &str, pct_change: f64) -> PerformanceIndicatorsRow {
+        PerformanceIndicatorsRow {
+            symbol: symbol.into(),
+            last_price: 100.0,
+            pct_change,
+            period_min: 90.0,
+            period_max: 110.0,
+            sma: 100.0,
+            asset_class: Default::default(),
+        }
+    }
+
+    #[test]
+    fn rows_are_matched_against_all_rules() {
+        let webhook = Webhook::new(
+            0,
+            WebhookRegistration {
+                url: "http://localhost:8080/hook".to_string(),
+                symbol: Some(" aapl ".to_string()),
+                threshold: Some(5.0),
+            },
+        );
+
+        assert!(webhook.matches(&row("AAPL", 5.0)));
+        assert!(webhook.matches(&row("AAPL", -7.5)));
+        assert!(!webhook.matches(&row("AAPL", 4.9)));
+        assert!(!webhook.matches(&row("MSFT", 10.0)));
+    }
+
+    #[test]
+    fn invalid_registration_is_rejected() {
+        let registration = WebhookRegistration {
+            url: "ftp://localhost/hook".to_string(),
+            symbol: None,
+            threshold: None,
+        };
+        assert!(registration.validate().is_err());
+
+        let registration = WebhookRegistration {
+            url: "http://localhost:8080/hook".to_string(),
+            symbol: None,
+            threshold: Some(-1.0),
+        };
+        assert!(registration.validate().is_err());
+
+        let registration = WebhookRegistration {
+            url: "https://example.com/hook".to_string(),
+            symbol: None,
+            threshold: Some(2.5),
+        };
+        assert!(registration.validate().is_ok());
+    }
+}